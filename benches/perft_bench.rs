@@ -1,11 +1,36 @@
 use chrono::Local;
 use oxi_chess_lib::board::ChessBoard;
-use oxi_chess_lib::perft::perft;
+use oxi_chess_lib::perft::{perft, perft_filtered, perft_parallel};
+use std::env;
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::thread;
 use std::time::{Duration, Instant};
 use thousands::Separable;
 
+// average nodes and time over the rounds after the first.
+fn measure(perft_fn: &dyn Fn(&mut ChessBoard, u32) -> u64, depth: u32) -> (u64, Duration) {
+    let mut total_elapsed: Duration = Duration::ZERO;
+    let mut total_nodes = 0;
+
+    let rounds = 8;
+    for i in 1..=rounds {
+        let start = Instant::now();
+        let mut board = ChessBoard::initialize();
+        let nodes = perft_fn(&mut board, depth);
+        let elapsed = start.elapsed();
+        if i != 1 {
+            // skip first round: avoids cold cache, branch predictor slowdowns
+            total_elapsed += elapsed;
+            total_nodes += nodes;
+        }
+    }
+    (
+        total_nodes / ((rounds - 1) as u64),
+        total_elapsed / (rounds - 1),
+    )
+}
+
 fn main() {
     let timestamp = Local::now().format("%Y%m%d_%H%M%S").to_string();
     let file_name = format!("benches/perft_results_{}.txt", timestamp);
@@ -17,33 +42,39 @@ fn main() {
         .open(file_name)
         .unwrap();
 
-    for depth in 1..=6 {
-        let mut total_elapsed: Duration = Duration::ZERO;
-        let mut total_nodes = 0;
+    // PERFT_THREADS overrides the thread count for the multithreaded run.
+    let threads = env::var("PERFT_THREADS")
+        .ok()
+        .and_then(|threads| threads.parse().ok())
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()));
+    let parallel = |board: &mut ChessBoard, depth| perft_parallel(board, depth, threads);
 
-        let rounds = 8;
-        for i in 1..=rounds {
-            let start = Instant::now();
-            let mut board = ChessBoard::initialize();
-            let nodes = perft(&mut board, depth);
-            let elapsed = start.elapsed();
-            if i != 1 {
-                // skip first round: avoids cold cache, branch predictor slowdowns
-                total_elapsed += elapsed;
-                total_nodes += nodes;
-            }
+    for (label, perft_fn) in [
+        (
+            "legal move generation".to_string(),
+            &perft as &dyn Fn(&mut ChessBoard, u32) -> u64,
+        ),
+        (
+            format!("legal move generation, {threads} threads"),
+            &parallel,
+        ),
+        (
+            "pseudolegal moves + legality test".to_string(),
+            &perft_filtered,
+        ),
+    ] {
+        writeln!(file, "{label}:").unwrap();
+        for depth in 1..=6 {
+            let (avg_nodes, avg_elapsed) = measure(perft_fn, depth);
+            writeln!(
+                file,
+                "depth {}: {} nodes in {:?}\n  nodes per second = {}",
+                depth,
+                avg_nodes,
+                avg_elapsed,
+                (((avg_nodes as f64) / avg_elapsed.as_secs_f64()) as u64).separate_with_commas()
+            )
+            .unwrap();
         }
-        let avg_elapsed = total_elapsed / (rounds - 1);
-        let avg_nodes = total_nodes / ((rounds - 1) as u64);
-
-        writeln!(
-            file,
-            "depth {}: {} nodes in {:?}\n  nodes per second = {}",
-            depth,
-            avg_nodes,
-            avg_elapsed,
-            (((avg_nodes as f64) / avg_elapsed.as_secs_f64()) as u64).separate_with_commas()
-        )
-        .unwrap();
     }
 }
@@ -1,10 +1,11 @@
 use oxi_chess_lib::game::{ChessGame, GameResult};
+use oxi_chess_lib::types::Color;
 use oxi_chess_lib::utils::render_board;
 use std::io;
 use std::io::Write;
 
 fn main() {
-    let mut game = ChessGame::initialize((1, 1), None);
+    let mut game = ChessGame::new((1, 1));
     render_board(&game.board);
     while game.result == GameResult::InProgress {
         print!("Enter move: ");
@@ -14,10 +15,10 @@ fn main() {
         let input = input.trim();
         if input == "resign" {
             match game.board.side_to_move {
-                true => {
+                Color::White => {
                     game.result = GameResult::BlackWins(oxi_chess_lib::game::WinReason::Resignation)
                 }
-                false => {
+                Color::Black => {
                     game.result = GameResult::WhiteWins(oxi_chess_lib::game::WinReason::Resignation)
                 }
             }
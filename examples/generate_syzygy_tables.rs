@@ -0,0 +1,772 @@
+// writes small Syzygy tables (.rtbw and .rtbz) for the tests, by default to tests/syzygy.
+// a development tool: the tables it writes exercise the decoder, but since they come from this
+// crate they can't show that it reads the official tables, which `SYZYGY_PATH` is for (see the
+// tests in src/syzygy.rs).
+//
+// every position of a table is solved by retrograde passes over its indices: captures and
+// promotions are looked up in the smaller tables written before it, the rest are moves within
+// the table. the values are then compressed the way the format expects (recursive pairing,
+// canonical Huffman codes, fixed-size blocks with a sparse index) and the written files are
+// probed back through `SyzygyTablebase` to check them against the solution.
+//
+// only tables whose distances to zeroing fit the 50-move rule and without pawns on both sides
+// are supported, which covers the endings the tests need.
+
+use oxi_chess_lib::bitboard::Bitboard;
+use oxi_chess_lib::board::ChessBoard;
+use oxi_chess_lib::moves::get_legal_moves;
+use oxi_chess_lib::rules::is_check;
+use oxi_chess_lib::syzygy::{
+    Encoding, SyzygyTablebase, TableLayout, FLAG_LOSS_PLIES, FLAG_MAPPED, FLAG_SINGLE_VALUE,
+    FLAG_WIN_PLIES,
+};
+use oxi_chess_lib::tablebase::{TablebaseBackend, Wdl, DTZ_MAGIC, WDL_MAGIC};
+use oxi_chess_lib::types::{Color, Square};
+use rustc_hash::FxHashMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+// in the order they are written: each table's captures and promotions lead to earlier ones.
+const TABLES: [&str; 7] = ["KBvK", "KNvK", "KQvK", "KRvK", "KPvK", "KNNvK", "KRvKR"];
+
+const BLOCK_BITS: u8 = 8; // 256-byte blocks
+const SPAN_BITS: u8 = 12; // a sparse index entry every 4096 values
+const MAX_BLOCK_VALUES: usize = 65536 - (1 << (SPAN_BITS - 1));
+const MAX_SYMBOLS: usize = 4095; // 0xFFF marks a single value
+const MAX_SYMBOL_VALUES: u32 = 256;
+const ZEROING: u32 = 1 << 31;
+
+// the pieces of a table in the order the index lists them: the leading pawns (or the kings and
+// a piece there is only one of), then the rest grouped by kind. white is the side named first.
+fn table_pieces(material: &str) -> Vec<u8> {
+    let (white, black) = material.split_once('v').unwrap();
+    let codes = |side: &str, color: u8| -> Vec<u8> {
+        let mut codes: Vec<u8> = side
+            .chars()
+            .map(|letter| "PNBRQK".find(letter).unwrap() as u8 + 1 + color)
+            .collect();
+        codes.sort_unstable();
+        codes
+    };
+    let (white, black) = (codes(white, 0), codes(black, 8));
+    let pawns = |codes: &[u8]| codes.iter().filter(|&&code| code & 7 == 1).count();
+    let mut rest: Vec<u8> = white.iter().chain(&black).copied().collect();
+    rest.sort_unstable();
+
+    let mut pieces = Vec::new();
+    if pawns(&white) + pawns(&black) > 0 {
+        let white_leads =
+            pawns(&white) > 0 && (pawns(&black) == 0 || pawns(&white) <= pawns(&black));
+        let (lead, other) = if white_leads { (1, 9) } else { (9, 1) };
+        for code in [lead, other] {
+            pieces.extend(rest.iter().filter(|&&c| c == code));
+            rest.retain(|&c| c != code);
+        }
+    } else {
+        pieces.extend([6, 14]);
+        rest.retain(|&c| c & 7 != 6);
+        let unique = rest
+            .iter()
+            .position(|&code| rest.iter().filter(|&&c| c == code).count() == 1);
+        if let Some(i) = unique {
+            pieces.push(rest.remove(i));
+        }
+    }
+    pieces.extend(rest);
+    pieces
+}
+
+fn encodings(material: &str, sides: usize) -> Vec<Vec<Encoding>> {
+    let pieces = table_pieces(material);
+    let groups = Encoding::new(&pieces, [0, 0xF], 0).groups();
+    let (white, black) = material.split_once('v').unwrap();
+    let files = if material.contains('P') { 4 } else { 1 };
+    let order = if white.contains('P') && black.contains('P') {
+        [groups as u8 - 1, groups as u8 - 2]
+    } else {
+        [groups as u8 - 1, 0xF]
+    };
+    (0..files)
+        .map(|file| vec![Encoding::new(&pieces, order, file); sides])
+        .collect()
+}
+
+fn board_from(pieces: &[u8], squares: &[u8], black_to_move: bool) -> ChessBoard {
+    let mut board = ChessBoard::empty();
+    for (&code, &sq) in pieces.iter().zip(squares) {
+        let bb = Bitboard::from_square(Square::new(sq));
+        match code & 7 {
+            1 => board.pawns |= bb,
+            2 => board.knights |= bb,
+            3 => board.bishops |= bb,
+            4 => board.rooks |= bb,
+            5 => board.queens |= bb,
+            _ => board.kings |= bb,
+        }
+        if code & 8 == 0 {
+            board.white_pieces |= bb;
+        } else {
+            board.black_pieces |= bb;
+        }
+    }
+    board.side_to_move = Color::from(!black_to_move);
+    board.zobrist_hash = board.generate_zobrist_hash();
+    board
+}
+
+// a position packed as six bits per square, in table order, and the side to move on top.
+fn pack(squares: &[u8], black_to_move: bool) -> u64 {
+    let packed = squares
+        .iter()
+        .rev()
+        .fold(0, |packed, &sq| (packed << 6) | sq as u64);
+    packed | (black_to_move as u64) << 63
+}
+
+fn unpack(packed: u64, len: usize) -> (Vec<u8>, bool) {
+    let squares = (0..len).map(|i| ((packed >> (6 * i)) & 63) as u8).collect();
+    (squares, packed >> 63 == 1)
+}
+
+// the positions of a table, one per index, with the moves between them.
+struct Graph {
+    layout: TableLayout,
+    pieces: Vec<u8>,
+    first_state: Vec<Vec<usize>>, // by file and side
+    positions: Vec<u64>,          // a packed position per state, u64::MAX if none
+    edges: Vec<u32>,              // moves within the table, ZEROING set for pawn moves
+    edge_start: Vec<usize>,       // where each state's moves start in `edges`
+    exits: Vec<Option<Wdl>>,      // the best capture or promotion into a smaller table
+    mated: Vec<bool>,
+}
+
+impl Graph {
+    fn state(&self, board: &ChessBoard) -> u32 {
+        let (file, side, index) = self.layout.locate(board).unwrap();
+        (self.first_state[file][side] + index as usize) as u32
+    }
+
+    fn board(&self, state: usize) -> Option<ChessBoard> {
+        if self.positions[state] == u64::MAX {
+            return None;
+        }
+        let (squares, black_to_move) = unpack(self.positions[state], self.pieces.len());
+        Some(board_from(&self.pieces, &squares, black_to_move))
+    }
+
+    fn edges(&self, state: usize) -> &[u32] {
+        &self.edges[self.edge_start[state]..self.edge_start[state + 1]]
+    }
+}
+
+// calls `visit` with every placement of the pieces, pawns off the first and last ranks.
+fn placements(pieces: &[u8], squares: &mut Vec<u8>, visit: &mut impl FnMut(&[u8])) {
+    if squares.len() == pieces.len() {
+        visit(squares);
+        return;
+    }
+    let pawn = pieces[squares.len()] & 7 == 1;
+    for sq in 0..64u8 {
+        if squares.contains(&sq) || (pawn && !(8..56).contains(&sq)) {
+            continue;
+        }
+        squares.push(sq);
+        placements(pieces, squares, visit);
+        squares.pop();
+    }
+}
+
+fn build_graph(material: &str, smaller: &SyzygyTablebase) -> Graph {
+    let (white, black) = material.split_once('v').unwrap();
+    let sides = if white == black { 1 } else { 2 };
+    let layout = TableLayout::new(material, encodings(material, sides));
+    let pieces = layout.encoding(0, 0).pieces().to_vec();
+
+    let mut first_state = Vec::new();
+    let mut states = 0;
+    for file in 0..layout.files() {
+        let mut per_side = Vec::new();
+        for side in 0..sides {
+            per_side.push(states);
+            states += layout.encoding(file, side).size() as usize;
+        }
+        first_state.push(per_side);
+    }
+
+    let mut graph = Graph {
+        layout,
+        pieces,
+        first_state,
+        positions: vec![u64::MAX; states],
+        edges: Vec::new(),
+        edge_start: vec![0; states + 1],
+        exits: vec![None; states],
+        mated: vec![false; states],
+    };
+
+    let pieces = graph.pieces.clone();
+    let mut positions = std::mem::take(&mut graph.positions);
+    placements(&pieces, &mut Vec::new(), &mut |squares| {
+        for black_to_move in [false, true] {
+            let board = board_from(&pieces, squares, black_to_move);
+            if is_check(&board, !board.side_to_move) {
+                continue;
+            }
+            let state = graph.state(&board) as usize;
+            if positions[state] == u64::MAX {
+                positions[state] = pack(squares, black_to_move);
+            }
+        }
+    });
+    graph.positions = positions;
+
+    for state in 0..states {
+        graph.edge_start[state] = graph.edges.len();
+        let Some(mut board) = graph.board(state) else {
+            continue;
+        };
+        let moves = get_legal_moves(&mut board);
+        graph.mated[state] = moves.is_empty() && is_check(&board, board.side_to_move);
+        for mv in moves {
+            let pawn_move = board.pawns.contains(mv.from());
+            let undo_info = board.make_move(mv).unwrap();
+            if mv.is_capture() || mv.promotion().is_some() {
+                let value = smaller
+                    .probe_wdl(&board)
+                    .expect("a smaller table is missing");
+                let value = value.flip();
+                assert!(value.is_decisive() || value == Wdl::Draw);
+                graph.exits[state] = graph.exits[state].max(Some(value));
+            } else {
+                let en_passant = get_legal_moves(&mut board)
+                    .iter()
+                    .any(|mv| mv.is_en_passant());
+                assert!(!en_passant, "en passant isn't supported");
+                let zeroing = if pawn_move { ZEROING } else { 0 };
+                graph.edges.push(graph.state(&board) | zeroing);
+            }
+            board.unmake_move(mv, &undo_info).unwrap();
+        }
+    }
+    graph.edge_start[states] = graph.edges.len();
+    graph
+}
+
+// win/draw/loss of every state, by passes until nothing changes: a win needs one move to a
+// lost position, a loss needs every move to lead to a won one.
+fn solve_wdl(graph: &Graph) -> Vec<Option<Wdl>> {
+    let states = graph.positions.len();
+    let mut wdl: Vec<Option<Wdl>> = (0..states)
+        .map(|state| graph.mated[state].then_some(Wdl::Loss))
+        .collect();
+    let child = |wdl: &[Option<Wdl>], edge: u32| wdl[(edge & !ZEROING) as usize];
+    loop {
+        let mut changed = false;
+        for state in 0..states {
+            if graph.positions[state] == u64::MAX || wdl[state].is_some() {
+                continue;
+            }
+            let edges = graph.edges(state);
+            let exit = graph.exits[state];
+            if exit == Some(Wdl::Win) || edges.iter().any(|&e| child(&wdl, e) == Some(Wdl::Loss)) {
+                wdl[state] = Some(Wdl::Win);
+                changed = true;
+            } else if (exit.is_some() || !edges.is_empty())
+                && exit.is_none_or(|exit| exit == Wdl::Loss)
+                && edges.iter().all(|&e| child(&wdl, e) == Some(Wdl::Win))
+            {
+                wdl[state] = Some(Wdl::Loss);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    // what is left can't be forced either way (stalemates included).
+    for (state, value) in wdl.iter_mut().enumerate() {
+        if graph.positions[state] != u64::MAX && value.is_none() {
+            *value = Some(Wdl::Draw);
+        }
+    }
+    wdl
+}
+
+// the distance to zeroing of every won or lost state, in plies, by layers: a win takes the
+// quickest way to a capture, pawn move or mate, a loss the slowest.
+fn solve_dtz(graph: &Graph, wdl: &[Option<Wdl>]) -> Vec<i32> {
+    let states = graph.positions.len();
+    let mut dtz = vec![0; states];
+    for layer in 1..=100 {
+        let mut next = dtz.clone();
+        for state in 0..states {
+            if dtz[state] != 0 {
+                continue;
+            }
+            let edges = graph.edges(state);
+            match wdl[state] {
+                Some(Wdl::Win) => {
+                    let found = if layer == 1 {
+                        graph.exits[state] == Some(Wdl::Win)
+                            || edges.iter().any(|&e| {
+                                let child = (e & !ZEROING) as usize;
+                                wdl[child] == Some(Wdl::Loss)
+                                    && (e & ZEROING != 0 || graph.mated[child])
+                            })
+                    } else {
+                        edges.iter().any(|&e| {
+                            let child = (e & !ZEROING) as usize;
+                            e & ZEROING == 0
+                                && wdl[child] == Some(Wdl::Loss)
+                                && dtz[child] == -(layer - 1)
+                        })
+                    };
+                    if found {
+                        next[state] = layer;
+                    }
+                }
+                Some(Wdl::Loss) => {
+                    let mut longest = 1;
+                    let mut known = true;
+                    for &e in edges.iter().filter(|&&e| e & ZEROING == 0) {
+                        let child = dtz[(e & !ZEROING) as usize];
+                        known &= child > 0;
+                        longest = longest.max(child + 1);
+                    }
+                    if known && longest == layer {
+                        next[state] = -layer;
+                    }
+                }
+                _ => {}
+            }
+        }
+        dtz = next;
+    }
+    for state in 0..states {
+        let decisive = wdl[state].is_some_and(Wdl::is_decisive);
+        assert_eq!(decisive, dtz[state] != 0, "a win or loss beyond 100 plies");
+    }
+    dtz
+}
+
+// a table part compressed: its header, sparse index, block lengths and blocks.
+#[derive(Default)]
+struct Part {
+    header: Vec<u8>,
+    sparse_index: Vec<u8>,
+    block_lengths: Vec<u8>,
+    data: Vec<u8>,
+}
+
+// writes `bits` bits of `code`, most significant first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u64, len: u32) {
+        for i in (0..len).rev() {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (code >> i) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.bits % 8);
+            }
+            self.bits += 1;
+        }
+    }
+}
+
+// replaces frequent pairs of neighbouring symbols with new symbols, a batch of the most frequent
+// at a time, until no pair repeats enough or the symbols run out.
+fn pair_symbols(sequence: &mut Vec<u16>, pairs: &mut Vec<(u16, u16)>, values: &mut Vec<u32>) {
+    loop {
+        let mut counts: FxHashMap<(u16, u16), u32> = FxHashMap::default();
+        for pair in sequence.windows(2) {
+            *counts.entry((pair[0], pair[1])).or_default() += 1;
+        }
+        let mut frequent: Vec<((u16, u16), u32)> = counts
+            .into_iter()
+            .filter(|&((left, right), count)| {
+                count >= 8 && values[left as usize] + values[right as usize] <= MAX_SYMBOL_VALUES
+            })
+            .collect();
+        frequent.sort_unstable_by_key(|&(pair, count)| (std::cmp::Reverse(count), pair));
+        frequent.truncate(64.min(MAX_SYMBOLS - pairs.len()));
+        if frequent.is_empty() {
+            return;
+        }
+
+        let mut replacements: FxHashMap<(u16, u16), u16> = FxHashMap::default();
+        for ((left, right), _) in frequent {
+            replacements.insert((left, right), pairs.len() as u16);
+            pairs.push((left, right));
+            values.push(values[left as usize] + values[right as usize]);
+        }
+        let mut paired = Vec::with_capacity(sequence.len());
+        let mut i = 0;
+        while i < sequence.len() {
+            let pair = sequence.get(i + 1).map(|&right| (sequence[i], right));
+            match pair.and_then(|pair| replacements.get(&pair)) {
+                Some(&symbol) => {
+                    paired.push(symbol);
+                    i += 2;
+                }
+                None => {
+                    paired.push(sequence[i]);
+                    i += 1;
+                }
+            }
+        }
+        *sequence = paired;
+    }
+}
+
+// Huffman code lengths of the symbols used, by repeatedly merging the two rarest subtrees.
+fn code_lengths(sequence: &[u16], symbols: usize) -> Vec<u32> {
+    let mut counts = vec![0u64; symbols];
+    for &symbol in sequence {
+        counts[symbol as usize] += 1;
+    }
+    let mut lengths = vec![0; symbols];
+    let mut trees: Vec<(u64, Vec<usize>)> = (0..symbols)
+        .filter(|&symbol| counts[symbol] > 0)
+        .map(|symbol| (counts[symbol], vec![symbol]))
+        .collect();
+    if trees.len() == 1 {
+        lengths[trees[0].1[0]] = 1;
+    }
+    while trees.len() > 1 {
+        trees.sort_unstable_by_key(|tree| std::cmp::Reverse(tree.0));
+        let (count_a, a) = trees.pop().unwrap();
+        let (count_b, b) = trees.pop().unwrap();
+        for &symbol in a.iter().chain(&b) {
+            lengths[symbol] += 1;
+        }
+        trees.push((count_a + count_b, [a, b].concat()));
+    }
+    assert!(lengths.iter().all(|&len| len <= 32), "code too long");
+    lengths
+}
+
+fn compress(values: &[u16], flags: u8) -> Part {
+    let mut distinct: Vec<u16> = values.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() == 1 {
+        return Part {
+            header: vec![flags | FLAG_SINGLE_VALUE, distinct[0] as u8],
+            ..Part::default()
+        };
+    }
+
+    let mut pairs: Vec<(u16, u16)> = distinct.iter().map(|&value| (value, 0xFFF)).collect();
+    let mut symbol_values = vec![1; pairs.len()];
+    let mut sequence: Vec<u16> = values
+        .iter()
+        .map(|value| distinct.binary_search(value).unwrap() as u16)
+        .collect();
+    pair_symbols(&mut sequence, &mut pairs, &mut symbol_values);
+
+    // symbols are renumbered so that longer codes come first, and codes are canonical, longer
+    // codes numerically lower. symbols that only appear inside others come last.
+    let lengths = code_lengths(&sequence, pairs.len());
+    let mut order: Vec<usize> = (0..pairs.len()).collect();
+    order.sort_by_key(|&symbol| (lengths[symbol] == 0, std::cmp::Reverse(lengths[symbol])));
+    let mut renumbered = vec![0u16; pairs.len()];
+    for (new, &old) in order.iter().enumerate() {
+        renumbered[old] = new as u16;
+    }
+    let used: Vec<u32> = lengths.iter().copied().filter(|&len| len > 0).collect();
+    let min_len = *used.iter().min().unwrap();
+    let max_len = *used.iter().max().unwrap();
+    let count = |len: u32| used.iter().filter(|&&l| l == len).count() as u64;
+    let lowest: Vec<u64> = (min_len..=max_len)
+        .map(|len| used.iter().filter(|&&l| l > len).count() as u64)
+        .collect();
+    let mut base = vec![0u64; lowest.len()];
+    for i in (0..lowest.len() - 1).rev() {
+        base[i] = (base[i + 1] + count(min_len + i as u32 + 1)) / 2;
+    }
+    let code = |symbol: usize| -> (u64, u32) {
+        let i = (lengths[symbol] - min_len) as usize;
+        (
+            base[i] + renumbered[symbol] as u64 - lowest[i],
+            lengths[symbol],
+        )
+    };
+
+    let mut header = vec![flags, BLOCK_BITS, SPAN_BITS, 0];
+    let mut blocks: Vec<Vec<u8>> = Vec::new();
+    let mut block_values: Vec<usize> = Vec::new();
+    let mut writer = BitWriter {
+        bytes: Vec::new(),
+        bits: 0,
+    };
+    let mut in_block = 0;
+    for &symbol in &sequence {
+        let (code, len) = code(symbol as usize);
+        let symbol_len = symbol_values[symbol as usize] as usize;
+        if writer.bits + len as usize > 8 << BLOCK_BITS || in_block + symbol_len > MAX_BLOCK_VALUES
+        {
+            blocks.push(std::mem::take(&mut writer.bytes));
+            block_values.push(in_block);
+            writer.bits = 0;
+            in_block = 0;
+        }
+        writer.write(code, len);
+        in_block += symbol_len;
+    }
+    blocks.push(writer.bytes);
+    block_values.push(in_block);
+
+    header.extend((blocks.len() as u32).to_le_bytes());
+    header.extend([max_len as u8, min_len as u8]);
+    for &lowest in &lowest {
+        header.extend((lowest as u16).to_le_bytes());
+    }
+    header.extend((pairs.len() as u16).to_le_bytes());
+    let mut tree = vec![(0, 0); pairs.len()];
+    for (old, &(left, right)) in pairs.iter().enumerate() {
+        tree[renumbered[old] as usize] = if right == 0xFFF {
+            (left, right)
+        } else {
+            (renumbered[left as usize], renumbered[right as usize])
+        };
+    }
+    for (left, right) in tree {
+        header.extend([
+            left as u8,
+            ((left >> 8) | ((right & 0xF) << 4)) as u8,
+            (right >> 4) as u8,
+        ]);
+    }
+    if pairs.len() % 2 == 1 {
+        header.push(0);
+    }
+
+    // each sparse index entry points at the middle index of its span.
+    let mut block_starts = vec![0];
+    for &len in &block_values {
+        block_starts.push(block_starts.last().unwrap() + len);
+    }
+    let span = 1usize << SPAN_BITS;
+    let mut sparse_index = Vec::new();
+    for middle in (span / 2..values.len().next_multiple_of(span)).step_by(span) {
+        let block = block_starts[1..blocks.len()].partition_point(|&start| start <= middle);
+        let offset = middle - block_starts[block];
+        sparse_index.extend((block as u32).to_le_bytes());
+        sparse_index.extend((offset as u16).to_le_bytes());
+    }
+    let block_lengths = block_values
+        .iter()
+        .flat_map(|&len| (len as u16 - 1).to_le_bytes())
+        .collect();
+    let data = blocks
+        .into_iter()
+        .flat_map(|mut block| {
+            block.resize(1 << BLOCK_BITS, 0);
+            block
+        })
+        .collect();
+
+    Part {
+        header,
+        sparse_index,
+        block_lengths,
+        data,
+    }
+}
+
+// a part's stored values; indices no position uses repeat the value before them.
+fn fill_values(values: &[Option<u16>]) -> Vec<u16> {
+    let mut last = values.iter().flatten().next().copied().unwrap_or(0);
+    values
+        .iter()
+        .map(|value| {
+            last = value.unwrap_or(last);
+            last
+        })
+        .collect()
+}
+
+fn write_table(path: &Path, magic: [u8; 4], graph: &Graph, parts: &[Part], maps: &[Vec<u8>]) {
+    let material = graph.layout.material();
+    let (white, black) = material.split_once('v').unwrap();
+    let both_pawns = white.contains('P') && black.contains('P');
+    let mut bytes = magic.to_vec();
+    bytes.push((white != black) as u8 | (material.contains('P') as u8) << 1);
+    for file in 0..graph.layout.files() {
+        // both sides to move number their positions the same way.
+        let encoding = encodings(material, 1).swap_remove(file).swap_remove(0);
+        let groups = encoding.groups() as u8;
+        bytes.push((groups - 1) * 0x11);
+        if both_pawns {
+            bytes.push((groups - 2) * 0x11);
+        }
+        bytes.extend(encoding.pieces().iter().map(|&code| code * 0x11));
+    }
+    if bytes.len() % 2 == 1 {
+        bytes.push(0);
+    }
+    for part in parts {
+        bytes.extend(&part.header);
+    }
+    if !maps.is_empty() {
+        for map in maps {
+            bytes.extend(map);
+        }
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+    }
+    for part in parts {
+        bytes.extend(&part.sparse_index);
+    }
+    for part in parts {
+        bytes.extend(&part.block_lengths);
+    }
+    for part in parts {
+        bytes.resize(bytes.len().next_multiple_of(64), 0);
+        bytes.extend(&part.data);
+    }
+    fs::write(path, bytes).unwrap();
+}
+
+fn write_wdl(dir: &Path, graph: &Graph, wdl: &[Option<Wdl>]) {
+    let mut parts = Vec::new();
+    for file in 0..graph.layout.files() {
+        for &first in &graph.first_state[file] {
+            let size = graph.layout.encoding(file, 0).size() as usize;
+            let values: Vec<Option<u16>> = wdl[first..first + size]
+                .iter()
+                .map(|value| value.map(|wdl| (wdl as i16 + 2) as u16))
+                .collect();
+            parts.push(compress(&fill_values(&values), 0));
+        }
+    }
+    let path = dir.join(format!("{}.rtbw", graph.layout.material()));
+    write_table(&path, WDL_MAGIC, graph, &parts, &[]);
+}
+
+// the DTZ table keeps white to move only: wins and losses in moves unless some distance is an
+// even number of plies, each through a map of the distances that occur.
+fn write_dtz(dir: &Path, graph: &Graph, wdl: &[Option<Wdl>], dtz: &[i32]) {
+    let mut parts = Vec::new();
+    let mut maps = Vec::new();
+    for file in 0..graph.layout.files() {
+        let first = graph.first_state[file][0];
+        let size = graph.layout.encoding(file, 0).size() as usize;
+        let states = first..first + size;
+        let even = |wdl_value: Wdl| {
+            states
+                .clone()
+                .any(|state| wdl[state] == Some(wdl_value) && dtz[state] % 2 == 0)
+        };
+        let mut flags = 0;
+        if even(Wdl::Win) {
+            flags |= FLAG_WIN_PLIES;
+        }
+        if even(Wdl::Loss) {
+            flags |= FLAG_LOSS_PLIES;
+        }
+        let stored = |state: usize| -> Option<(Wdl, u16)> {
+            let value = wdl[state]?;
+            let plies = match value {
+                Wdl::Win => FLAG_WIN_PLIES,
+                Wdl::Loss => FLAG_LOSS_PLIES,
+                _ => return None,
+            };
+            let distance = dtz[state].unsigned_abs() as u16 - 1;
+            Some((
+                value,
+                if flags & plies != 0 {
+                    distance
+                } else {
+                    distance / 2
+                },
+            ))
+        };
+
+        let mut win_map: Vec<u16> = states
+            .clone()
+            .filter_map(stored)
+            .filter(|s| s.0 == Wdl::Win)
+            .map(|s| s.1)
+            .collect();
+        let mut loss_map: Vec<u16> = states
+            .clone()
+            .filter_map(stored)
+            .filter(|s| s.0 == Wdl::Loss)
+            .map(|s| s.1)
+            .collect();
+        for map in [&mut win_map, &mut loss_map] {
+            map.sort_unstable();
+            map.dedup();
+        }
+        let values: Vec<Option<u16>> = states
+            .map(|state| {
+                let (value, distance) = stored(state)?;
+                let map = if value == Wdl::Win {
+                    &win_map
+                } else {
+                    &loss_map
+                };
+                Some(map.binary_search(&distance).unwrap() as u16)
+            })
+            .collect();
+        if !win_map.is_empty() || !loss_map.is_empty() {
+            flags |= FLAG_MAPPED;
+            let mut map = Vec::new();
+            for list in [&win_map, &loss_map, &Vec::new(), &Vec::new()] {
+                map.push(list.len() as u8);
+                map.extend(list.iter().map(|&distance| distance as u8));
+            }
+            maps.push(map);
+        }
+        parts.push(compress(&fill_values(&values), flags));
+    }
+    let path = dir.join(format!("{}.rtbz", graph.layout.material()));
+    write_table(&path, DTZ_MAGIC, graph, &parts, &maps);
+}
+
+// probes the positions back through the written files.
+fn check(dir: &Path, graph: &Graph, wdl: &[Option<Wdl>], dtz: &[i32]) {
+    let tablebase = SyzygyTablebase::open(dir).unwrap();
+    for state in (0..graph.positions.len()).step_by(7) {
+        let Some(board) = graph.board(state) else {
+            continue;
+        };
+        assert_eq!(
+            tablebase.probe_wdl(&board),
+            wdl[state],
+            "{}",
+            board.to_fen()
+        );
+        assert_eq!(
+            tablebase.probe_dtz(&board),
+            Some(dtz[state]),
+            "{}",
+            board.to_fen()
+        );
+    }
+}
+
+fn main() {
+    let dir = env::args().nth(1).map_or_else(
+        || PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy")),
+        PathBuf::from,
+    );
+    fs::create_dir_all(&dir).unwrap();
+    for material in TABLES {
+        let smaller = SyzygyTablebase::open(&dir).unwrap();
+        let graph = build_graph(material, &smaller);
+        let wdl = solve_wdl(&graph);
+        let dtz = solve_dtz(&graph, &wdl);
+        write_wdl(&dir, &graph, &wdl);
+        write_dtz(&dir, &graph, &wdl, &dtz);
+        check(&dir, &graph, &wdl, &dtz);
+        println!("{material}: {} positions", graph.positions.len());
+    }
+}
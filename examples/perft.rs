@@ -6,5 +6,12 @@ fn main() {
         "r3k2r/p1pNqpb1/bn2pnp1/3P4/1p2P3/2N2Q2/PPPBBPpP/R3K2R w KQkq - 0 1",
     )
     .unwrap();
-    perft::perft_divide(&mut board, 1);
+    let divide = perft::perft_divide(&mut board, 1);
+    for (move_i, nodes) in &divide {
+        println!("{move_i}: {nodes}");
+    }
+    println!(
+        "Total: {}",
+        divide.iter().map(|(_, nodes)| nodes).sum::<u64>()
+    );
 }
@@ -0,0 +1,141 @@
+// runs a perft suite and reports the positions whose node counts don't match.
+// run with `cargo run --release --bin perft -- [--depth N] [--threads N] [suite.epd]`; without a
+// file it runs the bundled tests/perftsuite.epd, up to depth 4 unless --depth says otherwise.
+//
+// for each mismatch it prints the per-move counts (a perft divide) of the move generator next to
+// those of the older filtering generator, marking the moves where they differ, and follows the
+// differing counts down to the first position where the two generators' moves differ.
+
+use oxi_chess_lib::board::ChessBoard;
+use oxi_chess_lib::perft::{
+    find_divergence, load_perft_suite, parse_perft_suite, perft_divide, perft_divide_filtered,
+    perft_parallel, PerftCase,
+};
+use oxi_chess_lib::types::Move;
+use std::collections::BTreeMap;
+use std::env;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Instant;
+
+const BUNDLED_SUITE: &str = include_str!("../../tests/perftsuite.epd");
+
+struct Options {
+    max_depth: u32,
+    threads: usize,
+    path: Option<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        max_depth: 4,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        path: None,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut number = |name: &str| {
+            args.next()
+                .and_then(|value| value.parse().ok())
+                .ok_or(format!("{name} needs a number"))
+        };
+        match arg.as_str() {
+            "--depth" => options.max_depth = number("--depth")? as u32,
+            "--threads" => options.threads = number("--threads")?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+            _ => options.path = Some(arg),
+        }
+    }
+    Ok(options)
+}
+
+// the node count after each root move, keyed by uci so both generators line up.
+fn divide(counts: Vec<(Move, u64)>) -> BTreeMap<String, u64> {
+    counts
+        .into_iter()
+        .map(|(move_i, nodes)| (move_i.to_string(), nodes))
+        .collect()
+}
+
+fn print_divide_diff(case: &PerftCase, depth: u32) {
+    let mut board = case.board.clone();
+    let ours = divide(perft_divide(&mut board, depth));
+    let reference = divide(perft_divide_filtered(&mut board, depth));
+    let mut moves: Vec<&String> = ours.keys().chain(reference.keys()).collect();
+    moves.sort();
+    moves.dedup();
+    println!("  divide at depth {depth} (move generator / filtering generator):");
+    for mv in moves {
+        let show = |count: Option<&u64>| count.map_or("-".to_string(), u64::to_string);
+        let (a, b) = (ours.get(mv), reference.get(mv));
+        let marker = if a == b { " " } else { "*" };
+        println!("  {marker} {mv}: {} / {}", show(a), show(b));
+    }
+
+    // follow the first differing move down to where the move lists part ways.
+    let as_strings = |board: &ChessBoard, depth| {
+        Ok(divide(perft_divide_filtered(&mut board.clone(), depth))
+            .into_iter()
+            .collect())
+    };
+    let root: Vec<(String, u64)> = reference.into_iter().collect();
+    match find_divergence(&case.board, depth, &root, as_strings) {
+        Ok(Some(divergence)) => println!("  first divergence: {divergence}"),
+        Ok(None) => println!("  both generators agree, so the expected count may be wrong"),
+        Err(err) => println!("  {err}"),
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}");
+            eprintln!("usage: perft [--depth N] [--threads N] [suite.epd]");
+            return ExitCode::FAILURE;
+        }
+    };
+    let suite = match &options.path {
+        Some(path) => load_perft_suite(path).map_err(|err| format!("{path}: {err}")),
+        None => parse_perft_suite(BUNDLED_SUITE).map_err(|err| err.to_string()),
+    };
+    let suite = match suite {
+        Ok(suite) => suite,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let start = Instant::now();
+    let (mut nodes, mut checked, mut failed) = (0, 0, 0);
+    for case in &suite {
+        for &(depth, expected) in &case.expected {
+            if depth > options.max_depth {
+                continue;
+            }
+            let counted = perft_parallel(&case.board, depth, options.threads);
+            nodes += counted;
+            checked += 1;
+            if counted != expected {
+                failed += 1;
+                println!(
+                    "FAIL {} depth {depth}: expected {expected}, got {counted}",
+                    case.fen
+                );
+                print_divide_diff(case, depth);
+                break;
+            }
+        }
+    }
+    println!(
+        "{} positions, {checked} counts checked, {failed} failed, {nodes} nodes in {:?}",
+        suite.len(),
+        start.elapsed()
+    );
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
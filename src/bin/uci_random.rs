@@ -0,0 +1,71 @@
+// a UCI engine that plays a random legal move, for testing GUIs and the protocol layer.
+// run with `cargo run --bin uci_random` and type UCI commands, or point a GUI at the binary.
+
+use oxi_chess_lib::game::ChessGame;
+use oxi_chess_lib::types::Move;
+use oxi_chess_lib::uci::{GoParams, SearchInfo, UciSearcher, UciSession};
+use std::io::{self, Write};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc;
+use std::thread;
+
+struct RandomSearcher;
+
+impl UciSearcher for RandomSearcher {
+    fn name(&self) -> String {
+        "oxi random".to_string()
+    }
+
+    fn author(&self) -> String {
+        "oxi_chess_lib".to_string()
+    }
+
+    fn search(
+        &mut self,
+        game: &mut ChessGame,
+        params: &GoParams,
+        _stop: &AtomicBool,
+        info: &mut dyn FnMut(SearchInfo),
+    ) -> Option<Move> {
+        let candidates: Vec<Move> = game
+            .legal_moves
+            .iter()
+            .copied()
+            .filter(|mv| {
+                params.searchmoves.is_empty() || params.searchmoves.contains(&mv.to_string())
+            })
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        let best = candidates[rand::random_range(0..candidates.len())];
+        info(SearchInfo {
+            depth: Some(1),
+            nodes: Some(candidates.len() as u64),
+            pv: vec![best],
+            ..SearchInfo::default()
+        });
+        Some(best)
+    }
+}
+
+fn main() {
+    let (out, responses) = mpsc::channel();
+    let writer = thread::spawn(move || {
+        let mut stdout = io::stdout().lock();
+        for response in responses {
+            if writeln!(stdout, "{response}")
+                .and_then(|_| stdout.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let mut session = UciSession::new(RandomSearcher, out);
+    session.run(io::stdin().lock());
+    // dropping the session closes the channel, so the writer ends after the last response.
+    drop(session);
+    writer.join().unwrap();
+}
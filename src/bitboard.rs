@@ -0,0 +1,404 @@
+// this file defines the Bitboard type: a set of squares packed into a u64 (LSB = a1).
+
+use crate::types::Square;
+use std::fmt;
+use std::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr, Sub, SubAssign,
+};
+
+/// A set of squares, one bit per square with bit 0 = a1 and bit 63 = h8.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+    pub const FULL: Bitboard = Bitboard(u64::MAX);
+
+    pub const FILE_A: Bitboard = Bitboard(0x0101010101010101);
+    pub const FILE_B: Bitboard = Bitboard(0x0101010101010101 << 1);
+    pub const FILE_C: Bitboard = Bitboard(0x0101010101010101 << 2);
+    pub const FILE_D: Bitboard = Bitboard(0x0101010101010101 << 3);
+    pub const FILE_E: Bitboard = Bitboard(0x0101010101010101 << 4);
+    pub const FILE_F: Bitboard = Bitboard(0x0101010101010101 << 5);
+    pub const FILE_G: Bitboard = Bitboard(0x0101010101010101 << 6);
+    pub const FILE_H: Bitboard = Bitboard(0x0101010101010101 << 7);
+
+    pub const RANK_1: Bitboard = Bitboard(0xFF);
+    pub const RANK_2: Bitboard = Bitboard(0xFF << 8);
+    pub const RANK_3: Bitboard = Bitboard(0xFF << 16);
+    pub const RANK_4: Bitboard = Bitboard(0xFF << 24);
+    pub const RANK_5: Bitboard = Bitboard(0xFF << 32);
+    pub const RANK_6: Bitboard = Bitboard(0xFF << 40);
+    pub const RANK_7: Bitboard = Bitboard(0xFF << 48);
+    pub const RANK_8: Bitboard = Bitboard(0xFF << 56);
+
+    /// Files a..h, indexed by `Square::file()`.
+    pub const FILES: [Bitboard; 8] = [
+        Self::FILE_A,
+        Self::FILE_B,
+        Self::FILE_C,
+        Self::FILE_D,
+        Self::FILE_E,
+        Self::FILE_F,
+        Self::FILE_G,
+        Self::FILE_H,
+    ];
+
+    /// Ranks 1..8, indexed by `Square::rank()`.
+    pub const RANKS: [Bitboard; 8] = [
+        Self::RANK_1,
+        Self::RANK_2,
+        Self::RANK_3,
+        Self::RANK_4,
+        Self::RANK_5,
+        Self::RANK_6,
+        Self::RANK_7,
+        Self::RANK_8,
+    ];
+
+    pub const fn from_square(sq: Square) -> Self {
+        Bitboard(1 << sq.index())
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn is_nonempty(self) -> bool {
+        self.0 != 0
+    }
+
+    pub const fn contains(self, sq: Square) -> bool {
+        self.0 & (1 << sq.index()) != 0
+    }
+
+    /// Whether the two sets share at least one square.
+    pub const fn intersects(self, other: Bitboard) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub const fn popcount(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Whether more than one square is set.
+    pub const fn has_many(self) -> bool {
+        self.0 & self.0.wrapping_sub(1) != 0
+    }
+
+    /// The lowest square in the set (closest to a1).
+    pub const fn lsb(self) -> Option<Square> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(Square::new(self.0.trailing_zeros() as u8))
+        }
+    }
+
+    /// The highest square in the set (closest to h8).
+    pub const fn msb(self) -> Option<Square> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some(Square::new(63 - self.0.leading_zeros() as u8))
+        }
+    }
+
+    /// Removes and returns the lowest square in the set.
+    pub fn pop_lsb(&mut self) -> Option<Square> {
+        let sq = self.lsb()?;
+        self.0 &= self.0 - 1;
+        Some(sq)
+    }
+
+    // edge-aware shifts: squares that would wrap around to the other side of the board are dropped.
+    pub const fn north(self) -> Self {
+        Bitboard(self.0 << 8)
+    }
+
+    pub const fn south(self) -> Self {
+        Bitboard(self.0 >> 8)
+    }
+
+    pub const fn east(self) -> Self {
+        Bitboard((self.0 & !Self::FILE_H.0) << 1)
+    }
+
+    pub const fn west(self) -> Self {
+        Bitboard((self.0 & !Self::FILE_A.0) >> 1)
+    }
+
+    pub const fn north_east(self) -> Self {
+        Bitboard((self.0 & !Self::FILE_H.0) << 9)
+    }
+
+    pub const fn north_west(self) -> Self {
+        Bitboard((self.0 & !Self::FILE_A.0) << 7)
+    }
+
+    pub const fn south_east(self) -> Self {
+        Bitboard((self.0 & !Self::FILE_H.0) >> 7)
+    }
+
+    pub const fn south_west(self) -> Self {
+        Bitboard((self.0 & !Self::FILE_A.0) >> 9)
+    }
+
+    // const versions of the set operators, for use in compile-time table generation.
+    pub const fn union(self, other: Bitboard) -> Self {
+        Bitboard(self.0 | other.0)
+    }
+
+    pub const fn intersection(self, other: Bitboard) -> Self {
+        Bitboard(self.0 & other.0)
+    }
+
+    pub const fn without(self, other: Bitboard) -> Self {
+        Bitboard(self.0 & !other.0)
+    }
+
+    /// Iterates over the squares in the set, from a1 towards h8.
+    pub const fn squares(self) -> Squares {
+        Squares(self)
+    }
+}
+
+/// Iterator over the squares of a `Bitboard`, lowest first.
+#[derive(Debug, Clone)]
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        self.0.pop_lsb()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.popcount() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Squares {}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = Squares;
+
+    fn into_iter(self) -> Squares {
+        self.squares()
+    }
+}
+
+impl FromIterator<Square> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = Square>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Bitboard::EMPTY, |bb, sq| bb | Bitboard::from(sq))
+    }
+}
+
+impl From<Square> for Bitboard {
+    fn from(sq: Square) -> Self {
+        Bitboard::from_square(sq)
+    }
+}
+
+impl From<u64> for Bitboard {
+    fn from(bits: u64) -> Self {
+        Bitboard(bits)
+    }
+}
+
+impl From<Bitboard> for u64 {
+    fn from(bb: Bitboard) -> Self {
+        bb.0
+    }
+}
+
+macro_rules! impl_bit_op {
+    ($trait:ident, $fn:ident, $assign_trait:ident, $assign_fn:ident, $op:tt) => {
+        impl $trait for Bitboard {
+            type Output = Bitboard;
+
+            fn $fn(self, rhs: Bitboard) -> Bitboard {
+                Bitboard(self.0 $op rhs.0)
+            }
+        }
+
+        impl $trait<Square> for Bitboard {
+            type Output = Bitboard;
+
+            fn $fn(self, rhs: Square) -> Bitboard {
+                Bitboard(self.0 $op rhs.bb().0)
+            }
+        }
+
+        impl $assign_trait for Bitboard {
+            fn $assign_fn(&mut self, rhs: Bitboard) {
+                self.0 = self.0 $op rhs.0;
+            }
+        }
+
+        impl $assign_trait<Square> for Bitboard {
+            fn $assign_fn(&mut self, rhs: Square) {
+                self.0 = self.0 $op rhs.bb().0;
+            }
+        }
+    };
+}
+
+impl_bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+impl_bit_op!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+impl_bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+// set difference: `a - b` is the squares of `a` not in `b`.
+impl Sub for Bitboard {
+    type Output = Bitboard;
+
+    fn sub(self, rhs: Bitboard) -> Bitboard {
+        Bitboard(self.0 & !rhs.0)
+    }
+}
+
+impl SubAssign for Bitboard {
+    fn sub_assign(&mut self, rhs: Bitboard) {
+        self.0 &= !rhs.0;
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    fn not(self) -> Bitboard {
+        Bitboard(!self.0)
+    }
+}
+
+// raw shifts, for when wrapping across the board edge is intended or already masked off.
+impl Shl<u8> for Bitboard {
+    type Output = Bitboard;
+
+    fn shl(self, rhs: u8) -> Bitboard {
+        Bitboard(self.0 << rhs)
+    }
+}
+
+impl Shr<u8> for Bitboard {
+    type Output = Bitboard;
+
+    fn shr(self, rhs: u8) -> Bitboard {
+        Bitboard(self.0 >> rhs)
+    }
+}
+
+impl fmt::Display for Bitboard {
+    // an 8x8 grid from white's point of view, 'X' for set squares and '.' for empty ones.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rank in (0..8).rev() {
+            for file in 0..8 {
+                let sq = Square::from_file_rank(file, rank);
+                let c = if self.contains(sq) { 'X' } else { '.' };
+                write!(f, "{c}")?;
+                if file < 7 {
+                    write!(f, " ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Binary for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Binary::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_and_ranks() {
+        for i in 0..64 {
+            let sq = Square::new(i);
+            let bb = Bitboard::from(sq);
+            assert!(Bitboard::FILES[sq.file() as usize].contains(sq));
+            assert!(Bitboard::RANKS[sq.rank() as usize].contains(sq));
+            assert_eq!(
+                (Bitboard::FILES[sq.file() as usize] & Bitboard::RANKS[sq.rank() as usize]),
+                bb
+            );
+        }
+        assert_eq!(
+            Bitboard::FILES.iter().fold(Bitboard::EMPTY, |a, &b| a | b),
+            Bitboard::FULL
+        );
+        assert_eq!(Bitboard::RANK_8.popcount(), 8);
+    }
+
+    #[test]
+    fn test_iteration() {
+        let mut bb = Bitboard(0x8000_0000_1000_0001); // a1, e4, h8
+        let squares: Vec<String> = bb.into_iter().map(|sq| sq.to_string()).collect();
+        assert_eq!(squares, ["a1", "e4", "h8"]);
+        assert_eq!(bb.squares().len(), 3);
+        assert_eq!(bb.squares().collect::<Bitboard>(), bb);
+
+        assert_eq!(bb.lsb(), Some(Square::A1));
+        assert_eq!(bb.msb(), Some(Square::H8));
+        assert_eq!(bb.pop_lsb(), Some(Square::A1));
+        assert_eq!(bb.popcount(), 2);
+        assert!(bb.has_many());
+        assert_eq!(Bitboard::EMPTY.lsb(), None);
+        assert!(!Bitboard::from(Square::E1).has_many());
+    }
+
+    #[test]
+    fn test_shifts() {
+        let e4: Bitboard = "e4".parse::<Square>().unwrap().into();
+        let sq = |s: &str| Bitboard::from(s.parse::<Square>().unwrap());
+        assert_eq!(e4.north(), sq("e5"));
+        assert_eq!(e4.south(), sq("e3"));
+        assert_eq!(e4.east(), sq("f4"));
+        assert_eq!(e4.west(), sq("d4"));
+        assert_eq!(e4.north_east(), sq("f5"));
+        assert_eq!(e4.north_west(), sq("d5"));
+        assert_eq!(e4.south_east(), sq("f3"));
+        assert_eq!(e4.south_west(), sq("d3"));
+
+        // nothing wraps around the edges.
+        assert!(Bitboard::FILE_H.east().is_empty());
+        assert!(Bitboard::FILE_H.north_east().is_empty());
+        assert!(Bitboard::FILE_H.south_east().is_empty());
+        assert!(Bitboard::FILE_A.west().is_empty());
+        assert!(Bitboard::FILE_A.north_west().is_empty());
+        assert!(Bitboard::FILE_A.south_west().is_empty());
+        assert!(Bitboard::RANK_8.north().is_empty());
+        assert!(Bitboard::RANK_1.south().is_empty());
+        assert_eq!(Bitboard::FILE_A.east(), Bitboard::FILE_B);
+    }
+
+    #[test]
+    fn test_operators() {
+        let a = Bitboard::RANK_1;
+        let b = Bitboard::FILE_A;
+        assert_eq!((a & b), Bitboard::from(Square::A1));
+        assert_eq!((a | b).popcount(), 15);
+        assert_eq!((a ^ b).popcount(), 14);
+        assert_eq!((a - b).popcount(), 7);
+        assert_eq!(!Bitboard::EMPTY, Bitboard::FULL);
+        assert_eq!(a & Square::H1, Bitboard::from(Square::H1));
+
+        let mut c = Bitboard::EMPTY;
+        c |= Square::E1;
+        c |= Bitboard::from(Square::E8);
+        assert_eq!(c.popcount(), 2);
+        c -= Bitboard::RANK_8;
+        assert_eq!(c, Bitboard::from(Square::E1));
+        assert!(c.intersects(Bitboard::FILE_E));
+        assert!(!c.intersects(Bitboard::FILE_D));
+    }
+}
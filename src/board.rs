@@ -1,55 +1,102 @@
+use crate::bitboard::Bitboard;
+use crate::error::{ChessError, FenField};
+use crate::moves;
+use crate::rules;
+use crate::types::{Color, Move, Piece, PieceKind, Square};
 use crate::utils;
 use crate::zobrist_keys::ZOBRIST_CASTLING;
 use crate::zobrist_keys::ZOBRIST_EP;
 use crate::zobrist_keys::ZOBRIST_PIECES;
 use crate::zobrist_keys::ZOBRIST_SIDE;
+use std::fmt;
+use std::num::{IntErrorKind, ParseIntError};
 
 /// Struct representing a chess board.
 /// We will let the least significant bit represent the a1 square.
 #[derive(Debug, PartialEq, Clone, Eq)]
 pub struct ChessBoard {
-    pub pawns: u64,
-    pub knights: u64,
-    pub bishops: u64,
-    pub rooks: u64,
-    pub queens: u64,
-    pub kings: u64,
-    pub white_pieces: u64,
-    pub black_pieces: u64,
-    pub side_to_move: bool,   // let true = white to move
-    pub en_passant: u64, // let u64 = en passant target (location of capture square). Let value = 0 if no en passant is possible.
+    pub pawns: Bitboard,
+    pub knights: Bitboard,
+    pub bishops: Bitboard,
+    pub rooks: Bitboard,
+    pub queens: Bitboard,
+    pub kings: Bitboard,
+    pub white_pieces: Bitboard,
+    pub black_pieces: Bitboard,
+    pub side_to_move: Color,
+    pub en_passant: Bitboard, // en passant target (location of capture square). Empty if no en passant is possible.
     pub castling_rights: u8, // uses 4 least significant bits (from most sig to least sig: white kingside, white queenside, black kingside, black queenside)
-    pub halfmove_clock: u8,  // tracks half moves since last capture or pawn move.
-    pub fullmove_number: u16, // tracks full moves since start of game.
+    pub castling_rook_files: [u8; 4], // start file (0 = a) of the rook for each castling right, in the order white kingside, white queenside, black kingside, black queenside.
+    pub chess960: bool,               // if true, castling moves are generated as king-takes-rook.
+    pub halfmove_clock: u8,           // tracks half moves since last capture or pawn move.
+    pub fullmove_number: u16,         // tracks full moves since start of game.
     pub zobrist_hash: u64,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct UndoInfo {
     halfmove_clock: u8,
+    fullmove_number: u16,
     castling_rights: u8,
     en_passant_square: Option<u8>,
     captured_type: Option<u8>, // let (pawn, knight, bishop, rook, queen) = (0, 1, 2, 3, 4)
     zobrist_hash: u64,
 }
 
+// castling right bits, in the order used by ZOBRIST_CASTLING and castling_rook_files.
+pub(crate) const CASTLING_BITS: [u8; 4] = [0b1000, 0b0100, 0b0010, 0b0001];
+const STANDARD_ROOK_FILES: [u8; 4] = [7, 0, 7, 0];
+
+/// The squares a castling move touches. In Chess960 the king or rook may already stand on its
+/// destination, or on the other piece's destination.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct CastleSquares {
+    pub right: usize, // index into CASTLING_BITS / castling_rook_files
+    pub king_from: Square,
+    pub king_to: Square,
+    pub rook_from: Square,
+    pub rook_to: Square,
+}
+
+impl CastleSquares {
+    // squares the king stands on, passes over or lands on; none may be attacked.
+    pub fn king_path(&self) -> Bitboard {
+        rank_span(self.king_from, self.king_to)
+    }
+
+    // squares that must be empty, apart from the castling king and rook themselves.
+    pub fn must_be_empty(&self) -> Bitboard {
+        (rank_span(self.king_from, self.king_to) | rank_span(self.rook_from, self.rook_to))
+            - self.king_from.bb()
+            - self.rook_from.bb()
+    }
+}
+
+// every square from a to b inclusive; a and b must share a rank.
+fn rank_span(a: Square, b: Square) -> Bitboard {
+    let (low, high) = (a.index().min(b.index()), a.index().max(b.index()));
+    Bitboard((u64::MAX >> (63 - high)) & (u64::MAX << low))
+}
+
 /// Creates a new chess board with the standard starting position.
 impl ChessBoard {
     pub fn initialize() -> Self {
         let mut board = ChessBoard {
-            pawns: 0x00FF00000000FF00,   // pawns at ranks 2 & 7.
-            knights: 0x4200000000000042, // knights at b1, g1, b8, & g8.
-            bishops: 0x2400000000000024, // bishops at c1, f1, c8, & f8.
-            rooks: 0x8100000000000081,   // rooks at a1, h1, a8, & h8.
-            queens: 0x0800000000000008,  // queens at d1 & d8.
-            kings: 0x1000000000000010,   // kings at e1 & e8.
-
-            white_pieces: 0x000000000000FFFF,
-            black_pieces: 0xFFFF000000000000,
-
-            side_to_move: true,
-            en_passant: 0,
+            pawns: Bitboard(0x00FF00000000FF00),   // pawns at ranks 2 & 7.
+            knights: Bitboard(0x4200000000000042), // knights at b1, g1, b8, & g8.
+            bishops: Bitboard(0x2400000000000024), // bishops at c1, f1, c8, & f8.
+            rooks: Bitboard(0x8100000000000081),   // rooks at a1, h1, a8, & h8.
+            queens: Bitboard(0x0800000000000008),  // queens at d1 & d8.
+            kings: Bitboard(0x1000000000000010),   // kings at e1 & e8.
+
+            white_pieces: Bitboard(0x000000000000FFFF),
+            black_pieces: Bitboard(0xFFFF000000000000),
+
+            side_to_move: Color::White,
+            en_passant: Bitboard::EMPTY,
             castling_rights: 0b1111,
+            castling_rook_files: STANDARD_ROOK_FILES,
+            chess960: false,
             halfmove_clock: 0,
             fullmove_number: 1,
             zobrist_hash: 0,
@@ -58,22 +105,87 @@ impl ChessBoard {
         board
     }
 
+    /// Creates the Chess960 start position with the given Scharnagl index (518 is the standard
+    /// position). Castling moves on the board are generated king-takes-rook.
+    /// Panics if `index` is not below 960.
+    pub fn initialize_chess960(index: u16) -> Self {
+        assert!(index < 960, "Chess960 index out of range");
+        let mut back_rank: [Option<PieceKind>; 8] = [None; 8];
+        let mut n = index as usize;
+
+        back_rank[(n % 4) * 2 + 1] = Some(PieceKind::Bishop); // light-squared bishop
+        n /= 4;
+        back_rank[(n % 4) * 2] = Some(PieceKind::Bishop); // dark-squared bishop
+        n /= 4;
+        let mut place_on_nth_empty = |kind: PieceKind, nth: usize| {
+            let file = (0..8).filter(|&f| back_rank[f].is_none()).nth(nth).unwrap();
+            back_rank[file] = Some(kind);
+        };
+        place_on_nth_empty(PieceKind::Queen, n % 6);
+        n /= 6;
+        // knight placements among the five remaining files, in Scharnagl order.
+        const KNIGHTS: [(usize, usize); 10] = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ];
+        let (first, second) = KNIGHTS[n];
+        // once the first knight is placed, the second one's file is one fewer empty file along.
+        place_on_nth_empty(PieceKind::Knight, first);
+        place_on_nth_empty(PieceKind::Knight, second - 1);
+        // rook, king, rook on the three files left over.
+        for kind in [PieceKind::Rook, PieceKind::King, PieceKind::Rook] {
+            place_on_nth_empty(kind, 0);
+        }
+
+        let mut board = ChessBoard::empty();
+        for (file, kind) in back_rank.into_iter().enumerate() {
+            let white_sq = Square::from_file_rank(file as u8, 0).bb();
+            let black_sq = Square::from_file_rank(file as u8, 7).bb();
+            *board.pieces_mut(kind.unwrap()) |= white_sq | black_sq;
+            board.white_pieces |= white_sq;
+            board.black_pieces |= black_sq;
+        }
+        board.pawns = Bitboard::RANK_2 | Bitboard::RANK_7;
+        board.white_pieces |= Bitboard::RANK_2;
+        board.black_pieces |= Bitboard::RANK_7;
+
+        let mut rook_files = (board.rooks & Bitboard::RANK_1)
+            .into_iter()
+            .map(Square::file);
+        let (queenside, kingside) = (rook_files.next().unwrap(), rook_files.next().unwrap());
+        board.castling_rook_files = [kingside, queenside, kingside, queenside];
+        board.castling_rights = 0b1111;
+        board.chess960 = true;
+        board.zobrist_hash = board.generate_zobrist_hash();
+        board
+    }
+
     /// Creates a new empty chess board. White to move by default.
     pub fn empty() -> Self {
         let mut board = ChessBoard {
-            pawns: 0,
-            knights: 0,
-            bishops: 0,
-            rooks: 0,
-            queens: 0,
-            kings: 0,
-
-            white_pieces: 0,
-            black_pieces: 0,
-
-            side_to_move: true, // white to move by default.
-            en_passant: 0,
+            pawns: Bitboard::EMPTY,
+            knights: Bitboard::EMPTY,
+            bishops: Bitboard::EMPTY,
+            rooks: Bitboard::EMPTY,
+            queens: Bitboard::EMPTY,
+            kings: Bitboard::EMPTY,
+
+            white_pieces: Bitboard::EMPTY,
+            black_pieces: Bitboard::EMPTY,
+
+            side_to_move: Color::White, // white to move by default.
+            en_passant: Bitboard::EMPTY,
             castling_rights: 0,
+            castling_rook_files: STANDARD_ROOK_FILES,
+            chess960: false,
             halfmove_clock: 0,
             fullmove_number: 1,
             zobrist_hash: 0,
@@ -82,128 +194,183 @@ impl ChessBoard {
         board
     }
 
-    /// Creates a new chess board from a FEN string.
-    pub fn initialize_from_fen(fen: &str) -> Result<Self, String> {
-        if !verify_fen(&fen) {
-            return Err("Invalid FEN string.".to_string());
-        } else {
-            let fen_components: Vec<&str> = fen.split_whitespace().collect();
-
-            // parse fen_components[0] (piece locations).
-            let ranks: Vec<&str> = fen_components[0].split('/').collect();
-            let mut square = 0;
-
-            let mut pawns1: u64 = 0;
-            let mut knights1: u64 = 0;
-            let mut bishops1: u64 = 0;
-            let mut rooks1: u64 = 0;
-            let mut queens1: u64 = 0;
-            let mut kings1: u64 = 0;
-
-            let mut white_pieces1: u64 = 0;
-            let mut black_pieces1: u64 = 0;
-
-            for rank in ranks {
-                for ch in rank.chars().rev() {
-                    // increment by 1 for alphabetical values and set piece_type[bit] = 1,
-                    // increment by numerical for numberical values
-                    if ch.is_ascii_alphabetic() {
-                        match ch {
-                            'k' | 'K' => kings1 |= 1 << (63 - square),
-                            'q' | 'Q' => queens1 |= 1 << (63 - square),
-                            'r' | 'R' => rooks1 |= 1 << (63 - square),
-                            'b' | 'B' => bishops1 |= 1 << (63 - square),
-                            'n' | 'N' => knights1 |= 1 << (63 - square),
-                            'p' | 'P' => pawns1 |= 1 << (63 - square),
-                            _ => (),
-                        }
-                        match ch.is_uppercase() {
-                            true => white_pieces1 |= 1 << (63 - square),
-                            false => black_pieces1 |= 1 << (63 - square),
+    /// Creates a new chess board from a FEN string. The halfmove clock and fullmove number may be
+    /// left off (EPD style), in which case they default to 0 and 1.
+    pub fn initialize_from_fen(fen: &str) -> Result<Self, ChessError> {
+        let fen_components: Vec<&str> = fen.split_whitespace().collect();
+        if fen_components.len() != 6 && fen_components.len() != 4 {
+            return Err(fen_error(
+                FenField::Record,
+                format!(
+                    "expected 6 fields (or 4 without clocks), found {}",
+                    fen_components.len()
+                ),
+            ));
+        }
+
+        let mut board = ChessBoard::empty();
+        parse_placement(&mut board, fen_components[0])?;
+
+        board.side_to_move = match fen_components[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => {
+                return Err(fen_error(
+                    FenField::SideToMove,
+                    format!("expected 'w' or 'b', found '{other}'"),
+                ))
+            }
+        };
+
+        // the side that just moved can't have left its king attacked.
+        if rules::is_check(&board, !board.side_to_move) {
+            let waiting = if board.side_to_move.is_white() {
+                "black"
+            } else {
+                "white"
+            };
+            return Err(fen_error(
+                FenField::SideToMove,
+                format!("{waiting} is in check but not to move"),
+            ));
+        }
+
+        parse_castling(&mut board, fen_components[2])?;
+        board.en_passant = parse_en_passant(&board, fen_components[3])?;
+
+        if fen_components.len() == 6 {
+            board.halfmove_clock = parse_clock(fen_components[4], FenField::HalfmoveClock)?;
+            board.fullmove_number = parse_clock(fen_components[5], FenField::FullmoveNumber)?;
+            if board.fullmove_number == 0 {
+                return Err(fen_error(
+                    FenField::FullmoveNumber,
+                    "must be at least 1".to_string(),
+                ));
+            }
+        }
+
+        board.zobrist_hash = board.generate_zobrist_hash();
+        Ok(board)
+    }
+
+    /// Serializes the board to a FEN string. Round-trips with `initialize_from_fen`.
+    /// Chess960 castling rights are written X-FEN style: `KQkq` for the outermost rooks, the rook's
+    /// file letter otherwise.
+    pub fn to_fen(&self) -> String {
+        self.fen_with_castling(false)
+    }
+
+    /// Serializes the board to a Shredder-FEN string, naming castling rooks by file (`HAha`).
+    pub fn to_shredder_fen(&self) -> String {
+        self.fen_with_castling(true)
+    }
+
+    fn fen_with_castling(&self, shredder: bool) -> String {
+        let mut fen = String::with_capacity(90);
+
+        // piece placement, rank 8 down to rank 1, files a through h.
+        for rank in (0..8u8).rev() {
+            let mut empty_count = 0;
+            for file in 0..8u8 {
+                match self.piece_at(Square::from_file_rank(file, rank)) {
+                    Some(piece) => {
+                        if empty_count > 0 {
+                            fen.push(char::from(b'0' + empty_count));
+                            empty_count = 0;
                         }
-                        square += 1;
-                    } else {
-                        square += ch.to_digit(10).unwrap();
+                        fen.push(piece.fen_char());
                     }
+                    None => empty_count += 1,
                 }
             }
-
-            // parse fen_components[2] (castling rights)
-            let mut castling_rights1: u8 = 0;
-            for ch in fen_components[2].chars() {
-                match ch {
-                    'K' => castling_rights1 |= 1 << 3,
-                    'Q' => castling_rights1 |= 1 << 2,
-                    'k' => castling_rights1 |= 1 << 1,
-                    'q' => castling_rights1 |= 1 << 0,
-                    _ => (),
-                }
+            if empty_count > 0 {
+                fen.push(char::from(b'0' + empty_count));
             }
-
-            // parse fen_components[3] (en passant target square)
-            let ep_square: u64;
-            if fen_components[3].len() == 2 {
-                ep_square = utils::square_to_bb(&fen_components[3]).unwrap();
-            } else if fen_components[3] == '-'.to_string() {
-                ep_square = 0;
-            } else {
-                return Err("Invalid en passant square".to_string());
+            if rank > 0 {
+                fen.push('/');
             }
+        }
 
-            let mut board = ChessBoard {
-                pawns: pawns1,
-                knights: knights1,
-                bishops: bishops1,
-                rooks: rooks1,
-                queens: queens1,
-                kings: kings1,
-
-                white_pieces: white_pieces1,
-                black_pieces: black_pieces1,
-
-                // parse fen_components[1] (color to move)
-                side_to_move: match fen_components[1] {
-                    "w" => true,
-                    _ => false,
-                },
-                en_passant: ep_square,
-                castling_rights: castling_rights1,
-                halfmove_clock: fen_components[4].parse::<u8>().unwrap(),
-                fullmove_number: fen_components[5].parse::<u16>().unwrap(),
-                zobrist_hash: 0,
-            };
-            board.zobrist_hash = board.generate_zobrist_hash();
+        fen.push(' ');
+        fen.push_str(&self.side_to_move.to_string());
+
+        // castling rights, same bit order as parsing (K = bit 3 ... q = bit 0).
+        fen.push(' ');
+        if self.castling_rights & 0b1111 == 0 {
+            fen.push('-');
+        } else {
+            for (right, ch) in ['K', 'Q', 'k', 'q'].into_iter().enumerate() {
+                if self.castling_rights & CASTLING_BITS[right] == 0 {
+                    continue;
+                }
+                let file = self.castling_rook_files[right];
+                let default_file = STANDARD_ROOK_FILES[right];
+                if shredder || outermost_rook_file(self, right).unwrap_or(default_file) != file {
+                    let letter = char::from(b'a' + file);
+                    fen.push(if right < 2 {
+                        letter.to_ascii_uppercase()
+                    } else {
+                        letter
+                    });
+                } else {
+                    fen.push(ch);
+                }
+            }
+        }
 
-            return Ok(board);
+        fen.push(' ');
+        match self.en_passant.lsb() {
+            None => fen.push('-'),
+            Some(ep_sq) => fen.push_str(&ep_sq.to_string()),
         }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+        fen
     }
 
     #[inline(always)]
-    pub fn is_occupied(&self, sq_i: u8) -> bool {
-        (1 << sq_i) & (self.white_pieces | self.black_pieces) != 0
+    pub fn is_occupied(&self, sq: Square) -> bool {
+        (self.white_pieces | self.black_pieces).contains(sq)
+    }
+
+    pub fn piece_type_at(&self, sq: Square) -> Option<PieceKind> {
+        self.piece_code_at(sq.index())
+            .and_then(PieceKind::from_index)
     }
 
-    // let (pawn, knight, bishop, rook, queen, king) = (0, 1, 2, 3, 4, 5)
+    pub fn piece_at(&self, sq: Square) -> Option<Piece> {
+        let kind = self.piece_type_at(sq)?;
+        let color = Color::from(self.white_pieces.contains(sq));
+        Some(Piece::new(color, kind))
+    }
+
+    // raw piece code used by make/unmake: (pawn, knight, bishop, rook, queen, king) = (0, 1, 2, 3, 4, 5)
     #[rustfmt::skip]
-    pub fn piece_type_at(&self, sq_i: u8) -> Option<u8> {
-        let sq_bb: u64 = 1 << sq_i;
-
-        if sq_bb & self.pawns != 0 { return Some(0) };
-        if sq_bb & self.knights != 0 { return Some(1) };
-        if sq_bb & self.bishops != 0 { return Some(2) };
-        if sq_bb & self.rooks != 0 { return Some(3) };
-        if sq_bb & self.queens != 0 { return Some(4) };
-        if sq_bb & self.kings != 0 { return Some(5) };
+    pub(crate) fn piece_code_at(&self, sq_i: u8) -> Option<u8> {
+        let sq = Square::new(sq_i);
+
+        if self.pawns.contains(sq) { return Some(0) };
+        if self.knights.contains(sq) { return Some(1) };
+        if self.bishops.contains(sq) { return Some(2) };
+        if self.rooks.contains(sq) { return Some(3) };
+        if self.queens.contains(sq) { return Some(4) };
+        if self.kings.contains(sq) { return Some(5) };
         return None;
     }
 
     // move: u16, most significant 6 digits = from square, next 6 = to_square, least sig 4 digits = move type flag
     // move type flags: normal = 0, capture = 1, castle = 2, en passant = 3, promotion (n,b,r,q) = (4,5,6,7) respectively, promo w/ capture (n,b,r,q) = (8,9,10,11) respectively
     #[rustfmt::skip]
-    pub fn make_move(&mut self, move_int: u16) -> Result<UndoInfo, String> {
-        let from_sqi = (move_int >> 10) as u8;
-        let to_sqi = ((move_int >> 4) & 0b111111) as u8;
-        let flag = (move_int & 0b1111) as u8;
+    pub fn make_move(&mut self, mv: Move) -> Result<UndoInfo, ChessError> {
+        let from_sqi = mv.from().index();
+        let to_sqi = mv.to().index();
+        let flag = mv.flag();
+        if mv.is_castle() {
+            return self.make_castle(mv);
+        }
 
         // collect unmake move data
         let prev_halfmove_clock = self.halfmove_clock;
@@ -213,7 +380,7 @@ impl ChessBoard {
 
         if matches!(flag, 1 | 8 | 9 | 10 | 11) {
             // if move flag is a capture
-            capture_type = self.piece_type_at(to_sqi);
+            capture_type = self.piece_code_at(to_sqi);
         } else if flag == 3 {
             // if move flag is en passant
             capture_type = Some(0);
@@ -221,14 +388,11 @@ impl ChessBoard {
             capture_type = None;
         }
 
-        if self.en_passant == 0 {
-            prev_en_passant = None;
-        } else {
-            prev_en_passant = Some(self.en_passant.trailing_zeros() as u8);
-        }
+        prev_en_passant = self.en_passant.lsb().map(Square::index);
 
         let undo_info = UndoInfo {
             halfmove_clock: prev_halfmove_clock,
+            fullmove_number: self.fullmove_number,
             castling_rights: prev_castling_rights,
             en_passant_square: prev_en_passant,
             captured_type: capture_type,
@@ -236,28 +400,32 @@ impl ChessBoard {
         };
 
         // check if there is a piece of the color to move at the given square.
-        let from_sq_bb: u64 = 1 << from_sqi;
-        let to_sq_bb: u64 = 1 << to_sqi;
-        let orig_piece_from_type = self.piece_type_at(from_sqi); // because piece from type can be changed for promotion handling.
+        let from_sq_bb = mv.from().bb();
+        let to_sq_bb = mv.to().bb();
+        let orig_piece_from_type = self.piece_code_at(from_sqi); // because piece from type can be changed for promotion handling.
         let mut piece_from_type = orig_piece_from_type;
 
         if piece_from_type.is_none() {
-            return Err("No piece at given square.".to_string());
+            return Err(ChessError::IllegalMove("No piece at given square.".to_string()));
         } else {
-            if self.side_to_move {
-                if self.white_pieces & from_sq_bb == 0 {
-                    return Err("Black piece cannot move on white's turn.".to_string());
+            if self.side_to_move.is_white() {
+                if !self.white_pieces.intersects(from_sq_bb) {
+                    return Err(ChessError::IllegalMove("Black piece cannot move on white's turn.".to_string()));
                 }
             } else {
-                if self.black_pieces & from_sq_bb == 0 {
-                    return Err("White piece cannot move on black's turn.".to_string());
+                if !self.black_pieces.intersects(from_sq_bb) {
+                    return Err(ChessError::IllegalMove("White piece cannot move on black's turn.".to_string()));
                 }
             }
         }
+        // the piece may still not move like that: the move could come from a corrupt table entry.
+        if !moves::is_pseudo_legal(self, mv) {
+            return Err(ChessError::IllegalMove(format!("{mv} is not a valid move here.")));
+        }
 
         // update board state: piece locations, en_passant, castling rights, halfmove clock, and zobrist hash.
-        if self.en_passant != 0 {
-            self.zobrist_hash ^= ZOBRIST_EP[(self.en_passant.trailing_zeros() % 8) as usize];
+        if let Some(ep_sq) = self.en_passant.lsb() {
+            self.zobrist_hash ^= ZOBRIST_EP[ep_sq.file() as usize];
         }
 
         if piece_from_type == Some(0) {
@@ -285,180 +453,72 @@ impl ChessBoard {
 
             self.halfmove_clock = 0;
             if ((from_sqi as i16) - (to_sqi as i16)).abs() == 16 {
-                if self.side_to_move {
+                if self.side_to_move.is_white() {
                     self.en_passant = to_sq_bb >> 8;
-                    self.zobrist_hash ^= ZOBRIST_EP[(self.en_passant.trailing_zeros() % 8) as usize]; // add new en passant file
+                    self.zobrist_hash ^= ZOBRIST_EP[(to_sqi % 8) as usize]; // add new en passant file
                 } else {
                     self.en_passant = to_sq_bb << 8;
-                    self.zobrist_hash ^= ZOBRIST_EP[(self.en_passant.trailing_zeros() % 8) as usize]; // add new en passant file
+                    self.zobrist_hash ^= ZOBRIST_EP[(to_sqi % 8) as usize]; // add new en passant file
                 }
             } else {
-                self.en_passant = 0;
+                self.en_passant = Bitboard::EMPTY;
             }
         } else if piece_from_type == Some(1) {
             // knight
             self.knights &= !from_sq_bb;
             self.knights |= to_sq_bb;
-            self.en_passant = 0;
-            self.halfmove_clock += 1;
+            self.en_passant = Bitboard::EMPTY;
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
         } else if piece_from_type == Some(2) {
             self.bishops &= !from_sq_bb;
             self.bishops |= to_sq_bb;
-            self.en_passant = 0;
-            self.halfmove_clock += 1;
+            self.en_passant = Bitboard::EMPTY;
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
         } else if piece_from_type == Some(3) {
             self.rooks &= !from_sq_bb;
             self.rooks |= to_sq_bb;
-            self.en_passant = 0;
-            self.halfmove_clock += 1;
+            self.en_passant = Bitboard::EMPTY;
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
 
-            match from_sqi {
-                0 => {
-                    if self.castling_rights & 0b0100 != 0 {
-                        self.zobrist_hash ^= ZOBRIST_CASTLING[1];
-                    }
-                    self.castling_rights &= !0b0100;
-                },
-                7 => {
-                    if self.castling_rights & 0b1000 != 0 {
-                        self.zobrist_hash ^= ZOBRIST_CASTLING[0];
-                    }
-                    self.castling_rights &= !0b1000;
-                },
-                56 => {
-                    if self.castling_rights & 0b0001 != 0 {
-                        self.zobrist_hash ^= ZOBRIST_CASTLING[3];
-                    }
-                    self.castling_rights &= !0b0001;
-                },
-                63 => {
-                    if self.castling_rights & 0b0010 != 0 {
-                        self.zobrist_hash ^= ZOBRIST_CASTLING[2];
-                    }
-                    self.castling_rights &= !0b0010;
-                },
-                _ => {}
-            }
+            self.clear_rook_castling_right(from_sqi);
         } else if piece_from_type == Some(4) {
             self.queens &= !from_sq_bb;
             self.queens |= to_sq_bb;
-            self.en_passant = 0;
-            self.halfmove_clock += 1;
+            self.en_passant = Bitboard::EMPTY;
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
         } else {
             self.kings &= !from_sq_bb;
             self.kings |= to_sq_bb;
-            self.en_passant = 0;
-            self.halfmove_clock += 1;
-
-            // handle castling.
-            if flag == 2 {
-                if self.side_to_move {
-                    if to_sqi == 2 {
-                        // c1
-                        if self.castling_rights & 0b0100 != 0 {
-                            self.rooks &= !0x0000000000000001;
-                            self.zobrist_hash ^= ZOBRIST_PIECES[0][3];
-                            self.rooks |= 0x00000000000000008;
-                            self.zobrist_hash ^= ZOBRIST_PIECES[3][3];
-                            self.white_pieces &= !0x0000000000000001;
-                            self.white_pieces |= 0x00000000000000008;
-                            if self.castling_rights & 0b1000 != 0 {
-                                self.zobrist_hash ^= ZOBRIST_CASTLING[0];
-                            }
-                            self.castling_rights &= !0b1100;
-                            self.zobrist_hash ^= ZOBRIST_CASTLING[1];
-                        } else {
-                            return Err("white cannot castle queenside.".to_string());
-                        }
-                    } else if to_sqi == 6 {
-                        // g1
-                        if self.castling_rights & 0b1000 != 0 {
-                            self.rooks &= !0x0000000000000080;
-                            self.zobrist_hash ^= ZOBRIST_PIECES[7][3];
-                            self.rooks |= 0x00000000000000020;
-                            self.zobrist_hash ^= ZOBRIST_PIECES[5][3];
-                            self.white_pieces &= !0x0000000000000080;
-                            self.white_pieces |= 0x00000000000000020;
-                            if self.castling_rights & 0b0100 != 0 {
-                                self.zobrist_hash ^= ZOBRIST_CASTLING[1];
-                            }
-                            self.castling_rights &= !0b1100;
-                            self.zobrist_hash ^= ZOBRIST_CASTLING[0];
-                        } else {
-                            return Err("white cannot castle kingside.".to_string());
-                        }
-                    } else {
-                        return Err("invalid castling move.".to_string());
-                    }
-                } else {
-                    if to_sqi == 58 {
-                        // c8
-                        if self.castling_rights & 0b0001 != 0 {
-                            self.rooks &= !0x0100000000000000;
-                            self.zobrist_hash ^= ZOBRIST_PIECES[56][9];
-                            self.rooks |= 0x0800000000000000;
-                            self.zobrist_hash ^= ZOBRIST_PIECES[59][9];
-                            self.black_pieces &= !0x0100000000000000;
-                            self.black_pieces |= 0x0800000000000000;
-                            if self.castling_rights & 0b0010 != 0 {
-                                self.zobrist_hash ^= ZOBRIST_CASTLING[2];
-                            }
-                            self.castling_rights &= !0b0011;
-                            self.zobrist_hash ^= ZOBRIST_CASTLING[3];
-                        } else {
-                            return Err("black cannot castle queenside.".to_string());
-                        }
-                    } else if to_sqi == 62 {
-                        // g8
-                        if self.castling_rights & 0b0010 != 0 {
-                            self.rooks &= !0x8000000000000000;
-                            self.zobrist_hash ^= ZOBRIST_PIECES[63][9];
-                            self.rooks |= 0x2000000000000000;
-                            self.zobrist_hash ^= ZOBRIST_PIECES[61][9];
-                            self.black_pieces &= !0x8000000000000000;
-                            self.black_pieces |= 0x2000000000000000;
-                            if self.castling_rights & 0b0001 != 0 {
-                                self.zobrist_hash ^= ZOBRIST_CASTLING[3];
-                            }
-                            self.castling_rights &= !0b0011;
-                            self.zobrist_hash ^= ZOBRIST_CASTLING[2];
-                        } else {
-                            return Err("white cannot castle kingside.".to_string());
-                        }
-                    } else {
-                        return Err("invalid castling move.".to_string());
-                    }
+            self.en_passant = Bitboard::EMPTY;
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+
+            // moving the king gives up both castling rights.
+            if self.side_to_move.is_white() {
+                if self.castling_rights & 0b1000 != 0 {
+                    self.zobrist_hash ^= ZOBRIST_CASTLING[0];
                 }
+                if self.castling_rights & 0b0100 != 0 {
+                    self.zobrist_hash ^= ZOBRIST_CASTLING[1];
+                }
+                self.castling_rights &= !0b1100;
             } else {
-                if self.side_to_move {
-                    if self.castling_rights & 0b1000 != 0 {
-                        self.zobrist_hash ^= ZOBRIST_CASTLING[0];
-                    }
-                    if self.castling_rights & 0b0100 != 0 {
-                        self.zobrist_hash ^= ZOBRIST_CASTLING[1];
-                    }
-                    self.castling_rights &= !0b1100;
-
-
-                } else {
-                    if self.castling_rights & 0b0010 != 0 {
-                        self.zobrist_hash ^= ZOBRIST_CASTLING[2];
-                    }
-                    if self.castling_rights & 0b0001 != 0 {
-                        self.zobrist_hash ^= ZOBRIST_CASTLING[3];
-                    }
-                    self.castling_rights &= !0b0011;
+                if self.castling_rights & 0b0010 != 0 {
+                    self.zobrist_hash ^= ZOBRIST_CASTLING[2];
                 }
+                if self.castling_rights & 0b0001 != 0 {
+                    self.zobrist_hash ^= ZOBRIST_CASTLING[3];
+                }
+                self.castling_rights &= !0b0011;
             }
         }
 
         if capture_type.is_some() {
             if flag == 3 { // en passant
                 if piece_from_type != Some(0) {
-                    return Err("Only pawns can capture en passant.".to_string());
+                    return Err(ChessError::IllegalMove("Only pawns can capture en passant.".to_string()));
                 }
                 self.pawns &= !(to_sq_bb >> 8);
-                if self.side_to_move {
+                if self.side_to_move.is_white() {
                     self.black_pieces &= !(to_sq_bb >> 8);
                     self.pawns &= !(to_sq_bb >> 8);
                     self.zobrist_hash ^= ZOBRIST_PIECES[(to_sqi - 8) as usize][6];
@@ -476,38 +536,12 @@ impl ChessBoard {
                     Some(2) => if capture_type != piece_from_type {self.bishops &= !to_sq_bb},
                     Some(3) => {
                         if capture_type != piece_from_type {self.rooks &= !to_sq_bb};
-                        match to_sqi {
-                            0 => {
-                                if self.castling_rights & 0b0100 != 0 {
-                                    self.zobrist_hash ^= ZOBRIST_CASTLING[1];
-                                }
-                                self.castling_rights &= !0b0100;
-                            },
-                            7 => {
-                                if self.castling_rights & 0b1000 != 0 {
-                                    self.zobrist_hash ^= ZOBRIST_CASTLING[0];
-                                }
-                                self.castling_rights &= !0b1000;
-                            },
-                            56 => {
-                                if self.castling_rights & 0b0001 != 0 {
-                                    self.zobrist_hash ^= ZOBRIST_CASTLING[3];
-                                }
-                                self.castling_rights &= !0b0001;
-                            },
-                            63 => {
-                                if self.castling_rights & 0b0010 != 0 {
-                                    self.zobrist_hash ^= ZOBRIST_CASTLING[2];
-                                }
-                                self.castling_rights &= !0b0010;
-                            },
-                            _ => {}
-                        };
+                        self.clear_rook_castling_right(to_sqi);
                     }
                     Some(4) => if capture_type != piece_from_type {self.queens &= !to_sq_bb},
                     _ => {}
                 }
-                if self.side_to_move {
+                if self.side_to_move.is_white() {
                     self.black_pieces &= !to_sq_bb;
                 } else {
                     self.white_pieces &= !to_sq_bb;
@@ -515,12 +549,12 @@ impl ChessBoard {
             }
         }
 
-        if self.side_to_move {
+        if self.side_to_move.is_white() {
             self.white_pieces &= !from_sq_bb;
             self.zobrist_hash ^= ZOBRIST_PIECES[from_sqi as usize][orig_piece_from_type.unwrap() as usize];
             self.white_pieces |= to_sq_bb;
             self.zobrist_hash ^= ZOBRIST_PIECES[to_sqi as usize][piece_from_type.unwrap() as usize];
-            self.side_to_move = false;
+            self.side_to_move = Color::Black;
             self.zobrist_hash ^= ZOBRIST_SIDE;
 
             match capture_type {
@@ -532,9 +566,9 @@ impl ChessBoard {
             self.zobrist_hash ^= ZOBRIST_PIECES[from_sqi as usize][(orig_piece_from_type.unwrap() as usize) + 6];
             self.black_pieces |= to_sq_bb;
             self.zobrist_hash ^= ZOBRIST_PIECES[to_sqi as usize][(piece_from_type.unwrap() + 6) as usize];
-            self.side_to_move = true;
+            self.side_to_move = Color::White;
             self.zobrist_hash ^= ZOBRIST_SIDE;
-            self.fullmove_number += 1;
+            self.fullmove_number = self.fullmove_number.saturating_add(1);
 
             match capture_type {
                 None => {},
@@ -553,33 +587,39 @@ impl ChessBoard {
         return Ok(undo_info);
     }
 
-    pub fn unmake_move(&mut self, move_int: u16, undo_info: &UndoInfo) -> Result<(), String> {
-        // verify valid move_int to undo
+    pub fn unmake_move(&mut self, mv: Move, undo_info: &UndoInfo) -> Result<(), ChessError> {
+        // verify valid move to undo
         // check that there is a piece on the to_square
-        let from_sqi = (move_int >> 10) as u8;
-        let to_sqi = ((move_int >> 4) & 0b111111) as u8;
-        let flag = (move_int & 0b1111) as u8;
+        let to_sqi = mv.to().index();
+        let flag = mv.flag();
+        if mv.is_castle() {
+            return self.unmake_castle(mv, undo_info);
+        }
 
-        if !self.is_occupied(to_sqi) {
-            return Err("No piece at target square.".to_string());
+        if !self.is_occupied(mv.to()) {
+            return Err(ChessError::IllegalMove(
+                "No piece at target square.".to_string(),
+            ));
         };
 
         // check that there is no piece at the from square
-        if self.is_occupied(from_sqi) {
-            return Err("Piece present at from square.".to_string());
+        if self.is_occupied(mv.from()) {
+            return Err(ChessError::IllegalMove(
+                "Piece present at from square.".to_string(),
+            ));
         }
 
         // check that flag is in the right range
         if !((0..=11).contains(&(flag as i32))) {
-            return Err("Invalid flag.".to_string());
+            return Err(ChessError::IllegalMove("Invalid flag.".to_string()));
         }
 
         // undo the move: update bitboards.
 
         // if promotion flag: remove to_sq piece and place pawn on from_sq
-        let to_sq_bb: u64 = 1 << to_sqi;
-        let from_sq_bb: u64 = 1 << from_sqi;
-        let to_sq_type = self.piece_type_at(to_sqi);
+        let to_sq_bb = mv.to().bb();
+        let from_sq_bb = mv.from().bb();
+        let to_sq_type = self.piece_code_at(to_sqi);
         if (4..=11).contains(&(flag as i32)) {
             match flag {
                 4 | 8 => self.knights &= !to_sq_bb,
@@ -619,44 +659,9 @@ impl ChessBoard {
                 _ => (),
             };
         }
-        // if castle: place rook on relevant corner square and remove from castled location.
-        if flag == 2 {
-            if self.side_to_move {
-                match to_sqi {
-                    58 => {
-                        self.rooks &= !0x0800000000000000;
-                        self.rooks |= 0x0100000000000000;
-                        self.black_pieces &= !0x0800000000000000;
-                        self.black_pieces |= 0x0100000000000000;
-                    }
-                    62 => {
-                        self.rooks &= !0x2000000000000000;
-                        self.rooks |= 0x8000000000000000;
-                        self.black_pieces &= !0x2000000000000000;
-                        self.black_pieces |= 0x8000000000000000;
-                    }
-                    _ => return Err("target square not valid castling target.".to_string()),
-                };
-            } else {
-                match to_sqi {
-                    2 => {
-                        self.rooks &= !0x0000000000000008;
-                        self.rooks |= 1;
-                        self.white_pieces &= !0x0000000000000008;
-                        self.white_pieces |= 1;
-                    }
-                    6 => {
-                        self.rooks &= !0x0000000000000020;
-                        self.rooks |= 0x0000000000000080;
-                        self.white_pieces &= !0x0000000000000020;
-                        self.white_pieces |= 0x0000000000000080;
-                    }
-                    _ => return Err("target square not valid castling target.".to_string()),
-                };
-            }
-        } else if flag == 3 {
+        if flag == 3 {
             // if en passant flag: place pawn of opposite color on correct square
-            if self.side_to_move {
+            if self.side_to_move.is_white() {
                 self.pawns |= to_sq_bb << 8;
                 self.white_pieces |= to_sq_bb << 8;
             } else {
@@ -671,22 +676,26 @@ impl ChessBoard {
                 Some(2) => self.bishops |= to_sq_bb,
                 Some(3) => self.rooks |= to_sq_bb,
                 Some(4) => self.queens |= to_sq_bb,
-                Some(5) => return Err("King cannot be captured.".to_string()),
+                Some(5) => {
+                    return Err(ChessError::IllegalMove(
+                        "King cannot be captured.".to_string(),
+                    ))
+                }
                 None => {
-                    return Err(
-                        "If move flag is a capture, capture type cannot be None.".to_string()
-                    )
+                    return Err(ChessError::IllegalMove(
+                        "If move flag is a capture, capture type cannot be None.".to_string(),
+                    ))
                 }
-                _ => return Err("Invalid piece type.".to_string()),
+                _ => return Err(ChessError::IllegalMove("Invalid piece type.".to_string())),
             }
-            if self.side_to_move {
+            if self.side_to_move.is_white() {
                 self.white_pieces |= to_sq_bb;
             } else {
                 self.black_pieces |= to_sq_bb;
             }
         }
         // update color bitboards (same for all cases: remove to_sq and add from_sq for opposite color as to_move)
-        if self.side_to_move {
+        if self.side_to_move.is_white() {
             self.black_pieces &= !to_sq_bb;
             self.black_pieces |= from_sq_bb;
         } else {
@@ -694,23 +703,7 @@ impl ChessBoard {
             self.white_pieces |= from_sq_bb;
         }
 
-        // set board state = undo_info
-        (self.castling_rights, self.halfmove_clock) =
-            (undo_info.castling_rights, undo_info.halfmove_clock);
-        match undo_info.en_passant_square {
-            None => self.en_passant = 0,
-            _ => self.en_passant = 1 << undo_info.en_passant_square.unwrap(),
-        }
-        // if white is to move: full_move counter -= 1, set black to move
-        // else: set white to move
-        if self.side_to_move {
-            self.fullmove_number -= 1;
-            self.side_to_move = false;
-        } else {
-            self.side_to_move = true;
-        }
-
-        self.zobrist_hash = undo_info.zobrist_hash;
+        self.restore_state(undo_info);
 
         // COMMENT OUT WHEN DONE DEBUGGING
         /*
@@ -723,6 +716,227 @@ impl ChessBoard {
         return Ok(());
     }
 
+    // set board state = undo_info, and hand the move back to the side that made it.
+    fn restore_state(&mut self, undo_info: &UndoInfo) {
+        (
+            self.castling_rights,
+            self.halfmove_clock,
+            self.fullmove_number,
+        ) = (
+            undo_info.castling_rights,
+            undo_info.halfmove_clock,
+            undo_info.fullmove_number,
+        );
+        self.en_passant = match undo_info.en_passant_square {
+            None => Bitboard::EMPTY,
+            Some(ep_sqi) => Square::new(ep_sqi).bb(),
+        };
+        self.side_to_move = !self.side_to_move;
+
+        self.zobrist_hash = undo_info.zobrist_hash;
+    }
+
+    /// All pieces of a kind, both colors.
+    pub fn pieces(&self, kind: PieceKind) -> Bitboard {
+        match kind {
+            PieceKind::Pawn => self.pawns,
+            PieceKind::Knight => self.knights,
+            PieceKind::Bishop => self.bishops,
+            PieceKind::Rook => self.rooks,
+            PieceKind::Queen => self.queens,
+            PieceKind::King => self.kings,
+        }
+    }
+
+    /// All pieces of a color.
+    pub fn pieces_of(&self, color: Color) -> Bitboard {
+        if color.is_white() {
+            self.white_pieces
+        } else {
+            self.black_pieces
+        }
+    }
+
+    pub(crate) fn pieces_mut(&mut self, kind: PieceKind) -> &mut Bitboard {
+        match kind {
+            PieceKind::Pawn => &mut self.pawns,
+            PieceKind::Knight => &mut self.knights,
+            PieceKind::Bishop => &mut self.bishops,
+            PieceKind::Rook => &mut self.rooks,
+            PieceKind::Queen => &mut self.queens,
+            PieceKind::King => &mut self.kings,
+        }
+    }
+
+    fn color_mut(&mut self, color: Color) -> &mut Bitboard {
+        if color.is_white() {
+            &mut self.white_pieces
+        } else {
+            &mut self.black_pieces
+        }
+    }
+
+    /// Where the king and rook start and end for `color` castling on the given side, using the
+    /// board's castling rook files. None if that side has no king on its back rank.
+    pub(crate) fn castle_squares(&self, color: Color, kingside: bool) -> Option<CastleSquares> {
+        let (back_rank, own) = if color.is_white() {
+            (0, self.white_pieces)
+        } else {
+            (7, self.black_pieces)
+        };
+        let king_from = (self.kings & own & Bitboard::RANKS[back_rank as usize]).lsb()?;
+        Some(self.castle_squares_from(color, king_from, kingside))
+    }
+
+    fn castle_squares_from(
+        &self,
+        color: Color,
+        king_from: Square,
+        kingside: bool,
+    ) -> CastleSquares {
+        let back_rank = king_from.rank();
+        let right = color.index() * 2 + if kingside { 0 } else { 1 };
+        let (king_file, rook_file) = if kingside { (6, 5) } else { (2, 3) };
+        CastleSquares {
+            right,
+            king_from,
+            king_to: Square::from_file_rank(king_file, back_rank),
+            rook_from: Square::from_file_rank(self.castling_rook_files[right], back_rank),
+            rook_to: Square::from_file_rank(rook_file, back_rank),
+        }
+    }
+
+    // resolves a castling move under either encoding (king destination or king-takes-rook).
+    fn castle_for_move(&self, mv: Move, color: Color) -> Result<CastleSquares, ChessError> {
+        let back_rank = if color.is_white() { 0 } else { 7 };
+        let castle = self.castle_squares_from(color, mv.from(), mv.is_kingside_castle());
+        if mv.from().rank() == back_rank
+            && (mv.to() == castle.king_to || mv.to() == castle.rook_from)
+        {
+            Ok(castle)
+        } else {
+            Err(ChessError::IllegalMove(
+                "invalid castling move.".to_string(),
+            ))
+        }
+    }
+
+    fn make_castle(&mut self, mv: Move) -> Result<UndoInfo, ChessError> {
+        let color = self.side_to_move;
+        let castle = self.castle_for_move(mv, color)?;
+        if self.castling_rights & CASTLING_BITS[castle.right] == 0 {
+            return Err(ChessError::IllegalMove(format!(
+                "{} cannot castle {}.",
+                if color.is_white() { "white" } else { "black" },
+                if castle.right.is_multiple_of(2) {
+                    "kingside"
+                } else {
+                    "queenside"
+                }
+            )));
+        }
+        let own = if color.is_white() {
+            self.white_pieces
+        } else {
+            self.black_pieces
+        };
+        if !(self.kings & own).contains(castle.king_from) {
+            return Err(ChessError::IllegalMove(
+                "No king at given square.".to_string(),
+            ));
+        }
+        if !(self.rooks & own).contains(castle.rook_from) {
+            return Err(ChessError::IllegalMove(
+                "no rook to castle with.".to_string(),
+            ));
+        }
+        if castle
+            .must_be_empty()
+            .intersects(self.white_pieces | self.black_pieces)
+        {
+            return Err(ChessError::IllegalMove(
+                "castling path is blocked.".to_string(),
+            ));
+        }
+
+        let undo_info = UndoInfo {
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            castling_rights: self.castling_rights,
+            en_passant_square: self.en_passant.lsb().map(Square::index),
+            captured_type: None,
+            zobrist_hash: self.zobrist_hash,
+        };
+
+        // lift both pieces before placing either: in Chess960 one may land where the other stood.
+        self.kings = (self.kings - castle.king_from.bb()) | castle.king_to.bb();
+        self.rooks = (self.rooks - castle.rook_from.bb()) | castle.rook_to.bb();
+        let own = self.color_mut(color);
+        *own = (*own - castle.king_from.bb() - castle.rook_from.bb())
+            | castle.king_to.bb()
+            | castle.rook_to.bb();
+
+        let piece_offset = if color.is_white() { 0 } else { 6 };
+        self.zobrist_hash ^= ZOBRIST_PIECES[castle.king_from.index() as usize][5 + piece_offset]
+            ^ ZOBRIST_PIECES[castle.king_to.index() as usize][5 + piece_offset]
+            ^ ZOBRIST_PIECES[castle.rook_from.index() as usize][3 + piece_offset]
+            ^ ZOBRIST_PIECES[castle.rook_to.index() as usize][3 + piece_offset];
+
+        for right in color.index() * 2..color.index() * 2 + 2 {
+            if self.castling_rights & CASTLING_BITS[right] != 0 {
+                self.zobrist_hash ^= ZOBRIST_CASTLING[right];
+                self.castling_rights &= !CASTLING_BITS[right];
+            }
+        }
+        if let Some(ep_sq) = self.en_passant.lsb() {
+            self.zobrist_hash ^= ZOBRIST_EP[ep_sq.file() as usize];
+        }
+        self.en_passant = Bitboard::EMPTY;
+        self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+
+        if color.is_black() {
+            self.fullmove_number = self.fullmove_number.saturating_add(1);
+        }
+        self.side_to_move = !color;
+        self.zobrist_hash ^= ZOBRIST_SIDE;
+
+        Ok(undo_info)
+    }
+
+    fn unmake_castle(&mut self, mv: Move, undo_info: &UndoInfo) -> Result<(), ChessError> {
+        let color = !self.side_to_move;
+        let castle = self.castle_for_move(mv, color)?;
+        if !self.kings.contains(castle.king_to) || !self.rooks.contains(castle.rook_to) {
+            return Err(ChessError::IllegalMove(
+                "target square not valid castling target.".to_string(),
+            ));
+        }
+
+        self.kings = (self.kings - castle.king_to.bb()) | castle.king_from.bb();
+        self.rooks = (self.rooks - castle.rook_to.bb()) | castle.rook_from.bb();
+        let own = self.color_mut(color);
+        *own = (*own - castle.king_to.bb() - castle.rook_to.bb())
+            | castle.king_from.bb()
+            | castle.rook_from.bb();
+
+        self.restore_state(undo_info);
+        Ok(())
+    }
+
+    // clears the castling right (if any) whose rook starts on sq_i, e.g. when that rook moves or
+    // is captured.
+    fn clear_rook_castling_right(&mut self, sq_i: u8) {
+        for (right, bit) in CASTLING_BITS.into_iter().enumerate() {
+            let back_rank_start = if right < 2 { 0 } else { 56 };
+            if self.castling_rights & bit != 0
+                && sq_i == back_rank_start + self.castling_rook_files[right]
+            {
+                self.zobrist_hash ^= ZOBRIST_CASTLING[right];
+                self.castling_rights &= !bit;
+            }
+        }
+    }
+
     pub fn generate_zobrist_hash(&mut self) -> u64 {
         // index zobrist hashes as follows:
         // ZOBRIST_PIECES[square 0..=63][piece 0..=11]
@@ -743,17 +957,13 @@ impl ChessBoard {
         .iter()
         .enumerate()
         {
-            let mut bb = *piece_bb;
-            while bb != 0 {
-                let square = bb.trailing_zeros() as usize;
-                let square_bb = 1 << square;
-                if square_bb & self.white_pieces != 0 {
+            for sq in *piece_bb {
+                let square = sq.index() as usize;
+                if self.white_pieces.contains(sq) {
                     hash ^= ZOBRIST_PIECES[square][piece_type];
                 } else {
                     hash ^= ZOBRIST_PIECES[square][piece_type + 6];
                 }
-
-                bb &= bb - 1;
             }
         }
 
@@ -766,11 +976,11 @@ impl ChessBoard {
         }
 
         // xor with en passant file
-        if self.en_passant != 0 {
-            hash ^= ZOBRIST_EP[(self.en_passant.trailing_zeros() % 8) as usize];
+        if let Some(ep_sq) = self.en_passant.lsb() {
+            hash ^= ZOBRIST_EP[ep_sq.file() as usize];
         }
 
-        if self.side_to_move {
+        if self.side_to_move.is_white() {
             hash ^= ZOBRIST_SIDE;
         }
 
@@ -778,114 +988,257 @@ impl ChessBoard {
     }
 }
 
-/// Verifies if a string is a valid FEN string.
-pub fn verify_fen(fen: &str) -> bool {
-    // Split whitespace and create vector of fen components.
-    let fen_components: Vec<&str> = fen.split_whitespace().collect();
-    // fen_components indices:
-    //      [0] = board position
-    //      [1] = color to move
-    //      [2] = castling rights
-    //      [3] = en passant target square
-    //      [4] = halfmove clock
-    //      [5] = fullmove number
-    // Steps to verify:
-    // Piece locations:
-    //      Split by '/' characters. If len != 8, return false.
-
-    let ranks: Vec<&str> = fen_components[0].split('/').collect();
-    if ranks.len() != 8 {
-        return false;
+/// Displays the board as its FEN string.
+impl fmt::Display for ChessBoard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_fen())
     }
+}
 
-    //      For each line, take count of alphabetical characters and add value of digits. If for any line, sum != 8, return false.
-    //      For efficiency, only check if characters are in "PNBRQKpnbrqk12345678".
-    //      Check for 1 white and 1 black king while we're at it.
-    let mut white_king_count: u8 = 0;
-    let mut black_king_count: u8 = 0;
+/// Verifies if a string is a valid FEN string, reporting the first field that is not.
+pub fn verify_fen(fen: &str) -> Result<(), ChessError> {
+    ChessBoard::initialize_from_fen(fen).map(|_| ())
+}
+
+fn fen_error(field: FenField, reason: String) -> ChessError {
+    ChessError::InvalidFen { field, reason }
+}
 
-    for rank in &ranks {
-        let mut square_count = 0;
+// parses the piece placement field into the (empty) board's bitboards.
+fn parse_placement(board: &mut ChessBoard, placement: &str) -> Result<(), ChessError> {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(fen_error(
+            FenField::Placement,
+            format!("expected 8 ranks, found {}", ranks.len()),
+        ));
+    }
 
+    // ranks are listed from the 8th down to the 1st.
+    for (i, rank) in ranks.iter().enumerate() {
+        let rank_number = 8 - i;
+        let mut file: usize = 0;
         for ch in rank.chars() {
-            if "kqrbnpKQRBNP".contains(ch) {
-                square_count += 1;
-                if ch == 'K' {
-                    white_king_count += 1;
-                } else if ch == 'k' {
-                    black_king_count += 1;
-                }
-            } else if ch >= '1' && ch <= '8' {
-                square_count += ch.to_digit(10).unwrap();
+            if let Some(skip) = ch.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                file += skip as usize;
             } else {
-                return false;
+                let piece_bb = match ch.to_ascii_lowercase() {
+                    'p' => &mut board.pawns,
+                    'n' => &mut board.knights,
+                    'b' => &mut board.bishops,
+                    'r' => &mut board.rooks,
+                    'q' => &mut board.queens,
+                    'k' => &mut board.kings,
+                    _ => {
+                        return Err(fen_error(
+                            FenField::Placement,
+                            format!("bad piece character '{ch}' on rank {rank_number}"),
+                        ))
+                    }
+                };
+                if file >= 8 {
+                    return Err(rank_overflow(rank_number));
+                }
+                let sq_bb = Square::from_file_rank(file as u8, (rank_number - 1) as u8).bb();
+                *piece_bb |= sq_bb;
+                if ch.is_ascii_uppercase() {
+                    board.white_pieces |= sq_bb;
+                } else {
+                    board.black_pieces |= sq_bb;
+                }
+                file += 1;
+            }
+            if file > 8 {
+                return Err(rank_overflow(rank_number));
             }
         }
-        if square_count != 8 {
-            return false;
+        if file != 8 {
+            return Err(fen_error(
+                FenField::Placement,
+                format!("rank {rank_number} describes {file} squares instead of 8"),
+            ));
         }
     }
 
-    if white_king_count != 1 {
-        return false;
+    for (color, pieces) in [("white", board.white_pieces), ("black", board.black_pieces)] {
+        let king_count = (board.kings & pieces).popcount();
+        if king_count != 1 {
+            return Err(fen_error(
+                FenField::Placement,
+                format!("expected one {color} king, found {king_count}"),
+            ));
+        }
     }
-    if black_king_count != 1 {
-        return false;
+
+    if let Some(sq) = (board.pawns & (Bitboard::RANK_1 | Bitboard::RANK_8)).lsb() {
+        return Err(fen_error(
+            FenField::Placement,
+            format!("pawn on the back rank at {sq}"),
+        ));
     }
 
-    // Color to move:
-    //      If not in "wb" and length 1, return false.
-    if !"wb".contains(fen_components[1]) || fen_components[1].len() != 1 {
-        return false;
+    Ok(())
+}
+
+fn rank_overflow(rank_number: usize) -> ChessError {
+    fen_error(
+        FenField::Placement,
+        format!("rank {rank_number} overflows past the h-file"),
+    )
+}
+
+// the file of the rook furthest from the king on the given castling right's side, as `K`/`Q`
+// mean in X-FEN. None if there is no king on the back rank or no rook on that side of it.
+fn outermost_rook_file(board: &ChessBoard, right: usize) -> Option<u8> {
+    let (own, back_rank) = if right < 2 {
+        (board.white_pieces, Bitboard::RANK_1)
+    } else {
+        (board.black_pieces, Bitboard::RANK_8)
+    };
+    let king_file = (board.kings & own & back_rank).lsb()?.file();
+    let mut rook_files = (board.rooks & own & back_rank)
+        .into_iter()
+        .map(Square::file);
+    if right.is_multiple_of(2) {
+        rook_files.filter(|&file| file > king_file).last()
+    } else {
+        rook_files.find(|&file| file < king_file)
     }
+}
 
-    // Castling rights:
-    //      If not in "KQkq-" or not len 1..4 return false.
-    if !fen_components[2].chars().all(|c| "KQkq-".contains(c)) || fen_components[2].len() > 4 {
-        return false;
+// accepts standard `KQkq`, X-FEN (`K`/`Q` = outermost rook, or a file letter) and Shredder-FEN
+// (`HAha`), recording each right's rook file. The board's pieces must already be placed.
+fn parse_castling(board: &mut ChessBoard, castling: &str) -> Result<(), ChessError> {
+    if castling == "-" {
+        return Ok(());
+    }
+    let bad_castling = || {
+        fen_error(
+            FenField::Castling,
+            format!("bad castling string '{castling}'"),
+        )
+    };
+    let mut castling_rights: u8 = 0;
+    let mut names_files = false;
+    for ch in castling.chars() {
+        let color_offset = if ch.is_ascii_uppercase() { 0 } else { 2 };
+        let (right, file) = match ch.to_ascii_lowercase() {
+            'k' => (
+                color_offset,
+                outermost_rook_file(board, color_offset).unwrap_or(7),
+            ),
+            'q' => (
+                color_offset + 1,
+                outermost_rook_file(board, color_offset + 1).unwrap_or(0),
+            ),
+            letter @ 'a'..='h' => {
+                names_files = true;
+                let file = letter as u8 - b'a';
+                let (own, back_rank) = if color_offset == 0 {
+                    (board.white_pieces, Bitboard::RANK_1)
+                } else {
+                    (board.black_pieces, Bitboard::RANK_8)
+                };
+                let king_file = match (board.kings & own & back_rank).lsb() {
+                    Some(king_sq) if king_sq.file() != file => king_sq.file(),
+                    _ => return Err(bad_castling()),
+                };
+                let side = if file > king_file { 0 } else { 1 };
+                (color_offset + side, file)
+            }
+            _ => return Err(bad_castling()),
+        };
+        if castling_rights & CASTLING_BITS[right] != 0 {
+            return Err(bad_castling());
+        }
+        castling_rights |= CASTLING_BITS[right];
+        board.castling_rook_files[right] = file;
     }
-    //      Castling rights cannot be both empty and not empty.
-    if fen_components[2].contains("-") && fen_components[2] != "-" {
-        return false;
+    if castling_rights == 0 {
+        return Err(fen_error(FenField::Castling, "field is empty".to_string()));
     }
+    board.castling_rights = castling_rights;
 
-    // En passant target square:
-    //      If len > 2, return false.
-    if fen_components[3].len() > 2 {
-        return false;
-    }
-    //      If not '-':
-    //          If first char not "a".."h" or second char not '6' or '3':
-    //              return false.
-    if fen_components[3] != "-" {
-        let mut en_passant_chars = fen_components[3].chars();
-        let en_passant_char1 = en_passant_chars.next().unwrap(); // safe to unwrap because the component cannot be empty (white space erased, each component has len > 1.)
-        if en_passant_char1 < 'a' || en_passant_char1 > 'h' {
-            return false;
+    // each right needs its king on the back rank and its rook on the recorded file, on the
+    // side of the king it castles towards.
+    for (right, bit) in CASTLING_BITS.iter().enumerate() {
+        if castling_rights & bit == 0 {
+            continue;
         }
-        // Use Some() in case there is no second char, it returns false.
-        if let Some(en_passant_char2) = en_passant_chars.next() {
-            if en_passant_char2 != '6' && en_passant_char2 != '3' {
-                return false;
-            }
-        } else {
-            return false;
+        let (color, kingside) = (Color::from(right < 2), right.is_multiple_of(2));
+        let castle = board.castle_squares(color, kingside).filter(|castle| {
+            (board.rooks & board.pieces_of(color)).contains(castle.rook_from)
+                && (castle.rook_from.file() > castle.king_from.file()) == kingside
+        });
+        if castle.is_none() {
+            return Err(fen_error(
+                FenField::Castling,
+                format!("'{castling}' has no king and rook to castle with for every right"),
+            ));
         }
     }
 
-    // Halfmove clock:
-    //      If not a non-negative integer, return false.
-    if !fen_components[4].parse::<u16>().is_ok() {
-        return false;
+    // anything but e-file kings with a- and h-file rooks needs Chess960 castling.
+    let king_off_e_file = (0..4).any(|right| {
+        castling_rights & CASTLING_BITS[right] != 0
+            && board
+                .castle_squares(Color::from(right < 2), right.is_multiple_of(2))
+                .is_some_and(|castle| castle.king_from.file() != 4)
+    });
+    board.chess960 =
+        names_files || king_off_e_file || board.castling_rook_files != STANDARD_ROOK_FILES;
+    Ok(())
+}
+
+// the target square must be on the 6th rank when white is to move, the 3rd when black is, right
+// behind the pawn that just moved two squares. The board's pieces must already be placed.
+fn parse_en_passant(board: &ChessBoard, en_passant: &str) -> Result<Bitboard, ChessError> {
+    let side_to_move = board.side_to_move;
+    if en_passant == "-" {
+        return Ok(Bitboard::EMPTY);
+    }
+    let ep_bb = utils::square_to_bb(en_passant).map_err(|_| {
+        fen_error(
+            FenField::EnPassant,
+            format!("'{en_passant}' is not a square"),
+        )
+    })?;
+    let expected_rank = if side_to_move.is_white() { 6 } else { 3 };
+    let rank = utils::rank_value(ep_bb);
+    if rank != expected_rank {
+        return Err(fen_error(
+            FenField::EnPassant,
+            format!("impossible en passant rank {rank} (expected {expected_rank})"),
+        ));
     }
-    // Fullmove number:
-    //      If not a positive integer, return false.
-    if !fen_components[5].parse::<u16>().is_ok() || fen_components[5] == "0" {
-        return false;
+    let (pawn, origin) = if side_to_move.is_white() {
+        (ep_bb.south(), ep_bb.north())
+    } else {
+        (ep_bb.north(), ep_bb.south())
+    };
+    let occupied = board.white_pieces | board.black_pieces;
+    if !(board.pawns & board.pieces_of(!side_to_move)).intersects(pawn)
+        || occupied.intersects(ep_bb | origin)
+    {
+        return Err(fen_error(
+            FenField::EnPassant,
+            format!("no pawn just moved two squares past {en_passant}"),
+        ));
     }
+    Ok(ep_bb)
+}
 
-    // Finally: return true.
-    return true;
+fn parse_clock<T: std::str::FromStr<Err = ParseIntError>>(
+    value: &str,
+    field: FenField,
+) -> Result<T, ChessError> {
+    value.parse::<T>().map_err(|e| {
+        let reason = match e.kind() {
+            IntErrorKind::PosOverflow => format!("{value} is too large"),
+            _ => format!("expected a non-negative integer, found '{value}'"),
+        };
+        fen_error(field, reason)
+    })
 }
 
 #[cfg(test)]
@@ -897,16 +1250,16 @@ mod tests {
     #[test]
     fn test_initialize_board() {
         let board = ChessBoard::initialize();
-        assert_eq!(board.pawns, 0x00FF00000000FF00);
-        assert_eq!(board.knights, 0x4200000000000042);
-        assert_eq!(board.bishops, 0x2400000000000024);
-        assert_eq!(board.rooks, 0x8100000000000081);
-        assert_eq!(board.queens, 0x0800000000000008);
-        assert_eq!(board.kings, 0x1000000000000010);
-        assert_eq!(board.white_pieces, 0x000000000000FFFF);
-        assert_eq!(board.black_pieces, 0xFFFF000000000000);
-        assert_eq!(board.side_to_move, true);
-        assert_eq!(board.en_passant, 0);
+        assert_eq!(board.pawns, Bitboard(0x00FF00000000FF00));
+        assert_eq!(board.knights, Bitboard(0x4200000000000042));
+        assert_eq!(board.bishops, Bitboard(0x2400000000000024));
+        assert_eq!(board.rooks, Bitboard(0x8100000000000081));
+        assert_eq!(board.queens, Bitboard(0x0800000000000008));
+        assert_eq!(board.kings, Bitboard(0x1000000000000010));
+        assert_eq!(board.white_pieces, Bitboard(0x000000000000FFFF));
+        assert_eq!(board.black_pieces, Bitboard(0xFFFF000000000000));
+        assert_eq!(board.side_to_move, Color::White);
+        assert_eq!(board.en_passant, Bitboard::EMPTY);
         assert_eq!(board.castling_rights, 0b1111);
         assert_eq!(board.halfmove_clock, 0);
         assert_eq!(board.fullmove_number, 1);
@@ -915,16 +1268,16 @@ mod tests {
     #[test]
     fn test_empty_board() {
         let board = ChessBoard::empty();
-        assert_eq!(board.pawns, 0);
-        assert_eq!(board.knights, 0);
-        assert_eq!(board.bishops, 0);
-        assert_eq!(board.rooks, 0);
-        assert_eq!(board.queens, 0);
-        assert_eq!(board.kings, 0);
-        assert_eq!(board.white_pieces, 0);
-        assert_eq!(board.black_pieces, 0);
-        assert_eq!(board.side_to_move, true);
-        assert_eq!(board.en_passant, 0);
+        assert_eq!(board.pawns, Bitboard::EMPTY);
+        assert_eq!(board.knights, Bitboard::EMPTY);
+        assert_eq!(board.bishops, Bitboard::EMPTY);
+        assert_eq!(board.rooks, Bitboard::EMPTY);
+        assert_eq!(board.queens, Bitboard::EMPTY);
+        assert_eq!(board.kings, Bitboard::EMPTY);
+        assert_eq!(board.white_pieces, Bitboard::EMPTY);
+        assert_eq!(board.black_pieces, Bitboard::EMPTY);
+        assert_eq!(board.side_to_move, Color::White);
+        assert_eq!(board.en_passant, Bitboard::EMPTY);
         assert_eq!(board.castling_rights, 0);
         assert_eq!(board.halfmove_clock, 0);
         assert_eq!(board.fullmove_number, 1);
@@ -933,91 +1286,250 @@ mod tests {
     #[test]
     fn test_verify_fen() {
         let starting_fen = verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"); // Starting position.
-        assert_eq!(starting_fen, true);
+        assert!(starting_fen.is_ok());
 
         let empty_fen = verify_fen("8/8/8/8/8/8/8/8 w - - 0 1"); // No kings.
-        assert_eq!(empty_fen, false);
+        assert!(empty_fen.is_err());
 
         let random_fen =
             verify_fen("1rbq1rk1/5pbp/2pNn1p1/p1Pn4/Pp1P4/1B3N1P/1P3PP1/R1BQR1K1 w - - 1 18"); // Valid FEN of a middlegame.
-        assert_eq!(random_fen, true);
+        assert!(random_fen.is_ok());
 
         let too_many_ranks_false_fen = verify_fen(
             "1rbq1rk1/5pbp/2pNn1p1/p1Pn4/Pp1P4/1B3N1P/1P3PP1/R1BQR1K1/1rbq1rk1 w - - 1 18", // 9 ranks != 8.
         );
-        assert_eq!(too_many_ranks_false_fen, false);
+        assert!(too_many_ranks_false_fen.is_err());
 
         let too_few_ranks_false_fen =
             verify_fen("1rbq1rk1/5pbp/2pNn1p1/p1Pn4/Pp1P4/1B3N1P/1P3PP1 w - - 1 18"); // 7 ranks != 7.
-        assert_eq!(too_few_ranks_false_fen, false);
+        assert!(too_few_ranks_false_fen.is_err());
 
         let too_many_squares_false_fen =
             verify_fen("1rbq1rk1/5pbp/2pNn1p1/p1Pn4/Pp1P4/1B3N1P/1P3PP1/R2BQR1K1 w - - 1 18"); // 1st rank has too many squares.
-        assert_eq!(too_many_squares_false_fen, false);
+        assert!(too_many_squares_false_fen.is_err());
 
         let too_few_squares_false_fen =
             verify_fen("1rbq1rk1/5pbp/2pNn1p1/p1Pn4/Pp1P4/1B3N1P/1P3PP1/RBQR1K1 w - - 1 18"); // 1st rank has too few squares.
-        assert_eq!(too_few_squares_false_fen, false);
+        assert!(too_few_squares_false_fen.is_err());
 
         let wrong_characters_false_fen =
             verify_fen("1rbq1ak1/5pbp/2pNn1p1/p1Pn4/Pp1P4/1B3N1P/1P3PP1/R1BQR1K1 w - - 1 18"); // 8th rank contains the char 'a'.
-        assert_eq!(wrong_characters_false_fen, false);
+        assert!(wrong_characters_false_fen.is_err());
 
         let invalid_color_to_move_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR v KQkq - 0 1"); // Starting position with invalid color to move - 'v'.
-        assert_eq!(invalid_color_to_move_false_fen, false);
+        assert!(invalid_color_to_move_false_fen.is_err());
 
         let color_to_move_len_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR ww KQkq - 0 1"); // Starting position with invalid color to move - 'ww'.
-        assert_eq!(color_to_move_len_false_fen, false);
+        assert!(color_to_move_len_false_fen.is_err());
 
         let castle_rights_len_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkqK - 0 1"); // Castling rights too long.
-        assert_eq!(castle_rights_len_false_fen, false);
+        assert!(castle_rights_len_false_fen.is_err());
 
         let castle_invalid_char_false_fen =
-            verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQka - 0 1"); // Invalid character in castling rights.
-        assert_eq!(castle_invalid_char_false_fen, false);
+            verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1"); // Invalid character in castling rights.
+        assert!(castle_invalid_char_false_fen.is_err());
 
         let castling_contradiction_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w -K - 0 1");
-        assert_eq!(castling_contradiction_false_fen, false);
+        assert!(castling_contradiction_false_fen.is_err());
 
         let invalid_len_en_passant_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq a33 0 1");
-        assert_eq!(invalid_len_en_passant_false_fen, false);
+        assert!(invalid_len_en_passant_false_fen.is_err());
 
         let invalid_char_en_passant_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq i3 0 1");
-        assert_eq!(invalid_char_en_passant_false_fen, false);
+        assert!(invalid_char_en_passant_false_fen.is_err());
 
         let invalid_num_en_passant_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq a2 0 1");
-        assert_eq!(invalid_num_en_passant_false_fen, false);
+        assert!(invalid_num_en_passant_false_fen.is_err());
 
         let invalid_halfmove_negative_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - -1 1");
-        assert_eq!(invalid_halfmove_negative_false_fen, false);
+        assert!(invalid_halfmove_negative_false_fen.is_err());
 
         let invalid_halfmove_char_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - a 1");
-        assert_eq!(invalid_halfmove_char_false_fen, false);
+        assert!(invalid_halfmove_char_false_fen.is_err());
 
         let invalid_fullmove_negative_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 -1");
-        assert_eq!(invalid_fullmove_negative_false_fen, false);
+        assert!(invalid_fullmove_negative_false_fen.is_err());
 
         let invalid_fullmove_zero_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0");
-        assert_eq!(invalid_fullmove_zero_false_fen, false);
+        assert!(invalid_fullmove_zero_false_fen.is_err());
 
         let invalid_fullmove_char_false_fen =
             verify_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 a");
-        assert_eq!(invalid_fullmove_char_false_fen, false);
+        assert!(invalid_fullmove_char_false_fen.is_err());
 
         let random_fen2 =
             verify_fen("rnbqk2r/pp2nppp/2pbp3/3p4/3P4/1P1BPN2/PBP2PPP/RN1QK2R b KQkq - 2 6");
-        assert_eq!(random_fen2, true);
+        assert!(random_fen2.is_ok());
+    }
+
+    #[test]
+    fn test_fen_diagnostics() {
+        let field_of = |fen: &str| match ChessBoard::initialize_from_fen(fen) {
+            Err(ChessError::InvalidFen { field, .. }) => Some(field),
+            _ => None,
+        };
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+
+        assert_eq!(field_of(""), Some(FenField::Record));
+        assert_eq!(
+            field_of(&format!("{start} w KQkq - 0")),
+            Some(FenField::Record)
+        );
+        assert_eq!(
+            field_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1"),
+            Some(FenField::Placement)
+        );
+        assert_eq!(
+            field_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR9 w KQkq - 0 1"),
+            Some(FenField::Placement)
+        );
+        assert_eq!(
+            field_of(&format!("{start} x KQkq - 0 1")),
+            Some(FenField::SideToMove)
+        );
+        assert_eq!(
+            field_of(&format!("{start} w KK - 0 1")),
+            Some(FenField::Castling)
+        );
+        assert_eq!(
+            field_of(&format!("{start} w KQkq e3 0 1")),
+            Some(FenField::EnPassant)
+        );
+        assert_eq!(
+            field_of(&format!("{start} w KQkq - 300 1")),
+            Some(FenField::HalfmoveClock)
+        );
+        assert_eq!(
+            field_of(&format!("{start} w KQkq - 0 70000")),
+            Some(FenField::FullmoveNumber)
+        );
+
+        assert_eq!(
+            ChessBoard::initialize_from_fen(
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR1 w - - 0 1"
+            ),
+            Err(ChessError::InvalidFen {
+                field: FenField::Placement,
+                reason: "rank 1 overflows past the h-file".to_string()
+            })
+        );
+        assert_eq!(
+            ChessBoard::initialize_from_fen(&format!("{start} b KQkq e6 0 1")),
+            Err(ChessError::InvalidFen {
+                field: FenField::EnPassant,
+                reason: "impossible en passant rank 6 (expected 3)".to_string()
+            })
+        );
+
+        // 4-field EPD style FEN gets default clocks.
+        assert_eq!(
+            ChessBoard::initialize_from_fen(&format!("{start} w KQkq -")),
+            Ok(ChessBoard::initialize())
+        );
+
+        // truncated and garbage input never panics.
+        let full = format!("{start} w KQkq - 0 1");
+        for end in 0..=full.len() {
+            let _ = ChessBoard::initialize_from_fen(&full[..end]);
+        }
+        for fen in [
+            "/",
+            "////////",
+            "é",
+            "8/8/8/8/8/8/8/8/ w",
+            "kK w - -",
+            "9/ b - - 0 1",
+        ] {
+            assert!(ChessBoard::initialize_from_fen(fen).is_err());
+        }
+    }
+
+    #[test]
+    fn test_fen_rejects_impossible_positions() {
+        let field_of = |fen: &str| match ChessBoard::initialize_from_fen(fen) {
+            Err(ChessError::InvalidFen { field, .. }) => Some(field),
+            _ => None,
+        };
+
+        // the side that just moved left its king in check.
+        assert_eq!(
+            field_of("4k3/8/8/8/8/8/4Q3/4K3 w - - 0 1"),
+            Some(FenField::SideToMove)
+        );
+        assert_eq!(field_of("4k3/8/8/8/8/8/4Q3/4K3 b - - 0 1"), None);
+
+        // castling rights without the king and rook they need.
+        assert_eq!(
+            field_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1"),
+            Some(FenField::Castling)
+        );
+        assert_eq!(
+            field_of("rnbqkbnr/pppppppp/8/8/8/4K3/PPPPPPPP/RNBQ1BNR w kq - 0 1"),
+            None
+        );
+        assert_eq!(
+            field_of("rnbqkbnr/pppppppp/8/8/8/4K3/PPPPPPPP/RNBQ1BNR w Kkq - 0 1"),
+            Some(FenField::Castling)
+        );
+        assert_eq!(
+            field_of("4k3/8/8/8/8/8/8/R3K3 w B - 0 1"),
+            Some(FenField::Castling)
+        );
+        assert_eq!(field_of("4k3/8/8/8/8/8/8/R3K3 w A - 0 1"), None);
+
+        // an en passant square needs the pawn that just moved past it.
+        assert_eq!(
+            field_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq e3 0 1"),
+            Some(FenField::EnPassant)
+        );
+        assert_eq!(
+            field_of("rnbqkbnr/pppppppp/8/8/4P3/4B3/PPPP1PPP/RN1QKBNR b KQkq e3 0 1"),
+            Some(FenField::EnPassant)
+        );
+        assert_eq!(
+            field_of("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            None
+        );
+
+        // pawns never stand on the first or last rank.
+        assert_eq!(
+            field_of("4k2P/8/8/8/8/8/8/4K3 w - - 0 1"),
+            Some(FenField::Placement)
+        );
+        assert_eq!(
+            field_of("4k3/8/8/8/8/8/8/p3K3 w - - 0 1"),
+            Some(FenField::Placement)
+        );
+    }
+
+    #[test]
+    fn test_clocks_at_their_limits() {
+        // the halfmove clock stops at 255 instead of overflowing.
+        let mut board =
+            ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/8/4K1N1 w - - 255 1").unwrap();
+        let undo = board.make_move(encode_move(6, 21, 0)).unwrap();
+        assert_eq!(board.halfmove_clock, 255);
+        board.unmake_move(encode_move(6, 21, 0), &undo).unwrap();
+        assert_eq!(board.halfmove_clock, 255);
+
+        // so does the fullmove number at 65535, and unmaking the move still restores it.
+        let fen = "4k3/8/8/8/8/8/8/4K3 b - - 0 65535";
+        let mut board = ChessBoard::initialize_from_fen(fen).unwrap();
+        let undo = board.make_move(encode_move(60, 52, 0)).unwrap();
+        assert_eq!(board.fullmove_number, 65535);
+        board.unmake_move(encode_move(60, 52, 0), &undo).unwrap();
+        assert_eq!(board.to_fen(), fen);
     }
 
     #[test]
@@ -1026,16 +1538,16 @@ mod tests {
             "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
         )
         .unwrap();
-        assert_eq!(starting_board.pawns, 0x00FF00000000FF00);
-        assert_eq!(starting_board.knights, 0x4200000000000042);
-        assert_eq!(starting_board.bishops, 0x2400000000000024);
-        assert_eq!(starting_board.rooks, 0x8100000000000081);
-        assert_eq!(starting_board.queens, 0x0800000000000008);
-        assert_eq!(starting_board.kings, 0x1000000000000010);
-        assert_eq!(starting_board.white_pieces, 0x000000000000FFFF);
-        assert_eq!(starting_board.black_pieces, 0xFFFF000000000000);
-        assert_eq!(starting_board.side_to_move, true);
-        assert_eq!(starting_board.en_passant, 0);
+        assert_eq!(starting_board.pawns, Bitboard(0x00FF00000000FF00));
+        assert_eq!(starting_board.knights, Bitboard(0x4200000000000042));
+        assert_eq!(starting_board.bishops, Bitboard(0x2400000000000024));
+        assert_eq!(starting_board.rooks, Bitboard(0x8100000000000081));
+        assert_eq!(starting_board.queens, Bitboard(0x0800000000000008));
+        assert_eq!(starting_board.kings, Bitboard(0x1000000000000010));
+        assert_eq!(starting_board.white_pieces, Bitboard(0x000000000000FFFF));
+        assert_eq!(starting_board.black_pieces, Bitboard(0xFFFF000000000000));
+        assert_eq!(starting_board.side_to_move, Color::White);
+        assert_eq!(starting_board.en_passant, Bitboard::EMPTY);
         assert_eq!(starting_board.castling_rights, 0b1111);
         assert_eq!(starting_board.halfmove_clock, 0);
         assert_eq!(starting_board.fullmove_number, 1);
@@ -1046,38 +1558,38 @@ mod tests {
         .unwrap();
         assert_eq!(
             board1.pawns,
-            0b0000000011110010000000010000100000001000000000001110011100000000
+            Bitboard(0b0000000011110010000000010000100000001000000000001110011100000000)
         );
         assert_eq!(
             board1.knights,
-            0b0100000000000000000001000000000000000000001000000000000000000010
+            Bitboard(0b0100000000000000000001000000000000000000001000000000000000000010)
         );
         assert_eq!(
             board1.bishops,
-            0b0010000000000000000000000000000000100000000000000000000000000000
+            Bitboard(0b0010000000000000000000000000000000100000000000000000000000000000)
         );
         assert_eq!(
             board1.rooks,
-            0b1000000100000000000000000000000000000000000000000000000001000001
+            Bitboard(0b1000000100000000000000000000000000000000000000000000000001000001)
         );
         assert_eq!(
             board1.queens,
-            0b0000100000000000000000000000000000000000000010000000000000000000
+            Bitboard(0b0000100000000000000000000000000000000000000010000000000000000000)
         );
         assert_eq!(
             board1.kings,
-            0b0001000000000000000000000000000000000000000000000000000000010000
+            Bitboard(0b0001000000000000000000000000000000000000000000000000000000010000)
         );
         assert_eq!(
             board1.white_pieces,
-            0b0000000000000000000000000000000000101000001010001110011101010011
+            Bitboard(0b0000000000000000000000000000000000101000001010001110011101010011)
         );
         assert_eq!(
             board1.black_pieces,
-            0b1111100111110010000001010000100000000000000000000000000000000000
+            Bitboard(0b1111100111110010000001010000100000000000000000000000000000000000)
         );
-        assert_eq!(board1.side_to_move, false);
-        assert_eq!(board1.en_passant, 0);
+        assert_eq!(board1.side_to_move, Color::Black);
+        assert_eq!(board1.en_passant, Bitboard::EMPTY);
         assert_eq!(board1.castling_rights, 0b0111);
         assert_eq!(board1.halfmove_clock, 2);
         assert_eq!(board1.fullmove_number, 8);
@@ -1088,37 +1600,158 @@ mod tests {
         .unwrap();
         assert_eq!(
             board2.pawns,
-            0b0000000011111111000000000000000011000000000000000111111100000000
+            Bitboard(0b0000000011111111000000000000000011000000000000000111111100000000)
         );
-        assert_eq!(board2.knights, 0x4200000000000042);
-        assert_eq!(board2.bishops, 0x2400000000000024);
-        assert_eq!(board2.rooks, 0x8100000000000081);
-        assert_eq!(board2.queens, 0x0800000000000008);
-        assert_eq!(board2.kings, 0x1000000000000010);
+        assert_eq!(board2.knights, Bitboard(0x4200000000000042));
+        assert_eq!(board2.bishops, Bitboard(0x2400000000000024));
+        assert_eq!(board2.rooks, Bitboard(0x8100000000000081));
+        assert_eq!(board2.queens, Bitboard(0x0800000000000008));
+        assert_eq!(board2.kings, Bitboard(0x1000000000000010));
         assert_eq!(
             board2.white_pieces,
-            0b0000000000000000000000000000000010000000000000000111111111111111
+            Bitboard(0b0000000000000000000000000000000010000000000000000111111111111111)
         );
         assert_eq!(
             board2.black_pieces,
-            0b1111111111111111000000000000000001000000000000000000000000000000
+            Bitboard(0b1111111111111111000000000000000001000000000000000000000000000000)
         );
-        assert_eq!(board2.side_to_move, false);
+        assert_eq!(board2.side_to_move, Color::Black);
         assert_eq!(
             board2.en_passant,
-            0b0000000000000000000000000000000000000000100000000000000000000000
+            Bitboard(0b0000000000000000000000000000000000000000100000000000000000000000)
         );
         assert_eq!(board2.castling_rights, 0b1111);
         assert_eq!(board2.halfmove_clock, 0);
         assert_eq!(board2.fullmove_number, 1);
     }
 
+    #[test]
+    fn test_to_fen() {
+        // every FEN used across the test suites.
+        let fens = [
+            "1q2k1r1/Ppp4b/8/5Pp1/4p3/8/8/K7 b - - 0 1",
+            "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w - - 4 3",
+            "1r2k2r/8/8/8/8/8/8/1R2K1R1 b k - 3 2",
+            "1r2k2r/8/8/8/8/8/8/1R2K2R w Kk - 2 2",
+            "1rbq1rk1/5pbp/2pNn1p1/p1Pn4/Pp1P4/1B3N1P/1P3PP1/R1BQR1K1 w - - 1 18",
+            "2k5/8/8/8/4Q2Q/8/8/K3Q3 w - - 0 1",
+            "2kr1bnr/pppqpppp/2n5/3p1b2/3P1B2/2N1P3/PPPQ1PPP/2KR1BNR b - - 2 2",
+            "2kr1bnr/pppqpppp/2n5/3p1b2/3P1B2/2N1P3/PPPQ1PPP/R3KBNR w KQ - 1 2",
+            "4k3/1b2r2q/8/3PPP2/r2PKP1r/3PPP2/8/1q2r2b w - - 0 1",
+            "4k3/8/8/8/8/8/8/2N1K1N1 w - - 0 30",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "5b2/2K5/3B2k1/8/8/8/8/8 w - - 0 1",
+            "6b1/2K5/3B2k1/8/8/8/8/8 b - - 0 1",
+            "8/2K5/6k1/8/6B1/8/8/8 b - - 0 1",
+            "8/2K5/6k1/8/8/8/8/7N w - - 0 1",
+            "8/2n1n3/2n5/nnKnk3/1pnp4/n1n1n3/8/8 w - - 0 1",
+            "8/3k4/8/8/8/8/6R1/K7 b - - 100 50",
+            "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43",
+            "8/8/7n/8/6K1/2k5/8/8 w - - 0 1",
+            "8/8/8/2b5/6K1/2k5/8/8 b - - 0 1",
+            "8/8/8/8/6K1/2k5/8/8 w - - 0 1",
+            "8/P7/8/8/8/8/8/K1k5 w - - 0 1",
+            "B7/8/8/8/8/8/8/K1k5 b - - 0 1",
+            "K7/7k/6P1/8/8/8/8/8 b - - 0 1",
+            "K7/8/8/8/1N6/8/2k5/8 b - - 0 1",
+            "N7/8/8/8/8/8/8/K1k5 b - - 0 1",
+            "Q7/8/8/8/8/8/8/K1k5 b - - 0 1",
+            "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1",
+            "R7/8/8/8/8/8/8/K1k5 b - - 0 1",
+            "k1K5/8/8/8/8/8/8/1q6 w - - 0 2",
+            "k1K5/8/8/8/8/8/8/1r6 w - - 0 2",
+            "k1K5/8/8/8/8/8/8/b7 w - - 0 2",
+            "k1K5/8/8/8/8/8/8/n7 w - - 0 2",
+            "k1K5/8/8/8/8/8/8/q7 w - - 0 2",
+            "k1K5/8/8/8/8/8/8/r7 w - - 0 2",
+            "k1K5/8/8/8/8/8/p7/1R6 b - - 0 1",
+            "k1K5/8/8/8/8/8/p7/8 b - - 0 1",
+            "k5Q1/7R/8/8/8/8/8/K7 b - - 0 1",
+            "k5q1/7P/8/8/8/8/8/K7 w - - 0 1",
+            "k7/1p6/b7/8/8/B7/1P6/K7 b - - 0 1",
+            "k7/4N3/8/8/8/8/8/K7 b - - 0 1",
+            "k7/4P3/8/8/8/8/8/K7 w - - 0 1",
+            "k7/4b3/8/4B3/8/8/8/K7 w - - 0 1",
+            "k7/7P/8/8/8/8/8/K7 w - - 0 1",
+            "k7/8/4b3/4B3/8/8/8/K7 w - - 0 1",
+            "k7/8/4n3/8/3K4/8/8/8 w - - 0 1",
+            "k7/8/8/1N3N2/8/1N6/8/K7 w - - 0 1",
+            "k7/8/8/4p3/3K4/8/8/8 w - - 0 1",
+            "k7/8/8/4pP2/8/8/8/K7 w - e6 0 2",
+            "k7/8/8/8/2b5/8/8/K7 w - - 0 1",
+            "k7/8/8/8/2b5/b2b4/8/K7 w - - 0 1",
+            "k7/8/8/8/8/4n3/8/K7 w - - 0 1",
+            "k7/8/8/8/8/4n3/P7/K7 w - - 0 1",
+            "k7/8/8/8/8/8/6p1/K6N b - - 0 1",
+            "k7/8/8/8/8/8/8/K6B b - - 0 1",
+            "k7/8/8/8/8/8/8/K7 w - - 0 1",
+            "k7/8/8/8/8/8/P7/K7 w - - 0 1",
+            "7k/8/8/8/8/5P2/RNBQ1PK1/RNBQ4 w - - 0 1",
+            "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1",
+            "r2k2r1/ppp2P1p/8/3pP3/8/8/PPP2PPP/R3K2R w KQ d6 0 1",
+            "r2qkbnr/1p2pppp/p1n5/3p4/3P1B2/3Q1N2/PPP2PPP/RN2K1R1 b Qkq - 2 8",
+            "r3k2r/8/8/8/8/8/8/1R2K2R b Kkq - 1 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "r3k2r/p1pNqpb1/bn2pnp1/3P4/1p2P3/2N2Q2/PPPBBPpP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R b KQkq - 0 1",
+            "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1",
+            "r3kbnr/ppp2ppp/3p4/8/8/4P3/PPP2PPP/RNBQK2R b KQkq - 0 1",
+            "r3kbnr/pppP1ppp/4p3/8/8/4P3/PPP2PPP/RNBQK2R b KQkq - 0 1",
+            "r3kbnr/pppN1ppp/4p3/8/8/4P3/PPP2PPP/RNBQK2R w KQkq - 0 1",
+            "r3kbnr/pppN1ppp/4p3/8/8/4P3/PPPp1PPP/RNBQK2R w KQkq - 0 1",
+            "r3kbnr/pppqpppp/2n5/3p1b2/3P1B2/2N1P3/PPPQ1PPP/R3KBNR b KQkq - 0 1",
+            "rnb1kb1r/ppppqppp/8/5n2/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1",
+            "rnbq4/rnbq1pk1/5p2/8/8/8/8/7K b - - 0 1",
+            "rnbq1rk1/pppp1ppp/5n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 w - - 2 2",
+            "rnbqk2r/pp2nppp/2pbp3/3p4/3P4/1P1BPN2/PBP2PPP/RN1QK2R b KQkq - 2 6",
+            "rnbqk2r/pppp1ppp/5n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 1 1",
+            "rnbqk2r/pppp1ppp/5n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 1",
+            "rnbqkb1r/ppppppp1/5P1p/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3",
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2",
+            "rnbqkb1r/pppppppp/8/5n2/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkb1r/pppppppp/8/8/8/5nP1/PPPPPP2/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/3P4/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+            "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 1",
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/pppp1Bpp/8/4pp2/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/pppppppp/8/8/6pP/8/PPPPPPP1/RNBQKBNR b KQkq h3 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/P7/8/1PPPPPPP/RNBQKBNR b KQkq a3 0 1",
+        ];
+        for fen in fens {
+            let board = ChessBoard::initialize_from_fen(fen).unwrap();
+            assert_eq!(board.to_fen(), fen);
+            assert_eq!(board.to_string(), fen);
+            assert_eq!(
+                ChessBoard::initialize_from_fen(&board.to_fen()).unwrap(),
+                board
+            );
+        }
+
+        // fields updated by make_move serialize correctly.
+        let mut board = ChessBoard::initialize();
+        board.make_move(encode_move(12, 28, 0)).unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+        board.make_move(encode_move(62, 45, 0)).unwrap();
+        assert_eq!(
+            board.to_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2"
+        );
+    }
+
     #[test]
     fn test_piece_type_at() {
         let board1 = ChessBoard::initialize();
         let mut type_array: [Option<u8>; 64] = [None; 64];
         for i in 0..64 {
-            let piece_type = board1.piece_type_at(i);
+            let piece_type = board1.piece_type_at(Square::new(i)).map(|kind| kind as u8);
             type_array[i as usize] = piece_type;
         }
         let correct_type_array: [Option<u8>; 64] = [
@@ -1196,14 +1829,15 @@ mod tests {
         let mut board1 = ChessBoard::initialize();
         let board1_zob = board1.zobrist_hash;
 
-        let move1_int = 0b0011000111000000; // e2-e4
+        let move1_int = Move::from_raw(0b0011000111000000); // e2-e4
         let move1_undo = board1.make_move(move1_int);
 
         let correct_resulting_board1 = ChessBoard::initialize_from_fen(
             "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
         )
         .unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0b1111,
             en_passant_square: None,
@@ -1215,14 +1849,15 @@ mod tests {
         assert_eq!(move1_undo, correct_undo1);
 
         let board1_zob = board1.zobrist_hash;
-        let move2_int = 0b1111101011010000; // g8-f6
+        let move2_int = Move::from_raw(0b1111101011010000); // g8-f6
         let move2_undo = board1.make_move(move2_int);
 
         let correct_resulting_board2 = ChessBoard::initialize_from_fen(
             "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 2",
         )
         .unwrap();
-        let correct_undo2: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo2: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0b1111,
             en_passant_square: Some(20),
@@ -1233,20 +1868,21 @@ mod tests {
         assert_eq!(board1, correct_resulting_board2);
         assert_eq!(move2_undo, correct_undo2);
 
-        let move3_int = 0b0111001001000000; // e4-e5
+        let move3_int = Move::from_raw(0b0111001001000000); // e4-e5
         board1.make_move(move3_int);
-        let move4_int = 0b1101111011110000; // h7-h6
+        let move4_int = Move::from_raw(0b1101111011110000); // h7-h6
         board1.make_move(move4_int);
 
         let board1_zob = board1.zobrist_hash;
-        let move5_int = 0b1001001011010001; // e5 x f6;
+        let move5_int = Move::from_raw(0b1001001011010001); // e5 x f6;
         let move5_undo = board1.make_move(move5_int);
         let correct_resulting_board5 = ChessBoard::initialize_from_fen(
             "rnbqkb1r/ppppppp1/5P1p/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3",
         )
         .unwrap();
 
-        let correct_undo5: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo5: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 3,
             halfmove_clock: 0,
             castling_rights: 0b1111,
             en_passant_square: None,
@@ -1263,14 +1899,15 @@ mod tests {
         .unwrap();
         let board2_zob = board2.zobrist_hash;
 
-        let move1_int = 0b1001001010110011; // e5 x d5 en passant
+        let move1_int = Move::from_raw(0b1001001010110011); // e5 x d5 en passant
         let move1_undo = board2.make_move(move1_int);
 
         let correct_resulting_board1 = ChessBoard::initialize_from_fen(
             "rnbqkbnr/ppp1pppp/3P4/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
         )
         .unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0b1111,
             en_passant_square: Some(43),
@@ -1287,14 +1924,15 @@ mod tests {
         .unwrap();
         let board3_zob = board3.zobrist_hash;
 
-        let move1_int = 0b0001000001100010; // e1 - g1 (white kingside castle)
+        let move1_int = Move::from_raw(0b0001000001100010); // e1 - g1 (white kingside castle)
         let move1_undo = board3.make_move(move1_int);
 
         let correct_resulting_board1 = ChessBoard::initialize_from_fen(
             "rnbqk2r/pppp1ppp/5n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 1 1",
         )
         .unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0b1111,
             en_passant_square: None,
@@ -1306,14 +1944,15 @@ mod tests {
         assert_eq!(move1_undo, correct_undo1);
 
         let board3_zob = board3.zobrist_hash;
-        let move2_int = 0b1111001111100010; // e8 - g8 (black kingside castle)
+        let move2_int = Move::from_raw(0b1111001111100010); // e8 - g8 (black kingside castle)
         let move2_undo = board3.make_move(move2_int);
 
         let correct_resulting_board2 = ChessBoard::initialize_from_fen(
             "rnbq1rk1/pppp1ppp/5n2/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 w - - 2 2",
         )
         .unwrap();
-        let correct_undo2: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo2: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 1,
             castling_rights: 0b11,
             en_passant_square: None,
@@ -1330,14 +1969,15 @@ mod tests {
         .unwrap();
         let board4_zob = board4.zobrist_hash;
 
-        let move1_int = 0b1111001110100010; // e8 - c8 (black queenside castle)
+        let move1_int = Move::from_raw(0b1111001110100010); // e8 - c8 (black queenside castle)
         let move1_undo = board4.make_move(move1_int);
 
         let correct_resulting_board1 = ChessBoard::initialize_from_fen(
             "2kr1bnr/pppqpppp/2n5/3p1b2/3P1B2/2N1P3/PPPQ1PPP/R3KBNR w KQ - 1 2",
         )
         .unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0b1111,
             en_passant_square: None,
@@ -1349,14 +1989,15 @@ mod tests {
         assert_eq!(move1_undo, correct_undo1);
 
         let board4_zob = board4.zobrist_hash;
-        let move2_int = 0b0001000000100010; // e1 - c1 (white queenside castle)
+        let move2_int = Move::from_raw(0b0001000000100010); // e1 - c1 (white queenside castle)
         let move2_undo = board4.make_move(move2_int);
 
         let correct_resulting_board2 = ChessBoard::initialize_from_fen(
             "2kr1bnr/pppqpppp/2n5/3p1b2/3P1B2/2N1P3/PPPQ1PPP/2KR1BNR b - - 2 2",
         )
         .unwrap();
-        let correct_undo2: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo2: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 2,
             halfmove_clock: 1,
             castling_rights: 0b1100,
             en_passant_square: None,
@@ -1371,12 +2012,13 @@ mod tests {
             ChessBoard::initialize_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
         let board5_zob = board5.zobrist_hash;
 
-        let move1_int = 0b0000000000010000; // a1 - b1
+        let move1_int = Move::from_raw(0b0000000000010000); // a1 - b1
         let move1_undo = board5.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("r3k2r/8/8/8/8/8/8/1R2K2R b Kkq - 1 1").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0b1111,
             en_passant_square: None,
@@ -1388,12 +2030,13 @@ mod tests {
         assert_eq!(move1_undo, correct_undo1);
 
         let board5_zob = board5.zobrist_hash;
-        let move2_int = 0b1110001110010000; // a8 - b8
+        let move2_int = Move::from_raw(0b1110001110010000); // a8 - b8
         let move2_undo = board5.make_move(move2_int);
 
         let correct_resulting_board2 =
             ChessBoard::initialize_from_fen("1r2k2r/8/8/8/8/8/8/1R2K2R w Kk - 2 2").unwrap();
-        let correct_undo2: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo2: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 1,
             castling_rights: 0b1011,
             en_passant_square: None,
@@ -1405,12 +2048,13 @@ mod tests {
         assert_eq!(move2_undo, correct_undo2);
 
         let board5_zob = board5.zobrist_hash;
-        let move3_int = 0b0001110001100000; // h1 - g1
+        let move3_int = Move::from_raw(0b0001110001100000); // h1 - g1
         let move3_undo = board5.make_move(move3_int);
 
         let correct_resulting_board3 =
             ChessBoard::initialize_from_fen("1r2k2r/8/8/8/8/8/8/1R2K1R1 b k - 3 2").unwrap();
-        let correct_undo3: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo3: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 2,
             halfmove_clock: 2,
             castling_rights: 0b1010,
             en_passant_square: None,
@@ -1422,12 +2066,13 @@ mod tests {
         assert_eq!(move3_undo, correct_undo3);
 
         let board5_zob = board5.zobrist_hash;
-        let move4_int = 0b1111111111100000; // h8 - g8
+        let move4_int = Move::from_raw(0b1111111111100000); // h8 - g8
         let move4_undo = board5.make_move(move4_int);
 
         let correct_resulting_board4 =
             ChessBoard::initialize_from_fen("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w - - 4 3").unwrap();
-        let correct_undo4: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo4: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 2,
             halfmove_clock: 3,
             castling_rights: 0b0010,
             en_passant_square: None,
@@ -1442,12 +2087,13 @@ mod tests {
             ChessBoard::initialize_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
         let board6_zob = board6.zobrist_hash;
 
-        let move1_int = 0b0000001110000001; // a1 x a8
+        let move1_int = Move::from_raw(0b0000001110000001); // a1 x a8
         let move1_undo = board6.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0b1111,
             en_passant_square: None,
@@ -1461,12 +2107,13 @@ mod tests {
         let mut board7 = ChessBoard::initialize_from_fen("8/P7/8/8/8/8/8/K1k5 w - - 0 1").unwrap();
         let board7_zob = board7.zobrist_hash;
 
-        let move1_int = 0b1100001110000111; // a7 - a8 promote to q
+        let move1_int = Move::from_raw(0b1100001110000111); // a7 - a8 promote to q
         let move1_undo = board7.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("Q7/8/8/8/8/8/8/K1k5 b - - 0 1").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1480,12 +2127,13 @@ mod tests {
         let mut board7 = ChessBoard::initialize_from_fen("8/P7/8/8/8/8/8/K1k5 w - - 0 1").unwrap();
         let board7_zob = board7.zobrist_hash;
 
-        let move1_int = 0b1100001110000110; // a7 - a8 promote to r
+        let move1_int = Move::from_raw(0b1100001110000110); // a7 - a8 promote to r
         let move1_undo = board7.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("R7/8/8/8/8/8/8/K1k5 b - - 0 1").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1499,12 +2147,13 @@ mod tests {
         let mut board7 = ChessBoard::initialize_from_fen("8/P7/8/8/8/8/8/K1k5 w - - 0 1").unwrap();
         let board7_zob = board7.zobrist_hash;
 
-        let move1_int = 0b1100001110000101; // a7 - a8 promote to b
+        let move1_int = Move::from_raw(0b1100001110000101); // a7 - a8 promote to b
         let move1_undo = board7.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("B7/8/8/8/8/8/8/K1k5 b - - 0 1").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1518,12 +2167,13 @@ mod tests {
         let mut board7 = ChessBoard::initialize_from_fen("8/P7/8/8/8/8/8/K1k5 w - - 0 1").unwrap();
         let board7_zob = board7.zobrist_hash;
 
-        let move1_int = 0b1100001110000100; // a7 - a8 promote to n
+        let move1_int = Move::from_raw(0b1100001110000100); // a7 - a8 promote to n
         let move1_undo = board7.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("N7/8/8/8/8/8/8/K1k5 b - - 0 1").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1537,12 +2187,13 @@ mod tests {
         let mut board8 = ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/p7/8 b - - 0 1").unwrap();
         let board8_zob = board8.zobrist_hash;
 
-        let move1_int = 0b0010000000000111; // a2 - a1 promote to q
+        let move1_int = Move::from_raw(0b0010000000000111); // a2 - a1 promote to q
         let move1_undo = board8.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/8/q7 w - - 0 2").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1556,12 +2207,13 @@ mod tests {
         let mut board8 = ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/p7/8 b - - 0 1").unwrap();
         let board8_zob = board8.zobrist_hash;
 
-        let move1_int = 0b0010000000000110; // a2 - a1 promote to r
+        let move1_int = Move::from_raw(0b0010000000000110); // a2 - a1 promote to r
         let move1_undo = board8.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/8/r7 w - - 0 2").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1575,12 +2227,13 @@ mod tests {
         let mut board8 = ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/p7/8 b - - 0 1").unwrap();
         let board8_zob = board8.zobrist_hash;
 
-        let move1_int = 0b0010000000000101; // a2 - a1 promote to b
+        let move1_int = Move::from_raw(0b0010000000000101); // a2 - a1 promote to b
         let move1_undo = board8.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/8/b7 w - - 0 2").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1594,12 +2247,13 @@ mod tests {
         let mut board8 = ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/p7/8 b - - 0 1").unwrap();
         let board8_zob = board8.zobrist_hash;
 
-        let move1_int = 0b0010000000000100; // a2 - a1 promote to n
+        let move1_int = Move::from_raw(0b0010000000000100); // a2 - a1 promote to n
         let move1_undo = board8.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/8/n7 w - - 0 2").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1614,12 +2268,13 @@ mod tests {
             ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/p7/1R6 b - - 0 1").unwrap();
         let board9_zob = board9.zobrist_hash;
 
-        let move1_int = 0b0010000000011011; // a2 x b1 promote to q
+        let move1_int = Move::from_raw(0b0010000000011011); // a2 x b1 promote to q
         let move1_undo = board9.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/8/1q6 w - - 0 2").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1634,12 +2289,13 @@ mod tests {
             ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/p7/1R6 b - - 0 1").unwrap();
         let board9_zob = board9.zobrist_hash;
 
-        let move1_int = 0b0010000000011010; // a2 x b1 promote to r
+        let move1_int = Move::from_raw(0b0010000000011010); // a2 x b1 promote to r
         let move1_undo = board9.make_move(move1_int);
 
         let correct_resulting_board1 =
             ChessBoard::initialize_from_fen("k1K5/8/8/8/8/8/8/1r6 w - - 0 2").unwrap();
-        let correct_undo1: Result<UndoInfo, String> = Ok(UndoInfo {
+        let correct_undo1: Result<UndoInfo, ChessError> = Ok(UndoInfo {
+            fullmove_number: 1,
             halfmove_clock: 0,
             castling_rights: 0,
             en_passant_square: None,
@@ -1656,7 +2312,7 @@ mod tests {
         let mut board1 = ChessBoard::initialize();
         let board1_copy = board1.clone();
 
-        let move1_int = 0b0011000111000000; // e2-e4
+        let move1_int = Move::from_raw(0b0011000111000000); // e2-e4
         let move1_undo_info = board1.make_move(move1_int).unwrap();
         let move1_undo_result = board1.unmake_move(move1_int, &move1_undo_info);
 
@@ -1669,7 +2325,7 @@ mod tests {
         .unwrap();
         let board2_copy = board2.clone();
 
-        let move2_int = 0b1100001000000000; // a7 - a5
+        let move2_int = Move::from_raw(0b1100001000000000); // a7 - a5
         let move2_undo_info = board2.make_move(move2_int).unwrap();
         let move2_undo_result = board2.unmake_move(move2_int, &move2_undo_info);
 
@@ -1682,7 +2338,7 @@ mod tests {
         .unwrap();
         let board3_copy = board3.clone();
 
-        let move3_int = 0b0111001001010001; // e4 x f5
+        let move3_int = Move::from_raw(0b0111001001010001); // e4 x f5
         let move3_undo_info = board3.make_move(move3_int).unwrap();
         let move3_undo_result = board3.unmake_move(move3_int, &move3_undo_info);
 
@@ -1695,7 +2351,7 @@ mod tests {
         .unwrap();
         let board4_copy = board4.clone();
 
-        let move4_int = 0b0110110101000011; // d4 x e3 en passant
+        let move4_int = Move::from_raw(0b0110110101000011); // d4 x e3 en passant
         let move4_undo_info = board4.make_move(move4_int).unwrap();
         let move4_undo_result = board4.unmake_move(move4_int, &move4_undo_info);
 
@@ -1705,7 +2361,7 @@ mod tests {
         let mut board5 = ChessBoard::initialize_from_fen("k7/7P/8/8/8/8/8/K7 w - - 0 1").unwrap();
         let board5_copy = board5.clone();
 
-        let move5_int = 0b1101111111110100; // h7 - h8 promote to knight
+        let move5_int = Move::from_raw(0b1101111111110100); // h7 - h8 promote to knight
         let move5_undo_info = board5.make_move(move5_int).unwrap();
         let move5_undo_result = board5.unmake_move(move5_int, &move5_undo_info);
 
@@ -1715,7 +2371,7 @@ mod tests {
         let mut board5 = ChessBoard::initialize_from_fen("k7/7P/8/8/8/8/8/K7 w - - 0 1").unwrap();
         let board5_copy = board5.clone();
 
-        let move5_int = 0b1101111111110101; // h7 - h8 promote to bishop
+        let move5_int = Move::from_raw(0b1101111111110101); // h7 - h8 promote to bishop
         let move5_undo_info = board5.make_move(move5_int).unwrap();
         let move5_undo_result = board5.unmake_move(move5_int, &move5_undo_info);
 
@@ -1725,7 +2381,7 @@ mod tests {
         let mut board5 = ChessBoard::initialize_from_fen("k7/7P/8/8/8/8/8/K7 w - - 0 1").unwrap();
         let board5_copy = board5.clone();
 
-        let move5_int = 0b1101111111110110; // h7 - h8 promote to rook
+        let move5_int = Move::from_raw(0b1101111111110110); // h7 - h8 promote to rook
         let move5_undo_info = board5.make_move(move5_int).unwrap();
         let move5_undo_result = board5.unmake_move(move5_int, &move5_undo_info);
 
@@ -1735,7 +2391,7 @@ mod tests {
         let mut board5 = ChessBoard::initialize_from_fen("k7/7P/8/8/8/8/8/K7 w - - 0 1").unwrap();
         let board5_copy = board5.clone();
 
-        let move5_int = 0b1101111111110111; // h7 - h8 promote to queen
+        let move5_int = Move::from_raw(0b1101111111110111); // h7 - h8 promote to queen
         let move5_undo_info = board5.make_move(move5_int).unwrap();
         let move5_undo_result = board5.unmake_move(move5_int, &move5_undo_info);
 
@@ -1745,7 +2401,7 @@ mod tests {
         let mut board6 = ChessBoard::initialize_from_fen("k5q1/7P/8/8/8/8/8/K7 w - - 0 1").unwrap();
         let board6_copy = board6.clone();
 
-        let move6_int = 0b1101111111101011; // h7 x g8 promote to queen
+        let move6_int = Move::from_raw(0b1101111111101011); // h7 x g8 promote to queen
         let move6_undo_info = board6.make_move(move6_int).unwrap();
         let move6_undo_result = board6.unmake_move(move6_int, &move6_undo_info);
 
@@ -1757,7 +2413,7 @@ mod tests {
                 .unwrap();
         let board7_copy = board7.clone();
 
-        let move7_int = 0b0001000001100010; // e1 - g1 white kingside castles
+        let move7_int = Move::from_raw(0b0001000001100010); // e1 - g1 white kingside castles
         let move7_undo_info = board7.make_move(move7_int).unwrap();
         let move7_undo_result = board7.unmake_move(move7_int, &move7_undo_info);
 
@@ -1769,7 +2425,7 @@ mod tests {
                 .unwrap();
         let board7_copy = board7.clone();
 
-        let move7_int = 0b0001000000100010; // e1 - c1 white queenside castles
+        let move7_int = Move::from_raw(0b0001000000100010); // e1 - c1 white queenside castles
         let move7_undo_info = board7.make_move(move7_int).unwrap();
         let move7_undo_result = board7.unmake_move(move7_int, &move7_undo_info);
 
@@ -1781,7 +2437,7 @@ mod tests {
                 .unwrap();
         let board8_copy = board8.clone();
 
-        let move8_int = 0b1111001111100010; // e8 - g8 black kingside castles
+        let move8_int = Move::from_raw(0b1111001111100010); // e8 - g8 black kingside castles
         let move8_undo_info = board8.make_move(move8_int).unwrap();
         let move8_undo_result = board8.unmake_move(move8_int, &move8_undo_info);
 
@@ -1793,7 +2449,7 @@ mod tests {
                 .unwrap();
         let board8_copy = board8.clone();
 
-        let move8_int = 0b1111001110100010; // e8 - c8 black queenside castles
+        let move8_int = Move::from_raw(0b1111001110100010); // e8 - c8 black queenside castles
         let move8_undo_info = board8.make_move(move8_int).unwrap();
         let move8_undo_result = board8.unmake_move(move8_int, &move8_undo_info);
 
@@ -1801,6 +2457,104 @@ mod tests {
         assert_eq!(board8, board8_copy);
     }
 
+    #[test]
+    fn test_chess960() {
+        let board = ChessBoard::initialize_chess960(518);
+        let mut standard = ChessBoard::initialize();
+        standard.chess960 = true;
+        assert_eq!(board, standard);
+
+        let board = ChessBoard::initialize_chess960(0);
+        assert_eq!(
+            board.to_fen(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+        );
+        assert_eq!(
+            board.to_shredder_fen(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1"
+        );
+        assert_eq!(board.castling_rook_files, [7, 5, 7, 5]);
+
+        // inner rooks are named by file in X-FEN, and both notations parse to the same board.
+        let xfen = "1r2k1rr/8/8/8/8/8/8/RR2K2R w BHbg - 0 1";
+        let board = ChessBoard::initialize_from_fen(xfen).unwrap();
+        assert!(board.chess960);
+        assert_eq!(board.castling_rook_files, [7, 1, 6, 1]);
+        assert_eq!(board.to_fen(), "1r2k1rr/8/8/8/8/8/8/RR2K2R w KBgq - 0 1");
+        assert_eq!(
+            board.to_shredder_fen(),
+            "1r2k1rr/8/8/8/8/8/8/RR2K2R w HBgb - 0 1"
+        );
+        assert_eq!(
+            ChessBoard::initialize_from_fen(&board.to_fen()).unwrap(),
+            board
+        );
+        assert_eq!(
+            ChessBoard::initialize_from_fen(&board.to_shredder_fen()).unwrap(),
+            board
+        );
+        assert!(!ChessBoard::initialize().chess960);
+
+        // a rook letter needs a king on the back rank, and can't name the king's own file.
+        assert!(ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/8/R3K2R w E - 0 1").is_err());
+        assert!(ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/4K3/R6R w H - 0 1").is_err());
+    }
+
+    #[test]
+    fn test_make_unmake_castle_chess960() {
+        // the king already stands on g1, so kingside castling only moves the rook.
+        let fen = "1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1";
+        let mut board = ChessBoard::initialize_from_fen(fen).unwrap();
+        let copy = board.clone();
+        let castle = Move::castle(Square::G1, Square::H1);
+        let undo = board.make_move(castle).unwrap();
+        assert_eq!(
+            board.to_shredder_fen(),
+            "1r4kr/8/8/8/8/8/8/1R3RK1 b hb - 1 1"
+        );
+        let incremental_hash = board.zobrist_hash;
+        assert_eq!(incremental_hash, board.generate_zobrist_hash());
+        board.unmake_move(castle, &undo).unwrap();
+        assert_eq!(board, copy);
+
+        // queenside: the king crosses the rook, which lands on d1 next to it.
+        let castle = Move::castle(Square::G1, Square::from_file_rank(1, 0));
+        let undo = board.make_move(castle).unwrap();
+        assert_eq!(
+            board.to_shredder_fen(),
+            "1r4kr/8/8/8/8/8/8/2KR3R b hb - 1 1"
+        );
+        let incremental_hash = board.zobrist_hash;
+        assert_eq!(incremental_hash, board.generate_zobrist_hash());
+        board.unmake_move(castle, &undo).unwrap();
+        assert_eq!(board, copy);
+
+        // the standard king move encoding is still accepted.
+        let mut board = ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        board
+            .make_move(Move::castle(Square::E1, Square::G1))
+            .unwrap();
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+    }
+
+    #[test]
+    fn test_make_move_rejects_invalid() {
+        // moves no piece could make are refused before anything on the board changes.
+        let mut board = ChessBoard::initialize();
+        let copy = board.clone();
+        for mv in [
+            Move::quiet(Square::C1, "e3".parse().unwrap()),
+            Move::capture(Square::G1, "f3".parse().unwrap()),
+            Move::quiet("e2".parse().unwrap(), "e5".parse().unwrap()),
+            Move::en_passant("e2".parse().unwrap(), "d3".parse().unwrap()),
+            Move::castle(Square::E1, Square::G1),
+            Move::from_raw(u16::MAX),
+        ] {
+            assert!(board.make_move(mv).is_err(), "{mv}");
+            assert_eq!(board, copy);
+        }
+    }
+
     fn test_generate_zobrist_hash() {
         let mut board1 = ChessBoard::initialize();
         board1.make_move(encode_move(8, 16, 0));
@@ -0,0 +1,118 @@
+use std::error::Error;
+use std::fmt;
+
+/// The FEN field an `InvalidFen` error refers to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FenField {
+    Record, // the string as a whole, e.g. a missing field
+    Placement,
+    SideToMove,
+    Castling,
+    EnPassant,
+    HalfmoveClock,
+    FullmoveNumber,
+}
+
+impl fmt::Display for FenField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FenField::Record => "record",
+            FenField::Placement => "piece placement",
+            FenField::SideToMove => "side to move",
+            FenField::Castling => "castling rights",
+            FenField::EnPassant => "en passant square",
+            FenField::HalfmoveClock => "halfmove clock",
+            FenField::FullmoveNumber => "fullmove number",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Error type returned by every fallible public API in the crate.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ChessError {
+    InvalidFen {
+        field: FenField,
+        reason: String,
+    },
+    IllegalMove(String), // the move (or what was wrong with it)
+    AmbiguousMove {
+        san: String,
+        candidates: Vec<String>,
+    },
+    GameOver,
+    InvalidSquare(String),
+    InvalidPiece(String),
+    InvalidPromotion(String),
+    InvalidSan {
+        san: String,
+        reason: String,
+    },
+    InvalidUci(String),
+    InvalidUciCommand(String),
+    InvalidPgn(String),
+    InvalidBook(String),
+    InvalidEpd(String),
+}
+
+impl fmt::Display for ChessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChessError::InvalidFen { field, reason } => {
+                write!(f, "Invalid FEN {field}: {reason}")
+            }
+            ChessError::IllegalMove(mv) => write!(f, "Illegal move: {mv}"),
+            ChessError::AmbiguousMove { san, candidates } => {
+                write!(
+                    f,
+                    "Ambiguous move: {san} (could be {})",
+                    candidates.join(", ")
+                )
+            }
+            ChessError::GameOver => write!(f, "Game over"),
+            ChessError::InvalidSquare(square) => write!(f, "Invalid square: {square}"),
+            ChessError::InvalidPiece(piece) => write!(f, "Invalid piece: {piece}"),
+            ChessError::InvalidPromotion(promotion) => {
+                write!(f, "Invalid promotion: {promotion}")
+            }
+            ChessError::InvalidSan { san, reason } => write!(f, "Invalid SAN: {san} ({reason})"),
+            ChessError::InvalidUci(uci) => write!(f, "Invalid UCI move: {uci}"),
+            ChessError::InvalidUciCommand(line) => write!(f, "Invalid UCI command: {line}"),
+            ChessError::InvalidPgn(reason) => write!(f, "Invalid PGN: {reason}"),
+            ChessError::InvalidBook(reason) => write!(f, "Invalid opening book: {reason}"),
+            ChessError::InvalidEpd(reason) => write!(f, "Invalid EPD: {reason}"),
+        }
+    }
+}
+
+impl Error for ChessError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let fen_err = ChessError::InvalidFen {
+            field: FenField::Castling,
+            reason: "unexpected character 'x'".to_string(),
+        };
+        assert_eq!(
+            fen_err.to_string(),
+            "Invalid FEN castling rights: unexpected character 'x'"
+        );
+
+        let ambiguous = ChessError::AmbiguousMove {
+            san: "Nd6".to_string(),
+            candidates: vec!["Nbd6".to_string(), "Nfd6".to_string()],
+        };
+        assert_eq!(
+            ambiguous.to_string(),
+            "Ambiguous move: Nd6 (could be Nbd6, Nfd6)"
+        );
+
+        // usable as a boxed std error.
+        let boxed: Box<dyn Error> = Box::new(ChessError::GameOver);
+        assert_eq!(boxed.to_string(), "Game over");
+    }
+}
@@ -0,0 +1,499 @@
+// this file scores positions statically: material, tapered piece-square tables and pawn and
+// king terms, blended by game phase.
+
+use crate::bitboard::Bitboard;
+use crate::board::ChessBoard;
+use crate::types::{Color, PieceKind, Square};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+/// Nominal centipawn values indexed by PieceKind, for move ordering and exchange counting.
+pub const PIECE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+/// Game phase of the starting material. Knights and bishops count 1, rooks 2, queens 4.
+pub const MAX_PHASE: i32 = 24;
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+/// Scores a position in centipawns from the side to move's point of view.
+pub trait Evaluator {
+    fn evaluate(&self, board: &ChessBoard) -> i32;
+}
+
+/// A middlegame and an endgame score, blended by game phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TaperedScore {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl TaperedScore {
+    pub const ZERO: TaperedScore = TaperedScore::new(0, 0);
+
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        TaperedScore { mg, eg }
+    }
+
+    /// Blends the two scores; phase runs from 0 (bare kings and pawns) to MAX_PHASE.
+    pub const fn taper(self, phase: i32) -> i32 {
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for TaperedScore {
+    type Output = TaperedScore;
+
+    fn add(self, rhs: TaperedScore) -> TaperedScore {
+        TaperedScore::new(self.mg + rhs.mg, self.eg + rhs.eg)
+    }
+}
+
+impl AddAssign for TaperedScore {
+    fn add_assign(&mut self, rhs: TaperedScore) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for TaperedScore {
+    type Output = TaperedScore;
+
+    fn sub(self, rhs: TaperedScore) -> TaperedScore {
+        TaperedScore::new(self.mg - rhs.mg, self.eg - rhs.eg)
+    }
+}
+
+impl Mul<i32> for TaperedScore {
+    type Output = TaperedScore;
+
+    fn mul(self, rhs: i32) -> TaperedScore {
+        TaperedScore::new(self.mg * rhs, self.eg * rhs)
+    }
+}
+
+impl Neg for TaperedScore {
+    type Output = TaperedScore;
+
+    fn neg(self) -> TaperedScore {
+        TaperedScore::new(-self.mg, -self.eg)
+    }
+}
+
+/// An extra evaluation term for one side, such as pawn structure or king safety.
+pub type EvalTerm = fn(&ChessBoard, Color) -> TaperedScore;
+
+/// The game phase from the non-pawn material on the board, clamped to MAX_PHASE.
+pub fn game_phase(board: &ChessBoard) -> i32 {
+    let phase: i32 = [board.knights, board.bishops, board.rooks, board.queens]
+        .iter()
+        .zip(&PHASE_WEIGHTS[1..5])
+        .map(|(bb, weight)| bb.popcount() as i32 * weight)
+        .sum();
+    phase.min(MAX_PHASE)
+}
+
+// piece-square tables from white's point of view, written as the board is seen: the first row
+// is the 8th rank. Indexed by PieceKind.
+#[rustfmt::skip]
+const MG_TABLES: [[i32; 64]; 6] = [
+    [
+         0,   0,   0,   0,   0,   0,   0,   0,
+        50,  50,  50,  50,  50,  50,  50,  50,
+        10,  10,  20,  30,  30,  20,  10,  10,
+         5,   5,  10,  25,  25,  10,   5,   5,
+         0,   0,   0,  20,  20,   0,   0,   0,
+         5,  -5, -10,   0,   0, -10,  -5,   5,
+         5,  10,  10, -20, -20,  10,  10,   5,
+         0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [
+       -50, -40, -30, -30, -30, -30, -40, -50,
+       -40, -20,   0,   0,   0,   0, -20, -40,
+       -30,   0,  10,  15,  15,  10,   0, -30,
+       -30,   5,  15,  20,  20,  15,   5, -30,
+       -30,   0,  15,  20,  20,  15,   0, -30,
+       -30,   5,  10,  15,  15,  10,   5, -30,
+       -40, -20,   0,   5,   5,   0, -20, -40,
+       -50, -40, -30, -30, -30, -30, -40, -50,
+    ],
+    [
+       -20, -10, -10, -10, -10, -10, -10, -20,
+       -10,   0,   0,   0,   0,   0,   0, -10,
+       -10,   0,   5,  10,  10,   5,   0, -10,
+       -10,   5,   5,  10,  10,   5,   5, -10,
+       -10,   0,  10,  10,  10,  10,   0, -10,
+       -10,  10,  10,  10,  10,  10,  10, -10,
+       -10,   5,   0,   0,   0,   0,   5, -10,
+       -20, -10, -10, -10, -10, -10, -10, -20,
+    ],
+    [
+         0,   0,   0,   0,   0,   0,   0,   0,
+         5,  10,  10,  10,  10,  10,  10,   5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+         0,   0,   0,   5,   5,   0,   0,   0,
+    ],
+    [
+       -20, -10, -10,  -5,  -5, -10, -10, -20,
+       -10,   0,   0,   0,   0,   0,   0, -10,
+       -10,   0,   5,   5,   5,   5,   0, -10,
+        -5,   0,   5,   5,   5,   5,   0,  -5,
+         0,   0,   5,   5,   5,   5,   0,  -5,
+       -10,   5,   5,   5,   5,   5,   0, -10,
+       -10,   0,   5,   0,   0,   0,   0, -10,
+       -20, -10, -10,  -5,  -5, -10, -10, -20,
+    ],
+    [
+       -30, -40, -40, -50, -50, -40, -40, -30,
+       -30, -40, -40, -50, -50, -40, -40, -30,
+       -30, -40, -40, -50, -50, -40, -40, -30,
+       -30, -40, -40, -50, -50, -40, -40, -30,
+       -20, -30, -30, -40, -40, -30, -30, -20,
+       -10, -20, -20, -20, -20, -20, -20, -10,
+        20,  20,   0,   0,   0,   0,  20,  20,
+        20,  30,  10,   0,   0,  10,  30,  20,
+    ],
+];
+
+// in the endgame pawns are worth more the further they've advanced, the king belongs in the
+// centre, and the other pieces keep their middlegame tables.
+#[rustfmt::skip]
+const EG_TABLES: [[i32; 64]; 6] = [
+    [
+         0,   0,   0,   0,   0,   0,   0,   0,
+        80,  80,  80,  80,  80,  80,  80,  80,
+        50,  50,  50,  50,  50,  50,  50,  50,
+        30,  30,  30,  30,  30,  30,  30,  30,
+        15,  15,  15,  15,  15,  15,  15,  15,
+         5,   5,   5,   5,   5,   5,   5,   5,
+         0,   0,   0,   0,   0,   0,   0,   0,
+         0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    MG_TABLES[1],
+    MG_TABLES[2],
+    MG_TABLES[3],
+    MG_TABLES[4],
+    [
+       -50, -40, -30, -20, -20, -30, -40, -50,
+       -30, -20, -10,   0,   0, -10, -20, -30,
+       -30, -10,  20,  30,  30,  20, -10, -30,
+       -30, -10,  30,  40,  40,  30, -10, -30,
+       -30, -10,  30,  40,  40,  30, -10, -30,
+       -30, -10,  20,  30,  30,  20, -10, -30,
+       -30, -30,   0,   0,   0,   0, -30, -30,
+       -50, -30, -30, -30, -30, -30, -30, -50,
+    ],
+];
+
+const MG_VALUES: [i32; 6] = [82, 337, 365, 477, 1025, 0];
+const EG_VALUES: [i32; 6] = [94, 281, 297, 512, 936, 0];
+
+/// Material plus tapered piece-square tables, with pluggable pawn structure and king safety
+/// terms. `TaperedEvaluator::default()` is the crate's standard evaluation.
+#[derive(Debug, Clone, Copy)]
+pub struct TaperedEvaluator {
+    pub mg_values: [i32; 6], // indexed by PieceKind
+    pub eg_values: [i32; 6],
+    pub mg_tables: [[i32; 64]; 6], // from white's view, 8th rank first
+    pub eg_tables: [[i32; 64]; 6],
+    pub pawn_structure: EvalTerm,
+    pub king_safety: EvalTerm,
+}
+
+impl Default for TaperedEvaluator {
+    fn default() -> Self {
+        TaperedEvaluator {
+            mg_values: MG_VALUES,
+            eg_values: EG_VALUES,
+            mg_tables: MG_TABLES,
+            eg_tables: EG_TABLES,
+            pawn_structure,
+            king_safety,
+        }
+    }
+}
+
+impl TaperedEvaluator {
+    /// Material and piece-square score of one side's pieces.
+    pub fn piece_score(&self, board: &ChessBoard, color: Color) -> TaperedScore {
+        let own = board.pieces_of(color);
+        let mut score = TaperedScore::ZERO;
+        for kind in PieceKind::ALL {
+            let k = kind.index();
+            for sq in board.pieces(kind) & own {
+                let i = table_index(sq, color);
+                score += TaperedScore::new(
+                    self.mg_values[k] + self.mg_tables[k][i],
+                    self.eg_values[k] + self.eg_tables[k][i],
+                );
+            }
+        }
+        score
+    }
+
+    /// White's total score minus black's, before tapering.
+    pub fn score(&self, board: &ChessBoard) -> TaperedScore {
+        let side = |color| {
+            self.piece_score(board, color)
+                + (self.pawn_structure)(board, color)
+                + (self.king_safety)(board, color)
+        };
+        side(Color::White) - side(Color::Black)
+    }
+}
+
+impl Evaluator for TaperedEvaluator {
+    fn evaluate(&self, board: &ChessBoard) -> i32 {
+        let score = self.score(board).taper(game_phase(board));
+        if board.side_to_move.is_white() {
+            score
+        } else {
+            -score
+        }
+    }
+}
+
+// the tables are drawn with the 8th rank first, which is how black sees its own side.
+fn table_index(sq: Square, color: Color) -> usize {
+    let i = sq.index() as usize;
+    if color.is_white() {
+        i ^ 56
+    } else {
+        i
+    }
+}
+
+// squares in front of a pawn on its own file and the two next to it, for passed pawns.
+fn front_span(sq: Square, color: Color) -> Bitboard {
+    let file = Bitboard::FILES[sq.file() as usize];
+    let files = file | file.east() | file.west();
+    let ahead = (0..8u8)
+        .filter(|&rank| {
+            if color.is_white() {
+                rank > sq.rank()
+            } else {
+                rank < sq.rank()
+            }
+        })
+        .fold(Bitboard::EMPTY, |bb, rank| {
+            bb | Bitboard::RANKS[rank as usize]
+        });
+    files & ahead
+}
+
+const DOUBLED_PAWN: TaperedScore = TaperedScore::new(-10, -20);
+const ISOLATED_PAWN: TaperedScore = TaperedScore::new(-10, -15);
+// indexed by how many ranks the pawn has advanced.
+const PASSED_PAWN: [TaperedScore; 8] = [
+    TaperedScore::new(0, 0),
+    TaperedScore::new(0, 5),
+    TaperedScore::new(5, 10),
+    TaperedScore::new(10, 20),
+    TaperedScore::new(20, 35),
+    TaperedScore::new(30, 60),
+    TaperedScore::new(50, 90),
+    TaperedScore::new(0, 0),
+];
+
+/// Default pawn structure term: doubled and isolated pawn penalties and passed pawn bonuses.
+pub fn pawn_structure(board: &ChessBoard, color: Color) -> TaperedScore {
+    let own = board.pawns & board.pieces_of(color);
+    let enemy = board.pawns & board.pieces_of(!color);
+    let mut score = TaperedScore::ZERO;
+    for file in Bitboard::FILES {
+        let on_file = (own & file).popcount() as i32;
+        if on_file > 1 {
+            score += DOUBLED_PAWN * (on_file - 1);
+        }
+        if on_file > 0 && !own.intersects(file.east() | file.west()) {
+            score += ISOLATED_PAWN * on_file;
+        }
+    }
+    for sq in own {
+        if !enemy.intersects(front_span(sq, color)) {
+            let advanced = if color.is_white() {
+                sq.rank()
+            } else {
+                7 - sq.rank()
+            };
+            score += PASSED_PAWN[advanced as usize];
+        }
+    }
+    score
+}
+
+const SHIELD_PAWN: TaperedScore = TaperedScore::new(12, 0);
+
+/// Default king safety term: a middlegame bonus for each own pawn on the two ranks in front of
+/// the king, on its file and the files next to it.
+pub fn king_safety(board: &ChessBoard, color: Color) -> TaperedScore {
+    let own = board.pieces_of(color);
+    let Some(king_sq) = (board.kings & own).lsb() else {
+        return TaperedScore::ZERO;
+    };
+    let king = king_sq.bb();
+    let (one, two) = if color.is_white() {
+        (king.north(), king.north().north())
+    } else {
+        (king.south(), king.south().south())
+    };
+    let front = one | two;
+    let shield = front | front.east() | front.west();
+    SHIELD_PAWN * (board.pawns & own & shield).popcount() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{search_with, SearchLimits};
+    use std::sync::atomic::AtomicBool;
+
+    // the same position with colors swapped and the board flipped top to bottom.
+    fn mirror(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let swap_case = |s: &str| -> String {
+            s.chars()
+                .map(|c| {
+                    if c.is_ascii_uppercase() {
+                        c.to_ascii_lowercase()
+                    } else {
+                        c.to_ascii_uppercase()
+                    }
+                })
+                .collect()
+        };
+        let placement: Vec<String> = fields[0].split('/').rev().map(swap_case).collect();
+        let side = if fields[1] == "w" { "b" } else { "w" };
+        let castling = if fields[2] == "-" {
+            "-".to_string()
+        } else {
+            let swapped = swap_case(fields[2]);
+            let (white, black): (String, String) =
+                swapped.chars().partition(|c| c.is_ascii_uppercase());
+            white + &black
+        };
+        format!("{} {side} {castling} - 0 1", placement.join("/"))
+    }
+
+    fn eval(fen: &str) -> i32 {
+        TaperedEvaluator::default().evaluate(&ChessBoard::initialize_from_fen(fen).unwrap())
+    }
+
+    #[test]
+    fn test_game_phase() {
+        assert_eq!(game_phase(&ChessBoard::initialize()), MAX_PHASE);
+        assert_eq!(
+            game_phase(
+                &ChessBoard::initialize_from_fen("4k3/4p3/8/8/8/8/4P3/4K3 w - - 0 1").unwrap()
+            ),
+            0
+        );
+        assert_eq!(
+            game_phase(
+                &ChessBoard::initialize_from_fen("3qk3/8/8/8/8/8/8/1N2KR2 w - - 0 1").unwrap()
+            ),
+            7
+        );
+        // extra promoted queens don't push the phase past the start.
+        assert_eq!(
+            game_phase(
+                &ChessBoard::initialize_from_fen("QQQQkQQQ/8/8/8/8/8/8/4K3 b - - 0 1").unwrap()
+            ),
+            MAX_PHASE
+        );
+
+        let score = TaperedScore::new(100, 20);
+        assert_eq!(score.taper(MAX_PHASE), 100);
+        assert_eq!(score.taper(0), 20);
+        assert_eq!(score.taper(MAX_PHASE / 2), 60);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(
+            eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            0
+        );
+
+        // the evaluation is color blind: mirrored positions score the same for the side to move.
+        let fens = [
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 b - - 0 1",
+        ];
+        for fen in fens {
+            assert_eq!(eval(fen), eval(&mirror(fen)), "{fen}");
+            let other_side = fen
+                .replacen(" w ", " x ", 1)
+                .replacen(" b ", " w ", 1)
+                .replacen(" x ", " b ", 1);
+            assert_eq!(eval(fen), -eval(&other_side), "{fen}");
+        }
+
+        // an extra queen is worth about a queen.
+        let up_a_queen = eval(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+                .replace("rnbqkbnr", "rnb1kbnr")
+                .as_str(),
+        );
+        assert!(up_a_queen > 800 && up_a_queen < 1100);
+
+        // a passed pawn is better than a blocked one, and doubled isolated pawns are worse.
+        assert!(
+            eval("4k3/8/8/8/8/8/P7/4K3 w - - 0 1") > eval("4k3/p7/8/8/8/8/P7/4K3 w - - 0 1") + 50
+        );
+        let board = ChessBoard::initialize_from_fen("4k3/8/8/8/8/P7/P7/4K3 w - - 0 1").unwrap();
+        let doubled = pawn_structure(&board, Color::White);
+        assert_eq!(
+            doubled - PASSED_PAWN[2] - PASSED_PAWN[1],
+            DOUBLED_PAWN + ISOLATED_PAWN * 2
+        );
+
+        // castled behind three pawns vs with the pawns pushed away.
+        let shielded = ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/5PPP/6K1 w - - 0 1").unwrap();
+        assert_eq!(king_safety(&shielded, Color::White), SHIELD_PAWN * 3);
+        let bare = ChessBoard::initialize_from_fen("4k3/8/8/5PPP/8/8/8/6K1 w - - 0 1").unwrap();
+        assert_eq!(king_safety(&bare, Color::White), TaperedScore::ZERO);
+    }
+
+    #[test]
+    fn test_custom_evaluator() {
+        // values only knights, so the search trades a rook for one.
+        struct KnightLover;
+        impl Evaluator for KnightLover {
+            fn evaluate(&self, board: &ChessBoard) -> i32 {
+                let white = (board.knights & board.white_pieces).popcount() as i32;
+                let black = (board.knights & board.black_pieces).popcount() as i32;
+                let score = 100 * (white - black);
+                if board.side_to_move.is_white() {
+                    score
+                } else {
+                    -score
+                }
+            }
+        }
+
+        let fen = "4k3/8/8/3n4/8/8/8/3RK3 w - - 0 1";
+        let mut board = ChessBoard::initialize_from_fen(fen).unwrap();
+        let result = search_with(
+            &mut board,
+            &SearchLimits::depth(2),
+            &KnightLover,
+            None,
+            &AtomicBool::new(false),
+            |_| {},
+        );
+        assert_eq!(result.best_move.unwrap().to_string(), "d1d5");
+
+        // with the default terms swapped out, only material and tables remain.
+        let plain = TaperedEvaluator {
+            pawn_structure: |_, _| TaperedScore::ZERO,
+            king_safety: |_, _| TaperedScore::ZERO,
+            ..TaperedEvaluator::default()
+        };
+        let board = ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/8/4K2N w - - 0 1").unwrap();
+        let knight = TaperedScore::new(337 - 50, 281 - 50);
+        assert_eq!(plain.evaluate(&board), knight.taper(1));
+    }
+}
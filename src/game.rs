@@ -1,31 +1,207 @@
 use crate::board::{ChessBoard, UndoInfo};
+use crate::error::ChessError;
 use crate::moves::{self, get_legal_moves, has_legal_moves};
+use crate::types::{Color, Move};
 use crate::{board, rules, utils};
 use arrayvec::ArrayVec;
 use rustc_hash::FxHashMap;
+use std::time::Instant;
+
+/// How a per-move delay is applied before time is taken off the mover's clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayKind {
+    // simple (US) delay: the clock only starts counting down once the delay has passed.
+    Simple,
+    // bronstein delay: the full time used is deducted, then up to the delay is given back.
+    Bronstein,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    pub starting_ms: u64,
+    pub increment_ms: u64,
+    pub delay: Option<(DelayKind, u64)>, // (kind, delay_ms), None = no delay
+}
+
+impl TimeControl {
+    pub fn new(starting_ms: u64, increment_ms: u64) -> Self {
+        TimeControl {
+            starting_ms,
+            increment_ms,
+            delay: None,
+        }
+    }
+
+    pub fn with_delay(mut self, kind: DelayKind, delay_ms: u64) -> Self {
+        self.delay = Some((kind, delay_ms));
+        self
+    }
+}
+
+// (starting_ms, increment_ms), the original time control format.
+impl From<(u32, u32)> for TimeControl {
+    fn from(time_control: (u32, u32)) -> Self {
+        TimeControl::new(time_control.0 as u64, time_control.1 as u64)
+    }
+}
+
+/// Tracks the remaining time of both sides.
+/// The clock only measures wall time once it has been started with start(). Until then
+/// (or when moves are made with ChessGame::make_move_timed) the caller supplies the time used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChessClock {
+    pub time_control: TimeControl,
+    pub white_ms: u64,
+    pub black_ms: u64,
+    turn_started: Option<Instant>, // Some while the clock is running.
+    history: Vec<(u64, u64)>,      // (white_ms, black_ms) before each move, for unmake_move.
+}
+
+impl ChessClock {
+    pub fn new(time_control: TimeControl) -> Self {
+        ChessClock {
+            time_control,
+            white_ms: time_control.starting_ms,
+            black_ms: time_control.starting_ms,
+            turn_started: None,
+            history: Vec::new(),
+        }
+    }
+
+    /// Starts (or restarts) timing the current turn.
+    pub fn start(&mut self) {
+        self.turn_started = Some(Instant::now());
+    }
+
+    pub fn stop(&mut self) {
+        self.turn_started = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.turn_started.is_some()
+    }
+
+    /// Milliseconds spent on the current turn so far (0 if the clock is not running).
+    pub fn elapsed_ms(&self) -> u64 {
+        match self.turn_started {
+            Some(start) => start.elapsed().as_millis() as u64,
+            None => 0,
+        }
+    }
+
+    // remaining time of a side as of the last move (does not include the running turn).
+    pub fn remaining_ms(&self, color: Color) -> u64 {
+        if color.is_white() {
+            self.white_ms
+        } else {
+            self.black_ms
+        }
+    }
+
+    /// Time actually taken off the clock for a move that took elapsed_ms.
+    pub fn time_charged(&self, elapsed_ms: u64) -> u64 {
+        match self.time_control.delay {
+            Some((DelayKind::Simple, delay_ms)) => elapsed_ms.saturating_sub(delay_ms),
+            Some((DelayKind::Bronstein, _)) | None => elapsed_ms,
+        }
+    }
+
+    /// Returns true if a side runs out of time after spending elapsed_ms on its move.
+    /// With bronstein delay, the delay is only given back after the move, so it cannot save a flag.
+    pub fn is_flagged(&self, color: Color, elapsed_ms: u64) -> bool {
+        self.time_charged(elapsed_ms) > self.remaining_ms(color)
+    }
+
+    /// Charges a completed move to a side's clock and adds delay refund and increment.
+    /// The caller must check is_flagged() first.
+    pub fn punch(&mut self, color: Color, elapsed_ms: u64) {
+        self.history.push((self.white_ms, self.black_ms));
+
+        let mut charged = self.time_charged(elapsed_ms);
+        if let Some((DelayKind::Bronstein, delay_ms)) = self.time_control.delay {
+            charged -= elapsed_ms.min(delay_ms);
+        }
+
+        let remaining = if color.is_white() {
+            &mut self.white_ms
+        } else {
+            &mut self.black_ms
+        };
+        *remaining = remaining.saturating_sub(charged) + self.time_control.increment_ms;
+
+        if self.is_running() {
+            self.start(); // opponent's turn starts now.
+        }
+    }
+
+    /// Restores both clocks to their values before the last punch().
+    pub fn undo(&mut self) {
+        if let Some((white_ms, black_ms)) = self.history.pop() {
+            self.white_ms = white_ms;
+            self.black_ms = black_ms;
+        }
+        if self.is_running() {
+            self.start();
+        }
+    }
+
+    /// Sets a side's clock to 0 (used when it flags).
+    pub fn flag(&mut self, color: Color) {
+        if color.is_white() {
+            self.white_ms = 0;
+        } else {
+            self.black_ms = 0;
+        }
+        self.stop();
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ChessGame {
     pub board: board::ChessBoard,
-    pub time_control: (u32, u32), // starting_ms, increment_ms, ** CLOCK CONTROL NOT IMPLEMENTED YET **
-    pub moves: Vec<(u16, board::UndoInfo)>,
+    pub start_fen: Option<String>, // None = standard starting position
+    pub clock: ChessClock,
+    pub moves: Vec<(Move, board::UndoInfo)>,
     pub positions_count: FxHashMap<u64, u8>,
-    pub legal_moves: ArrayVec<u16, 256>,
+    pub legal_moves: ArrayVec<Move, 256>,
     pub result: GameResult,
 }
 impl ChessGame {
-    pub fn initialize(time_control: (u32, u32), fen: Option<&str>) -> Self {
-        let mut game_board = match fen {
-            None => ChessBoard::initialize(),
-            Some(f) => ChessBoard::initialize_from_fen(f).unwrap(),
-        };
+    // a game from the standard starting position.
+    pub fn new(time_control: impl Into<TimeControl>) -> Self {
+        Self::from_board(time_control.into(), ChessBoard::initialize(), None)
+    }
+
+    pub fn initialize(
+        time_control: impl Into<TimeControl>,
+        fen: Option<&str>,
+    ) -> Result<Self, ChessError> {
+        match fen {
+            None => Ok(Self::new(time_control)),
+            Some(f) => Self::initialize_from_fen(time_control, f),
+        }
+    }
+
+    pub fn initialize_from_fen(
+        time_control: impl Into<TimeControl>,
+        fen: &str,
+    ) -> Result<Self, ChessError> {
+        let board = ChessBoard::initialize_from_fen(fen)?;
+        Ok(Self::from_board(time_control.into(), board, Some(fen)))
+    }
 
+    fn from_board(
+        time_control: TimeControl,
+        mut game_board: ChessBoard,
+        fen: Option<&str>,
+    ) -> Self {
         let legal_moves = moves::get_legal_moves(&mut game_board);
         let mut positions_count = FxHashMap::default();
         positions_count.insert(game_board.zobrist_hash, 1);
         let mut game = ChessGame {
             board: game_board,
-            time_control: time_control,
+            start_fen: fen.map(|f| f.to_string()),
+            clock: ChessClock::new(time_control),
             moves: Vec::new(),
             positions_count: positions_count,
             legal_moves: legal_moves,
@@ -35,24 +211,48 @@ impl ChessGame {
         return game;
     }
 
+    // charges the time spent on the move to the clock, measured since the clock was started
+    // (or no time, if the clock is not running).
     pub fn make_move(
         &mut self,
-        movei: u16,
+        movei: Move,
+        gen_legal_moves: bool,
+        legal_move_bypass: bool,
+    ) -> Result<GameResult, ChessError> {
+        let elapsed_ms = self.clock.elapsed_ms();
+        self.make_move_timed(movei, elapsed_ms, gen_legal_moves, legal_move_bypass)
+    }
+
+    // same as make_move(), but with the time spent on the move given by the caller.
+    // if the mover has run out of time, the move is not made and the game ends on time.
+    pub fn make_move_timed(
+        &mut self,
+        movei: Move,
+        elapsed_ms: u64,
         gen_legal_moves: bool,
         legal_move_bypass: bool,
-    ) -> Result<GameResult, String> {
+    ) -> Result<GameResult, ChessError> {
         if self.result != GameResult::InProgress {
-            return Err("Game over".to_string());
+            return Err(ChessError::GameOver);
         }
 
         if legal_move_bypass || self.legal_moves.contains(&movei) {
             // move will not be in the legal moves list if it is not valid, thus this is sufficient validation.
+            // bypassed moves are still refused by board.make_move() unless they are pseudo-legal.
+            let mover = self.board.side_to_move;
+            if self.clock.is_flagged(mover, elapsed_ms) {
+                self.clock.flag(mover);
+                self.result = self.timeout_result(mover);
+                return Ok(self.result);
+            }
+
             let undo_info = self.board.make_move(movei)?; // returns error from board.make_move() if there is one.
+            self.clock.punch(mover, elapsed_ms);
             if gen_legal_moves {
                 self.legal_moves = get_legal_moves(&mut self.board);
             } else {
                 self.legal_moves = if has_legal_moves(&mut self.board) {
-                    ArrayVec::from_iter([u16::MAX])
+                    ArrayVec::from_iter([Move::from_raw(u16::MAX)])
                 } else {
                     ArrayVec::new()
                 };
@@ -65,13 +265,16 @@ impl ChessGame {
             self.result = self.check_result();
             return Ok(self.result);
         } else {
-            return Err("Invalid move".to_string());
+            return Err(ChessError::IllegalMove(utils::decode_to_uci(movei)?));
         }
     }
 
-    pub fn unmake_move(&mut self, gen_legal_moves: bool) -> Result<(), String> {
+    pub fn unmake_move(&mut self, gen_legal_moves: bool) -> Result<(), ChessError> {
         // simply pop off the moves list, which has the undo info,
-        let undo_info = self.moves.pop().unwrap();
+        let undo_info = self
+            .moves
+            .pop()
+            .ok_or(ChessError::IllegalMove("no move to unmake".to_string()))?;
         // decrement position count for current hash,
         if self.positions_count[&self.board.zobrist_hash] == 1 {
             self.positions_count.remove(&self.board.zobrist_hash);
@@ -83,6 +286,8 @@ impl ChessGame {
         }
         // call the board level move undo,
         self.board.unmake_move(undo_info.0, &undo_info.1)?;
+        // give back the time (and increment) of the undone move,
+        self.clock.undo();
         // regen legal moves.
         if gen_legal_moves {
             self.legal_moves = get_legal_moves(&mut self.board);
@@ -98,26 +303,43 @@ impl ChessGame {
         uci_move: &str,
         gen_legal_moves: bool,
         legal_move_bypass: bool,
-    ) -> Result<GameResult, String> {
+    ) -> Result<GameResult, ChessError> {
+        // uci only gives squares and promotion piece, the flag comes from the matching legal move.
         let movei = utils::encode_from_uci(uci_move)?;
-        if (movei << 12) == 0 {
-            // no flag is given
-            for lmovei in &self.legal_moves {
-                if lmovei >> 4 == movei >> 4 {
-                    return self.make_move(*lmovei, gen_legal_moves, legal_move_bypass);
-                }
+        for lmovei in &self.legal_moves {
+            if lmovei.from() == movei.from()
+                && lmovei.to() == movei.to()
+                && lmovei.promotion() == movei.promotion()
+            {
+                return self.make_move(*lmovei, gen_legal_moves, legal_move_bypass);
             }
-        } else {
-            if self.legal_moves.contains(&movei) {
-                // promotion
-                return self.make_move(movei, gen_legal_moves, legal_move_bypass);
-            } else if self.legal_moves.contains(&(movei + 4)) {
-                // promotion w capture
-                return self.make_move(movei + 4, gen_legal_moves, legal_move_bypass);
+        }
+
+        return Err(ChessError::IllegalMove(uci_move.to_string()));
+    }
+
+    // checks whether the side to move has run out of time on the running clock.
+    // meant to be polled while waiting for a move; ends the game if they have.
+    pub fn check_time(&mut self) -> GameResult {
+        if self.result == GameResult::InProgress && self.clock.is_running() {
+            let mover = self.board.side_to_move;
+            if self.clock.is_flagged(mover, self.clock.elapsed_ms()) {
+                self.clock.flag(mover);
+                self.result = self.timeout_result(mover);
             }
         }
+        self.result
+    }
 
-        return Err("invalid move".to_string());
+    // result when a side runs out of time: a loss, unless the opponent cannot possibly checkmate.
+    pub fn timeout_result(&self, flagged_side: Color) -> GameResult {
+        if !rules::can_checkmate(&self.board, !flagged_side) {
+            return GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial);
+        }
+        match flagged_side {
+            Color::White => GameResult::BlackWins(WinReason::Timeout),
+            Color::Black => GameResult::WhiteWins(WinReason::Timeout),
+        }
     }
 
     pub fn check_result(&mut self) -> GameResult {
@@ -126,8 +348,8 @@ impl ChessGame {
             if rules::is_check(&self.board, self.board.side_to_move) {
                 // if side to move is in check == checkmate
                 match self.board.side_to_move {
-                    true => return GameResult::BlackWins(WinReason::Checkmate),
-                    false => return GameResult::WhiteWins(WinReason::Checkmate),
+                    Color::White => return GameResult::BlackWins(WinReason::Checkmate),
+                    Color::Black => return GameResult::WhiteWins(WinReason::Checkmate),
                 }
             } else {
                 // stalemate
@@ -174,6 +396,7 @@ pub enum DrawReason {
     ThreefoldRepitition,
     InsufficientMaterial,
     Agreement,
+    TimeoutVsInsufficientMaterial,
 }
 
 #[cfg(test)]
@@ -185,7 +408,7 @@ mod tests {
     #[test]
     fn test_initialize_game() {
         // from standard starting position
-        let game = ChessGame::initialize((1, 1), None);
+        let game = ChessGame::new((1, 1));
 
         let mut starting_board = ChessBoard::initialize();
         let map: FxHashMap<u64, u8> = [(starting_board.zobrist_hash, 1)].into_iter().collect();
@@ -193,7 +416,8 @@ mod tests {
             game,
             ChessGame {
                 board: starting_board.clone(),
-                time_control: (1, 1),
+                start_fen: None,
+                clock: ChessClock::new(TimeControl::new(1, 1)),
                 moves: (Vec::new()),
                 positions_count: map,
                 legal_moves: moves::get_legal_moves(&mut starting_board),
@@ -202,7 +426,7 @@ mod tests {
         );
 
         // from fen position
-        let game = ChessGame::initialize((1, 1), Some("k7/8/8/8/2b5/b2b4/8/K7 w - - 0 1"));
+        let game = ChessGame::initialize((1, 1), Some("k7/8/8/8/2b5/b2b4/8/K7 w - - 0 1")).unwrap();
 
         let mut starting_board =
             ChessBoard::initialize_from_fen("k7/8/8/8/2b5/b2b4/8/K7 w - - 0 1").unwrap();
@@ -211,36 +435,46 @@ mod tests {
             game,
             ChessGame {
                 board: starting_board.clone(),
-                time_control: (1, 1),
+                start_fen: Some("k7/8/8/8/2b5/b2b4/8/K7 w - - 0 1".to_string()),
+                clock: ChessClock::new(TimeControl::new(1, 1)),
                 moves: (Vec::new()),
                 positions_count: map,
                 legal_moves: moves::get_legal_moves(&mut starting_board),
                 result: GameResult::Draw(DrawReason::Stalemate)
             }
         );
+
+        // an invalid FEN is an error, not a panic.
+        assert!(matches!(
+            ChessGame::initialize((1, 1), Some("4k3/8/8/8/8/8/4Q3/4K3 w - - 0 1")),
+            Err(ChessError::InvalidFen { .. })
+        ));
     }
 
     #[test]
     fn test_check_result() {
-        let mut game = ChessGame::initialize((1, 1), None); // starting position
+        let mut game = ChessGame::new((1, 1)); // starting position
         assert_eq!(game.check_result(), GameResult::InProgress);
 
-        let mut game = ChessGame::initialize((1, 1), Some("k7/8/8/8/2b5/b2b4/8/K7 w - - 0 1")); // white is in stalemate
+        let mut game =
+            ChessGame::initialize((1, 1), Some("k7/8/8/8/2b5/b2b4/8/K7 w - - 0 1")).unwrap(); // white is in stalemate
         assert_eq!(game.check_result(), GameResult::Draw(DrawReason::Stalemate));
 
-        let mut game = ChessGame::initialize((1, 1), Some("k5Q1/7R/8/8/8/8/8/K7 b - - 0 1")); // black is in checkmate
+        let mut game =
+            ChessGame::initialize((1, 1), Some("k5Q1/7R/8/8/8/8/8/K7 b - - 0 1")).unwrap(); // black is in checkmate
         assert_eq!(
             game.check_result(),
             GameResult::WhiteWins(WinReason::Checkmate)
         );
 
-        let mut game = ChessGame::initialize((1, 1), Some("8/3k4/8/8/8/8/6R1/K7 b - - 100 50")); // 50 move rule
+        let mut game =
+            ChessGame::initialize((1, 1), Some("8/3k4/8/8/8/8/6R1/K7 b - - 100 50")).unwrap(); // 50 move rule
         assert_eq!(
             game.check_result(),
             GameResult::Draw(DrawReason::FiftyMoveRule)
         );
 
-        let mut game = ChessGame::initialize((1, 1), None); // 3 fold repetition
+        let mut game = ChessGame::new((1, 1)); // 3 fold repetition
         game.make_move(encode_move(1, 18, 0), true, false);
         game.make_move(encode_move(57, 42, 0), true, false);
         game.make_move(encode_move(18, 1, 0), true, false);
@@ -257,48 +491,55 @@ mod tests {
 
         // insufficient material cases
 
-        let mut game = ChessGame::initialize((1, 1), Some("8/8/8/8/6K1/2k5/8/8 w - - 0 1")); // K vs K
+        let mut game =
+            ChessGame::initialize((1, 1), Some("8/8/8/8/6K1/2k5/8/8 w - - 0 1")).unwrap(); // K vs K
         assert_eq!(
             game.check_result(),
             GameResult::Draw(DrawReason::InsufficientMaterial)
         );
-        let mut game = ChessGame::initialize((1, 1), Some("8/8/8/2b5/6K1/2k5/8/8 b - - 0 1")); // K vs kb
+        let mut game =
+            ChessGame::initialize((1, 1), Some("8/8/8/2b5/6K1/2k5/8/8 b - - 0 1")).unwrap(); // K vs kb
         assert_eq!(
             game.check_result(),
             GameResult::Draw(DrawReason::InsufficientMaterial)
         );
-        let mut game = ChessGame::initialize((1, 1), Some("8/8/7n/8/6K1/2k5/8/8 w - - 0 1")); // K vs kn
+        let mut game =
+            ChessGame::initialize((1, 1), Some("8/8/7n/8/6K1/2k5/8/8 w - - 0 1")).unwrap(); // K vs kn
         assert_eq!(
             game.check_result(),
             GameResult::Draw(DrawReason::InsufficientMaterial)
         );
-        let mut game = ChessGame::initialize((1, 1), Some("8/2K5/6k1/8/6B1/8/8/8 b - - 0 1")); // KB vs k
+        let mut game =
+            ChessGame::initialize((1, 1), Some("8/2K5/6k1/8/6B1/8/8/8 b - - 0 1")).unwrap(); // KB vs k
         assert_eq!(
             game.check_result(),
             GameResult::Draw(DrawReason::InsufficientMaterial)
         );
-        let mut game = ChessGame::initialize((1, 1), Some("8/2K5/6k1/8/8/8/8/7N w - - 0 1")); // KN vs k
+        let mut game =
+            ChessGame::initialize((1, 1), Some("8/2K5/6k1/8/8/8/8/7N w - - 0 1")).unwrap(); // KN vs k
         assert_eq!(
             game.check_result(),
             GameResult::Draw(DrawReason::InsufficientMaterial)
         );
-        let mut game = ChessGame::initialize((1, 1), Some("5b2/2K5/3B2k1/8/8/8/8/8 w - - 0 1")); // KB vs kb (same color)
+        let mut game =
+            ChessGame::initialize((1, 1), Some("5b2/2K5/3B2k1/8/8/8/8/8 w - - 0 1")).unwrap(); // KB vs kb (same color)
         assert_eq!(
             game.check_result(),
             GameResult::Draw(DrawReason::InsufficientMaterial)
         );
-        let mut game = ChessGame::initialize((1, 1), Some("6b1/2K5/3B2k1/8/8/8/8/8 b - - 0 1")); // KB vs kb (opp_color=InProgress)
+        let mut game =
+            ChessGame::initialize((1, 1), Some("6b1/2K5/3B2k1/8/8/8/8/8 b - - 0 1")).unwrap(); // KB vs kb (opp_color=InProgress)
         assert_eq!(game.check_result(), GameResult::InProgress);
     }
 
     #[test]
     fn test_make_move() {
-        let mut game = ChessGame::initialize((1, 1), None); // starting position e2-e4
+        let mut game = ChessGame::new((1, 1)); // starting position e2-e4
         let movei = encode_move(12, 28, 0);
         let move_result = game.make_move(movei, false, false);
         assert!(move_result.is_ok());
 
-        let mut game = ChessGame::initialize((1, 1), None); // illegal first move
+        let mut game = ChessGame::new((1, 1)); // illegal first move
         let movei = encode_move(12, 36, 0);
         let move_result = game.make_move(movei, false, false);
         assert!(move_result.is_err());
@@ -306,7 +547,8 @@ mod tests {
         let mut game = ChessGame::initialize(
             (1, 1),
             Some("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 1"),
-        );
+        )
+        .unwrap();
         let movei = encode_move(59, 31, 0); // checkmating move
         let move_result = game.make_move(movei, false, false);
         assert_eq!(
@@ -315,8 +557,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_clock() {
+        // increment is added after time is charged, and undone by unmake_move.
+        let mut game = ChessGame::new((60_000, 2_000));
+        let move_result = game.make_move_timed(encode_move(12, 28, 0), 5_000, true, false);
+        assert_eq!(move_result, Ok(GameResult::InProgress));
+        assert_eq!(game.clock.white_ms, 57_000);
+        assert_eq!(game.clock.black_ms, 60_000);
+        _ = game.make_move_timed(encode_move(52, 36, 0), 1_000, true, false);
+        assert_eq!(game.clock.black_ms, 61_000);
+        _ = game.unmake_move(true);
+        assert_eq!((game.clock.white_ms, game.clock.black_ms), (57_000, 60_000));
+        _ = game.unmake_move(true);
+        assert_eq!((game.clock.white_ms, game.clock.black_ms), (60_000, 60_000));
+
+        // clock not running: make_move charges no time.
+        _ = game.make_move(encode_move(12, 28, 0), true, false);
+        assert_eq!(game.clock.white_ms, 62_000);
+
+        // simple delay: only time beyond the delay is charged.
+        let tc = TimeControl::new(10_000, 0).with_delay(DelayKind::Simple, 3_000);
+        let mut game = ChessGame::new(tc);
+        _ = game.make_move_timed(encode_move(12, 28, 0), 2_000, true, false);
+        assert_eq!(game.clock.white_ms, 10_000);
+        _ = game.make_move_timed(encode_move(52, 36, 0), 12_000, true, false);
+        assert_eq!(game.clock.black_ms, 1_000);
+
+        // bronstein delay: same refund, but it cannot save a flag.
+        let tc = TimeControl::new(10_000, 0).with_delay(DelayKind::Bronstein, 3_000);
+        let mut game = ChessGame::new(tc);
+        _ = game.make_move_timed(encode_move(12, 28, 0), 5_000, true, false);
+        assert_eq!(game.clock.white_ms, 8_000);
+        let move_result = game.make_move_timed(encode_move(52, 36, 0), 12_000, true, false);
+        assert_eq!(move_result, Ok(GameResult::WhiteWins(WinReason::Timeout)));
+    }
+
+    #[test]
+    fn test_timeout() {
+        // white flags: the move is not made and black wins.
+        let mut game = ChessGame::new((1_000, 0));
+        let move_result = game.make_move_timed(encode_move(12, 28, 0), 1_001, true, false);
+        assert_eq!(move_result, Ok(GameResult::BlackWins(WinReason::Timeout)));
+        assert_eq!(game.clock.white_ms, 0);
+        assert!(game.moves.is_empty());
+        assert!(game.make_move(encode_move(12, 28, 0), true, false).is_err());
+
+        // white flags, but black only has a king: draw.
+        let mut game =
+            ChessGame::initialize((1_000, 0), Some("k7/8/8/8/8/8/P7/K7 w - - 0 1")).unwrap();
+        let move_result = game.make_move_timed(encode_move(8, 16, 0), 5_000, true, false);
+        assert_eq!(
+            move_result,
+            Ok(GameResult::Draw(DrawReason::TimeoutVsInsufficientMaterial))
+        );
+
+        // polling a running clock.
+        let mut game = ChessGame::new((0, 0));
+        assert_eq!(game.check_time(), GameResult::InProgress); // not started
+        game.clock.start();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert_eq!(game.check_time(), GameResult::BlackWins(WinReason::Timeout));
+    }
+
     fn test_unmake_move() {
-        let mut game = ChessGame::initialize((1, 1), None);
+        let mut game = ChessGame::new((1, 1));
         let game_unchanged = game.clone();
         let movei = encode_move(12, 28, 0);
         _ = game.make_move(movei, false, false);
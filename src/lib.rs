@@ -1,9 +1,24 @@
+pub mod bitboard;
 pub mod board;
+pub mod error;
+pub mod eval;
 pub mod game;
 pub mod magic_gen;
 pub mod magic_tables;
+pub mod movepick;
 pub mod moves;
 pub mod perft;
+pub mod pgn;
+pub mod polyglot;
+pub mod polyglot_randoms;
 pub mod rules;
+pub mod san;
+pub mod search;
+pub mod see;
+pub mod syzygy;
+pub mod tablebase;
+pub mod tt;
+pub mod types;
+pub mod uci;
 pub mod utils;
 pub mod zobrist_keys;
@@ -35,6 +35,8 @@
 
 // rook gen
 
+use crate::bitboard::Bitboard;
+use crate::types::{Color, Square};
 use crate::{
     board::ChessBoard,
     moves::{bishop_attacks, rook_attacks, RAYS},
@@ -73,10 +75,10 @@ fn get_bit_indices(int: u64) -> Vec<u8> {
 
 pub fn rook_relevant_mask(sq_i: u8) -> u64 {
     let rays = RAYS[sq_i as usize];
-    let ray_n = clear_highest_bit(rays[0]);
-    let ray_e = clear_highest_bit(rays[2]);
-    let ray_s = clear_lowest_bit(rays[4]);
-    let ray_w = clear_lowest_bit(rays[6]);
+    let ray_n = clear_highest_bit(rays[0].0);
+    let ray_e = clear_highest_bit(rays[2].0);
+    let ray_s = clear_lowest_bit(rays[4].0);
+    let ray_w = clear_lowest_bit(rays[6].0);
 
     ray_n | ray_e | ray_s | ray_w
 }
@@ -102,22 +104,24 @@ fn generate_rook_blk_attacks(sq_i: u8) -> Vec<(u64, u64)> {
     for block_mask in blockers {
         // for the sake of reusing rook_attacks(), we make a board instance to pass in, where all blockers are assumed to be opposing pieces.
         let board: ChessBoard = ChessBoard {
-            pawns: 0,
-            knights: 0,
-            bishops: 0,
-            rooks: 0,
-            queens: 0,
-            kings: 0,
-            white_pieces: 0,
-            black_pieces: block_mask,
-            side_to_move: true,
-            en_passant: 0,
+            pawns: Bitboard::EMPTY,
+            knights: Bitboard::EMPTY,
+            bishops: Bitboard::EMPTY,
+            rooks: Bitboard::EMPTY,
+            queens: Bitboard::EMPTY,
+            kings: Bitboard::EMPTY,
+            white_pieces: Bitboard::EMPTY,
+            black_pieces: Bitboard(block_mask),
+            side_to_move: Color::White,
+            en_passant: Bitboard::EMPTY,
             castling_rights: 0,
+            castling_rook_files: [7, 0, 7, 0],
+            chess960: false,
             halfmove_clock: 0,
             fullmove_number: 0,
             zobrist_hash: 0,
         };
-        let attack_mask = rook_attacks(true, 1 << sq_i, &board);
+        let attack_mask = rook_attacks(Color::White, Square::new(sq_i), &board).0;
         blockers_attacks.push((block_mask, attack_mask));
     }
 
@@ -163,10 +167,10 @@ pub fn generate_magic_rook_table() -> ([u64; 64], Vec<[u64; 4096]>) {
 
 pub fn bishop_relevant_mask(sq_i: u8) -> u64 {
     let rays = RAYS[sq_i as usize];
-    let ray_ne = clear_highest_bit(rays[1]);
-    let ray_se = clear_lowest_bit(rays[3]);
-    let ray_sw = clear_lowest_bit(rays[5]);
-    let ray_nw = clear_highest_bit(rays[7]);
+    let ray_ne = clear_highest_bit(rays[1].0);
+    let ray_se = clear_lowest_bit(rays[3].0);
+    let ray_sw = clear_lowest_bit(rays[5].0);
+    let ray_nw = clear_highest_bit(rays[7].0);
 
     ray_ne | ray_se | ray_sw | ray_nw
 }
@@ -191,22 +195,24 @@ fn generate_bishop_blk_attacks(sq_i: u8) -> Vec<(u64, u64)> {
     let mut blockers_attacks: Vec<(u64, u64)> = Vec::new();
     for block_mask in blockers {
         let board: ChessBoard = ChessBoard {
-            pawns: 0,
-            knights: 0,
-            bishops: 0,
-            rooks: 0,
-            queens: 0,
-            kings: 0,
-            white_pieces: 0,
-            black_pieces: block_mask,
-            side_to_move: true,
-            en_passant: 0,
+            pawns: Bitboard::EMPTY,
+            knights: Bitboard::EMPTY,
+            bishops: Bitboard::EMPTY,
+            rooks: Bitboard::EMPTY,
+            queens: Bitboard::EMPTY,
+            kings: Bitboard::EMPTY,
+            white_pieces: Bitboard::EMPTY,
+            black_pieces: Bitboard(block_mask),
+            side_to_move: Color::White,
+            en_passant: Bitboard::EMPTY,
             castling_rights: 0,
+            castling_rook_files: [7, 0, 7, 0],
+            chess960: false,
             halfmove_clock: 0,
             fullmove_number: 0,
             zobrist_hash: 0,
         };
-        let attack_mask = bishop_attacks(true, 1 << sq_i, &board);
+        let attack_mask = bishop_attacks(Color::White, Square::new(sq_i), &board).0;
         blockers_attacks.push((block_mask, attack_mask));
     }
 
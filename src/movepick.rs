@@ -0,0 +1,305 @@
+// this file hands moves to the search one at a time, generating each stage only when the
+// previous one runs out, so a beta cutoff early on skips generating the rest.
+//
+// stages: the hash move, captures (most valuable victim / least valuable attacker first),
+// killer moves, then the remaining quiet moves. in check there is a single evasions stage
+// after the hash move instead, and quiescence only gets the captures. the hash move and the
+// killers are checked with moves::is_legal and played before anything is generated; the
+// stages generated later leave them out.
+
+use crate::board::ChessBoard;
+use crate::eval::PIECE_VALUES;
+use crate::moves::{generate_moves, is_legal, GenType};
+use crate::types::{Move, PieceKind};
+use arrayvec::ArrayVec;
+
+/// Where a `MovePicker` is in its sequence of moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    HashMove,
+    Captures,
+    Killers,
+    Quiets,
+    Evasions,
+    Done,
+}
+
+/// Yields the legal moves of a position in search order, lazily.
+#[derive(Debug, Clone)]
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    killer_index: usize, // the next killer to try
+    in_check: bool,
+    captures_only: bool,
+    generated: bool, // whether the current stage's moves have been generated
+    moves: ArrayVec<(Move, i32), 256>, // the current stage's moves with their order keys
+}
+
+impl MovePicker {
+    /// All legal moves: the hash move (from the table or the last pv) first, killers after
+    /// the captures.
+    pub fn new(board: &ChessBoard, hash_move: Option<Move>, killers: [Option<Move>; 2]) -> Self {
+        MovePicker {
+            stage: Stage::HashMove,
+            hash_move,
+            killers,
+            killer_index: 0,
+            in_check: crate::rules::is_check(board, board.side_to_move),
+            captures_only: false,
+            generated: false,
+            moves: ArrayVec::new(),
+        }
+    }
+
+    /// Captures, en passant and queen promotions only, for quiescence search.
+    pub fn captures(board: &ChessBoard) -> Self {
+        MovePicker {
+            in_check: false,
+            captures_only: true,
+            ..MovePicker::new(board, None, [None; 2])
+        }
+    }
+
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// The next move, or None once every move has been handed out. The board must be the
+    /// position the picker was made for.
+    pub fn next(&mut self, board: &mut ChessBoard) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = if self.in_check {
+                        Stage::Evasions
+                    } else {
+                        Stage::Captures
+                    };
+                    // the hash move may come from another position (a hash collision or a
+                    // stale pv), so it is only played if it is legal here.
+                    self.hash_move = self
+                        .hash_move
+                        .filter(|&mv| !self.captures_only && is_legal(board, mv));
+                    if self.hash_move.is_some() {
+                        return self.hash_move;
+                    }
+                }
+                Stage::Captures | Stage::Evasions => {
+                    if !self.generated {
+                        let gen = if self.stage == Stage::Evasions {
+                            GenType::Evasions
+                        } else {
+                            GenType::Captures
+                        };
+                        self.load(board, gen);
+                    }
+                    if let Some(mv) = self.pick_best() {
+                        return Some(mv);
+                    }
+                    self.stage = if self.stage == Stage::Evasions || self.captures_only {
+                        Stage::Done
+                    } else {
+                        Stage::Killers
+                    };
+                    self.generated = false;
+                }
+                Stage::Killers => {
+                    while let Some(&killer) = self.killers.get(self.killer_index) {
+                        self.killer_index += 1;
+                        if self.is_fresh_killer(board, killer) {
+                            return killer;
+                        }
+                        // a killer that isn't played here mustn't be left out of the quiets.
+                        self.killers[self.killer_index - 1] = None;
+                    }
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    if !self.generated {
+                        self.load(board, GenType::Quiets);
+                        // reversed, so popping hands them out in generation order.
+                        self.moves.reverse();
+                    }
+                    if let Some((mv, _)) = self.moves.pop() {
+                        return Some(mv);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    // a legal quiet killer that hasn't been played already, as the hash move or the other
+    // killer.
+    fn is_fresh_killer(&self, board: &ChessBoard, killer: Option<Move>) -> bool {
+        let Some(mv) = killer else {
+            return false;
+        };
+        let played_first = self.killers[..self.killer_index - 1].contains(&killer);
+        killer != self.hash_move
+            && !played_first
+            && !GenType::Captures.includes(mv)
+            && is_legal(board, mv)
+    }
+
+    // replaces the stage's moves with freshly generated ones, scored for ordering, leaving out
+    // the hash move and killers already played.
+    fn load(&mut self, board: &mut ChessBoard, gen: GenType) {
+        let played = |mv: Move| {
+            Some(mv) == self.hash_move
+                || (gen == GenType::Quiets && self.killers.contains(&Some(mv)))
+        };
+        self.moves = generate_moves(board, gen)
+            .into_iter()
+            .filter(|&mv| !played(mv))
+            .map(|mv| (mv, order_key(board, mv)))
+            .collect();
+        self.generated = true;
+    }
+
+    // selection sort, one move per call: cheaper than sorting when a cutoff comes early.
+    fn pick_best(&mut self) -> Option<Move> {
+        let (i, _) = self
+            .moves
+            .iter()
+            .enumerate()
+            .max_by_key(|&(i, &(_, key))| (key, std::cmp::Reverse(i)))?;
+        Some(self.moves.remove(i).0)
+    }
+}
+
+// captures by most valuable victim, then least valuable attacker; promotions by the piece.
+fn order_key(board: &ChessBoard, mv: Move) -> i32 {
+    let mut key = 0;
+    if mv.is_capture() {
+        let victim = board.piece_type_at(mv.to()).unwrap_or(PieceKind::Pawn); // en passant
+        let attacker = board.piece_type_at(mv.from()).unwrap_or(PieceKind::Pawn);
+        key += 10 * PIECE_VALUES[victim.index()] - PIECE_VALUES[attacker.index()] / 10;
+    }
+    if let Some(kind) = mv.promotion() {
+        key += PIECE_VALUES[kind.index()];
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::get_legal_moves;
+
+    fn board(fen: &str) -> ChessBoard {
+        ChessBoard::initialize_from_fen(fen).unwrap()
+    }
+
+    fn legal(board: &mut ChessBoard, uci: &str) -> Move {
+        get_legal_moves(board)
+            .into_iter()
+            .find(|mv| mv.to_string() == uci)
+            .unwrap()
+    }
+
+    fn drain(picker: &mut MovePicker, board: &mut ChessBoard) -> Vec<String> {
+        std::iter::from_fn(|| picker.next(board))
+            .map(|mv| mv.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_stages() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = board(kiwipete);
+        let hash_move = legal(&mut position, "e1g1");
+        let killer = legal(&mut position, "a2a3");
+        let mut picker = MovePicker::new(&position, Some(hash_move), [Some(killer), None]);
+
+        assert_eq!(picker.next(&mut position), Some(hash_move));
+        assert_eq!(picker.stage(), Stage::Captures);
+        // the bishop is the most valuable piece white can take.
+        let first_capture = picker.next(&mut position).unwrap();
+        assert_eq!(first_capture.to_string(), "e2a6");
+
+        let rest = drain(&mut picker, &mut position);
+        let captures = rest.iter().take_while(|mv| **mv != "a2a3").count();
+        assert_eq!(
+            captures + 1,
+            generate_moves(&mut position, GenType::Captures).len()
+        );
+        assert_eq!(rest[captures], "a2a3");
+
+        // every legal move exactly once.
+        let mut all: Vec<String> = [hash_move.to_string(), first_capture.to_string()]
+            .into_iter()
+            .chain(rest)
+            .collect();
+        let mut expected: Vec<String> = get_legal_moves(&mut position)
+            .iter()
+            .map(|mv| mv.to_string())
+            .collect();
+        all.sort();
+        expected.sort();
+        assert_eq!(all, expected);
+    }
+
+    #[test]
+    fn test_capture_order_and_bad_hash_moves() {
+        // the rook can take the queen or a pawn; the pawn can take the queen too.
+        let mut position = board("4k3/p7/8/R2q4/2P5/8/8/4K3 w - - 0 1");
+        let mut picker = MovePicker::captures(&position);
+        assert_eq!(drain(&mut picker, &mut position), ["c4d5", "a5d5", "a5a7"]);
+
+        // a hash move that isn't legal here is skipped, and killers aren't played twice.
+        let mut position = board("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+        let bogus = Move::quiet("a1".parse().unwrap(), "a8".parse().unwrap());
+        let castle = legal(&mut position, "e1g1");
+        let mut picker = MovePicker::new(&position, Some(bogus), [Some(castle), Some(castle)]);
+        let moves = drain(&mut picker, &mut position);
+        assert_eq!(moves[0], "e1g1");
+        assert_eq!(moves.len(), get_legal_moves(&mut position).len());
+        assert!(!moves.contains(&"a1a8".to_string()));
+    }
+
+    #[test]
+    fn test_hash_move_before_generation() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = board(kiwipete);
+        let hash_move = legal(&mut position, "e2a6");
+        let killer = legal(&mut position, "a2a3");
+        let mut picker = MovePicker::new(&position, Some(hash_move), [Some(killer), None]);
+
+        // the hash move comes out before the capture stage has generated anything.
+        assert_eq!(picker.next(&mut position), Some(hash_move));
+        assert_eq!(picker.stage(), Stage::Captures);
+        assert!(!picker.generated);
+        assert!(picker.moves.is_empty());
+
+        // neither the capture hash move nor the killer is handed out again.
+        let rest = drain(&mut picker, &mut position);
+        assert_eq!(rest.iter().filter(|mv| **mv == "e2a6").count(), 0);
+        assert_eq!(rest.iter().filter(|mv| **mv == "a2a3").count(), 1);
+        assert_eq!(rest.len() + 1, get_legal_moves(&mut position).len());
+
+        // a killer that isn't legal here is skipped without generating the quiets.
+        let mut position = board("4k3/8/8/8/8/8/8/4K2R w - - 0 1");
+        let castle = Move::castle("e1".parse().unwrap(), "g1".parse().unwrap());
+        let mut picker = MovePicker::new(&position, None, [Some(castle), None]);
+        let moves = drain(&mut picker, &mut position);
+        assert!(!moves.contains(&"e1g1".to_string()));
+        assert_eq!(moves.len(), get_legal_moves(&mut position).len());
+    }
+
+    #[test]
+    fn test_evasions() {
+        // in check only evasions come out, the capture of the checker first.
+        let mut position = board("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1");
+        let mut picker = MovePicker::new(&position, None, [None; 2]);
+        assert_eq!(
+            picker.next(&mut position).map(|mv| mv.to_string()),
+            Some("e1d2".to_string())
+        );
+        assert_eq!(picker.stage(), Stage::Evasions);
+        assert_eq!(drain(&mut picker, &mut position), ["e1f1"]);
+    }
+}
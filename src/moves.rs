@@ -1,35 +1,29 @@
 // this file produces attack masks for each piece on a given board.
 
+use crate::bitboard::Bitboard;
 use crate::board;
 use crate::magic_tables::{
     BISHOP_ATTACKS, BISHOP_MAGIC_NUMS, BISHOP_MASKS, ROOK_ATTACKS, ROOK_MAGIC_NUMS, ROOK_MASKS,
 };
+use crate::rules;
+use crate::types::{Color, Move, PieceKind, Square};
 use crate::utils;
 use arrayvec::ArrayVec;
 
-pub const A_FILE: u64 = 0x0101010101010101;
-pub const H_FILE: u64 = 0x8080808080808080;
-
-const fn generate_one_pawn_attacks(color: bool, square: u64) -> u64 {
+const fn generate_one_pawn_attacks(color: bool, square: Bitboard) -> Bitboard {
     // generates pawn attack squares for one square. no pruning based on occupied squares.
-    let mut pawn_attacks: u64 = 0;
-
     if color {
-        pawn_attacks = pawn_attacks | ((square & !A_FILE) << 7);
-        pawn_attacks = pawn_attacks | ((square & !H_FILE) << 9);
+        square.north_west().union(square.north_east())
     } else {
-        pawn_attacks = pawn_attacks | ((square & !A_FILE) >> 9);
-        pawn_attacks = pawn_attacks | ((square & !H_FILE) >> 7);
+        square.south_west().union(square.south_east())
     }
-
-    pawn_attacks
 }
 
-const fn generate_white_pawn_attacks() -> [u64; 64] {
-    let mut white_pawn_attacks = [0u64; 64];
+const fn generate_white_pawn_attacks() -> [Bitboard; 64] {
+    let mut white_pawn_attacks = [Bitboard::EMPTY; 64];
     let mut i = 0;
     while i < 64 {
-        let i_square: u64 = 1 << i;
+        let i_square = Bitboard(1 << i);
         white_pawn_attacks[i] = generate_one_pawn_attacks(true, i_square);
         i += 1;
     }
@@ -37,11 +31,11 @@ const fn generate_white_pawn_attacks() -> [u64; 64] {
     white_pawn_attacks
 }
 
-const fn generate_black_pawn_attacks() -> [u64; 64] {
-    let mut black_pawn_attacks = [0u64; 64];
+const fn generate_black_pawn_attacks() -> [Bitboard; 64] {
+    let mut black_pawn_attacks = [Bitboard::EMPTY; 64];
     let mut i = 0;
     while i < 64 {
-        let i_square: u64 = 1 << i;
+        let i_square = Bitboard(1 << i);
         black_pawn_attacks[i] = generate_one_pawn_attacks(false, i_square);
         i += 1;
     }
@@ -49,60 +43,54 @@ const fn generate_black_pawn_attacks() -> [u64; 64] {
     black_pawn_attacks
 }
 
-pub const WHITE_PAWN_ATTACKS: [u64; 64] = generate_white_pawn_attacks();
-pub const BLACK_PAWN_ATTACKS: [u64; 64] = generate_black_pawn_attacks();
+pub const WHITE_PAWN_ATTACKS: [Bitboard; 64] = generate_white_pawn_attacks();
+pub const BLACK_PAWN_ATTACKS: [Bitboard; 64] = generate_black_pawn_attacks();
 
-// returns squares attacked by one pawn.
-pub fn pawn_attacks(color: bool, square: u64, board: &board::ChessBoard) -> u64 {
-    let mut pawn_attacks: u64;
-    if color {
-        pawn_attacks = WHITE_PAWN_ATTACKS[square.trailing_zeros() as usize];
-        pawn_attacks &= !board.white_pieces;
+// squares a piece of this color may move to: everything but its own pieces.
+fn not_friendly(board: &board::ChessBoard, color: Color) -> Bitboard {
+    if color.is_white() {
+        !board.white_pieces
     } else {
-        pawn_attacks = BLACK_PAWN_ATTACKS[square.trailing_zeros() as usize];
-        pawn_attacks &= !board.black_pieces;
+        !board.black_pieces
     }
-
-    pawn_attacks
 }
 
-const fn generate_one_knight_attacks(square: u64) -> u64 {
-    // take the piece, use rank/file data and if statements, use bitwise-or with u64 = 0 to add attack squares.
-    let mut knight_attacks: u64 = 0;
-    if !((utils::on_rank_7(square) || utils::on_rank_8(square)) || utils::on_a_file(square)) {
-        knight_attacks = knight_attacks | (square << 15);
-    }
-    if !((utils::on_rank_7(square) || utils::on_rank_8(square)) || utils::on_h_file(square)) {
-        knight_attacks = knight_attacks | (square << 17);
-    }
-    if !(utils::on_rank_8(square) || (utils::on_a_file(square) || utils::on_b_file(square))) {
-        knight_attacks = knight_attacks | (square << 6);
-    }
-    if !(utils::on_rank_8(square) || (utils::on_g_file(square) || utils::on_h_file(square))) {
-        knight_attacks = knight_attacks | (square << 10);
-    }
-    if !((utils::on_rank_1(square) || utils::on_rank_2(square)) || utils::on_a_file(square)) {
-        knight_attacks = knight_attacks | (square >> 17);
-    }
-    if !((utils::on_rank_1(square) || utils::on_rank_2(square)) || utils::on_h_file(square)) {
-        knight_attacks = knight_attacks | (square >> 15);
-    }
-    if !(utils::on_rank_1(square) || (utils::on_a_file(square) || utils::on_b_file(square))) {
-        knight_attacks = knight_attacks | (square >> 10);
-    }
-    if !(utils::on_rank_1(square) || (utils::on_g_file(square) || utils::on_h_file(square))) {
-        knight_attacks = knight_attacks | (square >> 6);
-    }
+// returns squares attacked by one pawn.
+pub fn pawn_attacks(color: Color, square: Square, board: &board::ChessBoard) -> Bitboard {
+    let pawn_attacks = if color.is_white() {
+        WHITE_PAWN_ATTACKS[square.index() as usize]
+    } else {
+        BLACK_PAWN_ATTACKS[square.index() as usize]
+    };
 
-    knight_attacks
+    pawn_attacks & not_friendly(board, color)
+}
+
+const fn generate_one_knight_attacks(square: Bitboard) -> Bitboard {
+    // one step orthogonally, then one step diagonally away from where we started. the edge-aware
+    // shifts drop anything that would fall off the board.
+    let north = square.north();
+    let south = square.south();
+    let east = square.east();
+    let west = square.west();
+
+    north
+        .north_east()
+        .union(north.north_west())
+        .union(south.south_east())
+        .union(south.south_west())
+        .union(east.north_east())
+        .union(east.south_east())
+        .union(west.north_west())
+        .union(west.south_west())
 }
 
-const fn generate_knight_attacks() -> [u64; 64] {
-    let mut knight_attacks = [0u64; 64];
+const fn generate_knight_attacks() -> [Bitboard; 64] {
+    let mut knight_attacks = [Bitboard::EMPTY; 64];
 
     let mut i = 0;
     while i < 64 {
-        let i_square: u64 = 1 << i;
+        let i_square = Bitboard(1 << i);
         knight_attacks[i] = generate_one_knight_attacks(i_square);
         i += 1;
     }
@@ -110,139 +98,45 @@ const fn generate_knight_attacks() -> [u64; 64] {
     knight_attacks
 }
 
-pub const KNIGHT_ATTACKS: [u64; 64] = generate_knight_attacks();
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = generate_knight_attacks();
 
 // returns squares attacked by one knight.
-pub fn knight_attacks(color: bool, square: u64, board: &board::ChessBoard) -> u64 {
-    let mut knight_attacks = KNIGHT_ATTACKS[square.trailing_zeros() as usize];
-
-    if color {
-        knight_attacks = knight_attacks & !(board.white_pieces);
-    } else {
-        knight_attacks = knight_attacks & !(board.black_pieces);
-    }
-    knight_attacks
-}
-
-pub const fn generate_north_ray(square: u64) -> u64 {
-    let mut ray: u64 = 0;
-
-    let mut n_square = square.wrapping_shl(8);
-    while n_square > square {
-        ray |= n_square;
-        n_square = n_square.wrapping_shl(8);
-    }
-    ray
-}
-
-pub const fn generate_south_ray(square: u64) -> u64 {
-    let mut ray: u64 = 0;
-
-    let mut n_square = square.wrapping_shr(8);
-    while n_square != 0 {
-        ray |= n_square;
-        n_square = n_square.wrapping_shr(8);
-    }
-    ray
-}
-
-pub const fn generate_east_ray(square: u64) -> u64 {
-    if utils::on_h_file(square) {
-        0
-    } else {
-        let mut ray: u64 = 0;
-
-        let mut e_square = square;
-        while !utils::on_h_file(e_square) {
-            e_square = e_square << 1;
-            ray |= e_square;
-        }
-        ray
-    }
-}
-
-pub const fn generate_west_ray(square: u64) -> u64 {
-    if utils::on_a_file(square) {
-        0
-    } else {
-        let mut ray: u64 = 0;
-
-        let mut w_square = square;
-        while !utils::on_a_file(w_square) {
-            w_square = w_square >> 1;
-            ray |= w_square;
-        }
-        ray
-    }
-}
-
-pub const fn generate_ne_ray(square: u64) -> u64 {
-    if utils::on_h_file(square) || utils::on_rank_8(square) {
-        0
-    } else {
-        let mut ray: u64 = 0;
-
-        let mut ne_square = square;
-        while !(utils::on_h_file(ne_square) || utils::on_rank_8(ne_square)) {
-            ne_square = ne_square << 9;
-            ray |= ne_square;
-        }
-        ray
-    }
-}
-
-pub const fn generate_nw_ray(square: u64) -> u64 {
-    if utils::on_a_file(square) || utils::on_rank_8(square) {
-        0
-    } else {
-        let mut ray: u64 = 0;
-
-        let mut nw_square = square;
-        while !(utils::on_a_file(nw_square) || utils::on_rank_8(nw_square)) {
-            nw_square = nw_square << 7;
-            ray |= nw_square;
-        }
-        ray
-    }
+pub fn knight_attacks(color: Color, square: Square, board: &board::ChessBoard) -> Bitboard {
+    KNIGHT_ATTACKS[square.index() as usize] & not_friendly(board, color)
 }
 
-pub const fn generate_sw_ray(square: u64) -> u64 {
-    if utils::on_a_file(square) || utils::on_rank_1(square) {
-        0
-    } else {
-        let mut ray: u64 = 0;
+// walks from square in one direction (given as a const shift) until it falls off the board.
+macro_rules! generate_ray {
+    ($name:ident, $step:ident) => {
+        pub const fn $name(square: Bitboard) -> Bitboard {
+            let mut ray = Bitboard::EMPTY;
 
-        let mut sw_square = square;
-        while !(utils::on_a_file(sw_square) || utils::on_rank_1(sw_square)) {
-            sw_square = sw_square >> 9;
-            ray |= sw_square;
+            let mut next_square = square.$step();
+            while next_square.is_nonempty() {
+                ray = ray.union(next_square);
+                next_square = next_square.$step();
+            }
+            ray
         }
-        ray
-    }
+    };
 }
 
-pub const fn generate_se_ray(square: u64) -> u64 {
-    if utils::on_h_file(square) || utils::on_rank_1(square) {
-        0
-    } else {
-        let mut ray: u64 = 0;
-
-        let mut se_square = square;
-        while !(utils::on_h_file(se_square) || utils::on_rank_1(se_square)) {
-            se_square = se_square >> 7;
-            ray |= se_square;
-        }
-        ray
-    }
-}
+generate_ray!(generate_north_ray, north);
+generate_ray!(generate_south_ray, south);
+generate_ray!(generate_east_ray, east);
+generate_ray!(generate_west_ray, west);
+generate_ray!(generate_ne_ray, north_east);
+generate_ray!(generate_nw_ray, north_west);
+generate_ray!(generate_sw_ray, south_west);
+generate_ray!(generate_se_ray, south_east);
 
-pub const fn generate_rays() -> [[u64; 8]; 64] {
+pub const fn generate_rays() -> [[Bitboard; 8]; 64] {
     // indices of inner array defined as follows (0-7): [n, ne, e, se, s, sw, w, nw]
-    let mut rays: [[u64; 8]; 64] = [[0; 8]; 64];
+    let mut rays: [[Bitboard; 8]; 64] = [[Bitboard::EMPTY; 8]; 64];
 
     let mut i = 0;
     while i < 64 {
-        let piece_bb: u64 = 1 << i;
+        let piece_bb = Bitboard(1 << i);
 
         rays[i][0] = generate_north_ray(piece_bb);
         rays[i][1] = generate_ne_ray(piece_bb);
@@ -258,192 +152,130 @@ pub const fn generate_rays() -> [[u64; 8]; 64] {
     rays
 }
 
-pub const RAYS: [[u64; 8]; 64] = generate_rays();
-// relevant ray = RAYS[square_bb.trailing_zeros()][i in 0-7 in order n, ne, e, se, s, sw, w, nw]
-
-pub fn check_along_ray(piece: u64, ray: u64, friendly_pieces: u64, enemy_pieces: u64) -> u64 {
-    // define nw, n, ne, e as dir = true, else dir = false
-    let dir = ray > piece;
+pub const RAYS: [[Bitboard; 8]; 64] = generate_rays();
+// relevant ray = RAYS[square.index()][i in 0-7 in order n, ne, e, se, s, sw, w, nw]
+
+pub fn check_along_ray(
+    piece: Square,
+    ray: Bitboard,
+    friendly_pieces: Bitboard,
+    enemy_pieces: Bitboard,
+) -> Bitboard {
+    // nw, n, ne, e rays run towards h8, so walk them lowest square first; the rest highest first.
+    let towards_h8 = ray.0 > piece.bb().0;
     let mut ray_left = ray;
-    let mut updated_ray = 0;
+    let mut updated_ray = Bitboard::EMPTY;
 
-    if dir {
-        let mut next_sqi = (1 as u64).wrapping_shl(ray_left.trailing_zeros());
-
-        while ray_left != 0 {
-            if next_sqi & friendly_pieces != 0 {
-                return updated_ray;
-            } else if next_sqi & enemy_pieces != 0 {
-                updated_ray = updated_ray | next_sqi;
-                return updated_ray;
-            } else {
-                updated_ray = updated_ray | next_sqi;
-                ray_left = ray_left & !(next_sqi);
-                next_sqi = (1 as u64).wrapping_shl(ray_left.trailing_zeros());
-            }
-        }
-        return updated_ray;
+    let mut next_sq = if towards_h8 {
+        ray_left.lsb()
     } else {
-        let mut next_sqi = (0x8000000000000000 as u64).wrapping_shr(ray_left.leading_zeros());
-
-        while ray_left != 0 {
-            if next_sqi & friendly_pieces != 0 {
-                return updated_ray;
-            } else if next_sqi & enemy_pieces != 0 {
-                updated_ray = updated_ray | next_sqi;
-                return updated_ray;
-            } else {
-                updated_ray = updated_ray | next_sqi;
-                ray_left = ray_left & !(next_sqi);
-                next_sqi = (0x8000000000000000 as u64).wrapping_shr(ray_left.leading_zeros());
-            }
+        ray_left.msb()
+    };
+    while let Some(sq) = next_sq {
+        if friendly_pieces.contains(sq) {
+            return updated_ray;
         }
-        return updated_ray;
+        updated_ray |= sq;
+        if enemy_pieces.contains(sq) {
+            return updated_ray;
+        }
+        ray_left ^= sq;
+        next_sq = if towards_h8 {
+            ray_left.lsb()
+        } else {
+            ray_left.msb()
+        };
     }
+    updated_ray
 }
 
-// returns squares attacked by one bishop.
-pub fn bishop_attacks(color: bool, square: u64, board: &board::ChessBoard) -> u64 {
-    let mut bishop_attacks: u64 = 0;
-
-    let friendly_pieces;
-    let enemy_pieces;
-    if color {
-        friendly_pieces = board.white_pieces;
-        enemy_pieces = board.black_pieces;
+fn friendly_and_enemy(board: &board::ChessBoard, color: Color) -> (Bitboard, Bitboard) {
+    if color.is_white() {
+        (board.white_pieces, board.black_pieces)
     } else {
-        friendly_pieces = board.black_pieces;
-        enemy_pieces = board.white_pieces;
-    }
-
-    let sq_trzs = square.trailing_zeros() as usize;
-    let bishop_rays: [u64; 4] = [
-        RAYS[sq_trzs][1], //ne
-        RAYS[sq_trzs][3], //se
-        RAYS[sq_trzs][5], //sw
-        RAYS[sq_trzs][7], //nw
-    ];
-    for ray in bishop_rays {
-        bishop_attacks =
-            bishop_attacks | check_along_ray(square, ray, friendly_pieces, enemy_pieces);
+        (board.black_pieces, board.white_pieces)
     }
+}
 
-    bishop_attacks
+// returns squares attacked by one bishop.
+pub fn bishop_attacks(color: Color, square: Square, board: &board::ChessBoard) -> Bitboard {
+    let (friendly_pieces, enemy_pieces) = friendly_and_enemy(board, color);
+
+    let rays = RAYS[square.index() as usize];
+    [rays[1], rays[3], rays[5], rays[7]] // ne, se, sw, nw
+        .into_iter()
+        .fold(Bitboard::EMPTY, |attacks, ray| {
+            attacks | check_along_ray(square, ray, friendly_pieces, enemy_pieces)
+        })
 }
 
 // returns squares attacked by one rook
-pub fn rook_attacks(color: bool, square: u64, board: &board::ChessBoard) -> u64 {
-    let mut rook_attacks: u64 = 0;
-
-    let friendly_pieces;
-    let enemy_pieces;
-    if color {
-        friendly_pieces = board.white_pieces;
-        enemy_pieces = board.black_pieces;
-    } else {
-        friendly_pieces = board.black_pieces;
-        enemy_pieces = board.white_pieces;
-    }
-
-    let sq_trzs = square.trailing_zeros() as usize;
-    let rook_rays: [u64; 4] = [
-        RAYS[sq_trzs][0], //n
-        RAYS[sq_trzs][2], //e
-        RAYS[sq_trzs][4], //s
-        RAYS[sq_trzs][6], //w
-    ];
-
-    for ray in rook_rays {
-        rook_attacks = rook_attacks | check_along_ray(square, ray, friendly_pieces, enemy_pieces);
-    }
-
-    rook_attacks
+pub fn rook_attacks(color: Color, square: Square, board: &board::ChessBoard) -> Bitboard {
+    let (friendly_pieces, enemy_pieces) = friendly_and_enemy(board, color);
+
+    let rays = RAYS[square.index() as usize];
+    [rays[0], rays[2], rays[4], rays[6]] // n, e, s, w
+        .into_iter()
+        .fold(Bitboard::EMPTY, |attacks, ray| {
+            attacks | check_along_ray(square, ray, friendly_pieces, enemy_pieces)
+        })
 }
 
 // returns squares attacked by one queen.
-pub fn queen_attacks(color: bool, square: u64, board: &board::ChessBoard) -> u64 {
-    let mut queen_attacks: u64 = 0;
-    queen_attacks = queen_attacks | rook_attacks(color, square, board);
-    queen_attacks = queen_attacks | bishop_attacks(color, square, board);
-    queen_attacks
+pub fn queen_attacks(color: Color, square: Square, board: &board::ChessBoard) -> Bitboard {
+    rook_attacks(color, square, board) | bishop_attacks(color, square, board)
 }
 
-fn magic_rook_index(sq_i: u8, occupancy: u64) -> usize {
-    let blockers = occupancy & ROOK_MASKS[sq_i as usize];
+fn magic_rook_index(sq_i: u8, occupancy: Bitboard) -> usize {
+    let blockers = occupancy.0 & ROOK_MASKS[sq_i as usize];
     (blockers.wrapping_mul(ROOK_MAGIC_NUMS[sq_i as usize]) >> 52) as usize
 }
 
-fn magic_bishop_index(sq_i: u8, occupancy: u64) -> usize {
-    let blockers = occupancy & BISHOP_MASKS[sq_i as usize];
+fn magic_bishop_index(sq_i: u8, occupancy: Bitboard) -> usize {
+    let blockers = occupancy.0 & BISHOP_MASKS[sq_i as usize];
     (blockers.wrapping_mul(BISHOP_MAGIC_NUMS[sq_i as usize]) >> 52) as usize
 }
 
+// squares a rook on sq attacks given occupancy, ignoring piece colors.
+pub(crate) fn rook_attack_lookup(sq: Square, occupancy: Bitboard) -> Bitboard {
+    let sq_i = sq.index();
+    Bitboard(ROOK_ATTACKS[sq_i as usize][magic_rook_index(sq_i, occupancy)])
+}
+
+// squares a bishop on sq attacks given occupancy, ignoring piece colors.
+pub(crate) fn bishop_attack_lookup(sq: Square, occupancy: Bitboard) -> Bitboard {
+    let sq_i = sq.index();
+    Bitboard(BISHOP_ATTACKS[sq_i as usize][magic_bishop_index(sq_i, occupancy)])
+}
+
 // returns squares attacked by one rook, via magic bitboard lookup.
-pub fn get_rook_attacks(board: &board::ChessBoard, color: bool, sq_i: u8) -> u64 {
+pub fn get_rook_attacks(board: &board::ChessBoard, color: Color, sq: Square) -> Bitboard {
     let occupancy = board.white_pieces | board.black_pieces;
-    let attacks = ROOK_ATTACKS[sq_i as usize][magic_rook_index(sq_i, occupancy)];
-
-    if color {
-        attacks & !board.white_pieces
-    } else {
-        attacks & !board.black_pieces
-    }
+    rook_attack_lookup(sq, occupancy) & not_friendly(board, color)
 }
 
 // returns squares attacked by one bishop, via magic bitboard lookup.
-pub fn get_bishop_attacks(board: &board::ChessBoard, color: bool, sq_i: u8) -> u64 {
+pub fn get_bishop_attacks(board: &board::ChessBoard, color: Color, sq: Square) -> Bitboard {
     let occupancy = board.white_pieces | board.black_pieces;
-    let attacks = BISHOP_ATTACKS[sq_i as usize][magic_bishop_index(sq_i, occupancy)];
-
-    if color {
-        attacks & !board.white_pieces
-    } else {
-        attacks & !board.black_pieces
-    }
+    bishop_attack_lookup(sq, occupancy) & not_friendly(board, color)
 }
 
 // returns squares attacked by one queen, via magic bitboard lookups.
-pub fn get_queen_attacks(board: &board::ChessBoard, color: bool, sq_i: u8) -> u64 {
-    get_rook_attacks(board, color, sq_i) | get_bishop_attacks(board, color, sq_i)
+pub fn get_queen_attacks(board: &board::ChessBoard, color: Color, sq: Square) -> Bitboard {
+    get_rook_attacks(board, color, sq) | get_bishop_attacks(board, color, sq)
 }
 
-pub const fn generate_one_king_attacks(square: u64) -> u64 {
-    let mut king_attacks: u64 = 0;
-
-    if !utils::on_a_file(square) {
-        king_attacks |= square >> 1;
-        if !utils::on_rank_8(square) {
-            king_attacks |= square << 7;
-        }
-        if !utils::on_rank_1(square) {
-            king_attacks |= square >> 9;
-        }
-    }
-    if !utils::on_h_file(square) {
-        king_attacks |= square << 1;
-        if !utils::on_rank_8(square) {
-            king_attacks |= square << 9;
-        }
-        if !utils::on_rank_1(square) {
-            king_attacks |= square >> 7;
-        }
-    }
-    if !utils::on_rank_1(square) {
-        king_attacks |= square >> 8;
-    }
-    if !utils::on_rank_8(square) {
-        king_attacks |= square << 8;
-    }
-
-    king_attacks
+pub const fn generate_one_king_attacks(square: Bitboard) -> Bitboard {
+    let row = square.union(square.east()).union(square.west());
+    row.union(row.north()).union(row.south()).without(square)
 }
 
-pub const fn generate_king_attacks() -> [u64; 64] {
-    let mut king_attacks = [0u64; 64];
+pub const fn generate_king_attacks() -> [Bitboard; 64] {
+    let mut king_attacks = [Bitboard::EMPTY; 64];
 
     let mut i = 0;
     while i < 64 {
-        let i_square: u64 = 1 << i;
+        let i_square = Bitboard(1 << i);
         king_attacks[i] = generate_one_king_attacks(i_square);
         i += 1;
     }
@@ -451,111 +283,87 @@ pub const fn generate_king_attacks() -> [u64; 64] {
     king_attacks
 }
 
-pub const KING_ATTACKS: [u64; 64] = generate_king_attacks();
+pub const KING_ATTACKS: [Bitboard; 64] = generate_king_attacks();
 
 // returns squares attacked by one king.
-pub fn king_attacks(color: bool, square: u64, board: &board::ChessBoard) -> u64 {
-    let mut king_attacks = KING_ATTACKS[square.trailing_zeros() as usize];
-
-    if color {
-        king_attacks = king_attacks & !(board.white_pieces);
-    } else {
-        king_attacks = king_attacks & !(board.black_pieces);
-    }
-    king_attacks
+pub fn king_attacks(color: Color, square: Square, board: &board::ChessBoard) -> Bitboard {
+    KING_ATTACKS[square.index() as usize] & not_friendly(board, color)
 }
 
 // returns whether a square is attacked by a color
 // empty_square is used to remove a piece to check if a piece is pinned.
 // will be used for check detection, so king attacks are not necessary.
 pub fn square_attacked(
-    color: bool,
-    square: u64,
+    color: Color,
+    square: Square,
     board: &board::ChessBoard,
-    empty_square: Option<u64>,
-    occupied_square: Option<u64>,
+    empty_square: Option<Bitboard>,
+    occupied_square: Option<Bitboard>,
 ) -> bool {
-    let sq_i = square.trailing_zeros() as usize;
-    let vacated = empty_square.unwrap_or(0);
-    let occupied = occupied_square.unwrap_or(0);
-    // check opposing king
-    let opposing_king: u64;
-    if color {
-        opposing_king = (board.kings & board.white_pieces) & !vacated;
+    let sq_i = square.index() as usize;
+    let vacated = empty_square.unwrap_or_default();
+    let occupied = occupied_square.unwrap_or_default();
+    let color_mask = if color.is_white() {
+        board.white_pieces
     } else {
-        opposing_king = (board.kings & board.black_pieces) & !vacated;
-    }
-    if KING_ATTACKS[sq_i] & opposing_king != 0 {
+        board.black_pieces
+    };
+
+    // check opposing king
+    let opposing_king = board.kings & color_mask & !vacated;
+    if KING_ATTACKS[sq_i].intersects(opposing_king) {
         return true;
     }
 
     // check knights
-    let opposing_knights: u64;
-    if color {
-        opposing_knights = (board.knights & board.white_pieces) & !vacated & !occupied;
-    } else {
-        opposing_knights = (board.knights & board.black_pieces) & !vacated & !occupied;
-    }
-    if KNIGHT_ATTACKS[sq_i] & opposing_knights != 0 {
+    let opposing_knights = board.knights & color_mask & !vacated & !occupied;
+    if KNIGHT_ATTACKS[sq_i].intersects(opposing_knights) {
         return true;
     }
 
     // check pawns
-    let opposing_pawns: u64;
-    let potential_sqs: u64;
-    if color {
-        opposing_pawns = (board.pawns & board.white_pieces) & !vacated & !occupied;
-        potential_sqs = BLACK_PAWN_ATTACKS[sq_i];
+    let opposing_pawns = board.pawns & color_mask & !vacated & !occupied;
+    let potential_sqs = if color.is_white() {
+        BLACK_PAWN_ATTACKS[sq_i]
     } else {
-        opposing_pawns = (board.pawns & board.black_pieces) & !vacated & !occupied;
-        potential_sqs = WHITE_PAWN_ATTACKS[sq_i];
-    }
-    if opposing_pawns & potential_sqs != 0 {
+        WHITE_PAWN_ATTACKS[sq_i]
+    };
+    if opposing_pawns.intersects(potential_sqs) {
         return true;
     }
 
     // check sliding pieces
-    let opposing_diagonals: u64;
-    let opposing_orthogonals: u64;
-    if color {
-        opposing_diagonals = (board.bishops | board.queens) & board.white_pieces & !occupied;
-        opposing_orthogonals = (board.rooks | board.queens) & board.white_pieces & !occupied;
-    } else {
-        opposing_diagonals = (board.bishops | board.queens) & board.black_pieces & !occupied;
-        opposing_orthogonals = (board.rooks | board.queens) & board.black_pieces & !occupied;
-    }
+    let opposing_diagonals = (board.bishops | board.queens) & color_mask & !occupied;
+    let opposing_orthogonals = (board.rooks | board.queens) & color_mask & !occupied;
 
     // hypothetical occupancy: real occupancy, plus a pretend-occupied square (e.g. a move's
     // destination), minus any pretend-vacated squares (e.g. a piece that just moved away).
     let occupancy = ((board.white_pieces | board.black_pieces) | occupied) & !vacated;
 
-    if opposing_orthogonals != 0 {
-        let attacks = ROOK_ATTACKS[sq_i][magic_rook_index(sq_i as u8, occupancy)];
-        if attacks & opposing_orthogonals != 0 {
-            return true;
-        }
+    if opposing_orthogonals.is_nonempty()
+        && rook_attack_lookup(square, occupancy).intersects(opposing_orthogonals)
+    {
+        return true;
     }
 
-    if opposing_diagonals != 0 {
-        let attacks = BISHOP_ATTACKS[sq_i][magic_bishop_index(sq_i as u8, occupancy)];
-        if attacks & opposing_diagonals != 0 {
-            return true;
-        }
+    if opposing_diagonals.is_nonempty()
+        && bishop_attack_lookup(square, occupancy).intersects(opposing_diagonals)
+    {
+        return true;
     }
 
-    return false;
+    false
 }
 
-pub fn board_attacks(board: &board::ChessBoard, color: bool) -> u64 {
+pub fn board_attacks(board: &board::ChessBoard, color: Color) -> Bitboard {
     // The idea is to return a bitboard of all squares attacked BY specified color.
-    let mut attacks: u64 = 0;
+    let mut attacks = Bitboard::EMPTY;
 
-    let color_mask;
-    if color {
-        color_mask = board.white_pieces;
+    let color_mask = if color.is_white() {
+        board.white_pieces
     } else {
-        color_mask = board.black_pieces;
-    }
+        board.black_pieces
+    };
 
     for piece_bb in [
         ("pawns", board.pawns),
@@ -565,330 +373,730 @@ pub fn board_attacks(board: &board::ChessBoard, color: bool) -> u64 {
         ("queens", board.queens),
         ("kings", board.kings),
     ] {
-        let mut colored_bb = piece_bb.1 & color_mask;
-
-        while colored_bb.trailing_zeros() != 64 {
-            let from_square: u64 = 1 << colored_bb.trailing_zeros();
-            let from_sq_i: u8 = colored_bb.trailing_zeros() as u8;
-
-            let attack_squares: u64 = match piece_bb.0 {
-                "pawns" => pawn_attacks(color, from_square, board),
-                "knights" => knight_attacks(color, from_square, board),
-                "bishops" => get_bishop_attacks(board, color, from_sq_i),
-                "rooks" => get_rook_attacks(board, color, from_sq_i),
-                "queens" => get_queen_attacks(board, color, from_sq_i),
-                "kings" => king_attacks(color, from_square, board),
-                &_ => 0,
+        for from_sq in piece_bb.1 & color_mask {
+            attacks |= match piece_bb.0 {
+                "pawns" => pawn_attacks(color, from_sq, board),
+                "knights" => knight_attacks(color, from_sq, board),
+                "bishops" => get_bishop_attacks(board, color, from_sq),
+                "rooks" => get_rook_attacks(board, color, from_sq),
+                "queens" => get_queen_attacks(board, color, from_sq),
+                "kings" => king_attacks(color, from_sq, board),
+                &_ => Bitboard::EMPTY,
             };
-
-            colored_bb = colored_bb & !(1 << colored_bb.trailing_zeros()); // erase current from_piece from colored_bb.
-            attacks |= attack_squares;
         }
     }
     attacks
 }
 
-pub fn get_pawn_plmoves(board: &board::ChessBoard, plmoves: &mut ArrayVec<u16, 256>) -> () {
-    let mut friendly_pawns: u64; // bitboard of pieces to check
+pub fn get_pawn_plmoves(board: &board::ChessBoard, plmoves: &mut ArrayVec<Move, 256>) {
     let to_move = board.side_to_move;
-    if board.side_to_move {
-        friendly_pawns = board.white_pieces & board.pawns;
+    let (friendly_pawns, enemy_pieces, promotion_rank, start_rank) = if to_move.is_white() {
+        (
+            board.white_pieces & board.pawns,
+            board.black_pieces,
+            Bitboard::RANK_7,
+            Bitboard::RANK_2,
+        )
     } else {
-        friendly_pawns = board.black_pieces & board.pawns;
-    }
+        (
+            board.black_pieces & board.pawns,
+            board.white_pieces,
+            Bitboard::RANK_2,
+            Bitboard::RANK_7,
+        )
+    };
 
-    // while there are still pawns to check, add moves for the next pawn
-    while friendly_pawns != 0 {
-        let this_piece_i: u8 = friendly_pawns.trailing_zeros() as u8; // next piece to check
-        let this_piece_bb: u64 = 1 << this_piece_i;
+    for this_piece in friendly_pawns {
+        let this_piece_i = this_piece.index();
+        let promoting = promotion_rank.contains(this_piece);
 
         // Move Logic:
         // from attack squares
-        let mut attack_sqs = pawn_attacks(to_move, this_piece_bb, board);
-        while attack_sqs != 0 {
-            // while there are still attack squares to check for this pawn
-            let this_target_i: u8 = attack_sqs.trailing_zeros() as u8;
-            let this_target_bb: u64 = 1 << attack_sqs.trailing_zeros();
-
-            // if white pawn
-            if to_move {
-                let ep = board.en_passant;
-                if this_target_bb == board.en_passant {
-                    // if en passant
-                    plmoves.push(utils::encode_move(this_piece_i, this_target_i, 3));
-                } else if this_target_bb & board.black_pieces != 0 {
-                    // if there is a capturable target
-                    if utils::on_rank_7(this_piece_bb) {
-                        // if promotion with capture
-                        for flag in 8..=11 {
-                            // one move for each promotion piece choice
-                            plmoves.push(utils::encode_move(this_piece_i, this_target_i, flag));
-                        }
-                    } else {
-                        // if normal capture
-                        plmoves.push(utils::encode_move(this_piece_i, this_target_i, 1));
-                    }
-                }
-            } else {
-                // if black pawn
-                if this_target_bb == board.en_passant {
-                    // if en passant
-                    plmoves.push(utils::encode_move(this_piece_i, this_target_i, 3));
-                } else if this_target_bb & board.white_pieces != 0 {
-                    if utils::on_rank_2(this_piece_bb) {
-                        // if promotion with capture
-                        for flag in 8..=11 {
-                            // one move for each promotion piece choice
-                            plmoves.push(utils::encode_move(this_piece_i, this_target_i, flag));
-                        }
-                    } else {
-                        // if normal capture
-                        plmoves.push(utils::encode_move(this_piece_i, this_target_i, 1));
+        for this_target in pawn_attacks(to_move, this_piece, board) {
+            let this_target_i = this_target.index();
+            if board.en_passant.contains(this_target) {
+                // if en passant
+                plmoves.push(utils::encode_move(this_piece_i, this_target_i, 3));
+            } else if enemy_pieces.contains(this_target) {
+                // if there is a capturable target
+                if promoting {
+                    // if promotion with capture
+                    for flag in 8..=11 {
+                        // one move for each promotion piece choice
+                        plmoves.push(utils::encode_move(this_piece_i, this_target_i, flag));
                     }
+                } else {
+                    // if normal capture
+                    plmoves.push(utils::encode_move(this_piece_i, this_target_i, 1));
                 }
             }
-
-            attack_sqs &= !this_target_bb;
         }
 
-        if to_move {
-            // if white pawn
-            // forward moves
-            if !board.is_occupied(this_piece_i + 8) {
-                if utils::on_rank_7(this_piece_bb) {
-                    // promotion w/out capture
-                    for flag in 4..=7 {
-                        // one move for each promotion piece choice
-                        plmoves.push(utils::encode_move(this_piece_i, this_piece_i + 8, flag));
-                    }
-                } else {
-                    plmoves.push(utils::encode_move(this_piece_i, this_piece_i + 8, 0)); // forward 1 square
-                    if utils::on_rank_2(this_piece_bb) {
-                        // if pawn is on starting square
-                        if !board.is_occupied(this_piece_i + 16) {
-                            plmoves.push(utils::encode_move(this_piece_i, this_piece_i + 16, 0));
-                            // forward 2 squares
-                        }
-                    }
-                }
+        // forward moves
+        // one rank towards the promotion rank; only called where that square exists.
+        let advance = |sq_i: u8| {
+            if to_move.is_white() {
+                sq_i + 8
+            } else {
+                sq_i - 8
             }
-        } else {
-            // if black pawn
-            // forward moves
-            if !board.is_occupied(this_piece_i - 8) {
-                if utils::on_rank_2(this_piece_bb) {
-                    // promotion w/out capture
-                    for flag in 4..=7 {
-                        // one move for each promotion piece choice
-                        plmoves.push(utils::encode_move(this_piece_i, this_piece_i - 8, flag));
-                    }
-                } else {
-                    plmoves.push(utils::encode_move(this_piece_i, this_piece_i - 8, 0)); // forward 1 square
-                    if utils::on_rank_7(this_piece_bb) {
-                        // if pawn is on starting square
-                        if !board.is_occupied(this_piece_i - 16) {
-                            plmoves.push(utils::encode_move(this_piece_i, this_piece_i - 16, 0));
-                            // forward 2 squares
-                        }
+        };
+        let one_step = advance(this_piece_i);
+        if !board.is_occupied(Square::new(one_step)) {
+            if promoting {
+                // promotion w/out capture
+                for flag in 4..=7 {
+                    // one move for each promotion piece choice
+                    plmoves.push(utils::encode_move(this_piece_i, one_step, flag));
+                }
+            } else {
+                plmoves.push(utils::encode_move(this_piece_i, one_step, 0)); // forward 1 square
+                if start_rank.contains(this_piece) {
+                    // if pawn is on starting square
+                    let two_steps = advance(one_step);
+                    if !board.is_occupied(Square::new(two_steps)) {
+                        plmoves.push(utils::encode_move(this_piece_i, two_steps, 0));
+                        // forward 2 squares
                     }
                 }
             }
         }
-
-        friendly_pawns &= !this_piece_bb; // remove checked piece
     }
 }
 
-pub fn get_nonpk_plmoves(board: &board::ChessBoard, plmoves: &mut ArrayVec<u16, 256>) {
+pub fn get_nonpk_plmoves(board: &board::ChessBoard, plmoves: &mut ArrayVec<Move, 256>) {
     // generates pseudolegal moves for all non pawn/king pieces
     let to_move = board.side_to_move;
-    let color_mask: u64;
-    if board.side_to_move {
-        color_mask = board.white_pieces;
+    let color_mask = if board.side_to_move.is_white() {
+        board.white_pieces
     } else {
-        color_mask = board.black_pieces;
-    }
+        board.black_pieces
+    };
 
     for (i, piece_mask) in [board.knights, board.bishops, board.rooks, board.queens]
-        .iter()
+        .into_iter()
         .enumerate()
     {
         // for piece type
-        let mut pieces: u64 = *piece_mask & color_mask;
-        while pieces != 0 {
-            // while there are pieces of this type left to check
-            let this_piece_i: u8 = pieces.trailing_zeros() as u8; // next piece to check
-            let this_piece_bb: u64 = 1 << this_piece_i;
-
+        for this_piece in piece_mask & color_mask {
             // create attack bb for this piece.
-            let mut piece_attacks: u64;
-            match i {
-                0 => piece_attacks = knight_attacks(to_move, this_piece_bb, &board),
-                1 => piece_attacks = get_bishop_attacks(&board, to_move, this_piece_i),
-                2 => piece_attacks = get_rook_attacks(&board, to_move, this_piece_i),
-                3 => piece_attacks = get_queen_attacks(&board, to_move, this_piece_i),
-                _ => piece_attacks = 0,
-            }
-
-            while piece_attacks != 0 {
-                // while there are attack squares left to encode
-                let this_target_i: u8 = piece_attacks.trailing_zeros() as u8;
-                let this_target_bb: u64 = 1 << piece_attacks.trailing_zeros();
+            let piece_attacks = match i {
+                0 => knight_attacks(to_move, this_piece, board),
+                1 => get_bishop_attacks(board, to_move, this_piece),
+                2 => get_rook_attacks(board, to_move, this_piece),
+                3 => get_queen_attacks(board, to_move, this_piece),
+                _ => Bitboard::EMPTY,
+            };
 
-                if board.is_occupied(this_target_i) {
+            for this_target in piece_attacks {
+                if board.is_occupied(this_target) {
                     // if capture
-                    plmoves.push(utils::encode_move(this_piece_i, this_target_i, 1));
+                    plmoves.push(Move::capture(this_piece, this_target));
                 } else {
                     // if normal move
-                    plmoves.push(utils::encode_move(this_piece_i, this_target_i, 0));
+                    plmoves.push(Move::quiet(this_piece, this_target));
                 }
-
-                piece_attacks &= !this_target_bb;
             }
-
-            pieces &= !this_piece_bb; // remove checked piece
         }
     }
 }
 
-pub fn get_king_plmoves(board: &board::ChessBoard, plmoves: &mut ArrayVec<u16, 256>) -> () {
-    let king_bb: u64;
-    if board.side_to_move {
-        king_bb = board.kings & board.white_pieces;
+pub fn get_king_plmoves(board: &board::ChessBoard, plmoves: &mut ArrayVec<Move, 256>) {
+    let king_bb = if board.side_to_move.is_white() {
+        board.kings & board.white_pieces
     } else {
-        king_bb = board.kings & board.black_pieces;
-    }
-    let king_i: u8 = king_bb.trailing_zeros() as u8;
+        board.kings & board.black_pieces
+    };
+    let Some(king_sq) = king_bb.lsb() else {
+        return;
+    };
 
     // add moves based on attack squares
-    let mut king_attacks = king_attacks(board.side_to_move, king_bb, &board);
-    while king_attacks != 0 {
-        // while there are attack squares left to encode
-        let this_target_i: u8 = king_attacks.trailing_zeros() as u8;
-        let this_target_bb: u64 = 1 << king_attacks.trailing_zeros();
-
-        if board.is_occupied(this_target_i) {
+    for this_target in king_attacks(board.side_to_move, king_sq, board) {
+        if board.is_occupied(this_target) {
             // if capture
-            plmoves.push(utils::encode_move(king_i, this_target_i, 1));
+            plmoves.push(Move::capture(king_sq, this_target));
         } else {
             // if normal move
-            plmoves.push(utils::encode_move(king_i, this_target_i, 0));
+            plmoves.push(Move::quiet(king_sq, this_target));
         }
-
-        king_attacks &= !this_target_bb;
     }
 
-    // add castling moves
-    if board.side_to_move {
-        if board.castling_rights & 0b1000 != 0 {
-            // white kingside castle
-            if !board.is_occupied(5) && !board.is_occupied(6) {
-                plmoves.push(utils::encode_move(4, 6, 2));
-            }
-        }
-        if board.castling_rights & 0b0100 != 0 {
-            // white queenside castle
-            if !board.is_occupied(1) && !board.is_occupied(2) && !board.is_occupied(3) {
-                plmoves.push(utils::encode_move(4, 2, 2));
-            }
-        }
+    // add castling moves: the castling rook must still be there, and every square the king and
+    // rook cross must be empty apart from those two pieces.
+    let own_rooks = if board.side_to_move.is_white() {
+        board.rooks & board.white_pieces
     } else {
-        if board.castling_rights & 0b0010 != 0 {
-            // black kingside castle
-            if !board.is_occupied(61) && !board.is_occupied(62) {
-                plmoves.push(utils::encode_move(60, 62, 2));
-            }
-        }
-        if board.castling_rights & 0b0001 != 0 {
-            // black queenside castle
-            if !board.is_occupied(57) && !board.is_occupied(58) && !board.is_occupied(59) {
-                plmoves.push(utils::encode_move(60, 58, 2));
-            }
+        board.rooks & board.black_pieces
+    };
+    let occupied = board.white_pieces | board.black_pieces;
+    for kingside in [true, false] {
+        let Some(castle) = board.castle_squares(board.side_to_move, kingside) else {
+            continue;
+        };
+        if board.castling_rights & board::CASTLING_BITS[castle.right] == 0
+            || castle.king_from != king_sq
+            || !own_rooks.contains(castle.rook_from)
+            || castle.must_be_empty().intersects(occupied)
+        {
+            continue;
         }
+        let to = if board.chess960 {
+            castle.rook_from
+        } else {
+            castle.king_to
+        };
+        plmoves.push(Move::castle(king_sq, to));
     }
 }
 
-pub fn get_pseudolegal_moves(board: &board::ChessBoard) -> ArrayVec<u16, 256> {
-    let mut pl_moves = ArrayVec::<u16, 256>::new();
+pub fn get_pseudolegal_moves(board: &board::ChessBoard) -> ArrayVec<Move, 256> {
+    let mut pl_moves = ArrayVec::<Move, 256>::new();
     get_pawn_plmoves(board, &mut pl_moves);
     get_nonpk_plmoves(board, &mut pl_moves);
     get_king_plmoves(board, &mut pl_moves);
     return pl_moves;
 }
 
-pub fn test_plmove_legality(board: &mut board::ChessBoard, move_i: u16) -> bool {
-    // assumes the move given is ALREADY in the pseudolegal moves for the position.
-    // will only check if a move results in check, if castling moves through check
+// whether a move, whatever raw encoding it carries (Move::from_raw accepts any u16), is one
+// get_pseudolegal_moves could produce here (castling may also use the other encoding, as
+// make_move accepts both), worked out from the move alone. moves from the transposition table,
+// killers or a GUI can be garbage, so everything is checked: the piece and its owner, the flag
+// against what stands on the target, the path and the castling rights.
+pub fn is_pseudo_legal(board: &board::ChessBoard, mv: Move) -> bool {
     let to_move = board.side_to_move;
+    let (own, enemy) = friendly_and_enemy(board, to_move);
+    let (from, to) = (mv.from(), mv.to());
+    let Some(kind) = board.piece_type_at(from).filter(|_| own.contains(from)) else {
+        return false;
+    };
+    if mv.flag() > Move::PROMOTION_CAPTURE + 3 {
+        return false;
+    }
+    if mv.is_castle() {
+        return kind == PieceKind::King && castle_is_pseudo_legal(board, mv);
+    }
+    if own.contains(to) || mv.is_capture() != (enemy.contains(to) || mv.is_en_passant()) {
+        return false;
+    }
 
-    // castling legality
-    let move_decoded = utils::decode_move(move_i);
-    let [from_sqi, to_sqi, flag]: [u8; 3] = move_decoded;
-    let from_sq = 1 << from_sqi;
-    let to_sq = 1 << to_sqi;
-    if flag == 2 {
-        let mut sqs_to_check: u64;
-        match move_decoded[1] {
-            // match the to_sq to determine which in-between squares to check (includes target sq)
-            2 => sqs_to_check = 0x1C,
-            6 => sqs_to_check = 0x70,
-            58 => sqs_to_check = 0x1C00000000000000,
-            62 => sqs_to_check = 0x7000000000000000,
-            _ => return false,
+    if kind != PieceKind::Pawn {
+        if mv.is_en_passant() || mv.is_promotion() {
+            return false;
         }
-        while sqs_to_check != 0 {
-            let this_sq = 1 << sqs_to_check.trailing_zeros();
-            if square_attacked(!to_move, this_sq, board, None, None) {
-                return false;
-            } else {
-                sqs_to_check &= !this_sq;
+        let occupancy = own | enemy;
+        let attacks = match kind {
+            PieceKind::Knight => KNIGHT_ATTACKS[from.index() as usize],
+            PieceKind::Bishop => bishop_attack_lookup(from, occupancy),
+            PieceKind::Rook => rook_attack_lookup(from, occupancy),
+            PieceKind::Queen => {
+                rook_attack_lookup(from, occupancy) | bishop_attack_lookup(from, occupancy)
             }
-        }
-        return true;
+            _ => KING_ATTACKS[from.index() as usize],
+        };
+        return attacks.contains(to);
+    }
+
+    // pawns promote exactly when they reach the last rank.
+    if (Bitboard::RANK_1 | Bitboard::RANK_8).contains(to) != mv.is_promotion() {
+        return false;
+    }
+    let attacks = if to_move.is_white() {
+        WHITE_PAWN_ATTACKS[from.index() as usize]
+    } else {
+        BLACK_PAWN_ATTACKS[from.index() as usize]
+    };
+    if mv.is_en_passant() {
+        return attacks.contains(to) && board.en_passant.contains(to);
+    }
+    if mv.is_capture() {
+        return attacks.contains(to);
+    }
+    let empty = !(own | enemy);
+    let (one_step, start_rank) = if to_move.is_white() {
+        (from.bb().north() & empty, Bitboard::RANK_2)
+    } else {
+        (from.bb().south() & empty, Bitboard::RANK_7)
+    };
+    let two_steps = if to_move.is_white() {
+        one_step.north()
+    } else {
+        one_step.south()
+    } & empty;
+    one_step.contains(to) || (start_rank.contains(from) && two_steps.contains(to))
+}
+
+// the castling right is held, the king and rook stand on its squares and nothing is in between.
+fn castle_is_pseudo_legal(board: &board::ChessBoard, mv: Move) -> bool {
+    let Some(castle) = board.castle_squares(board.side_to_move, mv.is_kingside_castle()) else {
+        return false;
+    };
+    let own = friendly_and_enemy(board, board.side_to_move).0;
+    board.castling_rights & board::CASTLING_BITS[castle.right] != 0
+        && mv.from() == castle.king_from
+        && (mv.to() == castle.king_to || mv.to() == castle.rook_from)
+        && (board.rooks & own).contains(castle.rook_from)
+        && !castle
+            .must_be_empty()
+            .intersects(board.white_pieces | board.black_pieces)
+}
+
+pub fn test_plmove_legality(board: &mut board::ChessBoard, move_i: Move) -> bool {
+    // assumes the move given is ALREADY in the pseudolegal moves for the position; use
+    // is_legal for moves that may not be.
+    // will only check if a move results in check, if castling moves through check
+    let to_move = board.side_to_move;
+
+    // castling legality
+    let from_sq = move_i.from().bb();
+    let to_sq = move_i.to().bb();
+    if move_i.is_castle() {
+        castle_is_legal(board, move_i)
     } else {
         // regular legality
         // if king moves, check target square
-        if board.kings & (from_sq) != 0 {
-            if square_attacked(!to_move, (to_sq), board, Some(from_sq), None) {
-                return false;
-            } else {
-                return true;
-            }
+        if board.kings.intersects(from_sq) {
+            !square_attacked(!to_move, move_i.to(), board, Some(from_sq), None)
         } else {
             // if other piece moves, check king square with vacated piece.
-            let king_sq: u64;
-            if to_move {
-                king_sq = board.kings & board.white_pieces;
+            let king_bb = if to_move.is_white() {
+                board.kings & board.white_pieces
             } else {
-                king_sq = board.kings & board.black_pieces;
-            }
-            let mut vacated: u64 = from_sq;
-            if move_decoded[2] == 3 {
-                if to_move {
-                    vacated |= to_sq >> 8;
+                board.kings & board.black_pieces
+            };
+            let Some(king_sq) = king_bb.lsb() else {
+                return true;
+            };
+            let mut vacated = from_sq;
+            if move_i.is_en_passant() {
+                if to_move.is_white() {
+                    vacated |= to_sq.south();
                 } else {
-                    vacated |= to_sq << 8;
+                    vacated |= to_sq.north();
                 }
             }
-            if square_attacked(!to_move, king_sq, board, Some(vacated), Some(to_sq)) {
-                return false;
-            } else {
-                return true;
-            }
+            !square_attacked(!to_move, king_sq, board, Some(vacated), Some(to_sq))
         }
     }
 }
 
-pub fn get_legal_moves(board: &mut board::ChessBoard) -> ArrayVec<u16, 256> {
+// generates the legal moves by filtering the pseudolegal ones through test_plmove_legality.
+// slower than get_legal_moves, but kept as an independent reference to check it against.
+pub fn get_legal_moves_filtered(board: &mut board::ChessBoard) -> ArrayVec<Move, 256> {
     let mut legal_moves = get_pseudolegal_moves(board);
     legal_moves.retain(|m| test_plmove_legality(board, *m));
-    return legal_moves;
+    legal_moves
+}
+
+const fn generate_between() -> [[Bitboard; 64]; 64] {
+    // squares strictly between two squares on a shared rank, file or diagonal; empty otherwise.
+    let mut between = [[Bitboard::EMPTY; 64]; 64];
+    let mut a = 0;
+    while a < 64 {
+        let mut dir = 0;
+        while dir < 8 {
+            let ray = RAYS[a][dir];
+            let mut b = 0;
+            while b < 64 {
+                if ray.0 & (1 << b) != 0 {
+                    between[a][b] = ray.intersection(RAYS[b][(dir + 4) % 8]);
+                }
+                b += 1;
+            }
+            dir += 1;
+        }
+        a += 1;
+    }
+    between
+}
+
+const fn generate_lines() -> [[Bitboard; 64]; 64] {
+    // the whole line (edge to edge) through two squares that share one; empty otherwise.
+    let mut lines = [[Bitboard::EMPTY; 64]; 64];
+    let mut a = 0;
+    while a < 64 {
+        let mut dir = 0;
+        while dir < 8 {
+            let line = RAYS[a][dir]
+                .union(RAYS[a][(dir + 4) % 8])
+                .union(Bitboard(1 << a));
+            let mut b = 0;
+            while b < 64 {
+                if RAYS[a][dir].0 & (1 << b) != 0 {
+                    lines[a][b] = line;
+                }
+                b += 1;
+            }
+            dir += 1;
+        }
+        a += 1;
+    }
+    lines
+}
+
+pub static BETWEEN: [[Bitboard; 64]; 64] = generate_between();
+pub static LINES: [[Bitboard; 64]; 64] = generate_lines();
+
+// returns every piece, of either color, attacking square given the occupancy. pieces not in
+// occupancy are left out, and sliders see through squares missing from it.
+pub fn attackers_to(board: &board::ChessBoard, square: Square, occupancy: Bitboard) -> Bitboard {
+    let sq_i = square.index() as usize;
+    let white_pawns = board.pawns & board.white_pieces & BLACK_PAWN_ATTACKS[sq_i];
+    let black_pawns = board.pawns & board.black_pieces & WHITE_PAWN_ATTACKS[sq_i];
+    let leapers = (board.knights & KNIGHT_ATTACKS[sq_i]) | (board.kings & KING_ATTACKS[sq_i]);
+    let orthogonals = (board.rooks | board.queens) & rook_attack_lookup(square, occupancy);
+    let diagonals = (board.bishops | board.queens) & bishop_attack_lookup(square, occupancy);
+    (white_pawns | black_pawns | leapers | orthogonals | diagonals) & occupancy
+}
+
+// what move generation needs to know about the side to move's king, worked out once per position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KingSafety {
+    pub king: Square,
+    pub checkers: Bitboard,
+    pub pinned: Bitboard, // own pieces that may only move along the line through them and the king
+    pub evasions: Bitboard, // squares a non-king move must land on: every square when not in check
+}
+
+impl KingSafety {
+    pub fn new(board: &board::ChessBoard) -> Option<Self> {
+        let (own, enemy) = friendly_and_enemy(board, board.side_to_move);
+        let king = (board.kings & own).lsb()?;
+        let occupancy = own | enemy;
+        let checkers = attackers_to(board, king, occupancy) & enemy;
+
+        // enemy sliders that would see the king through exactly one of our pieces pin it.
+        let snipers = ((board.rooks | board.queens) & rook_attack_lookup(king, enemy)
+            | (board.bishops | board.queens) & bishop_attack_lookup(king, enemy))
+            & enemy;
+        let mut pinned = Bitboard::EMPTY;
+        for sniper in snipers {
+            let blockers = BETWEEN[king.index() as usize][sniper.index() as usize] & occupancy;
+            if !blockers.has_many() && blockers.intersects(own) {
+                pinned |= blockers;
+            }
+        }
+
+        let evasions = match checkers.lsb() {
+            None => !Bitboard::EMPTY,
+            Some(_) if checkers.has_many() => Bitboard::EMPTY,
+            Some(checker) => BETWEEN[king.index() as usize][checker.index() as usize] | checker,
+        };
+        Some(KingSafety {
+            king,
+            checkers,
+            pinned,
+            evasions,
+        })
+    }
+
+    pub fn in_check(&self) -> bool {
+        self.checkers.is_nonempty()
+    }
+
+    // the squares a piece on from may move to without exposing the king.
+    fn allowed_targets(&self, from: Square) -> Bitboard {
+        if self.pinned.contains(from) {
+            self.evasions & LINES[self.king.index() as usize][from.index() as usize]
+        } else {
+            self.evasions
+        }
+    }
+}
+
+/// Which legal moves to generate. `Captures` and `Quiets` split the legal moves between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenType {
+    All,
+    /// Captures, en passant and queen promotions: what quiescence search looks at.
+    Captures,
+    /// Everything else: quiet moves, castling and underpromotions.
+    Quiets,
+    /// Every legal move, but only when in check; nothing otherwise.
+    Evasions,
+    /// The quiet moves that give check.
+    QuietChecks,
+}
+
+impl GenType {
+    /// Whether a move belongs to this kind of generation (ignoring check for the last two).
+    pub fn includes(self, mv: Move) -> bool {
+        let noisy = match mv.promotion() {
+            Some(kind) => kind == PieceKind::Queen,
+            None => mv.is_capture(),
+        };
+        match self {
+            GenType::All | GenType::Evasions => true,
+            GenType::Captures => noisy,
+            GenType::Quiets | GenType::QuietChecks => !noisy,
+        }
+    }
+
+    fn captures(self) -> bool {
+        matches!(self, GenType::All | GenType::Evasions | GenType::Captures)
+    }
+
+    fn quiets(self) -> bool {
+        !matches!(self, GenType::Captures)
+    }
+}
+
+fn push_pawn_moves(
+    from: Square,
+    to: Square,
+    capture: bool,
+    gen: GenType,
+    moves: &mut ArrayVec<Move, 256>,
+) {
+    if (Bitboard::RANK_1 | Bitboard::RANK_8).contains(to) {
+        if gen.quiets() {
+            for kind in [PieceKind::Knight, PieceKind::Bishop, PieceKind::Rook] {
+                moves.push(Move::promote(from, to, kind, capture));
+            }
+        }
+        if gen.captures() {
+            moves.push(Move::promote(from, to, PieceKind::Queen, capture));
+        }
+    } else if capture {
+        if gen.captures() {
+            moves.push(Move::capture(from, to));
+        }
+    } else if gen.quiets() {
+        moves.push(Move::quiet(from, to));
+    }
+}
+
+fn get_legal_pawn_moves(
+    board: &board::ChessBoard,
+    safety: &KingSafety,
+    gen: GenType,
+    moves: &mut ArrayVec<Move, 256>,
+) {
+    let to_move = board.side_to_move;
+    let (own, enemy) = friendly_and_enemy(board, to_move);
+    let empty = !(own | enemy);
+    let (attacks, start_rank, promotion_rank) = if to_move.is_white() {
+        (&WHITE_PAWN_ATTACKS, Bitboard::RANK_2, Bitboard::RANK_7)
+    } else {
+        (&BLACK_PAWN_ATTACKS, Bitboard::RANK_7, Bitboard::RANK_2)
+    };
+
+    for from in board.pawns & own {
+        let allowed = safety.allowed_targets(from);
+        let promoting = promotion_rank.contains(from);
+        let attacked = attacks[from.index() as usize];
+        if gen.captures() || promoting {
+            for to in attacked & enemy & allowed {
+                push_pawn_moves(from, to, true, gen, moves);
+            }
+        }
+        if let Some(ep_sq) = (attacked & board.en_passant)
+            .lsb()
+            .filter(|_| gen.captures())
+        {
+            // taking en passant clears two squares on the capturing rank, which can uncover a
+            // rook or queen along it, so it gets a full check rather than the pin masks.
+            let captured = if to_move.is_white() {
+                ep_sq.bb().south()
+            } else {
+                ep_sq.bb().north()
+            };
+            if !square_attacked(
+                !to_move,
+                safety.king,
+                board,
+                Some(from.bb() | captured),
+                Some(ep_sq.bb()),
+            ) {
+                moves.push(Move::en_passant(from, ep_sq));
+            }
+        }
+
+        if !gen.quiets() && !promoting {
+            continue;
+        }
+        let one_step = if to_move.is_white() {
+            from.bb().north()
+        } else {
+            from.bb().south()
+        } & empty;
+        if let Some(to) = (one_step & allowed).lsb() {
+            push_pawn_moves(from, to, false, gen, moves);
+        }
+        if start_rank.contains(from) {
+            let two_steps = if to_move.is_white() {
+                one_step.north()
+            } else {
+                one_step.south()
+            } & empty;
+            if let Some(to) = (two_steps & allowed).lsb() {
+                moves.push(Move::quiet(from, to));
+            }
+        }
+    }
+}
+
+// the squares other pieces may move to for this kind of generation.
+fn gen_targets(board: &board::ChessBoard, gen: GenType) -> Bitboard {
+    let (own, enemy) = friendly_and_enemy(board, board.side_to_move);
+    match (gen.captures(), gen.quiets()) {
+        (true, true) => !own,
+        (true, false) => enemy,
+        _ => !(own | enemy),
+    }
+}
+
+fn get_legal_nonpk_moves(
+    board: &board::ChessBoard,
+    safety: &KingSafety,
+    gen: GenType,
+    moves: &mut ArrayVec<Move, 256>,
+) {
+    let (own, enemy) = friendly_and_enemy(board, board.side_to_move);
+    let occupancy = own | enemy;
+    let targets = gen_targets(board, gen) & safety.evasions;
+    for from in board.knights & own & !safety.pinned {
+        // a pinned knight can never move along the pin line.
+        for to in KNIGHT_ATTACKS[from.index() as usize] & targets {
+            moves.push(Move::new(from, to, enemy.contains(to) as u8));
+        }
+    }
+    for (sliders, lookup) in [
+        (
+            board.bishops,
+            bishop_attack_lookup as fn(Square, Bitboard) -> Bitboard,
+        ),
+        (board.rooks, rook_attack_lookup),
+        (board.queens, |sq, occupancy| {
+            rook_attack_lookup(sq, occupancy) | bishop_attack_lookup(sq, occupancy)
+        }),
+    ] {
+        for from in sliders & own {
+            for to in lookup(from, occupancy) & targets & safety.allowed_targets(from) {
+                moves.push(Move::new(from, to, enemy.contains(to) as u8));
+            }
+        }
+    }
+}
+
+fn get_legal_king_moves(
+    board: &board::ChessBoard,
+    safety: &KingSafety,
+    gen: GenType,
+    moves: &mut ArrayVec<Move, 256>,
+) {
+    let to_move = board.side_to_move;
+    let enemy = friendly_and_enemy(board, to_move).1;
+    let king = safety.king;
+    // the king is lifted off the board, so it can't hide behind itself from a slider.
+    for to in KING_ATTACKS[king.index() as usize] & gen_targets(board, gen) {
+        if !square_attacked(!to_move, to, board, Some(king.bb()), None) {
+            moves.push(Move::new(king, to, enemy.contains(to) as u8));
+        }
+    }
+    if safety.in_check() || !gen.quiets() {
+        return;
+    }
+
+    let mut castles = ArrayVec::<Move, 256>::new();
+    get_king_plmoves(board, &mut castles);
+    for mv in castles.into_iter().filter(|mv| mv.is_castle()) {
+        if castle_is_legal(board, mv) {
+            moves.push(mv);
+        }
+    }
+}
+
+// the king may not start in or pass through check. Its destination is judged again with the
+// castling pair moved, so a rook that was shielding the king (Chess960) no longer blocks the
+// attack.
+fn castle_is_legal(board: &board::ChessBoard, mv: Move) -> bool {
+    let to_move = board.side_to_move;
+    let Some(castle) = board.castle_squares(to_move, mv.is_kingside_castle()) else {
+        return false;
+    };
+    let vacated = (castle.king_from.bb() | castle.rook_from.bb()) - castle.rook_to.bb();
+    !castle
+        .king_path()
+        .into_iter()
+        .any(|this_sq| square_attacked(!to_move, this_sq, board, None, None))
+        && !square_attacked(
+            !to_move,
+            castle.king_to,
+            board,
+            Some(vacated),
+            Some(castle.rook_to.bb()),
+        )
+}
+
+// whether a move, whatever raw encoding it carries, is legal here, without generating the move
+// list: is_pseudo_legal, then the same pin and check masks generate_moves uses.
+pub fn is_legal(board: &board::ChessBoard, mv: Move) -> bool {
+    if !is_pseudo_legal(board, mv) {
+        return false;
+    }
+    let Some(safety) = KingSafety::new(board) else {
+        return true;
+    };
+    let to_move = board.side_to_move;
+    if mv.is_castle() {
+        !safety.in_check() && castle_is_legal(board, mv)
+    } else if mv.from() == safety.king {
+        !square_attacked(!to_move, mv.to(), board, Some(safety.king.bb()), None)
+    } else if safety.checkers.has_many() {
+        false
+    } else if mv.is_en_passant() {
+        let captured = if to_move.is_white() {
+            mv.to().bb().south()
+        } else {
+            mv.to().bb().north()
+        };
+        !square_attacked(
+            !to_move,
+            safety.king,
+            board,
+            Some(mv.from().bb() | captured),
+            Some(mv.to().bb()),
+        )
+    } else {
+        safety.allowed_targets(mv.from()).contains(mv.to())
+    }
+}
+
+// generates only legal moves of the given kind: checkers and pins are found once, so apart
+// from king moves and en passant no move needs its own attack probe. in double check only the
+// king moves.
+pub fn generate_moves(board: &mut board::ChessBoard, gen: GenType) -> ArrayVec<Move, 256> {
+    let mut moves = ArrayVec::<Move, 256>::new();
+    let Some(safety) = KingSafety::new(board) else {
+        // without a king nothing can be left in check.
+        let mut moves = get_pseudolegal_moves(board);
+        moves.retain(|mv| gen.includes(*mv));
+        return moves;
+    };
+    if gen == GenType::Evasions && !safety.in_check() {
+        return moves;
+    }
+    if !safety.checkers.has_many() {
+        get_legal_pawn_moves(board, &safety, gen, &mut moves);
+        get_legal_nonpk_moves(board, &safety, gen, &mut moves);
+    }
+    get_legal_king_moves(board, &safety, gen, &mut moves);
+    if gen == GenType::QuietChecks {
+        match rules::CheckSquares::new(board) {
+            Some(checks) => moves.retain(|mv| checks.check_kind(board, *mv).is_some()),
+            None => moves.clear(),
+        }
+    }
+    moves
+}
+
+pub fn get_legal_moves(board: &mut board::ChessBoard) -> ArrayVec<Move, 256> {
+    generate_moves(board, GenType::All)
 }
 
 pub fn has_legal_moves(board: &mut board::ChessBoard) -> bool {
-    get_pseudolegal_moves(board)
-        .iter()
-        .any(|m| test_plmove_legality(board, *m))
+    !generate_moves(board, GenType::All).is_empty()
 }
 
 // unit tests
@@ -896,52 +1104,53 @@ pub fn has_legal_moves(board: &mut board::ChessBoard) -> bool {
 mod tests {
     use super::*;
     use crate::board::ChessBoard;
+    use crate::types::*;
     use crate::utils::{decode_move, encode_move};
     #[test]
     fn test_pawn_attacks() {
         let empty_board = board::ChessBoard::empty();
 
         // white pawn on b5
-        let square1: u64 = utils::square_to_bb("b5").unwrap(); // b5 bit = 1.
-        let square1_pawn_attacks = pawn_attacks(true, square1, &empty_board);
-        assert_eq!(square1_pawn_attacks, 0x0000050000000000); // a6 and c6 bit = 1.
+        let square1: Square = "b5".parse().unwrap(); // b5 bit = 1.
+        let square1_pawn_attacks = pawn_attacks(Color::White, square1, &empty_board);
+        assert_eq!(square1_pawn_attacks, Bitboard(0x0000050000000000)); // a6 and c6 bit = 1.
 
         // black pawn on b5
-        let square2: u64 = utils::square_to_bb("b5").unwrap(); // b5 bit = 1.
-        let square2_pawn_attacks = pawn_attacks(false, square2, &empty_board);
-        assert_eq!(square2_pawn_attacks, 0x0000000005000000); // a4 and c4 bit = 1.
+        let square2: Square = "b5".parse().unwrap(); // b5 bit = 1.
+        let square2_pawn_attacks = pawn_attacks(Color::Black, square2, &empty_board);
+        assert_eq!(square2_pawn_attacks, Bitboard(0x0000000005000000)); // a4 and c4 bit = 1.
 
         // white pawn on a1
-        let square3: u64 = utils::square_to_bb("a1").unwrap(); // a1 bit = 1.
-        let square3_pawn_attacks = pawn_attacks(true, square3, &empty_board);
-        assert_eq!(square3_pawn_attacks, 0x0000000000000200); // b2 bit = 1.
+        let square3: Square = "a1".parse().unwrap(); // a1 bit = 1.
+        let square3_pawn_attacks = pawn_attacks(Color::White, square3, &empty_board);
+        assert_eq!(square3_pawn_attacks, Bitboard(0x0000000000000200)); // b2 bit = 1.
 
         // black pawn on a8
-        let square4: u64 = utils::square_to_bb("a8").unwrap(); // a8 bit = 1.
-        let square4_pawn_attacks = pawn_attacks(false, square4, &empty_board);
-        assert_eq!(square4_pawn_attacks, 0x0002000000000000); // b7 bit = 1.
+        let square4: Square = "a8".parse().unwrap(); // a8 bit = 1.
+        let square4_pawn_attacks = pawn_attacks(Color::Black, square4, &empty_board);
+        assert_eq!(square4_pawn_attacks, Bitboard(0x0002000000000000)); // b7 bit = 1.
 
         // white pawn on h1
-        let square5: u64 = utils::square_to_bb("h1").unwrap(); // h1 bit = 1.
-        let square5_pawn_attacks = pawn_attacks(true, square5, &empty_board);
-        assert_eq!(square5_pawn_attacks, 0x0000000000004000); // g2 bit = 1.
+        let square5: Square = "h1".parse().unwrap(); // h1 bit = 1.
+        let square5_pawn_attacks = pawn_attacks(Color::White, square5, &empty_board);
+        assert_eq!(square5_pawn_attacks, Bitboard(0x0000000000004000)); // g2 bit = 1.
 
         // black pawn on h8
-        let square6: u64 = utils::square_to_bb("h8").unwrap(); // h8 bit = 1.
-        let square6_pawn_attacks = pawn_attacks(false, square6, &empty_board);
-        assert_eq!(square6_pawn_attacks, 0x0040000000000000); // g7 bit = 1.
+        let square6: Square = "h8".parse().unwrap(); // h8 bit = 1.
+        let square6_pawn_attacks = pawn_attacks(Color::Black, square6, &empty_board);
+        assert_eq!(square6_pawn_attacks, Bitboard(0x0040000000000000)); // g7 bit = 1.
 
         let non_empty_board =
-            board::ChessBoard::initialize_from_fen("k7/1p6/b7/8/8/B7/1P6/K7 b KQkq - 0 1").unwrap();
+            board::ChessBoard::initialize_from_fen("k7/1p6/b7/8/8/B7/1P6/K7 b - - 0 1").unwrap();
 
         // white pawn blocked by its own piece.
-        let square7: u64 = utils::square_to_bb("b2").unwrap();
-        let square7_pawn_attacks = pawn_attacks(true, square7, &non_empty_board);
-        assert_eq!(square7_pawn_attacks, 0x0000000000040000);
+        let square7: Square = "b2".parse().unwrap();
+        let square7_pawn_attacks = pawn_attacks(Color::White, square7, &non_empty_board);
+        assert_eq!(square7_pawn_attacks, Bitboard(0x0000000000040000));
         // black pawn blocked by its own piece.
-        let square8: u64 = utils::square_to_bb("b7").unwrap();
-        let square8_pawn_attacks = pawn_attacks(false, square8, &non_empty_board);
-        assert_eq!(square8_pawn_attacks, 0x0000040000000000);
+        let square8: Square = "b7".parse().unwrap();
+        let square8_pawn_attacks = pawn_attacks(Color::Black, square8, &non_empty_board);
+        assert_eq!(square8_pawn_attacks, Bitboard(0x0000040000000000));
     }
 
     #[test]
@@ -950,121 +1159,121 @@ mod tests {
         let empty_board = board::ChessBoard::empty();
 
         // a1:
-        let square1 = utils::square_to_bb("a1").unwrap();
-        let sq1_knight_attacks = knight_attacks(true, square1, &empty_board);
-        assert_eq!(sq1_knight_attacks, 0x0000000000020400);
+        let square1: Square = "a1".parse().unwrap();
+        let sq1_knight_attacks = knight_attacks(Color::White, square1, &empty_board);
+        assert_eq!(sq1_knight_attacks, Bitboard(0x0000000000020400));
 
         // a2:
-        let square2 = utils::square_to_bb("a2").unwrap();
-        let sq2_knight_attacks = knight_attacks(true, square2, &empty_board);
-        assert_eq!(sq2_knight_attacks, 0x0000000002040004);
+        let square2: Square = "a2".parse().unwrap();
+        let sq2_knight_attacks = knight_attacks(Color::White, square2, &empty_board);
+        assert_eq!(sq2_knight_attacks, Bitboard(0x0000000002040004));
 
         // b1:
-        let square3 = utils::square_to_bb("b1").unwrap();
-        let sq3_knight_attacks = knight_attacks(true, square3, &empty_board);
-        assert_eq!(sq3_knight_attacks, 0x0000000000050800);
+        let square3: Square = "b1".parse().unwrap();
+        let sq3_knight_attacks = knight_attacks(Color::White, square3, &empty_board);
+        assert_eq!(sq3_knight_attacks, Bitboard(0x0000000000050800));
 
         // b2:
-        let square4 = utils::square_to_bb("b2").unwrap();
-        let sq4_knight_attacks = knight_attacks(true, square4, &empty_board);
-        assert_eq!(sq4_knight_attacks, 0x0000000005080008);
+        let square4: Square = "b2".parse().unwrap();
+        let sq4_knight_attacks = knight_attacks(Color::White, square4, &empty_board);
+        assert_eq!(sq4_knight_attacks, Bitboard(0x0000000005080008));
 
         // g1:
-        let square5 = utils::square_to_bb("g1").unwrap();
-        let sq5_knight_attacks = knight_attacks(true, square5, &empty_board);
-        assert_eq!(sq5_knight_attacks, 0x0000000000A01000);
+        let square5: Square = "g1".parse().unwrap();
+        let sq5_knight_attacks = knight_attacks(Color::White, square5, &empty_board);
+        assert_eq!(sq5_knight_attacks, Bitboard(0x0000000000A01000));
 
         // g2:
-        let square6 = utils::square_to_bb("g2").unwrap();
-        let sq6_knight_attacks = knight_attacks(true, square6, &empty_board);
-        assert_eq!(sq6_knight_attacks, 0x00000000A0100010);
+        let square6: Square = "g2".parse().unwrap();
+        let sq6_knight_attacks = knight_attacks(Color::White, square6, &empty_board);
+        assert_eq!(sq6_knight_attacks, Bitboard(0x00000000A0100010));
 
         // h1:
-        let square7 = utils::square_to_bb("h1").unwrap();
-        let sq7_knight_attacks = knight_attacks(true, square7, &empty_board);
-        assert_eq!(sq7_knight_attacks, 0x0000000000402000);
+        let square7: Square = "h1".parse().unwrap();
+        let sq7_knight_attacks = knight_attacks(Color::White, square7, &empty_board);
+        assert_eq!(sq7_knight_attacks, Bitboard(0x0000000000402000));
 
         // h2:
-        let square8 = utils::square_to_bb("h2").unwrap();
-        let sq8_knight_attacks = knight_attacks(true, square8, &empty_board);
-        assert_eq!(sq8_knight_attacks, 0x0000000040200020);
+        let square8: Square = "h2".parse().unwrap();
+        let sq8_knight_attacks = knight_attacks(Color::White, square8, &empty_board);
+        assert_eq!(sq8_knight_attacks, Bitboard(0x0000000040200020));
 
         // a7:
-        let square9 = utils::square_to_bb("a7").unwrap();
-        let sq9_knight_attacks = knight_attacks(true, square9, &empty_board);
-        assert_eq!(sq9_knight_attacks, 0x0400040200000000);
+        let square9: Square = "a7".parse().unwrap();
+        let sq9_knight_attacks = knight_attacks(Color::White, square9, &empty_board);
+        assert_eq!(sq9_knight_attacks, Bitboard(0x0400040200000000));
 
         // a8:
-        let square10 = utils::square_to_bb("a8").unwrap();
-        let sq10_knight_attacks = knight_attacks(true, square10, &empty_board);
-        assert_eq!(sq10_knight_attacks, 0x0004020000000000);
+        let square10: Square = "a8".parse().unwrap();
+        let sq10_knight_attacks = knight_attacks(Color::White, square10, &empty_board);
+        assert_eq!(sq10_knight_attacks, Bitboard(0x0004020000000000));
 
         // b7:
-        let square11 = utils::square_to_bb("b7").unwrap();
-        let sq11_knight_attacks = knight_attacks(true, square11, &empty_board);
-        assert_eq!(sq11_knight_attacks, 0x0800080500000000);
+        let square11: Square = "b7".parse().unwrap();
+        let sq11_knight_attacks = knight_attacks(Color::White, square11, &empty_board);
+        assert_eq!(sq11_knight_attacks, Bitboard(0x0800080500000000));
 
         let starting_board = board::ChessBoard::initialize();
 
         // b1 from starting position (exclude d2)
-        let square12 = utils::square_to_bb("b1").unwrap();
-        let sq12_knight_attacks = knight_attacks(true, square12, &starting_board);
-        assert_eq!(sq12_knight_attacks, 0x0000000000050000);
+        let square12: Square = "b1".parse().unwrap();
+        let sq12_knight_attacks = knight_attacks(Color::White, square12, &starting_board);
+        assert_eq!(sq12_knight_attacks, Bitboard(0x0000000000050000));
 
         // b8 from starting position (exclude d7)
-        let square13 = utils::square_to_bb("b8").unwrap();
-        let sq13_knight_attacks = knight_attacks(false, square13, &starting_board);
-        assert_eq!(sq13_knight_attacks, 0x0000050000000000);
+        let square13: Square = "b8".parse().unwrap();
+        let sq13_knight_attacks = knight_attacks(Color::Black, square13, &starting_board);
+        assert_eq!(sq13_knight_attacks, Bitboard(0x0000050000000000));
     }
 
     #[test]
     fn test_bishop_attacks() {
         let empty_board = board::ChessBoard::empty();
         // a1
-        let square1 = utils::square_to_bb("a1").unwrap();
-        let sq1_bishop_attacks = bishop_attacks(true, square1, &empty_board);
-        assert_eq!(sq1_bishop_attacks, 0x8040201008040200);
+        let square1: Square = "a1".parse().unwrap();
+        let sq1_bishop_attacks = bishop_attacks(Color::White, square1, &empty_board);
+        assert_eq!(sq1_bishop_attacks, Bitboard(0x8040201008040200));
         // // a8
-        let square2 = utils::square_to_bb("a8").unwrap();
-        let sq2_bishop_attacks = bishop_attacks(true, square2, &empty_board);
-        assert_eq!(sq2_bishop_attacks, 0x0002040810204080);
+        let square2: Square = "a8".parse().unwrap();
+        let sq2_bishop_attacks = bishop_attacks(Color::White, square2, &empty_board);
+        assert_eq!(sq2_bishop_attacks, Bitboard(0x0002040810204080));
         // // h1
-        let square3 = utils::square_to_bb("h1").unwrap();
-        let sq3_bishop_attacks = bishop_attacks(true, square3, &empty_board);
-        assert_eq!(sq3_bishop_attacks, 0x0102040810204000);
+        let square3: Square = "h1".parse().unwrap();
+        let sq3_bishop_attacks = bishop_attacks(Color::White, square3, &empty_board);
+        assert_eq!(sq3_bishop_attacks, Bitboard(0x0102040810204000));
         // // h8
-        let square4 = utils::square_to_bb("h8").unwrap();
-        let sq4_bishop_attacks = bishop_attacks(true, square4, &empty_board);
-        assert_eq!(sq4_bishop_attacks, 0x0040201008040201);
+        let square4: Square = "h8".parse().unwrap();
+        let sq4_bishop_attacks = bishop_attacks(Color::White, square4, &empty_board);
+        assert_eq!(sq4_bishop_attacks, Bitboard(0x0040201008040201));
         // // d4
-        let square5 = utils::square_to_bb("d4").unwrap();
-        let sq5_bishop_attacks = bishop_attacks(true, square5, &empty_board);
-        assert_eq!(sq5_bishop_attacks, 0x8041221400142241);
+        let square5: Square = "d4".parse().unwrap();
+        let sq5_bishop_attacks = bishop_attacks(Color::White, square5, &empty_board);
+        assert_eq!(sq5_bishop_attacks, Bitboard(0x8041221400142241));
     }
 
     #[test]
     fn test_rook_attacks() {
         let empty_board = board::ChessBoard::empty();
         // a1
-        let square1 = utils::square_to_bb("a1").unwrap();
-        let sq1_rook_attacks = rook_attacks(true, square1, &empty_board);
-        assert_eq!(sq1_rook_attacks, 0x01010101010101FE);
+        let square1: Square = "a1".parse().unwrap();
+        let sq1_rook_attacks = rook_attacks(Color::White, square1, &empty_board);
+        assert_eq!(sq1_rook_attacks, Bitboard(0x01010101010101FE));
         // a8
-        let square2 = utils::square_to_bb("a8").unwrap();
-        let sq2_rook_attacks = rook_attacks(true, square2, &empty_board);
-        assert_eq!(sq2_rook_attacks, 0xFE01010101010101);
+        let square2: Square = "a8".parse().unwrap();
+        let sq2_rook_attacks = rook_attacks(Color::White, square2, &empty_board);
+        assert_eq!(sq2_rook_attacks, Bitboard(0xFE01010101010101));
         // h1
-        let square3 = utils::square_to_bb("h1").unwrap();
-        let sq3_rook_attacks = rook_attacks(true, square3, &empty_board);
-        assert_eq!(sq3_rook_attacks, 0x808080808080807F);
+        let square3: Square = "h1".parse().unwrap();
+        let sq3_rook_attacks = rook_attacks(Color::White, square3, &empty_board);
+        assert_eq!(sq3_rook_attacks, Bitboard(0x808080808080807F));
         // h8
-        let square4 = utils::square_to_bb("h8").unwrap();
-        let sq4_rook_attacks = rook_attacks(true, square4, &empty_board);
-        assert_eq!(sq4_rook_attacks, 0x7F80808080808080);
+        let square4: Square = "h8".parse().unwrap();
+        let sq4_rook_attacks = rook_attacks(Color::White, square4, &empty_board);
+        assert_eq!(sq4_rook_attacks, Bitboard(0x7F80808080808080));
         // d4
-        let square5 = utils::square_to_bb("d4").unwrap();
-        let sq5_rook_attacks = rook_attacks(true, square5, &empty_board);
-        assert_eq!(sq5_rook_attacks, 0x08080808F7080808);
+        let square5: Square = "d4".parse().unwrap();
+        let sq5_rook_attacks = rook_attacks(Color::White, square5, &empty_board);
+        assert_eq!(sq5_rook_attacks, Bitboard(0x08080808F7080808));
     }
 
     #[test]
@@ -1072,66 +1281,81 @@ mod tests {
         let empty_board = board::ChessBoard::empty();
 
         //a1
-        let square1 = utils::square_to_bb("a1").unwrap();
-        let sq1_queen_attacks = queen_attacks(true, square1, &empty_board);
-        assert_eq!(sq1_queen_attacks, (0x01010101010101FE | 0x8040201008040200));
+        let square1: Square = "a1".parse().unwrap();
+        let sq1_queen_attacks = queen_attacks(Color::White, square1, &empty_board);
+        assert_eq!(
+            sq1_queen_attacks,
+            Bitboard(0x01010101010101FE | 0x8040201008040200)
+        );
         // a8
-        let square2 = utils::square_to_bb("a8").unwrap();
-        let sq2_queen_attacks = queen_attacks(true, square2, &empty_board);
-        assert_eq!(sq2_queen_attacks, (0xFE01010101010101 | 0x0002040810204080));
+        let square2: Square = "a8".parse().unwrap();
+        let sq2_queen_attacks = queen_attacks(Color::White, square2, &empty_board);
+        assert_eq!(
+            sq2_queen_attacks,
+            Bitboard(0xFE01010101010101 | 0x0002040810204080)
+        );
         // h1
-        let square3 = utils::square_to_bb("h1").unwrap();
-        let sq3_queen_attacks = queen_attacks(true, square3, &empty_board);
-        assert_eq!(sq3_queen_attacks, (0x808080808080807F | 0x0102040810204000));
+        let square3: Square = "h1".parse().unwrap();
+        let sq3_queen_attacks = queen_attacks(Color::White, square3, &empty_board);
+        assert_eq!(
+            sq3_queen_attacks,
+            Bitboard(0x808080808080807F | 0x0102040810204000)
+        );
         // h8
-        let square4 = utils::square_to_bb("h8").unwrap();
-        let sq4_queen_attacks = queen_attacks(true, square4, &empty_board);
-        assert_eq!(sq4_queen_attacks, (0x7F80808080808080 | 0x0040201008040201));
+        let square4: Square = "h8".parse().unwrap();
+        let sq4_queen_attacks = queen_attacks(Color::White, square4, &empty_board);
+        assert_eq!(
+            sq4_queen_attacks,
+            Bitboard(0x7F80808080808080 | 0x0040201008040201)
+        );
         // d4
-        let square5 = utils::square_to_bb("d4").unwrap();
-        let sq5_queen_attacks = queen_attacks(true, square5, &empty_board);
-        assert_eq!(sq5_queen_attacks, (0x08080808F7080808 | 0x8041221400142241));
+        let square5: Square = "d4".parse().unwrap();
+        let sq5_queen_attacks = queen_attacks(Color::White, square5, &empty_board);
+        assert_eq!(
+            sq5_queen_attacks,
+            Bitboard(0x08080808F7080808 | 0x8041221400142241)
+        );
     }
 
     #[test]
     fn test_king_attacks() {
         let board1 = board::ChessBoard::initialize();
         // white king starting position
-        let square1 = utils::square_to_bb("e1").unwrap();
-        let sq1_king_attacks = king_attacks(true, square1, &board1);
-        assert_eq!(sq1_king_attacks, 0);
+        let square1: Square = "e1".parse().unwrap();
+        let sq1_king_attacks = king_attacks(Color::White, square1, &board1);
+        assert_eq!(sq1_king_attacks, Bitboard::EMPTY);
         // black king starting position
-        let square2 = utils::square_to_bb("e8").unwrap();
-        let sq2_king_attacks = king_attacks(false, square2, &board1);
-        assert_eq!(sq2_king_attacks, 0);
+        let square2: Square = "e8".parse().unwrap();
+        let sq2_king_attacks = king_attacks(Color::Black, square2, &board1);
+        assert_eq!(sq2_king_attacks, Bitboard::EMPTY);
 
         let board2 = board::ChessBoard::empty();
         // white king a1 empty board
-        let square3 = utils::square_to_bb("a1").unwrap();
-        let sq3_king_attacks = king_attacks(true, square3, &board2);
-        assert_eq!(sq3_king_attacks, 0x0000000000000302);
+        let square3: Square = "a1".parse().unwrap();
+        let sq3_king_attacks = king_attacks(Color::White, square3, &board2);
+        assert_eq!(sq3_king_attacks, Bitboard(0x0000000000000302));
         // black king a1 empty board
-        let square3 = utils::square_to_bb("a1").unwrap();
-        let sq3_king_attacks = king_attacks(false, square3, &board2);
-        assert_eq!(sq3_king_attacks, 0x0000000000000302);
+        let square3: Square = "a1".parse().unwrap();
+        let sq3_king_attacks = king_attacks(Color::Black, square3, &board2);
+        assert_eq!(sq3_king_attacks, Bitboard(0x0000000000000302));
     }
 
     #[test]
     fn test_board_attacks() {
         // 2 pieces of each type (except king), black to move
         let board1 =
-            board::ChessBoard::initialize_from_fen("rnbq1p2/rnbq1pk1/8/8/8/8/8/K7 b KQkq - 0 1")
+            board::ChessBoard::initialize_from_fen("rnbq4/rnbq1pk1/5p2/8/8/8/8/7K b - - 0 1")
                 .unwrap();
-        let board_attacks_test = board_attacks(&board1, false);
-        let board_attacks_correct: u64 = 0xD090FF7FE9C98909;
+        let board_attacks_test = board_attacks(&board1, Color::Black);
+        let board_attacks_correct = Bitboard(0xF090DF7F69C98909);
         assert_eq!(board_attacks_test, board_attacks_correct);
 
         // 2 pieces of each type (except king), white to move
         let board2 =
-            board::ChessBoard::initialize_from_fen("k7/8/8/8/8/8/RNBQ1PK1/RNBQ1P2 w KQkq - 0 1")
+            board::ChessBoard::initialize_from_fen("7k/8/8/8/8/5P2/RNBQ1PK1/RNBQ4 w - - 0 1")
                 .unwrap();
-        let board_attacks_test = board_attacks(&board2, true);
-        let board_attacks_correct: u64 = 0x0989C9E97FFF90D0;
+        let board_attacks_test = board_attacks(&board2, Color::White);
+        let board_attacks_correct = Bitboard(0x0989C9697FDF90F0);
         assert_eq!(board_attacks_test, board_attacks_correct);
     }
 
@@ -1139,67 +1363,73 @@ mod tests {
     fn test_square_attacked() {
         // start board - king not attacked
         let start_board = ChessBoard::initialize();
-        let sq = utils::square_to_bb("e1").unwrap();
-        assert_eq!(square_attacked(false, sq, &start_board, None, None), false);
+        let sq: Square = "e1".parse().unwrap();
+        assert_eq!(
+            square_attacked(Color::Black, sq, &start_board, None, None),
+            false
+        );
 
         // start board - king not attacked
         let start_board = ChessBoard::initialize();
-        let sq = utils::square_to_bb("e8").unwrap();
-        assert_eq!(square_attacked(true, sq, &start_board, None, None), false);
+        let sq: Square = "e8".parse().unwrap();
+        assert_eq!(
+            square_attacked(Color::White, sq, &start_board, None, None),
+            false
+        );
 
         // white king attacked by knight.
         let board = ChessBoard::initialize_from_fen("k7/8/4n3/8/3K4/8/8/8 w - - 0 1").unwrap();
-        let sq = utils::square_to_bb("d4").unwrap();
-        assert_eq!(square_attacked(false, sq, &board, None, None), true);
+        let sq: Square = "d4".parse().unwrap();
+        assert_eq!(square_attacked(Color::Black, sq, &board, None, None), true);
 
         // white king attacked by pawn.
         let board = ChessBoard::initialize_from_fen("k7/8/8/4p3/3K4/8/8/8 w - - 0 1").unwrap();
-        let sq = utils::square_to_bb("d4").unwrap();
-        assert_eq!(square_attacked(false, sq, &board, None, None), true);
+        let sq: Square = "d4".parse().unwrap();
+        assert_eq!(square_attacked(Color::Black, sq, &board, None, None), true);
 
         // black king attacked by knight.
         let board = ChessBoard::initialize_from_fen("K7/8/8/8/1N6/8/2k5/8 b - - 0 1").unwrap();
-        let sq = utils::square_to_bb("c2").unwrap();
-        assert_eq!(square_attacked(true, sq, &board, None, None), true);
+        let sq: Square = "c2".parse().unwrap();
+        assert_eq!(square_attacked(Color::White, sq, &board, None, None), true);
 
         // black king attacked by pawn.
         let board = ChessBoard::initialize_from_fen("K7/7k/6P1/8/8/8/8/8 b - - 0 1").unwrap();
-        let sq = utils::square_to_bb("h7").unwrap();
-        assert_eq!(square_attacked(true, sq, &board, None, None), true);
+        let sq: Square = "h7".parse().unwrap();
+        assert_eq!(square_attacked(Color::White, sq, &board, None, None), true);
 
         // white king not attacked
         let board =
             ChessBoard::initialize_from_fen("8/2n1n3/2n5/nnKnk3/1pnp4/n1n1n3/8/8 w - - 0 1")
                 .unwrap();
-        let sq = utils::square_to_bb("c5").unwrap();
-        assert_eq!(square_attacked(false, sq, &board, None, None), false);
+        let sq: Square = "c5".parse().unwrap();
+        assert_eq!(square_attacked(Color::Black, sq, &board, None, None), false);
 
         // white king attacks d2 and not e8.
         let board = ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
-        let sq = utils::square_to_bb("d2").unwrap();
-        assert_eq!(square_attacked(true, sq, &board, None, None), true);
-        assert_eq!(square_attacked(false, sq, &board, None, None), false);
-        let sq = utils::square_to_bb("d8").unwrap();
-        assert_eq!(square_attacked(true, sq, &board, None, None), false);
-        assert_eq!(square_attacked(false, sq, &board, None, None), true);
+        let sq: Square = "d2".parse().unwrap();
+        assert_eq!(square_attacked(Color::White, sq, &board, None, None), true);
+        assert_eq!(square_attacked(Color::Black, sq, &board, None, None), false);
+        let sq: Square = "d8".parse().unwrap();
+        assert_eq!(square_attacked(Color::White, sq, &board, None, None), false);
+        assert_eq!(square_attacked(Color::Black, sq, &board, None, None), true);
 
         // white king surround by pawns, cannot be attacked unless f5 is vacated.
         let board =
             ChessBoard::initialize_from_fen("4k3/1b2r2q/8/3PPP2/r2PKP1r/3PPP2/8/1q2r2b w - - 0 1")
                 .unwrap();
-        let sq = utils::square_to_bb("e4").unwrap();
+        let sq: Square = "e4".parse().unwrap();
         let vacated = utils::square_to_bb("f5").unwrap();
-        assert_eq!(square_attacked(false, sq, &board, None, None), false);
+        assert_eq!(square_attacked(Color::Black, sq, &board, None, None), false);
         assert_eq!(
-            square_attacked(false, sq, &board, Some(vacated), None),
+            square_attacked(Color::Black, sq, &board, Some(vacated), None),
             true
         );
     }
 
     #[test]
     fn test_ray_generation() {
-        let square1 = utils::square_to_bb("e4").unwrap();
-        let rays: [u64; 8] = [
+        let square1 = "e4".parse::<Square>().unwrap().bb();
+        let rays: [Bitboard; 8] = [
             generate_north_ray(square1),
             generate_ne_ray(square1),
             generate_east_ray(square1),
@@ -1213,14 +1443,14 @@ mod tests {
         assert_eq!(
             rays,
             [
-                0x1010101000000000,
-                0x0080402000000000,
-                0x00000000E0000000,
-                0x0000000000204080,
-                0x0000000000101010,
-                0x0000000000080402,
-                0x000000000F000000,
-                0x0102040800000000
+                Bitboard(0x1010101000000000),
+                Bitboard(0x0080402000000000),
+                Bitboard(0x00000000E0000000),
+                Bitboard(0x0000000000204080),
+                Bitboard(0x0000000000101010),
+                Bitboard(0x0000000000080402),
+                Bitboard(0x000000000F000000),
+                Bitboard(0x0102040800000000)
             ]
         );
     }
@@ -1228,81 +1458,81 @@ mod tests {
     #[test]
     fn test_check_along_ray() {
         // east ray from e4, friendly piece on g4.
-        let square1 = utils::square_to_bb("e4").unwrap();
-        let e_ray = generate_east_ray(square1);
-        let friendly_pieces = 0x0000000040000000;
-        let enemy_pieces = 0;
+        let square1: Square = "e4".parse().unwrap();
+        let e_ray = generate_east_ray(square1.bb());
+        let friendly_pieces = Bitboard(0x0000000040000000);
+        let enemy_pieces = Bitboard::EMPTY;
         assert_eq!(
             check_along_ray(square1, e_ray, friendly_pieces, enemy_pieces),
-            0x0000000020000000
+            Bitboard(0x0000000020000000)
         );
 
         // east ray from e4, enemy piece on g4.
-        let enemy_pieces = 0x0000000040000000;
-        let friendly_pieces = 0;
+        let enemy_pieces = Bitboard(0x0000000040000000);
+        let friendly_pieces = Bitboard::EMPTY;
         assert_eq!(
             check_along_ray(square1, e_ray, friendly_pieces, enemy_pieces),
-            0x0000000060000000
+            Bitboard(0x0000000060000000)
         );
 
         // west ray from e4, friendly piece on a4.
-        let w_ray = generate_west_ray(square1);
-        let friendly_pieces = 0x0000000001000000;
-        let enemy_pieces = 0;
+        let w_ray = generate_west_ray(square1.bb());
+        let friendly_pieces = Bitboard(0x0000000001000000);
+        let enemy_pieces = Bitboard::EMPTY;
         assert_eq!(
             check_along_ray(square1, w_ray, friendly_pieces, enemy_pieces),
-            0x000000000E000000
+            Bitboard(0x000000000E000000)
         );
 
         // west ray from e4, enemy piece on a4.
-        let enemy_pieces = 0x0000000001000000;
-        let friendly_pieces = 0;
+        let enemy_pieces = Bitboard(0x0000000001000000);
+        let friendly_pieces = Bitboard::EMPTY;
         assert_eq!(
             check_along_ray(square1, w_ray, friendly_pieces, enemy_pieces),
-            0x000000000F000000
+            Bitboard(0x000000000F000000)
         );
 
         // ne ray from e4, enemy piece on g6
-        let ne_ray = generate_ne_ray(square1);
-        let enemy_pieces = 0x0000400000000000;
-        let friendly_pieces = 0;
+        let ne_ray = generate_ne_ray(square1.bb());
+        let enemy_pieces = Bitboard(0x0000400000000000);
+        let friendly_pieces = Bitboard::EMPTY;
         assert_eq!(
             check_along_ray(square1, ne_ray, friendly_pieces, enemy_pieces),
-            0x0000402000000000
+            Bitboard(0x0000402000000000)
         );
 
         // nw ray from e4, friendly piece on a8;
-        let nw_ray = generate_nw_ray(square1);
-        let enemy_pieces = 0;
-        let friendly_pieces = 0x0100000000000000;
+        let nw_ray = generate_nw_ray(square1.bb());
+        let enemy_pieces = Bitboard::EMPTY;
+        let friendly_pieces = Bitboard(0x0100000000000000);
         assert_eq!(
             check_along_ray(square1, nw_ray, friendly_pieces, enemy_pieces),
-            0x0002040800000000
+            Bitboard(0x0002040800000000)
         );
 
         // se ray from e4, enemy piece on g2
-        let se_ray = generate_se_ray(square1);
-        let enemy_pieces = 0x0000000000004000;
-        let friendly_pieces = 0;
+        let se_ray = generate_se_ray(square1.bb());
+        let enemy_pieces = Bitboard(0x0000000000004000);
+        let friendly_pieces = Bitboard::EMPTY;
         assert_eq!(
             check_along_ray(square1, se_ray, friendly_pieces, enemy_pieces),
-            0x0000000000204000
+            Bitboard(0x0000000000204000)
         );
 
         // sw ray from e4, friendly piece on b1
-        let sw_ray = generate_sw_ray(square1);
-        let enemy_pieces = 0;
-        let friendly_pieces = 0x0000000000000002;
+        let sw_ray = generate_sw_ray(square1.bb());
+        let enemy_pieces = Bitboard::EMPTY;
+        let friendly_pieces = Bitboard(0x0000000000000002);
         assert_eq!(
             check_along_ray(square1, sw_ray, friendly_pieces, enemy_pieces),
-            0x0000000000080400
+            Bitboard(0x0000000000080400)
         );
     }
 
     #[test]
     fn test_get_pawn_plmoves() {
         let board1 = ChessBoard::initialize(); // white in starting position
-        let mut correct_pawn_plmoves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut correct_pawn_plmoves: ArrayVec<Move, 256> = ArrayVec::new();
 
         for from_sqi in 8..=15 {
             correct_pawn_plmoves.push(utils::encode_move(from_sqi, from_sqi + 8, 0));
@@ -1310,14 +1540,14 @@ mod tests {
         }
         correct_pawn_plmoves.sort();
 
-        let mut pawn_pl_moves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut pawn_pl_moves: ArrayVec<Move, 256> = ArrayVec::new();
         get_pawn_plmoves(&board1, &mut pawn_pl_moves);
         pawn_pl_moves.sort();
 
         assert_eq!(pawn_pl_moves, correct_pawn_plmoves);
 
         let board2 = ChessBoard::initialize_from_fen("k7/8/8/8/8/8/6p1/K6N b - - 0 1").unwrap(); // black promotions test
-        let mut correct_pawn_plmoves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut correct_pawn_plmoves: ArrayVec<Move, 256> = ArrayVec::new();
 
         for flag in 8..=11 {
             correct_pawn_plmoves.push(utils::encode_move(14, 7, flag));
@@ -1327,18 +1557,18 @@ mod tests {
         }
         correct_pawn_plmoves.sort();
 
-        let mut pawn_pl_moves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut pawn_pl_moves: ArrayVec<Move, 256> = ArrayVec::new();
         get_pawn_plmoves(&board2, &mut pawn_pl_moves);
         pawn_pl_moves.sort();
 
         assert_eq!(pawn_pl_moves, correct_pawn_plmoves);
 
         let board3 = ChessBoard::initialize_from_fen("k7/8/8/4pP2/8/8/8/K7 w - e6 0 2").unwrap(); // white en passant
-        let mut correct_pawn_plmoves: ArrayVec<u16, 256> =
+        let mut correct_pawn_plmoves: ArrayVec<Move, 256> =
             ArrayVec::from_iter([utils::encode_move(37, 45, 0), utils::encode_move(37, 44, 3)]);
         correct_pawn_plmoves.sort();
 
-        let mut pawn_pl_moves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut pawn_pl_moves: ArrayVec<Move, 256> = ArrayVec::new();
         get_pawn_plmoves(&board3, &mut pawn_pl_moves);
         pawn_pl_moves.sort();
 
@@ -1348,7 +1578,7 @@ mod tests {
     #[test]
     fn test_get_nonpk_plmoves() {
         let board1 = ChessBoard::initialize(); // white in starting position
-        let mut correct_nonpk_plmoves: ArrayVec<u16, 256> = ArrayVec::from_iter([
+        let mut correct_nonpk_plmoves: ArrayVec<Move, 256> = ArrayVec::from_iter([
             utils::encode_move(1, 16, 0),
             utils::encode_move(1, 18, 0),
             utils::encode_move(6, 23, 0),
@@ -1356,7 +1586,7 @@ mod tests {
         ]);
         correct_nonpk_plmoves.sort();
 
-        let mut nonpk_pl_moves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut nonpk_pl_moves: ArrayVec<Move, 256> = ArrayVec::new();
         get_nonpk_plmoves(&board1, &mut nonpk_pl_moves);
         nonpk_pl_moves.sort();
 
@@ -1364,7 +1594,7 @@ mod tests {
 
         let board2 =
             ChessBoard::initialize_from_fen("1q2k1r1/Ppp4b/8/5Pp1/4p3/8/8/K7 b - - 0 1").unwrap(); // black in random position
-        let mut correct_nonpk_plmoves: ArrayVec<u16, 256> = ArrayVec::from_iter([
+        let mut correct_nonpk_plmoves: ArrayVec<Move, 256> = ArrayVec::from_iter([
             utils::encode_move(57, 56, 0), // 4 queen moves
             utils::encode_move(57, 58, 0),
             utils::encode_move(57, 59, 0),
@@ -1378,7 +1608,7 @@ mod tests {
         ]);
         correct_nonpk_plmoves.sort();
 
-        let mut nonpk_pl_moves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut nonpk_pl_moves: ArrayVec<Move, 256> = ArrayVec::new();
         get_nonpk_plmoves(&board2, &mut nonpk_pl_moves);
         nonpk_pl_moves.sort();
 
@@ -1388,10 +1618,10 @@ mod tests {
     #[test]
     fn test_get_king_plmoves() {
         let mut board1 = ChessBoard::initialize_from_fen(
-            "r3kbnr/pppP1ppp/4p3/8/8/4P3/PPPp1PPP/RNBQK2R w KQkq - 0 1",
+            "r3kbnr/pppN1ppp/4p3/8/8/4P3/PPPp1PPP/RNBQK2R w KQkq - 0 1",
         )
         .unwrap();
-        let mut correct_king_plmoves: ArrayVec<u16, 256> = ArrayVec::from_iter([
+        let mut correct_king_plmoves: ArrayVec<Move, 256> = ArrayVec::from_iter([
             utils::encode_move(4, 12, 0),
             utils::encode_move(4, 5, 0),
             utils::encode_move(4, 11, 1),
@@ -1399,14 +1629,14 @@ mod tests {
         ]);
         correct_king_plmoves.sort();
 
-        let mut king_pl_moves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut king_pl_moves: ArrayVec<Move, 256> = ArrayVec::new();
         get_king_plmoves(&board1, &mut king_pl_moves);
         king_pl_moves.sort();
 
         assert_eq!(king_pl_moves, correct_king_plmoves);
 
         board1.make_move(utils::encode_move(8, 16, 0));
-        let mut correct_king_plmoves: ArrayVec<u16, 256> = ArrayVec::from_iter([
+        let mut correct_king_plmoves: ArrayVec<Move, 256> = ArrayVec::from_iter([
             utils::encode_move(60, 59, 0),
             utils::encode_move(60, 52, 0),
             utils::encode_move(60, 51, 1),
@@ -1414,7 +1644,7 @@ mod tests {
         ]);
         correct_king_plmoves.sort();
 
-        let mut king_pl_moves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut king_pl_moves: ArrayVec<Move, 256> = ArrayVec::new();
         get_king_plmoves(&board1, &mut king_pl_moves);
         king_pl_moves.sort();
 
@@ -1424,7 +1654,7 @@ mod tests {
     #[test]
     fn test_test_plmove_legality() {
         let mut board = board::ChessBoard::initialize_from_fen(
-            "r3kbnr/pppP1ppp/4p3/8/8/4P3/PPPp1PPP/RNBQK2R w KQkq - 0 1",
+            "r3kbnr/pppN1ppp/4p3/8/8/4P3/PPPp1PPP/RNBQK2R w KQkq - 0 1",
         )
         .unwrap();
         assert_eq!(
@@ -1433,7 +1663,7 @@ mod tests {
         );
 
         let mut board = board::ChessBoard::initialize_from_fen(
-            "r3kbnr/pppP1ppp/4p3/8/8/4P3/PPP2PPP/RNBQK2R w KQkq - 0 1",
+            "r3kbnr/pppN1ppp/4p3/8/8/4P3/PPP2PPP/RNBQK2R w KQkq - 0 1",
         )
         .unwrap();
         assert_eq!(test_plmove_legality(&mut board, encode_move(4, 6, 2)), true);
@@ -1464,13 +1694,31 @@ mod tests {
             test_plmove_legality(&mut board, encode_move(28, 37, 1)),
             false
         );
+
+        // Chess960: the king already stands on c1, and castling moves the b1 rook out of the
+        // a1 rook's line.
+        let mut board =
+            board::ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/8/rRK5 w B - 0 1").unwrap();
+        let mut king_pl_moves: ArrayVec<Move, 256> = ArrayVec::new();
+        get_king_plmoves(&board, &mut king_pl_moves);
+        assert!(king_pl_moves.contains(&encode_move(2, 1, 2)));
+        assert!(!test_plmove_legality(&mut board, encode_move(2, 1, 2)));
+
+        let mut board =
+            board::ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/8/1RK5 w B - 0 1").unwrap();
+        assert!(test_plmove_legality(&mut board, encode_move(2, 1, 2)));
+
+        // ...but it can't castle out of check, even though the rook would land in between.
+        let mut board =
+            board::ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/8/1RK4r w B - 0 1").unwrap();
+        assert!(!test_plmove_legality(&mut board, encode_move(2, 1, 2)));
     }
 
     #[test]
     fn test_get_legal_moves() {
         let mut board = ChessBoard::initialize();
 
-        let mut correct_legal_moves: ArrayVec<u16, 256> = ArrayVec::new();
+        let mut correct_legal_moves: ArrayVec<Move, 256> = ArrayVec::new();
         for from_sqi in 8..=15 {
             correct_legal_moves.push(utils::encode_move(from_sqi, from_sqi + 8, 0));
             correct_legal_moves.push(utils::encode_move(from_sqi, from_sqi + 16, 0));
@@ -1486,4 +1734,207 @@ mod tests {
 
         assert_eq!(legal_moves, correct_legal_moves);
     }
+
+    #[test]
+    fn test_legal_move_generation() {
+        // en passant that uncovers a rook along the rank is illegal.
+        let mut board =
+            ChessBoard::initialize_from_fen("8/8/8/K1pP3r/8/8/8/7k w - c6 0 2").unwrap();
+        assert!(!get_legal_moves(&mut board)
+            .iter()
+            .any(|mv| mv.is_en_passant()));
+        // but taking the checking pawn en passant is an evasion.
+        let mut board =
+            ChessBoard::initialize_from_fen("8/8/8/2pP4/1K6/8/8/7k w - c6 0 2").unwrap();
+        assert!(get_legal_moves(&mut board)
+            .iter()
+            .any(|mv| mv.is_en_passant()));
+
+        // in double check only the king moves.
+        let mut board =
+            ChessBoard::initialize_from_fen("4k3/8/8/8/8/5n2/3Q4/4r1K1 w - - 0 1").unwrap();
+        let safety = KingSafety::new(&board).unwrap();
+        assert_eq!(safety.checkers.popcount(), 2);
+        let moves = get_legal_moves(&mut board);
+        assert!(moves.iter().all(|mv| mv.from() == safety.king));
+
+        // a pinned bishop stays on the pin line.
+        let mut board =
+            ChessBoard::initialize_from_fen("4k3/8/8/8/8/2b5/3B4/4K3 w - - 0 1").unwrap();
+        let safety = KingSafety::new(&board).unwrap();
+        assert_eq!(safety.pinned, "d2".parse::<Square>().unwrap().bb());
+        let bishop_moves: Vec<String> = get_legal_moves(&mut board)
+            .iter()
+            .filter(|mv| mv.from() == "d2".parse().unwrap())
+            .map(|mv| mv.to_string())
+            .collect();
+        assert_eq!(bishop_moves, ["d2c3"]);
+
+        // the same moves as filtering the pseudolegal ones, two plies deep from tricky positions.
+        fn compare(board: &mut ChessBoard, depth: u32) {
+            let mut fast = get_legal_moves(board);
+            let mut reference = get_legal_moves_filtered(board);
+            fast.sort();
+            reference.sort();
+            assert_eq!(fast, reference, "{}", board.to_fen());
+            if depth > 0 {
+                for mv in reference {
+                    let undo_info = board.make_move(mv).unwrap();
+                    compare(board, depth - 1);
+                    board.unmake_move(mv, &undo_info).unwrap();
+                }
+            }
+        }
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        ] {
+            compare(&mut ChessBoard::initialize_from_fen(fen).unwrap(), 2);
+        }
+    }
+
+    #[test]
+    fn test_generate_moves() {
+        // captures and quiets split the legal moves; quiet checks and evasions are subsets.
+        fn check_modes(board: &mut ChessBoard, depth: u32) {
+            let all = get_legal_moves(board);
+            let captures = generate_moves(board, GenType::Captures);
+            let quiets = generate_moves(board, GenType::Quiets);
+            let mut split: Vec<Move> = captures.iter().chain(quiets.iter()).copied().collect();
+            let mut expected = all.to_vec();
+            split.sort();
+            expected.sort();
+            assert_eq!(split, expected, "{}", board.to_fen());
+            assert!(captures.iter().all(|mv| GenType::Captures.includes(*mv)));
+            assert!(quiets.iter().all(|mv| GenType::Quiets.includes(*mv)));
+
+            let in_check = rules::is_check(board, board.side_to_move);
+            let evasions = generate_moves(board, GenType::Evasions);
+            assert_eq!(evasions.len(), if in_check { all.len() } else { 0 });
+
+            let to_move = board.side_to_move;
+            for mv in quiets {
+                let undo_info = board.make_move(mv).unwrap();
+                let check = rules::is_check(board, !to_move);
+                board.unmake_move(mv, &undo_info).unwrap();
+                let listed = generate_moves(board, GenType::QuietChecks).contains(&mv);
+                assert_eq!(check, listed, "{mv} in {}", board.to_fen());
+            }
+
+            if depth > 0 {
+                for mv in all {
+                    let undo_info = board.make_move(mv).unwrap();
+                    check_modes(board, depth - 1);
+                    board.unmake_move(mv, &undo_info).unwrap();
+                }
+            }
+        }
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            check_modes(&mut ChessBoard::initialize_from_fen(fen).unwrap(), 1);
+        }
+
+        // a promotion square: the queen promotion is a capture-stage move, the rest are quiet.
+        let mut board = ChessBoard::initialize_from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let captures = generate_moves(&mut board, GenType::Captures);
+        assert_eq!(
+            captures.iter().map(|mv| mv.to_string()).collect::<Vec<_>>(),
+            ["b7b8q"]
+        );
+        let quiet_checks = generate_moves(&mut board, GenType::QuietChecks);
+        assert_eq!(
+            quiet_checks
+                .iter()
+                .map(|mv| mv.to_string())
+                .collect::<Vec<_>>(),
+            ["b7b8r"]
+        );
+    }
+
+    #[test]
+    fn test_is_legal() {
+        // every u16 is judged the same way as by the generators. castling in the encoding the
+        // board doesn't generate is accepted too if make_move plays it as a generated castle.
+        fn check_all(board: &mut ChessBoard) {
+            let pseudo_legal = get_pseudolegal_moves(board);
+            let legal = get_legal_moves(board);
+            for raw in 0..=u16::MAX {
+                let mv = Move::from_raw(raw);
+                let generated = pseudo_legal.contains(&mv);
+                if !is_pseudo_legal(board, mv) {
+                    assert!(!generated, "{mv} ({raw}) rejected in {}", board.to_fen());
+                    assert!(!is_legal(board, mv));
+                    continue;
+                }
+                let same_as = if generated {
+                    mv
+                } else {
+                    let mut after = board.clone();
+                    after.make_move(mv).unwrap();
+                    *pseudo_legal
+                        .iter()
+                        .filter(|other| other.is_castle())
+                        .find(|other| {
+                            let mut other_after = board.clone();
+                            other_after.make_move(**other).unwrap();
+                            other_after.zobrist_hash == after.zobrist_hash
+                        })
+                        .unwrap_or_else(|| panic!("{mv} ({raw}) accepted in {}", board.to_fen()))
+                };
+                assert_eq!(
+                    is_legal(board, mv),
+                    legal.contains(&same_as),
+                    "{mv} ({raw}) in {}",
+                    board.to_fen()
+                );
+            }
+        }
+
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            // en passant would uncover the rook along the rank.
+            "8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1",
+            // double check: only the king may move.
+            "4r1k1/8/8/8/Q7/3n4/8/4K3 w - - 0 1",
+            // Chess960, with the king already on its castling square.
+            "1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1",
+            "1r2k1rr/8/8/8/8/8/8/RR2K2R w BHbg - 0 1",
+        ] {
+            check_all(&mut ChessBoard::initialize_from_fen(fen).unwrap());
+        }
+        let mut kiwipete = ChessBoard::initialize_from_fen(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        )
+        .unwrap();
+        for mv in get_legal_moves(&mut kiwipete) {
+            let undo_info = kiwipete.make_move(mv).unwrap();
+            check_all(&mut kiwipete);
+            kiwipete.unmake_move(mv, &undo_info).unwrap();
+        }
+
+        // a few kinds of garbage by hand.
+        let board = ChessBoard::initialize();
+        for (from, to, flag) in [
+            ("c1", "e3", Move::QUIET),     // through a pawn
+            ("g1", "f3", Move::CAPTURE),   // a capture of nothing
+            ("e2", "e5", Move::QUIET),     // three squares
+            ("e2", "e4", Move::PROMOTION), // promoting short of the last rank
+            ("e1", "g1", Move::CASTLE),    // castling through pieces
+            ("e7", "e5", Move::QUIET),     // the other side's pawn
+            ("g1", "f3", 12),              // no such flag
+        ] {
+            let mv = Move::new(from.parse().unwrap(), to.parse().unwrap(), flag);
+            assert!(!is_pseudo_legal(&board, mv), "{from}{to} flag {flag}");
+        }
+    }
 }
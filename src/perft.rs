@@ -1,7 +1,18 @@
 use crate::board::ChessBoard;
+use crate::error::ChessError;
 use crate::moves;
 use crate::moves::get_legal_moves;
+use crate::rules::{self, CheckKind};
+use crate::tt::TranspositionTable;
+use crate::types::Move;
 use crate::utils;
+use arrayvec::ArrayVec;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 pub fn perft(board: &mut ChessBoard, depth: u32) -> u64 {
     if depth == 0 {
@@ -17,16 +28,409 @@ pub fn perft(board: &mut ChessBoard, depth: u32) -> u64 {
     nodes
 }
 
-pub fn perft_divide(board: &mut ChessBoard, depth: u32) {
-    let moves = get_legal_moves(board);
-    let mut total = 0;
+// perft through the older generator (pseudolegal moves filtered one by one): a reference to
+// check perft against, and to compare their speed.
+pub fn perft_filtered(board: &mut ChessBoard, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = moves::get_legal_moves_filtered(board);
+    let mut nodes = 0;
     for move_i in moves {
         let undo_info = board.make_move(move_i).unwrap();
-        let nodes = perft(board, depth - 1);
+        nodes += perft_filtered(board, depth - 1);
+        board.unmake_move(move_i, &undo_info).unwrap();
+    }
+    nodes
+}
+
+// the same count as perft, spread over `threads` worker threads (at least one). the tree is
+// expanded a ply at a time until there are a few subtrees per thread, then each worker takes
+// the next unclaimed subtree and runs perft on its own copy of the board, so a thread that
+// gets small subtrees just takes more of them.
+pub fn perft_parallel(board: &ChessBoard, depth: u32, threads: usize) -> u64 {
+    let threads = threads.max(1);
+    let mut subtrees = vec![board.clone()];
+    let mut remaining = depth;
+    while remaining > 1 && subtrees.len() < threads * 8 {
+        subtrees = subtrees
+            .into_iter()
+            .flat_map(|mut parent| {
+                get_legal_moves(&mut parent).into_iter().map(move |move_i| {
+                    let mut child = parent.clone();
+                    child.make_move(move_i).unwrap();
+                    child
+                })
+            })
+            .collect();
+        remaining -= 1;
+    }
+
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut nodes = 0;
+                    while let Some(subtree) = subtrees.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        nodes += perft(&mut subtree.clone(), remaining);
+                    }
+                    nodes
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .sum()
+    })
+}
+
+// the same count as perft, with the counts of subtrees of depth 2 or more cached by
+// (zobrist_hash, depth) in a table of table_mb megabytes, so transpositions are counted once.
+// a wrong incremental hash shows up as a wrong total, which perft_hashed_verified can pin down.
+pub fn perft_hashed(board: &mut ChessBoard, depth: u32, table_mb: usize) -> u64 {
+    let table = TranspositionTable::new(table_mb);
+    hashed(board, depth, &table, &mut Vec::new(), false).unwrap()
+}
+
+/// The first position in a perft tree whose incrementally updated `zobrist_hash` differs from
+/// `generate_zobrist_hash()`, and the moves from the root that lead to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashMismatch {
+    pub moves: Vec<Move>,
+    pub incremental: u64,
+    pub from_scratch: u64,
+}
+
+impl fmt::Display for HashMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "zobrist hash diverges after [")?;
+        for (i, move_i) in self.moves.iter().enumerate() {
+            write!(f, "{}{move_i}", if i == 0 { "" } else { " " })?;
+        }
+        write!(
+            f,
+            "]: incremental {:#018x}, from scratch {:#018x}",
+            self.incremental, self.from_scratch
+        )
+    }
+}
+
+// perft_hashed's verification mode: the hash of every node is recomputed from scratch and
+// checked against the incremental one. the table is only filled, never probed, so no subtree
+// is skipped; the count comes back only if every hash matched.
+pub fn perft_hashed_verified(
+    board: &mut ChessBoard,
+    depth: u32,
+    table_mb: usize,
+) -> Result<u64, HashMismatch> {
+    let table = TranspositionTable::new(table_mb);
+    hashed(board, depth, &table, &mut Vec::new(), true)
+}
+
+fn hashed(
+    board: &mut ChessBoard,
+    depth: u32,
+    table: &TranspositionTable,
+    path: &mut Vec<Move>,
+    verify: bool,
+) -> Result<u64, HashMismatch> {
+    if verify {
+        let from_scratch = board.generate_zobrist_hash();
+        if from_scratch != board.zobrist_hash {
+            return Err(HashMismatch {
+                moves: path.clone(),
+                incremental: board.zobrist_hash,
+                from_scratch,
+            });
+        }
+    }
+    if depth == 0 {
+        return Ok(1);
+    }
+    let cached = depth >= 2 && depth <= u8::MAX as u32;
+    if cached && !verify {
+        if let Some(nodes) = table.probe_perft(board.zobrist_hash, depth as u8) {
+            return Ok(nodes);
+        }
+    }
+
+    let mut nodes = 0;
+    for move_i in get_legal_moves(board) {
+        let undo_info = board.make_move(move_i).unwrap();
+        path.push(move_i);
+        let subtree = hashed(board, depth - 1, table, path, verify);
+        path.pop();
+        board.unmake_move(move_i, &undo_info).unwrap();
+        nodes += subtree?;
+    }
+    if cached {
+        table.store_perft(board.zobrist_hash, depth as u8, nodes);
+    }
+    Ok(nodes)
+}
+
+/// Counts of the moves made at one ply of a perft run, by kind, as in the Chess Programming
+/// Wiki's perft tables. Captures include en passant, and checks include every kind of check.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerftStats {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passant: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub discovered_checks: u64,
+    pub double_checks: u64,
+    pub checkmates: u64,
+}
+
+// one PerftStats per ply, the first for the moves from the root: when perft(depth) is off,
+// the ply and kind of move that are off point to the broken part of the move generator.
+pub fn perft_detailed(board: &mut ChessBoard, depth: u32) -> Vec<PerftStats> {
+    let mut stats = vec![PerftStats::default(); depth as usize];
+    detailed(board, &mut stats, 0);
+    stats
+}
+
+fn detailed(board: &mut ChessBoard, stats: &mut [PerftStats], ply: usize) {
+    if ply == stats.len() {
+        return;
+    }
+    let checks = rules::CheckSquares::new(board);
+    for move_i in get_legal_moves(board) {
+        let kind = checks.as_ref().and_then(|c| c.check_kind(board, move_i));
+        let counts = &mut stats[ply];
+        counts.nodes += 1;
+        counts.captures += move_i.is_capture() as u64;
+        counts.en_passant += move_i.is_en_passant() as u64;
+        counts.castles += move_i.is_castle() as u64;
+        counts.promotions += move_i.is_promotion() as u64;
+        counts.checks += kind.is_some() as u64;
+        counts.discovered_checks += (kind == Some(CheckKind::Discovered)) as u64;
+        counts.double_checks += (kind == Some(CheckKind::Double)) as u64;
+
+        let undo_info = board.make_move(move_i).unwrap();
+        if kind.is_some() && !moves::has_legal_moves(board) {
+            counts.checkmates += 1;
+        }
+        detailed(board, stats, ply + 1);
         board.unmake_move(move_i, &undo_info).unwrap();
-        let decoded = utils::decode_to_uci(move_i).unwrap();
-        println!("{}: {}", decoded, nodes);
-        total += nodes;
     }
-    println!("Total: {total}");
+}
+
+// the perft count below each legal root move, in generation order; the counts add up to
+// perft(depth). empty at depth 0.
+pub fn perft_divide(board: &mut ChessBoard, depth: u32) -> Vec<(Move, u64)> {
+    divide_with(board, depth, get_legal_moves, perft)
+}
+
+// perft_divide through the older filtering generator, as a reference for perft_divide.
+pub fn perft_divide_filtered(board: &mut ChessBoard, depth: u32) -> Vec<(Move, u64)> {
+    divide_with(
+        board,
+        depth,
+        moves::get_legal_moves_filtered,
+        perft_filtered,
+    )
+}
+
+fn divide_with(
+    board: &mut ChessBoard,
+    depth: u32,
+    generate: fn(&mut ChessBoard) -> ArrayVec<Move, 256>,
+    count: fn(&mut ChessBoard, u32) -> u64,
+) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    generate(board)
+        .into_iter()
+        .map(|move_i| {
+            let undo_info = board.make_move(move_i).unwrap();
+            let nodes = count(board, depth - 1);
+            board.unmake_move(move_i, &undo_info).unwrap();
+            (move_i, nodes)
+        })
+        .collect()
+}
+
+// reads divide output such as another engine prints for `go perft`: one `<uci move>: <nodes>`
+// per line. other lines (blank ones, `Nodes searched: 20`, `Total: 20`) are skipped.
+pub fn parse_divide(text: &str) -> Result<Vec<(String, u64)>, ChessError> {
+    let mut counts = Vec::new();
+    for line in text.lines() {
+        let Some((mv, nodes)) = line.split_once(':') else {
+            continue;
+        };
+        let mv = mv.trim();
+        if utils::encode_from_uci(mv).is_err() {
+            continue;
+        }
+        let nodes = nodes
+            .trim()
+            .parse()
+            .map_err(|_| ChessError::InvalidUci(line.trim().to_string()))?;
+        counts.push((mv.to_string(), nodes));
+    }
+    Ok(counts)
+}
+
+/// Where a perft tree first disagrees with a reference divide: the moves from the root and
+/// the position they lead to, with the root moves that only one side has and, if both have
+/// the same moves, those whose counts differ as (move, ours, reference).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub moves: Vec<Move>,
+    pub fen: String,
+    pub depth: u32,
+    pub missing: Vec<String>, // in the reference, but not generated here
+    pub extra: Vec<String>,   // generated here, but not in the reference
+    pub counts: Vec<(String, u64, u64)>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "after [")?;
+        for (i, move_i) in self.moves.iter().enumerate() {
+            write!(f, "{}{move_i}", if i == 0 { "" } else { " " })?;
+        }
+        write!(f, "] ({}), depth {}:", self.fen, self.depth)?;
+        if !self.missing.is_empty() {
+            write!(f, " missing {}", self.missing.join(" "))?;
+        }
+        if !self.extra.is_empty() {
+            write!(f, " extra {}", self.extra.join(" "))?;
+        }
+        for (mv, ours, reference) in &self.counts {
+            write!(f, " {mv} {ours} (reference {reference})")?;
+        }
+        Ok(())
+    }
+}
+
+// narrows a perft mismatch down to the first position where the move lists differ. the root
+// is compared with `root_reference` (e.g. parse_divide of another engine's output); from there
+// the search walks into the first child whose count differs, asking `reference` for the divide
+// of each position it enters (another engine, or perft_divide_filtered). None if the root
+// divide matches. if a child matches after all, the divergence is reported at its parent, with
+// the counts that differ.
+pub fn find_divergence<F>(
+    board: &ChessBoard,
+    depth: u32,
+    root_reference: &[(String, u64)],
+    mut reference: F,
+) -> Result<Option<Divergence>, ChessError>
+where
+    F: FnMut(&ChessBoard, u32) -> Result<Vec<(String, u64)>, ChessError>,
+{
+    let mut board = board.clone();
+    let mut path = Vec::new();
+    let mut expected = root_reference.to_vec();
+    let mut depth = depth;
+    let mut divergence = None;
+    loop {
+        let ours: Vec<(String, u64, Move)> = perft_divide(&mut board, depth)
+            .into_iter()
+            .map(|(move_i, nodes)| (move_i.to_string(), nodes, move_i))
+            .collect();
+        let missing: Vec<String> = expected
+            .iter()
+            .filter(|(mv, _)| !ours.iter().any(|(ours, _, _)| ours == mv))
+            .map(|(mv, _)| mv.clone())
+            .collect();
+        let extra: Vec<String> = ours
+            .iter()
+            .filter(|(mv, _, _)| !expected.iter().any(|(theirs, _)| theirs == mv))
+            .map(|(mv, _, _)| mv.clone())
+            .collect();
+        let counts: Vec<(String, u64, u64, Move)> = ours
+            .iter()
+            .filter_map(|(mv, nodes, move_i)| {
+                let (_, theirs) = expected.iter().find(|(theirs, _)| theirs == mv)?;
+                (nodes != theirs).then(|| (mv.clone(), *nodes, *theirs, *move_i))
+            })
+            .collect();
+        if missing.is_empty() && extra.is_empty() && counts.is_empty() {
+            break;
+        }
+
+        let here = Divergence {
+            moves: path.clone(),
+            fen: board.to_fen(),
+            depth,
+            missing,
+            extra,
+            counts: counts
+                .iter()
+                .map(|(mv, ours, theirs, _)| (mv.clone(), *ours, *theirs))
+                .collect(),
+        };
+        // a move missing or extra here is the bug itself; otherwise it is further down.
+        let descend = here.missing.is_empty() && here.extra.is_empty() && depth > 1;
+        divergence = Some(here);
+        let Some(&(_, _, _, move_i)) = counts.first().filter(|_| descend) else {
+            break;
+        };
+        board.make_move(move_i).unwrap();
+        path.push(move_i);
+        depth -= 1;
+        expected = reference(&board, depth)?;
+    }
+    Ok(divergence)
+}
+
+/// A position from a perft suite with its expected node counts, as (depth, nodes) pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PerftCase {
+    pub fen: String,
+    pub board: ChessBoard,
+    pub expected: Vec<(u32, u64)>,
+}
+
+// reads a perft suite in the usual EPD layout, one position per line:
+//   <fen> ;D1 20 ;D2 400 ;D3 8902
+// the FEN may leave out its move counters. blank lines and lines starting with '#' are skipped.
+pub fn parse_perft_suite(text: &str) -> Result<Vec<PerftCase>, ChessError> {
+    let mut cases = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |reason: String| ChessError::InvalidEpd(format!("line {}: {reason}", i + 1));
+        let mut fields = line.split(';');
+        let fen = fields.next().unwrap_or_default().trim();
+        let board = ChessBoard::initialize_from_fen(fen).map_err(|err| invalid(err.to_string()))?;
+        let mut expected = Vec::new();
+        for field in fields {
+            let count = field
+                .trim()
+                .strip_prefix('D')
+                .and_then(|count| count.split_once(' '))
+                .and_then(|(depth, nodes)| Some((depth.parse().ok()?, nodes.trim().parse().ok()?)))
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "expected `D<depth> <nodes>`, got `{}`",
+                        field.trim()
+                    ))
+                })?;
+            expected.push(count);
+        }
+        if expected.is_empty() {
+            return Err(invalid("no perft counts".to_string()));
+        }
+        cases.push(PerftCase {
+            fen: fen.to_string(),
+            board,
+            expected,
+        });
+    }
+    Ok(cases)
+}
+
+pub fn load_perft_suite(path: impl AsRef<Path>) -> io::Result<Vec<PerftCase>> {
+    let text = fs::read_to_string(path)?;
+    parse_perft_suite(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
 }
@@ -0,0 +1,656 @@
+// this file reads and writes games in Portable Game Notation (PGN).
+
+use crate::board::ChessBoard;
+use crate::error::ChessError;
+use crate::game::{ChessGame, DrawReason, GameResult, TimeControl, WinReason};
+use crate::san::{move_to_san, san_to_move};
+
+// the seven tag roster, in the order required for export.
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// NAGs and comment attached to one move of the movetext.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MoveAnnotation {
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+}
+
+/// A game together with its PGN tags and annotations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>, // in file order
+    pub game: ChessGame,
+    pub initial_comment: Option<String>, // comment before the first move
+    pub annotations: Vec<MoveAnnotation>, // one per move in game.moves
+}
+
+impl PgnGame {
+    /// Wraps a game with the seven tag roster set to unknown values.
+    pub fn from_game(game: ChessGame) -> Self {
+        let annotations = vec![MoveAnnotation::default(); game.moves.len()];
+        let mut pgn_game = PgnGame {
+            tags: Vec::new(),
+            game,
+            initial_comment: None,
+            annotations,
+        };
+        for name in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Date" => "????.??.??",
+                "Result" => "*",
+                _ => "?",
+            };
+            pgn_game.set_tag(name, value);
+        }
+        pgn_game
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Exports the game as PGN: seven tag roster first, then the other tags, then SAN movetext.
+    /// Fails if the start position or a move doesn't hold up when the game is replayed, which
+    /// can only happen if `game` was edited by hand.
+    pub fn to_pgn(&self) -> Result<String, ChessError> {
+        let mut output = String::new();
+
+        // result comes from the game if it is decided, otherwise from the tag.
+        let result = match self.game.result {
+            GameResult::InProgress => self.tag("Result").unwrap_or("*").to_string(),
+            decided => result_token(decided).to_string(),
+        };
+
+        for name in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => Some(result.as_str()),
+                _ => self.tag(name),
+            };
+            let default = if name == "Date" { "????.??.??" } else { "?" };
+            push_tag(&mut output, name, value.unwrap_or(default));
+        }
+        let start_fen = self
+            .game
+            .start_fen
+            .as_deref()
+            .filter(|fen| *fen != STARTING_FEN);
+        if let Some(fen) = start_fen {
+            push_tag(&mut output, "SetUp", "1");
+            push_tag(&mut output, "FEN", fen);
+        }
+        for (name, value) in &self.tags {
+            if SEVEN_TAG_ROSTER.contains(&name.as_str()) || name == "SetUp" || name == "FEN" {
+                continue;
+            }
+            push_tag(&mut output, name, value);
+        }
+        output.push('\n');
+
+        // movetext: replay the game from its starting position to produce SAN.
+        let mut board = match start_fen {
+            Some(fen) => ChessBoard::initialize_from_fen(fen)?,
+            None => ChessBoard::initialize(),
+        };
+        let mut tokens: Vec<String> = Vec::new();
+        if let Some(comment) = &self.initial_comment {
+            tokens.push(format!("{{{comment}}}"));
+        }
+        let mut need_number = true;
+        for (i, (movei, _)) in self.game.moves.iter().enumerate() {
+            if board.side_to_move.is_white() {
+                tokens.push(format!("{}.", board.fullmove_number));
+            } else if need_number {
+                tokens.push(format!("{}...", board.fullmove_number));
+            }
+            need_number = false;
+
+            tokens.push(move_to_san(&mut board, *movei)?);
+            board.make_move(*movei)?;
+
+            if let Some(annotation) = self.annotations.get(i) {
+                for nag in &annotation.nags {
+                    tokens.push(format!("${nag}"));
+                    need_number = true;
+                }
+                if let Some(comment) = &annotation.comment {
+                    tokens.push(format!("{{{comment}}}"));
+                    need_number = true;
+                }
+            }
+        }
+        tokens.push(result);
+
+        // wrap lines at 80 characters.
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len > 0 && line_len + 1 + token.len() > 80 {
+                output.push('\n');
+                line_len = 0;
+            } else if line_len > 0 {
+                output.push(' ');
+                line_len += 1;
+            }
+            line_len += token.len();
+            output.push_str(&token);
+        }
+        output.push('\n');
+        Ok(output)
+    }
+}
+
+/// Exports a game with unknown seven tag roster values.
+pub fn write_pgn(game: &ChessGame) -> Result<String, ChessError> {
+    PgnGame::from_game(game.clone()).to_pgn()
+}
+
+/// Parses the first game of a PGN string.
+pub fn parse_pgn(pgn: &str) -> Result<PgnGame, ChessError> {
+    let mut games = parse_pgn_games(pgn)?;
+    if games.is_empty() {
+        return Err(ChessError::InvalidPgn("No game found in PGN.".to_string()));
+    }
+    Ok(games.remove(0))
+}
+
+/// Parses every game of a PGN string (e.g. a whole database file).
+pub fn parse_pgn_games(pgn: &str) -> Result<Vec<PgnGame>, ChessError> {
+    let tokens = tokenize(pgn)?;
+    let mut games = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        // tag section
+        let mut tags: Vec<(String, String)> = Vec::new();
+        while let Some(Token::Tag(name, value)) = tokens.get(i) {
+            tags.push((name.clone(), value.clone()));
+            i += 1;
+        }
+
+        // movetext section, up to the game termination marker or the next tag section.
+        let start = i;
+        while i < tokens.len() {
+            match &tokens[i] {
+                Token::Tag(..) => break,
+                Token::Result(_) => {
+                    i += 1;
+                    break;
+                }
+                _ => i += 1,
+            }
+        }
+        if tags.is_empty() && start == i {
+            break;
+        }
+        games.push(build_game(tags, &tokens[start..i])?);
+    }
+
+    Ok(games)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    San(String),
+    Result(String),
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, ChessError> {
+    let chars: Vec<char> = pgn.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut variation_depth = 0; // recursive annotation variations are skipped.
+
+    while i < chars.len() {
+        let ch = chars[i];
+        let line_start = i == 0 || chars[i - 1] == '\n';
+
+        if ch.is_whitespace() {
+            i += 1;
+        } else if ch == '%' && line_start {
+            // escape mechanism: ignore the rest of the line.
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if ch == ';' {
+            // rest of line comment
+            let start = i + 1;
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            if variation_depth == 0 {
+                let comment: String = chars[start..i].iter().collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+        } else if ch == '{' {
+            let start = i + 1;
+            while i < chars.len() && chars[i] != '}' {
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(ChessError::InvalidPgn("Unterminated comment.".to_string()));
+            }
+            if variation_depth == 0 {
+                let comment: String = chars[start..i].iter().collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            i += 1;
+        } else if ch == '(' {
+            variation_depth += 1;
+            i += 1;
+        } else if ch == ')' {
+            if variation_depth == 0 {
+                return Err(ChessError::InvalidPgn(
+                    "Unmatched ')' in movetext.".to_string(),
+                ));
+            }
+            variation_depth -= 1;
+            i += 1;
+        } else if ch == '}' || ch == ']' {
+            return Err(ChessError::InvalidPgn(format!(
+                "Unmatched '{ch}' in movetext."
+            )));
+        } else if ch == '[' && variation_depth > 0 {
+            return Err(ChessError::InvalidPgn(
+                "Tag pair inside a variation.".to_string(),
+            ));
+        } else if ch == '[' {
+            // tag pair: [Name "Value"]
+            i += 1;
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            let name_start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let name: String = chars[name_start..i].iter().collect();
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            if name.is_empty() || i == chars.len() || chars[i] != '"' {
+                return Err(ChessError::InvalidPgn(format!("Invalid tag pair: [{name}")));
+            }
+            i += 1;
+            let mut value = String::new();
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    i += 1; // \" and \\ escapes
+                }
+                value.push(chars[i]);
+                i += 1;
+            }
+            while i < chars.len() && chars[i] != ']' {
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(ChessError::InvalidPgn(format!(
+                    "Unterminated tag pair: [{name}"
+                )));
+            }
+            i += 1;
+            tokens.push(Token::Tag(name, value));
+        } else {
+            // symbol: move number, SAN, NAG or result.
+            let start = i;
+            while i < chars.len() && !chars[i].is_whitespace() && !"{}()[];".contains(chars[i]) {
+                i += 1;
+            }
+            if variation_depth > 0 {
+                continue;
+            }
+            let symbol: String = chars[start..i].iter().collect();
+            tokenize_symbol(&symbol, &mut tokens)?;
+        }
+    }
+
+    if variation_depth != 0 {
+        return Err(ChessError::InvalidPgn(
+            "Unterminated variation in movetext.".to_string(),
+        ));
+    }
+    Ok(tokens)
+}
+
+fn tokenize_symbol(symbol: &str, tokens: &mut Vec<Token>) -> Result<(), ChessError> {
+    if ["1-0", "0-1", "1/2-1/2", "*"].contains(&symbol) {
+        tokens.push(Token::Result(symbol.to_string()));
+        return Ok(());
+    }
+    if let Some(nag) = symbol.strip_prefix('$') {
+        let nag = nag
+            .parse::<u8>()
+            .map_err(|_| ChessError::InvalidPgn(format!("Invalid NAG: {symbol}")))?;
+        tokens.push(Token::Nag(nag));
+        return Ok(());
+    }
+
+    // strip a move number ("12." or "12...") which may be glued to the move ("12.e4").
+    let mut rest = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() != symbol.len() {
+        if !rest.starts_with('.') && !rest.is_empty() {
+            // not a move number after all (e.g. "0-0" castling).
+            rest = symbol;
+        } else {
+            rest = rest.trim_start_matches('.');
+        }
+    }
+    if rest.is_empty() {
+        return Ok(());
+    }
+
+    // move suffix annotations (!, ?, !!, ??, !?, ?!) become NAGs 1-6.
+    let san = rest.trim_end_matches(['!', '?']);
+    let suffix = &rest[san.len()..];
+    if !san.is_empty() {
+        tokens.push(Token::San(san.to_string()));
+    }
+    let nag = match suffix {
+        "" => None,
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => {
+            return Err(ChessError::InvalidPgn(format!(
+                "Invalid move suffix annotation: {symbol}"
+            )))
+        }
+    };
+    if let Some(nag) = nag {
+        tokens.push(Token::Nag(nag));
+    }
+    Ok(())
+}
+
+fn build_game(tags: Vec<(String, String)>, movetext: &[Token]) -> Result<PgnGame, ChessError> {
+    let tag_value = |name: &str| {
+        tags.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+
+    let fen = tag_value("FEN");
+    let time_control = tag_value("TimeControl")
+        .and_then(parse_time_control)
+        .unwrap_or(TimeControl::new(0, 0));
+
+    let mut pgn_game = PgnGame {
+        tags: tags.clone(),
+        game: match fen {
+            Some(fen) => ChessGame::initialize_from_fen(time_control, fen)?,
+            None => ChessGame::new(time_control),
+        },
+        initial_comment: None,
+        annotations: Vec::new(),
+    };
+
+    for token in movetext {
+        match token {
+            Token::San(san) => {
+                let game = &mut pgn_game.game;
+                // repetition and fifty move draws have to be claimed in a real game, so play on.
+                if matches!(
+                    game.result,
+                    GameResult::Draw(DrawReason::ThreefoldRepitition)
+                        | GameResult::Draw(DrawReason::FiftyMoveRule)
+                ) {
+                    game.result = GameResult::InProgress;
+                }
+                let movei = san_to_move(&mut game.board, san)?;
+                game.make_move(movei, true, false)?;
+                pgn_game.annotations.push(MoveAnnotation::default());
+            }
+            Token::Nag(nag) => match pgn_game.annotations.last_mut() {
+                Some(annotation) => annotation.nags.push(*nag),
+                None => {
+                    return Err(ChessError::InvalidPgn(
+                        "NAG before the first move.".to_string(),
+                    ))
+                }
+            },
+            Token::Comment(comment) => {
+                let target = match pgn_game.annotations.last_mut() {
+                    Some(annotation) => &mut annotation.comment,
+                    None => &mut pgn_game.initial_comment,
+                };
+                match target {
+                    Some(existing) => {
+                        existing.push(' ');
+                        existing.push_str(comment);
+                    }
+                    None => *target = Some(comment.clone()),
+                }
+            }
+            Token::Result(result) => {
+                if pgn_game.game.result == GameResult::InProgress {
+                    let termination = tag_value("Termination").unwrap_or("");
+                    pgn_game.game.result = result_from_token(result, termination);
+                }
+            }
+            Token::Tag(..) => {}
+        }
+    }
+
+    Ok(pgn_game)
+}
+
+// time control tag of the form "base+increment" in seconds, e.g. "300+2".
+fn parse_time_control(tag: &str) -> Option<TimeControl> {
+    let (base, increment) = match tag.split_once('+') {
+        Some((base, increment)) => (base, increment),
+        None => (tag, "0"),
+    };
+    let base = base.parse::<u64>().ok()?;
+    let increment = increment.parse::<u64>().ok()?;
+    Some(TimeControl::new(base * 1000, increment * 1000))
+}
+
+fn result_token(result: GameResult) -> &'static str {
+    match result {
+        GameResult::InProgress => "*",
+        GameResult::WhiteWins(_) => "1-0",
+        GameResult::BlackWins(_) => "0-1",
+        GameResult::Draw(_) => "1/2-1/2",
+    }
+}
+
+// decisive results only reach here when the board doesn't show them, so they were not checkmate.
+fn result_from_token(token: &str, termination: &str) -> GameResult {
+    let reason = if termination.to_lowercase().contains("time") {
+        WinReason::Timeout
+    } else {
+        WinReason::Resignation
+    };
+    match token {
+        "1-0" => GameResult::WhiteWins(reason),
+        "0-1" => GameResult::BlackWins(reason),
+        "1/2-1/2" => GameResult::Draw(DrawReason::Agreement),
+        _ => GameResult::InProgress,
+    }
+}
+
+fn push_tag(output: &mut String, name: &str, value: &str) {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    output.push_str(&format!("[{name} \"{escaped}\"]\n"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::encode_move;
+
+    #[test]
+    fn test_parse_pgn() {
+        let pgn = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.} 3... a6
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+"#;
+        let pgn_game = parse_pgn(pgn).unwrap();
+        assert_eq!(pgn_game.tag("White"), Some("Fischer, Robert J."));
+        assert_eq!(pgn_game.game.moves.len(), 85);
+        assert_eq!(
+            pgn_game.annotations[4].comment.as_deref(),
+            Some("This opening is called the Ruy Lopez.")
+        );
+        assert_eq!(
+            pgn_game.game.result,
+            GameResult::Draw(DrawReason::Agreement)
+        );
+        assert_eq!(
+            pgn_game.game.board,
+            ChessBoard::initialize_from_fen("8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_pgn_annotations() {
+        let pgn = r#"[Event "?"]
+% this line is ignored
+{Start} 1.e4! $14 e5?! (1...c5 2.Nf3 (2.c3)) 2.Qh5 ; attacking f7
+Nc6 3.Bc4 Nf6?? 4.Qxf7# 1-0"#;
+        let pgn_game = parse_pgn(pgn).unwrap();
+        assert_eq!(pgn_game.initial_comment.as_deref(), Some("Start"));
+        assert_eq!(pgn_game.annotations[0].nags, vec![1, 14]);
+        assert_eq!(pgn_game.annotations[1].nags, vec![6]);
+        assert_eq!(
+            pgn_game.annotations[2].comment.as_deref(),
+            Some("attacking f7")
+        );
+        assert_eq!(pgn_game.annotations[5].nags, vec![4]);
+        assert_eq!(
+            pgn_game.game.result,
+            GameResult::WhiteWins(WinReason::Checkmate)
+        );
+    }
+
+    #[test]
+    fn test_parse_pgn_fen() {
+        let pgn = r#"[Event "Promotion"]
+[SetUp "1"]
+[FEN "k7/4P3/8/8/8/8/8/K7 w - - 0 1"]
+
+1. e8=Q+ Kb7 2. Qd7+ *"#;
+        let pgn_game = parse_pgn(pgn).unwrap();
+        assert_eq!(pgn_game.game.moves[0].0, encode_move(52, 60, 7));
+        assert_eq!(pgn_game.game.result, GameResult::InProgress);
+
+        assert!(matches!(
+            parse_pgn("[FEN \"bad fen\"]\n\n*"),
+            Err(ChessError::InvalidFen { .. })
+        ));
+        assert_eq!(
+            parse_pgn("1. e5 *").unwrap_err(),
+            ChessError::IllegalMove("e5".to_string())
+        );
+        assert_eq!(
+            parse_pgn("1. e4 {never closed").unwrap_err(),
+            ChessError::InvalidPgn("Unterminated comment.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_pgn_stray_delimiters() {
+        assert_eq!(
+            parse_pgn("1. e4 } e5 *").unwrap_err(),
+            ChessError::InvalidPgn("Unmatched '}' in movetext.".to_string())
+        );
+        assert_eq!(
+            parse_pgn("1. e4 ] e5 *").unwrap_err(),
+            ChessError::InvalidPgn("Unmatched ']' in movetext.".to_string())
+        );
+        assert_eq!(
+            parse_pgn("1. e4 (1. d4 [x]) e5 *").unwrap_err(),
+            ChessError::InvalidPgn("Tag pair inside a variation.".to_string())
+        );
+        assert_eq!(
+            parse_pgn("1. e4 ) e5 *").unwrap_err(),
+            ChessError::InvalidPgn("Unmatched ')' in movetext.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_pgn_games() {
+        let pgn = "[Event \"a\"]\n\n1. d4 d5 1-0\n\n[Event \"b\"]\n\n1. e4 0-1\n";
+        let games = parse_pgn_games(pgn).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("Event"), Some("a"));
+        assert_eq!(
+            games[0].game.result,
+            GameResult::WhiteWins(WinReason::Resignation)
+        );
+        assert_eq!(games[1].game.moves.len(), 1);
+    }
+
+    #[test]
+    fn test_write_pgn() {
+        let mut game = ChessGame::new((0, 0));
+        for uci in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            game.make_move_from_uci(uci, true, false).unwrap();
+        }
+        let pgn = write_pgn(&game).unwrap();
+        assert_eq!(
+            pgn,
+            "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"?\"]\n\
+             [Black \"?\"]\n[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n"
+        );
+
+        // from a position, with annotations and a disambiguated knight move.
+        let fen = "4k3/8/8/8/8/8/8/2N1K1N1 w - - 0 30";
+        let mut game = ChessGame::initialize((0, 0), Some(fen)).unwrap();
+        game.make_move(encode_move(6, 12, 0), true, false).unwrap();
+        let mut pgn_game = PgnGame::from_game(game);
+        pgn_game.set_tag("White", "A \"quoted\" name");
+        pgn_game.set_tag("Annotator", "me");
+        pgn_game.annotations[0].comment = Some("only move".to_string());
+        let pgn = pgn_game.to_pgn().unwrap();
+        assert!(pgn.contains("[White \"A \\\"quoted\\\" name\"]\n"));
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/2N1K1N1 w - - 0 30\"]\n"));
+        assert!(pgn.ends_with("[Annotator \"me\"]\n\n30. Nge2 {only move} *\n"));
+
+        // round trip
+        let parsed = parse_pgn(&pgn).unwrap();
+        assert_eq!(parsed.game.board, pgn_game.game.board);
+        assert_eq!(parsed.tag("White"), Some("A \"quoted\" name"));
+        assert_eq!(parsed.to_pgn(), Ok(pgn));
+
+        // a move that doesn't replay from the start position is an error, not a panic.
+        pgn_game.game.moves[0].0 = encode_move(6, 22, 0);
+        assert!(matches!(pgn_game.to_pgn(), Err(ChessError::IllegalMove(_))));
+        pgn_game.game.start_fen = Some("bad fen".to_string());
+        assert!(matches!(
+            pgn_game.to_pgn(),
+            Err(ChessError::InvalidFen { .. })
+        ));
+    }
+}
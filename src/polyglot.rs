@@ -0,0 +1,499 @@
+// this file reads and writes Polyglot opening books (.bin).
+//
+// a book is a sequence of 16-byte big-endian entries sorted by key:
+//   key u64, move u16, weight u16, learn u32.
+// the key is a Zobrist hash built from Polyglot's own 781 random numbers (not the crate's
+// zobrist_keys), laid out as
+//   0..768   pieces: 64 * piece + 8 * rank + file, piece = 2 * kind (P N B R Q K) + is_white
+//   768..772 castling rights: white short, white long, black short, black long
+//   772..780 en passant file, only if a pawn of the side to move can actually capture
+//   780      white to move
+// the published numbers are bundled as `polyglot_randoms::RANDOM64` (`PolyglotRandoms::official`);
+// other tables can still be read with `PolyglotRandoms::parse`.
+
+use crate::board::{ChessBoard, CASTLING_BITS};
+use crate::error::ChessError;
+use crate::game::{ChessGame, GameResult};
+use crate::moves::get_legal_moves;
+use crate::polyglot_randoms::RANDOM64;
+use crate::types::{Color, Move, PieceKind};
+use rustc_hash::FxHashMap;
+use std::path::Path;
+use std::{fs, io};
+
+pub const RANDOM_COUNT: usize = 781;
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+const ENTRY_SIZE: usize = 16;
+
+/// Key of the starting position under the official Polyglot random numbers.
+pub const OFFICIAL_START_KEY: u64 = 0x463B_9618_1691_FC9C;
+
+/// The 781 random numbers Polyglot keys are built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolyglotRandoms(Box<[u64; RANDOM_COUNT]>);
+
+impl PolyglotRandoms {
+    pub fn new(randoms: [u64; RANDOM_COUNT]) -> Self {
+        PolyglotRandoms(Box::new(randoms))
+    }
+
+    /// The official numbers, which every Polyglot book in circulation uses.
+    pub fn official() -> Self {
+        PolyglotRandoms::new(RANDOM64)
+    }
+
+    /// Reads 781 hexadecimal numbers, in order, from text such as the `Random64` array of the
+    /// Polyglot sources: `0x` prefixes, `U64(...)` wrappers, commas and whitespace are skipped.
+    pub fn parse(text: &str) -> Result<Self, ChessError> {
+        let mut randoms = [0u64; RANDOM_COUNT];
+        let mut count = 0;
+        for token in text.split(|c: char| !c.is_ascii_alphanumeric()) {
+            let Some(hex) = token.strip_prefix("0x").or(token.strip_prefix("0X")) else {
+                continue;
+            };
+            let value = u64::from_str_radix(hex, 16)
+                .map_err(|_| ChessError::InvalidBook(format!("bad random number '{token}'")))?;
+            if count == RANDOM_COUNT {
+                return Err(ChessError::InvalidBook(format!(
+                    "more than {RANDOM_COUNT} random numbers"
+                )));
+            }
+            randoms[count] = value;
+            count += 1;
+        }
+        if count < RANDOM_COUNT {
+            return Err(ChessError::InvalidBook(format!(
+                "expected {RANDOM_COUNT} random numbers, found {count}"
+            )));
+        }
+        Ok(PolyglotRandoms::new(randoms))
+    }
+
+    /// True if these are the official numbers, i.e. the start position gets the published key.
+    pub fn is_official(&self) -> bool {
+        self.key(&ChessBoard::initialize()) == OFFICIAL_START_KEY
+    }
+
+    /// The Polyglot key of a position.
+    pub fn key(&self, board: &ChessBoard) -> u64 {
+        let randoms = &self.0;
+        let mut key = 0;
+        for kind in PieceKind::ALL {
+            for color in [Color::Black, Color::White] {
+                let piece = 2 * kind.index() + color.is_white() as usize;
+                for sq in board.pieces(kind) & board.pieces_of(color) {
+                    key ^= randoms[64 * piece + sq.index() as usize];
+                }
+            }
+        }
+        for (right, bit) in CASTLING_BITS.iter().enumerate() {
+            if board.castling_rights & bit != 0 {
+                key ^= randoms[CASTLING_OFFSET + right];
+            }
+        }
+        if let Some(ep_sq) = board.en_passant.lsb() {
+            // the pawns of the side to move that could take en passant sit beside the target's
+            // file, one rank behind it (from their point of view).
+            let (behind, own) = if board.side_to_move.is_white() {
+                (ep_sq.bb().south(), board.white_pieces)
+            } else {
+                (ep_sq.bb().north(), board.black_pieces)
+            };
+            if (behind.east() | behind.west()).intersects(board.pawns & own) {
+                key ^= randoms[EN_PASSANT_OFFSET + ep_sq.file() as usize];
+            }
+        }
+        if board.side_to_move.is_white() {
+            key ^= randoms[TURN_OFFSET];
+        }
+        key
+    }
+}
+
+/// One book entry, as stored in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    pub key: u64,
+    pub mv: u16, // Polyglot move encoding, see encode_move
+    pub weight: u16,
+    pub learn: u32,
+}
+
+/// Encodes a move the Polyglot way: to file, to rank, from file, from rank (3 bits each, from
+/// the low bits up), then the promotion piece (1 = knight ... 4 = queen). Castling is written as
+/// the king taking its own rook.
+pub fn encode_move(board: &ChessBoard, mv: Move) -> u16 {
+    let from = mv.from();
+    let mut to = mv.to();
+    if mv.is_castle() {
+        let color = board.side_to_move;
+        if let Some(castle) = board.castle_squares(color, mv.is_kingside_castle()) {
+            to = castle.rook_from;
+        }
+    }
+    let promotion = match mv.promotion() {
+        Some(PieceKind::Knight) => 1,
+        Some(PieceKind::Bishop) => 2,
+        Some(PieceKind::Rook) => 3,
+        Some(PieceKind::Queen) => 4,
+        _ => 0,
+    };
+    to.file() as u16
+        | (to.rank() as u16) << 3
+        | (from.file() as u16) << 6
+        | (from.rank() as u16) << 9
+        | promotion << 12
+}
+
+/// The legal move a Polyglot move stands for in the position, if any.
+pub fn decode_move(board: &mut ChessBoard, polyglot_move: u16) -> Option<Move> {
+    get_legal_moves(board)
+        .into_iter()
+        .find(|&mv| encode_move(board, mv) == polyglot_move)
+}
+
+/// An opening book held in memory, sorted by key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolyglotBook {
+    pub entries: Vec<BookEntry>,
+    pub randoms: PolyglotRandoms,
+}
+
+impl PolyglotBook {
+    pub fn from_bytes(bytes: &[u8], randoms: PolyglotRandoms) -> Result<Self, ChessError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(ChessError::InvalidBook(format!(
+                "length {} is not a multiple of {ENTRY_SIZE}",
+                bytes.len()
+            )));
+        }
+        let entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                mv: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(chunk[12..16].try_into().unwrap()),
+            })
+            .collect();
+        if !entries.is_sorted_by_key(|entry| entry.key) {
+            return Err(ChessError::InvalidBook(
+                "entries are not sorted by key".to_string(),
+            ));
+        }
+        Ok(PolyglotBook { entries, randoms })
+    }
+
+    pub fn open(path: impl AsRef<Path>, randoms: PolyglotRandoms) -> io::Result<Self> {
+        let bytes = fs::read(path)?;
+        PolyglotBook::from_bytes(&bytes, randoms)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * ENTRY_SIZE);
+        for entry in &self.entries {
+            bytes.extend_from_slice(&entry.key.to_be_bytes());
+            bytes.extend_from_slice(&entry.mv.to_be_bytes());
+            bytes.extend_from_slice(&entry.weight.to_be_bytes());
+            bytes.extend_from_slice(&entry.learn.to_be_bytes());
+        }
+        bytes
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    /// The entries for a key, found by binary search.
+    pub fn entries_for(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = start + self.entries[start..].partition_point(|entry| entry.key == key);
+        &self.entries[start..end]
+    }
+
+    /// The book moves for a position with their weights, heaviest first. Entries that don't
+    /// decode to a legal move are skipped.
+    pub fn moves(&self, board: &mut ChessBoard) -> Vec<(Move, u16)> {
+        let mut moves: Vec<(Move, u16)> = self
+            .entries_for(self.randoms.key(board))
+            .iter()
+            .filter_map(|entry| Some((decode_move(board, entry.mv)?, entry.weight)))
+            .collect();
+        moves.sort_by_key(|&(_, weight)| std::cmp::Reverse(weight));
+        moves
+    }
+
+    /// The heaviest book move, if the position is in the book.
+    pub fn best_move(&self, board: &mut ChessBoard) -> Option<Move> {
+        self.moves(board).first().map(|&(mv, _)| mv)
+    }
+}
+
+/// Builds a book by accumulating weights over positions and moves.
+#[derive(Debug, Clone)]
+pub struct BookBuilder {
+    randoms: PolyglotRandoms,
+    weights: FxHashMap<(u64, u16), u64>,
+}
+
+impl BookBuilder {
+    pub fn new(randoms: PolyglotRandoms) -> Self {
+        BookBuilder {
+            randoms,
+            weights: FxHashMap::default(),
+        }
+    }
+
+    /// Adds `weight` to the move played in the position.
+    pub fn add_move(&mut self, board: &ChessBoard, mv: Move, weight: u64) {
+        let key = (self.randoms.key(board), encode_move(board, mv));
+        *self.weights.entry(key).or_insert(0) += weight;
+    }
+
+    /// Adds every move of a game, the first `max_plies` of them if given. Moves by the winner
+    /// weigh 2, moves in a drawn or unfinished game 1 and moves by the loser 0, as in
+    /// Polyglot's own book maker.
+    pub fn add_game(
+        &mut self,
+        game: &ChessGame,
+        max_plies: Option<usize>,
+    ) -> Result<(), ChessError> {
+        let mut board = match &game.start_fen {
+            Some(fen) => ChessBoard::initialize_from_fen(fen)?,
+            None => ChessBoard::initialize(),
+        };
+        let plies = max_plies.unwrap_or(usize::MAX);
+        for &(mv, _) in game.moves.iter().take(plies) {
+            let weight = match (game.result, board.side_to_move) {
+                (GameResult::WhiteWins(_), Color::White)
+                | (GameResult::BlackWins(_), Color::Black) => 2,
+                (GameResult::WhiteWins(_), Color::Black)
+                | (GameResult::BlackWins(_), Color::White) => 0,
+                _ => 1,
+            };
+            self.add_move(&board, mv, weight);
+            board.make_move(mv)?;
+        }
+        Ok(())
+    }
+
+    /// The book, sorted by key and then by weight, heaviest first. Weights are scaled down
+    /// to fit in 16 bits if needed, and entries that end up at weight 0 are dropped.
+    pub fn build(&self) -> PolyglotBook {
+        let max_weight = self.weights.values().copied().max().unwrap_or(0);
+        let scale = max_weight.div_ceil(u16::MAX as u64).max(1);
+        let mut entries: Vec<BookEntry> = self
+            .weights
+            .iter()
+            .map(|(&(key, mv), &weight)| BookEntry {
+                key,
+                mv,
+                weight: (weight / scale) as u16,
+                learn: 0,
+            })
+            .filter(|entry| entry.weight > 0)
+            .collect();
+        entries.sort_by_key(|entry| (entry.key, std::cmp::Reverse(entry.weight), entry.mv));
+        PolyglotBook {
+            entries,
+            randoms: self.randoms.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::WinReason;
+
+    fn legal(board: &mut ChessBoard, uci: &str) -> Move {
+        get_legal_moves(board)
+            .into_iter()
+            .find(|mv| mv.to_string() == uci)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_randoms() {
+        let randoms = PolyglotRandoms::official();
+        let text: Vec<String> = randoms
+            .0
+            .iter()
+            .map(|r| format!("U64(0x{r:016X}),"))
+            .collect();
+        assert_eq!(
+            PolyglotRandoms::parse(&text.join("\n")),
+            Ok(randoms.clone())
+        );
+        assert!(randoms.is_official());
+        let mut other = RANDOM64;
+        other[TURN_OFFSET] ^= 1;
+        assert!(!PolyglotRandoms::new(other).is_official());
+
+        assert!(PolyglotRandoms::parse(&text[..780].join(" ")).is_err());
+        assert!(PolyglotRandoms::parse(&format!("{} 0x1", text.join(" "))).is_err());
+        assert!(PolyglotRandoms::parse("0xZZ").is_err());
+    }
+
+    #[test]
+    fn test_published_keys() {
+        // the test positions from the Polyglot book format description, each reached by playing
+        // the moves from the start.
+        let randoms = PolyglotRandoms::official();
+        let mut board = ChessBoard::initialize();
+        assert_eq!(randoms.key(&board), OFFICIAL_START_KEY);
+        for (uci, expected) in [
+            ("e2e4", 0x823C_9B50_FD11_4196),
+            ("d7d5", 0x0756_B944_61C5_0FB0),
+            ("e4e5", 0x662F_AFB9_65DB_29D4),
+            // f6 can be taken en passant, so its file counts.
+            ("f7f5", 0x22A4_8B5A_8E47_FF78),
+            // the king moves lose white's castling rights, then black's.
+            ("e1e2", 0x652A_607C_A3F2_42C1),
+            ("e8f7", 0x00FD_D303_C946_BDD9),
+        ] {
+            let mv = legal(&mut board, uci);
+            board.make_move(mv).unwrap();
+            assert_eq!(randoms.key(&board), expected, "after {uci}");
+        }
+
+        let mut board = ChessBoard::initialize();
+        for (uci, expected) in [
+            ("a2a4", None),
+            ("b7b5", None),
+            ("h2h4", None),
+            ("b5b4", None),
+            // c3 can be taken en passant by the pawn on b4.
+            ("c2c4", Some(0x3C81_23EA_7B06_7637)),
+            // the rook move loses white's long castling.
+            ("b4c3", None),
+            ("a1a3", Some(0x5C3F_9B82_9B27_9560)),
+        ] {
+            let mv = legal(&mut board, uci);
+            board.make_move(mv).unwrap();
+            if let Some(expected) = expected {
+                assert_eq!(randoms.key(&board), expected, "after {uci}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_key() {
+        let randoms = PolyglotRandoms::official();
+        let key = |fen: &str| randoms.key(&ChessBoard::initialize_from_fen(fen).unwrap());
+
+        // transpositions share a key, move order doesn't matter.
+        let mut a = ChessBoard::initialize();
+        let mut b = ChessBoard::initialize();
+        for uci in ["g1f3", "g8f6", "b1c3"] {
+            let mv = legal(&mut a, uci);
+            a.make_move(mv).unwrap();
+        }
+        for uci in ["b1c3", "g8f6", "g1f3"] {
+            let mv = legal(&mut b, uci);
+            b.make_move(mv).unwrap();
+        }
+        assert_eq!(randoms.key(&a), randoms.key(&b));
+        assert_ne!(randoms.key(&a), randoms.key(&ChessBoard::initialize()));
+
+        // side to move and each castling right change the key.
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_ne!(key(start), key(&start.replace(" w ", " b ")));
+        assert_ne!(key(start), key(&start.replace("KQkq", "Qkq")));
+        assert_ne!(
+            key(&start.replace("KQkq", "Kkq")),
+            key(&start.replace("KQkq", "Qkq"))
+        );
+
+        // the en passant file only counts if a pawn can actually take.
+        assert_eq!(
+            key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+        assert_ne!(
+            key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+    }
+
+    #[test]
+    fn test_encode_decode_move() {
+        let mut board = ChessBoard::initialize();
+        let mv = legal(&mut board, "e2e4");
+        // e4 = file 4 rank 3, e2 = file 4 rank 1.
+        assert_eq!(encode_move(&board, mv), 4 | 3 << 3 | 4 << 6 | 1 << 9);
+        let encoded = encode_move(&board, mv);
+        assert_eq!(decode_move(&mut board, encoded), Some(mv));
+        assert_eq!(decode_move(&mut board, 4 | 4 << 3 | 4 << 6 | 1 << 9), None);
+
+        // castling is the king taking its rook.
+        let mut board =
+            ChessBoard::initialize_from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let short = legal(&mut board, "e1g1");
+        let long = legal(&mut board, "e1c1");
+        assert_eq!(encode_move(&board, short), 7 | 4 << 6);
+        assert_eq!(encode_move(&board, long), 4 << 6);
+        assert_eq!(decode_move(&mut board, 7 | 4 << 6), Some(short));
+
+        let mut board = ChessBoard::initialize_from_fen("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let promotion = legal(&mut board, "e7e8n");
+        assert_eq!(
+            encode_move(&board, promotion),
+            4 | 7 << 3 | 4 << 6 | 6 << 9 | 1 << 12
+        );
+    }
+
+    #[test]
+    fn test_build_and_probe() {
+        let randoms = PolyglotRandoms::official();
+        let mut builder = BookBuilder::new(randoms.clone());
+
+        // fool's mate, won by black.
+        let mut game = ChessGame::new((1, 1));
+        for uci in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            game.make_move_from_uci(uci, true, false).unwrap();
+        }
+        assert_eq!(game.result, GameResult::BlackWins(WinReason::Checkmate));
+        builder.add_game(&game, None).unwrap();
+
+        // a drawn game starting 1. e4 and an unfinished one starting 1. f3.
+        let mut game = ChessGame::new((1, 1));
+        game.make_move_from_uci("e2e4", true, false).unwrap();
+        game.result = GameResult::Draw(crate::game::DrawReason::Agreement);
+        builder.add_game(&game, None).unwrap();
+        let mut game = ChessGame::new((1, 1));
+        game.make_move_from_uci("f2f3", true, false).unwrap();
+        builder.add_game(&game, None).unwrap();
+
+        let book = builder.build();
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), book.entries.len() * ENTRY_SIZE);
+        let book = PolyglotBook::from_bytes(&bytes, randoms.clone()).unwrap();
+
+        let mut board = ChessBoard::initialize();
+        let f3 = legal(&mut board, "f2f3");
+        let e4 = legal(&mut board, "e2e4");
+        // 1. f3 was played by the loser once (0) and in an unfinished game (1).
+        assert_eq!(book.moves(&mut board), vec![(e4, 1), (f3, 1)]);
+
+        // black's winning moves weigh 2, white's losing ones are left out.
+        board.make_move(f3).unwrap();
+        let e5 = legal(&mut board, "e7e5");
+        assert_eq!(book.moves(&mut board), vec![(e5, 2)]);
+        board.make_move(e5).unwrap();
+        assert_eq!(book.best_move(&mut board), None);
+
+        // entries past the cut-off aren't added.
+        let mut builder = BookBuilder::new(randoms.clone());
+        builder.add_game(&game, Some(0)).unwrap();
+        assert!(builder.build().entries.is_empty());
+
+        assert!(PolyglotBook::from_bytes(&bytes[1..], randoms.clone()).is_err());
+        let mut unsorted = book.clone();
+        unsorted.entries.reverse();
+        assert!(PolyglotBook::from_bytes(&unsorted.to_bytes(), randoms).is_err());
+    }
+}
@@ -1,15 +1,19 @@
 // todo!("Functions needed before finishing moves.rs and game.rs: is_check, is_checkmate, is_stalemate, is_fifty_move_rule, is_threefold_repetition, etc.");
 
+use crate::bitboard::Bitboard;
+use crate::types::{Color, Move, Piece, PieceKind, Square};
 use crate::{board, moves, utils};
 
 // checks if the player to move is in check.
-pub fn is_check(board: &board::ChessBoard, side_in_check: bool) -> bool {
-    let king_sq: u64;
-    if side_in_check {
-        king_sq = board.kings & board.white_pieces;
+pub fn is_check(board: &board::ChessBoard, side_in_check: Color) -> bool {
+    let king_bb = if side_in_check.is_white() {
+        board.kings & board.white_pieces
     } else {
-        king_sq = board.kings & board.black_pieces;
-    }
+        board.kings & board.black_pieces
+    };
+    let Some(king_sq) = king_bb.lsb() else {
+        return false;
+    };
 
     if moves::square_attacked(!side_in_check, king_sq, board, None, None) {
         return true;
@@ -19,24 +23,24 @@ pub fn is_check(board: &board::ChessBoard, side_in_check: bool) -> bool {
 }
 
 pub fn is_insuf_material(board: &board::ChessBoard) -> bool {
-    let white_material = (board.white_pieces & !board.kings).count_ones();
-    let black_material = (board.black_pieces & !board.kings).count_ones();
+    let white_material = (board.white_pieces & !board.kings).popcount();
+    let black_material = (board.black_pieces & !board.kings).popcount();
 
     if white_material == 0 {
         if black_material == 0 {
             return true;
         } else if black_material == 1 {
-            if board.black_pieces & board.knights != 0 {
+            if board.black_pieces.intersects(board.knights) {
                 return true;
-            } else if board.black_pieces & board.bishops != 0 {
+            } else if board.black_pieces.intersects(board.bishops) {
                 return true;
             }
         }
     } else if black_material == 0 {
         if white_material == 1 {
-            if board.white_pieces & board.knights != 0 {
+            if board.white_pieces.intersects(board.knights) {
                 return true;
-            } else if board.white_pieces & board.bishops != 0 {
+            } else if board.white_pieces.intersects(board.bishops) {
                 return true;
             }
         }
@@ -44,9 +48,9 @@ pub fn is_insuf_material(board: &board::ChessBoard) -> bool {
         if black_material == 1 {
             let white_bishop = board.white_pieces & board.bishops;
             let black_bishop = board.black_pieces & board.bishops;
-            if (white_bishop != 0) && (black_bishop != 0) {
-                return utils::square_color(white_bishop.trailing_zeros() as u8)
-                    == utils::square_color(black_bishop.trailing_zeros() as u8);
+            if let (Some(white_sq), Some(black_sq)) = (white_bishop.lsb(), black_bishop.lsb()) {
+                return utils::square_color(white_sq.index())
+                    == utils::square_color(black_sq.index());
                 // return true if bishops are of same color
             }
         }
@@ -54,25 +58,206 @@ pub fn is_insuf_material(board: &board::ChessBoard) -> bool {
     return false;
 }
 
+// checks if a side has any chance of checkmating, e.g. when the opponent runs out of time.
+// a lone king can never mate, and neither can anyone in a dead position (is_insuf_material).
+pub fn can_checkmate(board: &board::ChessBoard, color: Color) -> bool {
+    let color_mask = if color.is_white() {
+        board.white_pieces
+    } else {
+        board.black_pieces
+    };
+    if (color_mask & !board.kings).is_empty() {
+        return false;
+    }
+    !is_insuf_material(board)
+}
+
+/// How a move checks the opposing king.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckKind {
+    Direct,     // the moved piece gives check
+    Discovered, // a piece it uncovers does
+    Double,     // both
+}
+
+/// What it takes for the side to move to give check, worked out once per position so each
+/// move can be judged without making it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckSquares {
+    pub king: Square,           // the opposing king
+    pub squares: [Bitboard; 6], // by PieceKind: where a piece of ours would give check
+    pub discoverers: Bitboard, // our pieces that are all that stand between a slider of ours and the king
+}
+
+impl CheckSquares {
+    pub fn new(board: &board::ChessBoard) -> Option<Self> {
+        let us = board.side_to_move;
+        let own = board.pieces_of(us);
+        let king = (board.kings & board.pieces_of(!us)).lsb()?;
+        let occupancy = board.white_pieces | board.black_pieces;
+        let sq_i = king.index() as usize;
+
+        let diagonal = moves::bishop_attack_lookup(king, occupancy);
+        let orthogonal = moves::rook_attack_lookup(king, occupancy);
+        let pawn = if us.is_white() {
+            moves::BLACK_PAWN_ATTACKS[sq_i]
+        } else {
+            moves::WHITE_PAWN_ATTACKS[sq_i]
+        };
+        let squares = [
+            pawn,
+            moves::KNIGHT_ATTACKS[sq_i],
+            diagonal,
+            orthogonal,
+            diagonal | orthogonal,
+            Bitboard::EMPTY,
+        ];
+
+        let snipers = ((board.bishops | board.queens)
+            & moves::bishop_attack_lookup(king, Bitboard::EMPTY)
+            | (board.rooks | board.queens) & moves::rook_attack_lookup(king, Bitboard::EMPTY))
+            & own;
+        let mut discoverers = Bitboard::EMPTY;
+        for sniper in snipers {
+            let blockers = moves::BETWEEN[sq_i][sniper.index() as usize] & occupancy;
+            if blockers.is_nonempty() && !blockers.has_many() && blockers.intersects(own) {
+                discoverers |= blockers;
+            }
+        }
+        Some(CheckSquares {
+            king,
+            squares,
+            discoverers,
+        })
+    }
+
+    /// Whether and how a legal move checks the opposing king.
+    pub fn check_kind(&self, board: &board::ChessBoard, mv: Move) -> Option<CheckKind> {
+        if mv.is_castle() || mv.is_en_passant() {
+            // rare enough to just play out: both move or remove two pieces at once.
+            let mut after = board.clone();
+            after.make_move(mv).ok()?;
+            let landing = if mv.is_castle() {
+                board
+                    .castle_squares(board.side_to_move, mv.is_kingside_castle())?
+                    .rook_to
+            } else {
+                mv.to()
+            };
+            let occupancy = after.white_pieces | after.black_pieces;
+            let checkers = moves::attackers_to(&after, self.king, occupancy)
+                & after.pieces_of(board.side_to_move);
+            return match checkers.lsb()? {
+                _ if checkers.has_many() => Some(CheckKind::Double),
+                checker if checker == landing => Some(CheckKind::Direct),
+                _ => Some(CheckKind::Discovered),
+            };
+        }
+
+        let (from, to) = (mv.from(), mv.to());
+        let direct = match mv.promotion() {
+            // the pawn's own square opens up, which can matter for the new piece's line.
+            Some(kind) => {
+                let occupancy = (board.white_pieces | board.black_pieces) - from.bb();
+                let attacks = match kind {
+                    PieceKind::Knight => moves::KNIGHT_ATTACKS[to.index() as usize],
+                    PieceKind::Bishop => moves::bishop_attack_lookup(to, occupancy),
+                    PieceKind::Rook => moves::rook_attack_lookup(to, occupancy),
+                    _ => {
+                        moves::bishop_attack_lookup(to, occupancy)
+                            | moves::rook_attack_lookup(to, occupancy)
+                    }
+                };
+                attacks.contains(self.king)
+            }
+            None => {
+                let kind = board.piece_type_at(from)?;
+                self.squares[kind.index()].contains(to)
+            }
+        };
+        let discovered = self.discoverers.contains(from)
+            && !moves::LINES[self.king.index() as usize][from.index() as usize].contains(to);
+        match (direct, discovered) {
+            (true, true) => Some(CheckKind::Double),
+            (true, false) => Some(CheckKind::Direct),
+            (false, true) => Some(CheckKind::Discovered),
+            (false, false) => None,
+        }
+    }
+}
+
+// whether a legal move puts the opposing king in check.
+pub fn gives_check(board: &board::ChessBoard, mv: Move) -> bool {
+    check_kind(board, mv).is_some()
+}
+
+// how a legal move checks the opposing king, if it does.
+pub fn check_kind(board: &board::ChessBoard, mv: Move) -> Option<CheckKind> {
+    CheckSquares::new(board)?.check_kind(board, mv)
+}
+
+// whether a legal move checkmates.
+pub fn gives_checkmate(board: &mut board::ChessBoard, mv: Move) -> bool {
+    if !gives_check(board, mv) {
+        return false;
+    }
+    let Ok(undo_info) = board.make_move(mv) else {
+        return false;
+    };
+    let mate = !moves::has_legal_moves(board);
+    board
+        .unmake_move(mv, &undo_info)
+        .expect("a made move can always be unmade");
+    mate
+}
+
+// the piece standing on the move's origin square.
+pub fn moved_piece(board: &board::ChessBoard, mv: Move) -> Option<Piece> {
+    board.piece_at(mv.from())
+}
+
+// the kind of piece the move takes, if any. en passant takes a pawn off another square.
+pub fn captured_piece(board: &board::ChessBoard, mv: Move) -> Option<PieceKind> {
+    if mv.is_en_passant() {
+        return Some(PieceKind::Pawn);
+    }
+    if mv.is_castle() {
+        return None;
+    }
+    let victim = board.piece_at(mv.to())?;
+    (victim.color != board.side_to_move).then_some(victim.kind)
+}
+
+// whether the move takes a piece, judged from the board rather than the move's flag.
+pub fn is_capture(board: &board::ChessBoard, mv: Move) -> bool {
+    captured_piece(board, mv).is_some()
+}
+
+// whether the move is a pawn reaching the last rank.
+pub fn is_promotion(board: &board::ChessBoard, mv: Move) -> bool {
+    mv.is_promotion() && board.pawns.contains(mv.from())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::*;
     #[test]
     fn test_is_check() {
         let board1 = board::ChessBoard::initialize_from_fen(
             "rnbqkbnr/pppp1Bpp/8/4pp2/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 0 1",
         )
         .unwrap();
-        assert_eq!(is_check(&board1, false), true);
+        assert_eq!(is_check(&board1, Color::Black), true);
 
         let board2 = board::ChessBoard::initialize();
-        assert_eq!(is_check(&board2, true), false);
+        assert_eq!(is_check(&board2, Color::White), false);
 
         let board3 = board::ChessBoard::initialize_from_fen(
             "rnbqkb1r/pppppppp/8/8/8/5nP1/PPPPPP2/RNBQKBNR w KQkq - 0 1",
         )
         .unwrap();
-        assert_eq!(is_check(&board3, true), true);
+        assert_eq!(is_check(&board3, Color::White), true);
     }
 
     #[test]
@@ -107,4 +292,182 @@ mod tests {
             board::ChessBoard::initialize_from_fen("k7/8/4b3/4B3/8/8/8/K7 w - - 0 1").unwrap(); // K & B vs k & b (diff color)
         assert_eq!(is_insuf_material(&board8), false);
     }
+
+    #[test]
+    fn test_can_checkmate() {
+        let board1 = board::ChessBoard::initialize();
+        assert!(can_checkmate(&board1, Color::White));
+        assert!(can_checkmate(&board1, Color::Black));
+
+        let board2 =
+            board::ChessBoard::initialize_from_fen("k7/8/8/8/8/8/P7/K7 w - - 0 1").unwrap(); // K & P vs k
+        assert!(can_checkmate(&board2, Color::White));
+        assert!(!can_checkmate(&board2, Color::Black));
+
+        let board3 =
+            board::ChessBoard::initialize_from_fen("k7/8/8/8/8/4n3/P7/K7 w - - 0 1").unwrap(); // K & P vs k & n
+        assert!(can_checkmate(&board3, Color::Black));
+
+        let board4 =
+            board::ChessBoard::initialize_from_fen("k7/8/8/8/8/4n3/8/K7 w - - 0 1").unwrap(); // K vs k & n
+        assert!(!can_checkmate(&board4, Color::Black));
+    }
+
+    #[test]
+    fn test_check_kind() {
+        // (fen, move, expected)
+        let cases = [
+            // knight, pawn and slider checks, and a quiet move.
+            ("4k3/8/8/8/8/8/8/4K1N1 w - - 0 1", "g1f3", None),
+            ("4k3/8/8/8/8/8/8/4K1N1 w - - 0 1", "g1e2", None),
+            ("4k3/8/8/8/8/8/4K3/6N1 w - - 0 1", "g1f3", None),
+            (
+                "4k3/8/8/7N/8/8/8/4K3 w - - 0 1",
+                "h5f6",
+                Some(CheckKind::Direct),
+            ),
+            ("4k3/8/8/7N/8/8/8/4K3 w - - 0 1", "h5f4", None),
+            (
+                "4k3/8/3P4/8/8/8/8/4K3 w - - 0 1",
+                "d6d7",
+                Some(CheckKind::Direct),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+                "a1a8",
+                Some(CheckKind::Direct),
+            ),
+            (
+                "4k3/8/8/8/2B5/8/8/4K3 w - - 0 1",
+                "c4b5",
+                Some(CheckKind::Direct),
+            ),
+            ("4k3/8/8/8/2B5/8/8/4K3 w - - 0 1", "c4b3", None),
+            // the knight uncovers the bishop, and checks itself too.
+            (
+                "4k3/8/8/1N6/B7/8/8/4K3 w - - 0 1",
+                "b5c3",
+                Some(CheckKind::Discovered),
+            ),
+            (
+                "4k3/8/8/1N6/B7/8/8/4K3 w - - 0 1",
+                "b5c7",
+                Some(CheckKind::Double),
+            ),
+            (
+                "4k3/8/8/3N4/8/B7/8/4K3 w - - 0 1",
+                "d5c7",
+                Some(CheckKind::Direct),
+            ),
+            (
+                "4k3/8/8/8/4N3/8/8/4RK2 w - - 0 1",
+                "e4d6",
+                Some(CheckKind::Double),
+            ),
+            (
+                "4k3/8/8/8/4N3/8/8/4RK2 w - - 0 1",
+                "e4c5",
+                Some(CheckKind::Discovered),
+            ),
+            // moving along the line it blocks uncovers nothing.
+            ("4k3/8/8/8/4P3/8/8/4RK2 w - - 0 1", "e4e5", None),
+            // a promotion checking down the file the pawn just left.
+            (
+                "8/1P6/8/8/8/8/8/1k2K3 w - - 0 1",
+                "b7b8q",
+                Some(CheckKind::Direct),
+            ),
+            ("8/1P6/8/8/8/8/8/1k2K3 w - - 0 1", "b7b8n", None),
+            // en passant opening the rank, and castling with the rook checking.
+            (
+                "8/8/8/k1pP3R/8/8/8/4K3 w - c6 0 1",
+                "d5c6",
+                Some(CheckKind::Discovered),
+            ),
+            (
+                "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+                "e1g1",
+                Some(CheckKind::Direct),
+            ),
+            ("3k4/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", None),
+        ];
+        for (fen, uci, expected) in cases {
+            let mut board = board::ChessBoard::initialize_from_fen(fen).unwrap();
+            let mv = moves::get_legal_moves(&mut board)
+                .into_iter()
+                .find(|mv| mv.to_string() == uci)
+                .unwrap_or_else(|| panic!("{uci} is not legal in {fen}"));
+            assert_eq!(check_kind(&board, mv), expected, "{uci} in {fen}");
+        }
+
+        // gives_check agrees with making the move, two plies deep.
+        fn compare(board: &mut board::ChessBoard, depth: u32) {
+            for mv in moves::get_legal_moves(board) {
+                let expected = {
+                    let undo_info = board.make_move(mv).unwrap();
+                    let check = is_check(board, board.side_to_move);
+                    board.unmake_move(mv, &undo_info).unwrap();
+                    check
+                };
+                assert_eq!(
+                    gives_check(board, mv),
+                    expected,
+                    "{mv} in {}",
+                    board.to_fen()
+                );
+                if depth > 0 {
+                    let undo_info = board.make_move(mv).unwrap();
+                    compare(board, depth - 1);
+                    board.unmake_move(mv, &undo_info).unwrap();
+                }
+            }
+        }
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            compare(&mut board::ChessBoard::initialize_from_fen(fen).unwrap(), 1);
+        }
+    }
+
+    #[test]
+    fn test_move_queries() {
+        let mut board1 =
+            board::ChessBoard::initialize_from_fen("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1")
+                .unwrap();
+        let mate = utils::encode_from_uci("d1d8").unwrap();
+        let mv = moves::get_legal_moves(&mut board1)
+            .into_iter()
+            .find(|mv| mv.from() == mate.from() && mv.to() == mate.to())
+            .unwrap();
+        assert!(gives_checkmate(&mut board1, mv));
+        let check = utils::encode_move(3, 11, 0); // Rd2, no check at all
+        assert!(!gives_checkmate(&mut board1, check));
+        let board3 =
+            board::ChessBoard::initialize_from_fen("6k1/5pp1/8/8/8/8/5PPP/3R2K1 w - - 0 1")
+                .unwrap();
+        assert!(gives_check(&board3, mv));
+        assert!(!gives_checkmate(&mut board3.clone(), mv)); // the king escapes to h7
+
+        let board2 =
+            board::ChessBoard::initialize_from_fen("4k2r/8/8/8/1pP5/8/1p6/R3K3 b k c3 0 1")
+                .unwrap();
+        let bxc3 = utils::encode_move(25, 18, 3); // b4xc3 en passant
+        assert_eq!(captured_piece(&board2, bxc3), Some(PieceKind::Pawn));
+        assert!(is_capture(&board2, bxc3));
+        assert_eq!(
+            moved_piece(&board2, bxc3),
+            Some(Piece::new(Color::Black, PieceKind::Pawn))
+        );
+        let bxa1q = utils::encode_move(9, 0, 11); // b2xa1=Q
+        assert!(is_promotion(&board2, bxa1q));
+        assert_eq!(captured_piece(&board2, bxa1q), Some(PieceKind::Rook));
+        let quiet = utils::encode_move(60, 59, 0); // Kd8
+        assert!(!is_capture(&board2, quiet));
+        assert!(!is_promotion(&board2, quiet));
+        assert_eq!(moved_piece(&board2, utils::encode_move(20, 28, 0)), None); // nothing on e3
+        assert_eq!(captured_piece(&board2, utils::encode_move(60, 62, 2)), None);
+        // O-O
+    }
 }
@@ -0,0 +1,411 @@
+// this file converts moves to and from Standard Algebraic Notation (SAN).
+
+use crate::board::ChessBoard;
+use crate::error::ChessError;
+use crate::moves::{get_legal_moves, has_legal_moves};
+use crate::rules;
+use crate::types::{Move, PieceKind, Square};
+
+/// Produces minimally disambiguated SAN (e.g. "Nbd7", "exf6", "O-O-O", "e8=Q+", "Qxf7#")
+/// for a legal move in the given position.
+pub fn move_to_san(board: &mut ChessBoard, movei: Move) -> Result<String, ChessError> {
+    let legal_moves = get_legal_moves(board);
+    if !legal_moves.contains(&movei) {
+        return Err(ChessError::IllegalMove(movei.to_string()));
+    }
+
+    let (from, to) = (movei.from(), movei.to());
+    let mut san = String::new();
+
+    if movei.is_castle() {
+        san.push_str(if movei.is_kingside_castle() {
+            "O-O"
+        } else {
+            "O-O-O"
+        });
+    } else {
+        let piece_type = board.piece_type_at(from).unwrap(); // the move is legal.
+        let file_char = |sq: Square| (b'a' + sq.file()) as char;
+        let rank_char = |sq: Square| (b'1' + sq.rank()) as char;
+
+        if piece_type == PieceKind::Pawn {
+            if movei.is_capture() {
+                san.push(file_char(from));
+            }
+        } else {
+            san.push(piece_type.letter());
+            // disambiguate from other pieces of the same type that can reach the same square:
+            // by file if that is enough, else by rank, else by both.
+            let others: Vec<Square> = legal_moves
+                .iter()
+                .filter(|m| {
+                    m.to() == to
+                        && m.from() != from
+                        && board.piece_type_at(m.from()) == Some(piece_type)
+                })
+                .map(|m| m.from())
+                .collect();
+            if !others.is_empty() {
+                let file_unique = others.iter().all(|f| f.file() != from.file());
+                let rank_unique = others.iter().all(|f| f.rank() != from.rank());
+                if file_unique {
+                    san.push(file_char(from));
+                } else if rank_unique {
+                    san.push(rank_char(from));
+                } else {
+                    san.push_str(&from.to_string());
+                }
+            }
+        }
+        if movei.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&to.to_string());
+        if let Some(promoted) = movei.promotion() {
+            san.push('=');
+            san.push(promoted.letter());
+        }
+    }
+
+    // check and checkmate suffixes.
+    let undo_info = board.make_move(movei)?;
+    if rules::is_check(board, board.side_to_move) {
+        san.push(if has_legal_moves(board) { '+' } else { '#' });
+    }
+    board.unmake_move(movei, &undo_info)?;
+
+    Ok(san)
+}
+
+/// Same as move_to_san(), but marks en passant captures with " e.p." (e.g. "exf6 e.p.").
+pub fn move_to_san_ep(board: &mut ChessBoard, movei: Move) -> Result<String, ChessError> {
+    let mut san = move_to_san(board, movei)?;
+    if movei.is_en_passant() {
+        san.push_str(" e.p.");
+    }
+    Ok(san)
+}
+
+/// Converts a sequence of moves played from the given position (e.g. a principal variation)
+/// to SAN. The board is left unchanged.
+pub fn line_to_san(board: &mut ChessBoard, line: &[Move]) -> Result<Vec<String>, ChessError> {
+    let mut sans = Vec::with_capacity(line.len());
+    let mut undo_infos = Vec::with_capacity(line.len());
+    let mut result = Ok(());
+
+    for movei in line {
+        match move_to_san(board, *movei) {
+            Ok(san) => sans.push(san),
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+        undo_infos.push((*movei, board.make_move(*movei)?));
+    }
+    for (movei, undo_info) in undo_infos.iter().rev() {
+        board.unmake_move(*movei, undo_info)?;
+    }
+
+    result.map(|_| sans)
+}
+
+/// Resolves a SAN move against the legal moves of the given position.
+/// Accepts check/mate suffixes, annotation suffixes ("!?"), "e.p.", "0-0" style castling and
+/// promotions written without '=' ("e8Q").
+pub fn san_to_move(board: &mut ChessBoard, san: &str) -> Result<Move, ChessError> {
+    let trimmed = san.trim();
+    let trimmed = trimmed
+        .strip_suffix("e.p.")
+        .or_else(|| trimmed.strip_suffix("ep"))
+        .unwrap_or(trimmed)
+        .trim_end();
+    let trimmed = trimmed.trim_end_matches(['+', '#', '!', '?']);
+    let legal_moves = get_legal_moves(board);
+
+    if ["O-O", "0-0", "O-O-O", "0-0-0"].contains(&trimmed) {
+        let kingside = trimmed.len() == 3;
+        return legal_moves
+            .iter()
+            .find(|m| m.is_castle() && m.is_kingside_castle() == kingside)
+            .copied()
+            .ok_or(ChessError::IllegalMove(format!(
+                "{san} (castling is not legal here)"
+            )));
+    }
+
+    // split off the promotion piece, then the target square.
+    let (body, promotion) = match trimmed.split_once('=') {
+        Some((body, piece)) => (body, Some(piece)),
+        None => match trimmed.chars().last() {
+            Some(c) if "NBRQ".contains(c) && trimmed.len() > 2 => (
+                &trimmed[..trimmed.len() - 1],
+                Some(&trimmed[trimmed.len() - 1..]),
+            ),
+            _ => (trimmed, None),
+        },
+    };
+    let wanted_promotion = match promotion {
+        // knight, bishop, rook or queen, uppercase.
+        Some(piece) => match PieceKind::ALL[1..5]
+            .iter()
+            .find(|kind| kind.letter().to_string() == piece)
+        {
+            Some(kind) => Some(*kind),
+            None => return Err(ChessError::InvalidPromotion(san.to_string())),
+        },
+        None => None,
+    };
+    if body.len() < 2 || !body.is_char_boundary(body.len() - 2) {
+        return Err(invalid_san(san, "no target square"));
+    }
+    let to: Square = body[body.len() - 2..]
+        .parse()
+        .map_err(|_| invalid_san(san, "bad target square"))?;
+    let mut prefix = &body[..body.len() - 2];
+
+    let piece_type = match prefix.chars().next() {
+        Some(c) if c.is_ascii_uppercase() => {
+            prefix = &prefix[1..];
+            match PieceKind::ALL.into_iter().find(|kind| kind.letter() == c) {
+                Some(kind) => kind,
+                None => return Err(invalid_san(san, &format!("unknown piece '{c}'"))),
+            }
+        }
+        _ => PieceKind::Pawn,
+    };
+    let prefix = prefix.strip_suffix(['x', ':']).unwrap_or(prefix);
+    let mut from_file: Option<u8> = None;
+    let mut from_rank: Option<u8> = None;
+    for c in prefix.chars() {
+        match c {
+            'a'..='h' if from_file.is_none() && from_rank.is_none() => {
+                from_file = Some(c as u8 - b'a')
+            }
+            '1'..='8' if from_rank.is_none() => from_rank = Some(c as u8 - b'1'),
+            _ => return Err(invalid_san(san, "bad disambiguation")),
+        }
+    }
+
+    let candidates: Vec<Move> = legal_moves
+        .iter()
+        .copied()
+        .filter(|m| {
+            m.to() == to
+                && !m.is_castle()
+                && board.piece_type_at(m.from()) == Some(piece_type)
+                && m.promotion() == wanted_promotion
+                && from_file.is_none_or(|file| m.from().file() == file)
+                && from_rank.is_none_or(|rank| m.from().rank() == rank)
+        })
+        .collect();
+
+    match candidates.len() {
+        0 => {
+            if piece_type == PieceKind::Pawn
+                && wanted_promotion.is_none()
+                && (to.rank() == 0 || to.rank() == 7)
+            {
+                Err(ChessError::InvalidPromotion(format!(
+                    "{san} (promotion piece missing)"
+                )))
+            } else {
+                Err(ChessError::IllegalMove(san.to_string()))
+            }
+        }
+        1 => Ok(candidates[0]),
+        _ => {
+            let options: Vec<String> = candidates
+                .iter()
+                .map(|m| move_to_san(board, *m).unwrap()) // candidates are legal moves.
+                .collect();
+            Err(ChessError::AmbiguousMove {
+                san: san.to_string(),
+                candidates: options,
+            })
+        }
+    }
+}
+
+fn invalid_san(san: &str, reason: &str) -> ChessError {
+    ChessError::InvalidSan {
+        san: san.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::encode_move;
+
+    #[test]
+    fn test_move_to_san() {
+        let mut board = ChessBoard::initialize();
+        assert_eq!(
+            move_to_san(&mut board, encode_move(12, 28, 0)).unwrap(),
+            "e4"
+        );
+        assert_eq!(
+            move_to_san(&mut board, encode_move(6, 21, 0)).unwrap(),
+            "Nf3"
+        );
+        assert!(move_to_san(&mut board, encode_move(12, 36, 0)).is_err());
+
+        // disambiguation by file, by rank, and by both.
+        let mut board =
+            ChessBoard::initialize_from_fen("k7/8/8/1N3N2/8/1N6/8/K7 w - - 0 1").unwrap();
+        assert_eq!(
+            move_to_san(&mut board, encode_move(33, 43, 0)).unwrap(),
+            "Nbd6"
+        );
+        assert_eq!(
+            move_to_san(&mut board, encode_move(37, 43, 0)).unwrap(),
+            "Nfd6"
+        );
+        assert_eq!(
+            move_to_san(&mut board, encode_move(37, 27, 0)).unwrap(),
+            "Nfd4"
+        );
+        assert_eq!(
+            move_to_san(&mut board, encode_move(17, 27, 0)).unwrap(),
+            "N3d4"
+        );
+        assert_eq!(
+            move_to_san(&mut board, encode_move(33, 27, 0)).unwrap(),
+            "Nb5d4"
+        );
+        let mut board =
+            ChessBoard::initialize_from_fen("2k5/8/8/8/4Q2Q/8/8/K3Q3 w - - 0 1").unwrap();
+        assert_eq!(
+            move_to_san(&mut board, encode_move(28, 7, 0)).unwrap(),
+            "Qe4h1"
+        );
+
+        // captures, en passant, castling, promotion, checkmate.
+        let mut board =
+            ChessBoard::initialize_from_fen("r2k2r1/ppp2P1p/8/3pP3/8/8/PPP2PPP/R3K2R w KQ d6 0 1")
+                .unwrap();
+        assert_eq!(
+            move_to_san(&mut board, encode_move(36, 43, 3)).unwrap(),
+            "exd6"
+        );
+        assert_eq!(
+            move_to_san_ep(&mut board, encode_move(36, 43, 3)).unwrap(),
+            "exd6 e.p."
+        );
+        assert_eq!(
+            move_to_san(&mut board, encode_move(4, 6, 2)).unwrap(),
+            "O-O"
+        );
+        assert_eq!(
+            move_to_san(&mut board, encode_move(4, 2, 2)).unwrap(),
+            "O-O-O"
+        );
+        assert_eq!(
+            move_to_san(&mut board, encode_move(53, 62, 11)).unwrap(),
+            "fxg8=Q+"
+        );
+        assert_eq!(
+            move_to_san(&mut board, encode_move(53, 61, 6)).unwrap(),
+            "f8=R+"
+        );
+
+        let mut board = ChessBoard::initialize_from_fen(
+            "r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 0 1",
+        )
+        .unwrap();
+        assert_eq!(
+            move_to_san(&mut board, encode_move(39, 53, 1)).unwrap(),
+            "Qxf7#"
+        );
+    }
+
+    #[test]
+    fn test_san_to_move() {
+        let mut board = ChessBoard::initialize();
+        assert_eq!(san_to_move(&mut board, "e4"), Ok(encode_move(12, 28, 0)));
+        assert_eq!(san_to_move(&mut board, "Nf3!?"), Ok(encode_move(6, 21, 0)));
+        assert_eq!(san_to_move(&mut board, "Ng1f3"), Ok(encode_move(6, 21, 0)));
+        assert_eq!(
+            san_to_move(&mut board, "e5"),
+            Err(ChessError::IllegalMove("e5".to_string()))
+        );
+        assert!(matches!(
+            san_to_move(&mut board, "Zf3"),
+            Err(ChessError::InvalidSan { .. })
+        ));
+        assert!(matches!(
+            san_to_move(&mut board, "x"),
+            Err(ChessError::InvalidSan { .. })
+        ));
+
+        let mut board =
+            ChessBoard::initialize_from_fen("k7/8/8/1N3N2/8/1N6/8/K7 w - - 0 1").unwrap();
+        assert_eq!(
+            san_to_move(&mut board, "Nd6"),
+            Err(ChessError::AmbiguousMove {
+                san: "Nd6".to_string(),
+                candidates: vec!["Nbd6".to_string(), "Nfd6".to_string()]
+            })
+        );
+        assert_eq!(san_to_move(&mut board, "Nbd6"), Ok(encode_move(33, 43, 0)));
+        assert!(matches!(
+            san_to_move(&mut board, "Nbd4"),
+            Err(ChessError::AmbiguousMove { .. })
+        ));
+        assert_eq!(san_to_move(&mut board, "N3d4"), Ok(encode_move(17, 27, 0)));
+        assert_eq!(san_to_move(&mut board, "Nb3d4"), Ok(encode_move(17, 27, 0)));
+
+        let mut board =
+            ChessBoard::initialize_from_fen("r2k2r1/ppp2P1p/8/3pP3/8/8/PPP2PPP/R3K2R w KQ d6 0 1")
+                .unwrap();
+        assert_eq!(
+            san_to_move(&mut board, "exd6 e.p."),
+            Ok(encode_move(36, 43, 3))
+        );
+        assert_eq!(san_to_move(&mut board, "exd6"), Ok(encode_move(36, 43, 3)));
+        assert_eq!(san_to_move(&mut board, "O-O"), Ok(encode_move(4, 6, 2)));
+        assert_eq!(san_to_move(&mut board, "0-0-0"), Ok(encode_move(4, 2, 2)));
+        assert_eq!(
+            san_to_move(&mut board, "fxg8=Q+"),
+            Ok(encode_move(53, 62, 11))
+        );
+        assert_eq!(san_to_move(&mut board, "fxg8N"), Ok(encode_move(53, 62, 8)));
+        assert_eq!(san_to_move(&mut board, "f8=R"), Ok(encode_move(53, 61, 6)));
+        assert_eq!(
+            san_to_move(&mut board, "f8"),
+            Err(ChessError::InvalidPromotion(
+                "f8 (promotion piece missing)".to_string()
+            ))
+        );
+        assert_eq!(
+            san_to_move(&mut board, "f8=K"),
+            Err(ChessError::InvalidPromotion("f8=K".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_line_to_san() {
+        let mut board = ChessBoard::initialize();
+        let board_copy = board.clone();
+        let line = [
+            encode_move(13, 21, 0),
+            encode_move(52, 36, 0),
+            encode_move(14, 30, 0),
+            encode_move(59, 31, 0),
+        ];
+        assert_eq!(
+            line_to_san(&mut board, &line).unwrap(),
+            vec!["f3", "e5", "g4", "Qh4#"]
+        );
+        assert_eq!(board, board_copy);
+
+        assert!(line_to_san(
+            &mut board,
+            &[encode_move(13, 21, 0), encode_move(13, 21, 0)]
+        )
+        .is_err());
+        assert_eq!(board, board_copy);
+    }
+}
@@ -0,0 +1,497 @@
+// this file picks moves: negamax alpha-beta with iterative deepening and quiescence search.
+
+use crate::board::ChessBoard;
+use crate::eval::{Evaluator, TaperedEvaluator};
+use crate::movepick::MovePicker;
+use crate::moves::{get_legal_moves, GenType};
+use crate::rules;
+use crate::tt::{Bound, SharedTranspositionTable};
+use crate::types::Move;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Deepest line (in plies) the search will follow, quiescence included.
+pub const MAX_PLY: usize = 64;
+
+/// Internal scores are centipawns, with mates scored MATE minus the plies to the mate.
+pub const MATE: i32 = 32_000;
+/// Scores at least this far from zero are mates.
+pub const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+const INFINITY: i32 = MATE + 1;
+
+// time and the stop flag are only checked every this many nodes.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// A search score from the side to move's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    Mate(i32), // in moves, negative when the side to move is getting mated
+}
+
+impl Score {
+    fn from_internal(score: i32) -> Self {
+        if score.abs() < MATE_BOUND {
+            return Score::Centipawns(score);
+        }
+        let moves = (MATE - score.abs() + 1) / 2;
+        Score::Mate(if score > 0 { moves } else { -moves })
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Score::Centipawns(cp) => write!(f, "cp {cp}"),
+            Score::Mate(moves) => write!(f, "mate {moves}"),
+        }
+    }
+}
+
+/// When to stop searching. Unset limits don't apply; with none set the search runs to
+/// MAX_PLY or until it is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        SearchLimits {
+            nodes: Some(nodes),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        SearchLimits {
+            time: Some(time),
+            ..SearchLimits::default()
+        }
+    }
+}
+
+/// The outcome of the deepest completed iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: Option<Move>, // None if the side to move has no legal move
+    pub score: Score,
+    pub pv: Vec<Move>,
+    pub depth: u32,
+    pub nodes: u64, // over all iterations
+    pub elapsed: Duration,
+}
+
+/// Searches the position within the limits with the default evaluation, and returns the best
+/// move found.
+pub fn search(board: &mut ChessBoard, limits: &SearchLimits) -> SearchResult {
+    search_with(
+        board,
+        limits,
+        &TaperedEvaluator::default(),
+        None,
+        &AtomicBool::new(false),
+        |_| {},
+    )
+}
+
+/// Like `search`, but scores positions with `evaluator`, reuses results through `tt` if given
+/// (starting a new search generation in it, so earlier searches' entries are replaced first),
+/// also stops once `stop` is set (e.g. from another thread), and calls `on_iteration` with the
+/// result of each completed depth.
+pub fn search_with(
+    board: &mut ChessBoard,
+    limits: &SearchLimits,
+    evaluator: &dyn Evaluator,
+    tt: Option<&SharedTranspositionTable>,
+    stop: &AtomicBool,
+    mut on_iteration: impl FnMut(&SearchResult),
+) -> SearchResult {
+    if let Some(tt) = tt {
+        tt.new_search();
+    }
+    let mut searcher = Searcher {
+        board,
+        limits: *limits,
+        evaluator,
+        tt,
+        stop,
+        start: Instant::now(),
+        nodes: 0,
+        stopped: false,
+        path: Vec::with_capacity(MAX_PLY),
+        pv: [[Move::from_raw(0); MAX_PLY]; MAX_PLY],
+        pv_len: [0; MAX_PLY],
+        prev_pv: Vec::new(),
+        follow_pv: false,
+        killers: [[None; 2]; MAX_PLY],
+    };
+
+    // until depth 1 completes, fall back to any legal move.
+    let root_moves = get_legal_moves(searcher.board);
+    let root_score = if root_moves.is_empty() {
+        searcher.no_moves_score(0)
+    } else {
+        0
+    };
+    let mut result = SearchResult {
+        best_move: root_moves.first().copied(),
+        score: Score::from_internal(root_score),
+        pv: root_moves.first().copied().into_iter().collect(),
+        depth: 0,
+        nodes: 0,
+        elapsed: Duration::ZERO,
+    };
+
+    let max_depth = limits.depth.unwrap_or(u32::MAX).min(MAX_PLY as u32 - 1);
+    if !root_moves.is_empty() {
+        for depth in 1..=max_depth {
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+            searcher.follow_pv = true;
+            let score = searcher.negamax(depth, 0, -INFINITY, INFINITY);
+            if searcher.stopped {
+                break;
+            }
+            searcher.prev_pv = searcher.pv[0][..searcher.pv_len[0]].to_vec();
+            result = SearchResult {
+                best_move: searcher.prev_pv.first().copied(),
+                score: Score::from_internal(score),
+                pv: searcher.prev_pv.clone(),
+                depth,
+                nodes: searcher.nodes,
+                elapsed: searcher.start.elapsed(),
+            };
+            on_iteration(&result);
+            // a mate this close can't be improved on by searching deeper.
+            if score.abs() >= MATE_BOUND && MATE - score.abs() <= depth as i32 {
+                break;
+            }
+        }
+    }
+    result.nodes = searcher.nodes;
+    result.elapsed = searcher.start.elapsed();
+    result
+}
+
+struct Searcher<'a> {
+    board: &'a mut ChessBoard,
+    limits: SearchLimits,
+    evaluator: &'a dyn Evaluator,
+    tt: Option<&'a SharedTranspositionTable>,
+    stop: &'a AtomicBool,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+    path: Vec<u64>, // hashes of the positions before the current one, for repetitions
+    pv: [[Move; MAX_PLY]; MAX_PLY], // triangular pv table, row = ply
+    pv_len: [usize; MAX_PLY],
+    prev_pv: Vec<Move>, // pv of the last completed iteration, searched first
+    follow_pv: bool,    // the current node lies on prev_pv
+    killers: [[Option<Move>; 2]; MAX_PLY], // quiet moves that caused a cutoff, by ply
+}
+
+impl Searcher<'_> {
+    // counts a node and decides whether the search has to stop.
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        if self.limits.nodes.is_some_and(|limit| self.nodes >= limit) {
+            self.stopped = true;
+        }
+        if self.nodes.is_multiple_of(CHECK_INTERVAL)
+            && (self.stop.load(Ordering::Relaxed)
+                || self
+                    .limits
+                    .time
+                    .is_some_and(|time| self.start.elapsed() >= time))
+        {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    // checkmated or stalemated at the given ply.
+    fn no_moves_score(&self, ply: usize) -> i32 {
+        if rules::is_check(self.board, self.board.side_to_move) {
+            -MATE + ply as i32
+        } else {
+            0
+        }
+    }
+
+    fn is_draw(&self) -> bool {
+        self.board.halfmove_clock >= 100 || self.path.contains(&self.board.zobrist_hash)
+    }
+
+    fn negamax(&mut self, depth: u32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_len[ply] = 0;
+        if ply > 0 && self.is_draw() {
+            return 0;
+        }
+        if depth == 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(ply, alpha, beta);
+        }
+        if self.visit() {
+            return 0;
+        }
+
+        let hash = self.board.zobrist_hash;
+        let tt_entry = self.tt.and_then(|tt| tt.probe(hash, ply));
+        if let Some(entry) = tt_entry.filter(|entry| ply > 0 && entry.depth as u32 >= depth) {
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if usable {
+                return entry.score;
+            }
+        }
+
+        // on the last iteration's pv its move comes first, elsewhere the table's move.
+        let pv_move = if self.follow_pv {
+            self.prev_pv.get(ply).copied()
+        } else {
+            None
+        };
+        let hash_move = pv_move.or(tt_entry.and_then(|entry| entry.mv));
+        let mut picker = MovePicker::new(self.board, hash_move, self.killers[ply]);
+
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        while let Some(mv) = picker.next(self.board) {
+            // only the pv move's subtree stays on the pv.
+            self.follow_pv = pv_move == Some(mv);
+            let score = self.search_child(mv, |searcher| {
+                -searcher.negamax(depth - 1, ply + 1, -beta, -alpha)
+            });
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+                best_move = Some(mv);
+            }
+            if score > alpha {
+                alpha = score;
+                self.update_pv(ply, mv);
+                if alpha >= beta {
+                    if !GenType::Captures.includes(mv) {
+                        self.store_killer(ply, mv);
+                    }
+                    break;
+                }
+            }
+        }
+        if best_move.is_none() {
+            return self.no_moves_score(ply);
+        }
+
+        if let Some(tt) = self.tt {
+            let bound = if best <= original_alpha {
+                Bound::Upper
+            } else if best >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            tt.store(hash, ply, best_move, best, depth as u8, bound);
+        }
+        best
+    }
+
+    // searches captures and promotions until the position is quiet, so the static evaluation
+    // is never taken in the middle of an exchange.
+    fn quiescence(&mut self, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_len[ply] = 0;
+        if self.visit() {
+            return 0;
+        }
+        // in check there is no standing pat: every evasion is searched, and none means mate.
+        let in_check = rules::is_check(self.board, self.board.side_to_move);
+        let (mut picker, mut best) = if in_check && ply < MAX_PLY - 1 {
+            (MovePicker::new(self.board, None, [None; 2]), -INFINITY)
+        } else {
+            let stand_pat = self.evaluator.evaluate(self.board);
+            if stand_pat >= beta || ply >= MAX_PLY - 1 {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            (MovePicker::captures(self.board), stand_pat)
+        };
+
+        while let Some(mv) = picker.next(self.board) {
+            let score =
+                self.search_child(mv, |searcher| -searcher.quiescence(ply + 1, -beta, -alpha));
+            if self.stopped {
+                return 0;
+            }
+            if score > best {
+                best = score;
+            }
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        if best == -INFINITY {
+            return self.no_moves_score(ply);
+        }
+        best
+    }
+
+    fn search_child(&mut self, mv: Move, search: impl FnOnce(&mut Self) -> i32) -> i32 {
+        self.path.push(self.board.zobrist_hash);
+        let undo_info = self
+            .board
+            .make_move(mv)
+            .expect("legal moves can always be made");
+        let score = search(self);
+        self.board
+            .unmake_move(mv, &undo_info)
+            .expect("a made move can always be unmade");
+        self.path.pop();
+        score
+    }
+
+    fn update_pv(&mut self, ply: usize, mv: Move) {
+        let child_len = if ply + 1 < MAX_PLY {
+            self.pv_len[ply + 1]
+        } else {
+            0
+        };
+        self.pv[ply][0] = mv;
+        for i in 0..child_len {
+            self.pv[ply][i + 1] = self.pv[ply + 1][i];
+        }
+        self.pv_len[ply] = child_len + 1;
+    }
+
+    // keeps the two most recent killers at a ply, newest first.
+    fn store_killer(&mut self, ply: usize, mv: Move) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> ChessBoard {
+        ChessBoard::initialize_from_fen(fen).unwrap()
+    }
+
+    fn uci(mv: Option<Move>) -> String {
+        mv.map(|m| m.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_search_mates() {
+        // back rank mate in 1.
+        let mut board1 = board("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1");
+        let result = search(&mut board1, &SearchLimits::depth(3));
+        assert_eq!(uci(result.best_move), "d1d8");
+        assert_eq!(result.score, Score::Mate(1));
+        // quiescence searches the evasions when in check, so the mate is proven at depth 1 and
+        // the search stops there.
+        assert_eq!(result.depth, 1);
+
+        // rook ladder, mate in 2: Rb7 then Ra8#.
+        let mut board2 = board("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1");
+        let fen = board2.to_fen();
+        let result = search(&mut board2, &SearchLimits::depth(5));
+        assert_eq!(result.score, Score::Mate(2));
+        assert_eq!(result.pv.len(), 3);
+        assert_eq!(uci(result.pv.last().copied()), "a2a8");
+        assert_eq!(board2.to_fen(), fen);
+
+        // and the defender sees it coming.
+        let mut board3 = board("7k/1R6/8/8/8/8/R7/6K1 b - - 0 1");
+        let result = search(&mut board3, &SearchLimits::depth(3));
+        assert_eq!(result.score, Score::Mate(-1));
+    }
+
+    #[test]
+    fn test_search_no_moves() {
+        let mut checkmated = board("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        let result = search(&mut checkmated, &SearchLimits::depth(3));
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, Score::Mate(0));
+        assert!(result.pv.is_empty());
+
+        let mut stalemated = board("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        let result = search(&mut stalemated, &SearchLimits::depth(3));
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, Score::Centipawns(0));
+    }
+
+    #[test]
+    fn test_search_material() {
+        // the rook takes the queen, and quiescence sees that the queen can't take back safely.
+        let mut board1 = board("4k3/8/8/3q4/8/8/3R4/3RK3 w - - 0 1");
+        let result = search(&mut board1, &SearchLimits::depth(2));
+        assert_eq!(uci(result.best_move), "d2d5");
+        assert!(matches!(result.score, Score::Centipawns(cp) if cp > 500));
+
+        // don't grab a pawn defended by a pawn with the queen.
+        let mut board2 = board("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1");
+        let result = search(&mut board2, &SearchLimits::depth(1));
+        assert_ne!(uci(result.best_move), "d2d5");
+    }
+
+    #[test]
+    fn test_search_limits() {
+        let mut board1 = ChessBoard::initialize();
+        let result = search(&mut board1, &SearchLimits::nodes(2000));
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 2000);
+        assert_eq!(board1, ChessBoard::initialize());
+
+        let mut iterations = Vec::new();
+        let result = search_with(
+            &mut board1,
+            &SearchLimits::depth(3),
+            &TaperedEvaluator::default(),
+            None,
+            &AtomicBool::new(false),
+            |iteration| iterations.push(iteration.depth),
+        );
+        assert_eq!(iterations, [1, 2, 3]);
+        assert_eq!(result.depth, 3);
+        assert_eq!(result.pv.len(), 3);
+
+        // stopped before depth 1 completes: still a legal move.
+        let result = search_with(
+            &mut board1,
+            &SearchLimits::default(),
+            &TaperedEvaluator::default(),
+            None,
+            &AtomicBool::new(true),
+            |_| {},
+        );
+        assert!(result.best_move.is_some());
+        assert_eq!(result.depth, 0);
+
+        let result = search(&mut board1, &SearchLimits::time(Duration::from_millis(50)));
+        assert!(result.elapsed < Duration::from_secs(2));
+        assert!(result.best_move.is_some());
+    }
+}
@@ -0,0 +1,156 @@
+// this file works out static exchange evaluation (SEE): what a move wins or loses in material
+// if both sides keep recapturing on its target square with their least valuable piece, and
+// either side may stop once recapturing would lose more.
+//
+// pins and checks are ignored, except that a king never recaptures onto a square the other
+// side still attacks. sliders lined up behind a capturer join in as it leaves (x-rays).
+
+use crate::board::ChessBoard;
+use crate::eval::PIECE_VALUES;
+use crate::moves::attackers_to;
+use crate::types::{Move, PieceKind};
+
+/// The material balance (in `PIECE_VALUES` centipawns) for the side making the move after the
+/// exchange it starts on the target square. A quiet move scores 0, or less if the piece is
+/// simply lost; castling always scores 0.
+pub fn see(board: &ChessBoard, mv: Move) -> i32 {
+    if mv.is_castle() {
+        return 0;
+    }
+    let from = mv.from();
+    let to = mv.to();
+    let Some(mut on_square) = board.piece_type_at(from) else {
+        return 0;
+    };
+
+    let mut occupancy = (board.white_pieces | board.black_pieces) - from.bb();
+    let mut gain = [0i32; 32];
+    if mv.is_en_passant() {
+        let captured = if board.side_to_move.is_white() {
+            to.bb().south()
+        } else {
+            to.bb().north()
+        };
+        occupancy -= captured;
+        gain[0] = PIECE_VALUES[PieceKind::Pawn.index()];
+    } else if let Some(victim) = board.piece_type_at(to) {
+        gain[0] = PIECE_VALUES[victim.index()];
+    }
+    if let Some(kind) = mv.promotion() {
+        gain[0] += PIECE_VALUES[kind.index()] - PIECE_VALUES[PieceKind::Pawn.index()];
+        on_square = kind;
+    }
+
+    let mut side = !board.side_to_move;
+    let mut depth = 0;
+    loop {
+        let attackers = attackers_to(board, to, occupancy);
+        let own = attackers & board.pieces_of(side);
+        let Some((kind, attacker)) = PieceKind::ALL
+            .into_iter()
+            .find_map(|kind| Some((kind, (own & board.pieces(kind)).lsb()?)))
+        else {
+            break;
+        };
+        if kind == PieceKind::King && attackers.intersects(board.pieces_of(!side)) {
+            break;
+        }
+
+        depth += 1;
+        // what the recapturing side stands at if the exchange stops right after this capture.
+        gain[depth] = PIECE_VALUES[on_square.index()] - gain[depth - 1];
+        on_square = kind;
+        occupancy -= attacker.bb();
+        side = !side;
+        if depth == gain.len() - 1 {
+            break;
+        }
+    }
+
+    // each side recaptures only if that beats stopping.
+    while depth > 0 {
+        gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        depth -= 1;
+    }
+    gain[0]
+}
+
+/// Whether the exchange started by the move nets at least `threshold`, e.g. `see_ge(board,
+/// mv, 0)` for captures that don't lose material.
+pub fn see_ge(board: &ChessBoard, mv: Move, threshold: i32) -> bool {
+    see(board, mv) >= threshold
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::moves::get_legal_moves;
+
+    // (fen, move, expected see)
+    const SEE_POSITIONS: [(&str, &str, i32); 16] = [
+        // an undefended pawn, and one defended by a pawn.
+        ("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5", 100),
+        ("4k3/8/2p5/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5", 0),
+        // the queen takes a defended pawn, or steps onto a square a pawn covers.
+        ("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", "d1d5", -800),
+        ("4k3/8/2p5/8/8/8/8/3QK3 w - - 0 1", "d1d5", -900),
+        ("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", "d1d5", 0),
+        // the rook behind the first one backs it up through the x-ray.
+        ("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5", 100),
+        // two rooks each: white stops after losing the first one.
+        ("3rk3/3r4/8/3p4/8/8/3R4/3RK3 w - - 0 1", "d2d5", -400),
+        // a queen behind a bishop on the diagonal.
+        ("4k3/6b1/8/4p3/8/2B5/1Q6/4K3 w - - 0 1", "c3e5", 100),
+        ("4k3/6b1/8/4p3/8/2B5/8/4K3 w - - 0 1", "c3e5", -230),
+        // the king can't take back while the square is still covered.
+        ("8/8/8/3k4/3p4/8/3R4/3R2K1 w - - 0 1", "d2d4", 100),
+        ("8/8/8/3k4/3p4/8/3R4/6K1 w - - 0 1", "d2d4", -400),
+        // en passant.
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 100),
+        ("4k3/2p5/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 0),
+        // promotions, with and without a capture, and recaptured by the king.
+        ("3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1", "c7d8q", 400),
+        ("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q", -100),
+        // from the Chess Programming Wiki: an undefended pawn.
+        (
+            "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+            "e1e5",
+            100,
+        ),
+    ];
+
+    #[test]
+    fn test_see() {
+        for (fen, uci, expected) in SEE_POSITIONS {
+            let mut board = ChessBoard::initialize_from_fen(fen).unwrap();
+            let mv = get_legal_moves(&mut board)
+                .into_iter()
+                .find(|mv| mv.to_string() == uci)
+                .unwrap_or_else(|| panic!("{uci} is not legal in {fen}"));
+            assert_eq!(see(&board, mv), expected, "{uci} in {fen}");
+            assert!(see_ge(&board, mv, expected));
+            assert!(!see_ge(&board, mv, expected + 1));
+        }
+
+        // also from the wiki: knight, rook and queen against knight, bishop and the queen behind
+        // it. white does best to stop after black's first recapture.
+        let mut board = ChessBoard::initialize_from_fen(
+            "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+        )
+        .unwrap();
+        let mv = get_legal_moves(&mut board)
+            .into_iter()
+            .find(|mv| mv.to_string() == "d3e5")
+            .unwrap();
+        assert_eq!(see(&board, mv), 100 - 320);
+        assert!(!see_ge(&board, mv, 0));
+
+        // castling never trades anything.
+        let mut board = ChessBoard::initialize_from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let castle = get_legal_moves(&mut board)
+            .into_iter()
+            .find(|mv| mv.is_castle())
+            .unwrap();
+        assert_eq!(see(&board, castle), 0);
+    }
+}
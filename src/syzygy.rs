@@ -0,0 +1,1343 @@
+// this file reads Syzygy endgame tables (.rtbw for win/draw/loss, .rtbz for distance to zeroing)
+// and probes them as a `TablebaseBackend`.
+//
+// a table stores, for each side to move (and in tables with pawns, for each file a-d of the
+// leading pawn), one value per piece placement. the placement is folded into an index that
+// leaves out the board's symmetries: without pawns the leading piece is mirrored into the
+// a1-d1-d4 triangle, with pawns the leading pawn onto the a-d files, and identical pieces count
+// as one combination. the values are compressed by recursive pairing (a symbol stands for a pair
+// of symbols, down to single values) and canonical Huffman codes, in fixed-size blocks with a
+// sparse index to find the block holding an index.
+//
+// the tables leave out what a probe can work out by itself: a value may be wrong where a capture
+// (or, in DTZ tables, a pawn move) does at least as well, and DTZ tables only store one side to
+// move. `SyzygyTablebase` fills these in with a short search, as the reference prober does.
+
+use crate::bitboard::Bitboard;
+use crate::board::ChessBoard;
+use crate::moves::get_legal_moves;
+use crate::rules::is_check;
+use crate::tablebase::{material_name, SyzygyFiles, TablebaseBackend, Wdl, DTZ_MAGIC, WDL_MAGIC};
+use crate::types::{Color, Piece};
+use arrayvec::ArrayVec;
+use rustc_hash::FxHashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fs, io};
+
+/// The most pieces a Syzygy table can hold.
+pub const MAX_PIECES: usize = 7;
+
+// flags of a compressed table part.
+pub const FLAG_STM: u8 = 1; // DTZ: the side to move stored (0 white, 1 black)
+pub const FLAG_MAPPED: u8 = 2; // DTZ: values go through a per-outcome map
+pub const FLAG_WIN_PLIES: u8 = 4; // DTZ: wins are stored in plies rather than moves
+pub const FLAG_LOSS_PLIES: u8 = 8; // DTZ: losses are stored in plies rather than moves
+pub const FLAG_WIDE: u8 = 16; // DTZ: the map holds 16-bit values
+pub const FLAG_SINGLE_VALUE: u8 = 128; // every position has the same value
+
+// which DTZ map (win, loss, cursed win, blessed loss) an outcome uses, by `Wdl as i8 + 2`.
+const WDL_TO_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+
+const fn binomials() -> [[u64; 64]; MAX_PIECES] {
+    let mut table = [[0; 64]; MAX_PIECES];
+    let mut n = 0;
+    while n < 64 {
+        table[0][n] = 1;
+        let mut k = 1;
+        while k < MAX_PIECES && k <= n {
+            table[k][n] = table[k - 1][n - 1] + table[k][n - 1];
+            k += 1;
+        }
+        n += 1;
+    }
+    table
+}
+
+// BINOMIAL[k][n] is n choose k.
+const BINOMIAL: [[u64; 64]; MAX_PIECES] = binomials();
+
+// rank minus file: 0 on the a1-h8 diagonal, negative below it.
+const fn off_diagonal(sq: usize) -> i32 {
+    (sq / 8) as i32 - (sq % 8) as i32
+}
+
+const fn flip_diagonal(sq: usize) -> usize {
+    ((sq >> 3) | (sq << 3)) & 63
+}
+
+// the squares of the a1-d1-d4 triangle, below the diagonal first.
+const TRIANGLE: [usize; 10] = [1, 2, 3, 10, 11, 19, 0, 9, 18, 27];
+
+const fn triangle_map() -> [usize; 64] {
+    let mut map = [0; 64];
+    let mut i = 0;
+    while i < TRIANGLE.len() {
+        map[TRIANGLE[i]] = i;
+        i += 1;
+    }
+    map
+}
+
+const MAP_A1D1D4: [usize; 64] = triangle_map();
+
+// the 28 squares below the a1-h8 diagonal, numbered in square order.
+const fn below_diagonal_map() -> [usize; 64] {
+    let mut map = [0; 64];
+    let mut code = 0;
+    let mut sq = 0;
+    while sq < 64 {
+        if off_diagonal(sq) < 0 {
+            map[sq] = code;
+            code += 1;
+        }
+        sq += 1;
+    }
+    map
+}
+
+const MAP_B1H1H7: [usize; 64] = below_diagonal_map();
+
+// the 462 placements of two kings with the first in the a1-d1-d4 triangle, and the second not
+// above the diagonal when the first is on it. placements with both on the diagonal come last.
+const fn king_pair_map() -> [[u64; 64]; 10] {
+    let mut map = [[u64::MAX; 64]; 10];
+    let mut code = 0;
+    let mut both_on_diagonal = 0;
+    while both_on_diagonal < 2 {
+        let mut i = 0;
+        while i < 10 {
+            let first = TRIANGLE[i];
+            let mut second = 0;
+            while second < 64 {
+                let touching =
+                    (first % 8).abs_diff(second % 8) <= 1 && (first / 8).abs_diff(second / 8) <= 1;
+                let on_diagonal = off_diagonal(first) == 0 && off_diagonal(second) == 0;
+                let above = off_diagonal(first) == 0 && off_diagonal(second) > 0;
+                if !touching && !above && on_diagonal == (both_on_diagonal == 1) {
+                    map[i][second] = code;
+                    code += 1;
+                }
+                second += 1;
+            }
+            i += 1;
+        }
+        both_on_diagonal += 1;
+    }
+    map
+}
+
+const MAP_KK: [[u64; 64]; 10] = king_pair_map();
+
+// the ways to place three distinct pieces with the first in the triangle (see index).
+const THREE_PIECES: u64 = 6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + 4 * 7 * 6;
+const KING_PAIRS: u64 = 462;
+
+// squares a2-h7 numbered from the edge files inwards and, within a file pair, from rank 2 up,
+// highest first. the leading pawn is the one with the highest number.
+const fn pawn_map() -> [usize; 64] {
+    let mut map = [0; 64];
+    let mut available: i32 = 47;
+    let mut file = 0;
+    while file < 4 {
+        let mut rank = 1;
+        while rank < 7 {
+            let sq = rank * 8 + file;
+            map[sq] = available as usize;
+            map[sq ^ 7] = (available - 1) as usize;
+            available -= 2;
+            rank += 1;
+        }
+        file += 1;
+    }
+    map
+}
+
+const MAP_PAWNS: [usize; 64] = pawn_map();
+
+// for each number of leading pawns, the index of the leading pawn's square among the placements
+// on its file, and the number of placements per file.
+const fn lead_pawn_maps() -> ([[u64; 64]; MAX_PIECES], [[u64; 4]; MAX_PIECES]) {
+    let mut index = [[0; 64]; MAX_PIECES];
+    let mut size = [[0; 4]; MAX_PIECES];
+    let mut count = 1;
+    while count < MAX_PIECES {
+        let mut file = 0;
+        while file < 4 {
+            let mut total = 0;
+            let mut rank = 1;
+            while rank < 7 {
+                let sq = rank * 8 + file;
+                index[count][sq] = total;
+                total += BINOMIAL[count - 1][MAP_PAWNS[sq]];
+                rank += 1;
+            }
+            size[count][file] = total;
+            file += 1;
+        }
+        count += 1;
+    }
+    (index, size)
+}
+
+const LEAD_PAWN_MAPS: ([[u64; 64]; MAX_PIECES], [[u64; 4]; MAX_PIECES]) = lead_pawn_maps();
+const LEAD_PAWN_INDEX: [[u64; 64]; MAX_PIECES] = LEAD_PAWN_MAPS.0;
+const LEAD_PAWNS_SIZE: [[u64; 4]; MAX_PIECES] = LEAD_PAWN_MAPS.1;
+
+/// A piece as the tables code it: pawn 1 through king 6, plus 8 for black.
+pub const fn piece_code(piece: Piece) -> u8 {
+    piece.kind as u8 + 1 + if piece.color.is_black() { 8 } else { 0 }
+}
+
+const fn is_pawn(code: u8) -> bool {
+    code & 7 == 1
+}
+
+/// How one part of a table (one side to move, one file of the leading pawn) numbers its
+/// positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encoding {
+    pieces: ArrayVec<u8, MAX_PIECES>, // piece codes, in the order the table lists them
+    groups: ArrayVec<usize, MAX_PIECES>, // how many pieces each group holds
+    factors: ArrayVec<u64, MAX_PIECES>, // what each group's index is multiplied by
+    size: u64,
+    has_pawns: bool,
+    three_leading: bool, // the leading group is three distinct pieces rather than the kings
+    other_pawns: usize,  // pawns of the side without the leading pawn
+}
+
+impl Encoding {
+    /// The encoding for `pieces` listed in table order. `order` gives where the leading group
+    /// (and, with pawns on both sides, the other side's pawns) comes among the index's digits;
+    /// `file` is the leading pawn's file, 0 (a or h) to 3 (d or e).
+    pub fn new(pieces: &[u8], order: [u8; 2], file: usize) -> Encoding {
+        let has_pawns = pieces.iter().any(|&code| is_pawn(code));
+        let three_leading = !has_pawns
+            && pieces.iter().any(|&code| {
+                code & 7 != 6 && pieces.iter().filter(|&&other| other == code).count() == 1
+            });
+
+        // the leading group is the pawns of one side, or the first three pieces, or the kings.
+        let mut leading: i32 = if has_pawns {
+            0
+        } else if three_leading {
+            3
+        } else {
+            2
+        };
+        let mut groups: ArrayVec<usize, MAX_PIECES> = ArrayVec::new();
+        groups.push(1);
+        for i in 1..pieces.len() {
+            leading -= 1;
+            if leading > 0 || pieces[i] == pieces[i - 1] {
+                *groups.last_mut().unwrap() += 1;
+            } else {
+                groups.push(1);
+            }
+        }
+        let other_pawns = match groups.get(1) {
+            Some(&len) if has_pawns && is_pawn(pieces[groups[0]]) => len,
+            _ => 0,
+        };
+
+        let mut factors: ArrayVec<u64, MAX_PIECES> = groups.iter().map(|_| 0).collect();
+        let mut free = 64 - groups[0] - other_pawns;
+        let mut size: u64 = 1;
+        let mut next = if other_pawns > 0 { 2 } else { 1 };
+        let mut k = 0;
+        while next < groups.len() || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                factors[0] = size;
+                size *= if has_pawns {
+                    LEAD_PAWNS_SIZE[groups[0]][file]
+                } else if three_leading {
+                    THREE_PIECES
+                } else {
+                    KING_PAIRS
+                };
+            } else if k == order[1] as usize {
+                factors[1] = size;
+                size *= BINOMIAL[groups[1]][48 - groups[0]];
+            } else {
+                factors[next] = size;
+                size *= BINOMIAL[groups[next]][free];
+                free -= groups[next];
+                next += 1;
+            }
+            k += 1;
+        }
+
+        Encoding {
+            pieces: pieces.iter().copied().collect(),
+            groups,
+            factors,
+            size,
+            has_pawns,
+            three_leading,
+            other_pawns,
+        }
+    }
+
+    /// The piece codes in table order.
+    pub fn pieces(&self) -> &[u8] {
+        &self.pieces
+    }
+
+    /// The number of groups the pieces are indexed in.
+    pub fn groups(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// The number of indices, including ones no legal position maps to.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    // the index of a placement, given the square of each piece in table order with the leading
+    // pawn first. the squares are mirrored in place.
+    fn index(&self, squares: &mut [usize]) -> u64 {
+        if squares[0] % 8 > 3 {
+            squares.iter_mut().for_each(|sq| *sq ^= 7);
+        }
+
+        let mut index;
+        if self.has_pawns {
+            let lead = self.groups[0];
+            index = LEAD_PAWN_INDEX[lead][squares[0]];
+            squares[1..lead].sort_by_key(|&sq| MAP_PAWNS[sq]);
+            for (i, &sq) in squares.iter().enumerate().take(lead).skip(1) {
+                index += BINOMIAL[i][MAP_PAWNS[sq]];
+            }
+        } else {
+            if squares[0] / 8 > 3 {
+                squares.iter_mut().for_each(|sq| *sq ^= 56);
+            }
+            // the first leading piece off the diagonal goes below it.
+            if let Some(i) = (0..self.groups[0]).find(|&i| off_diagonal(squares[i]) != 0) {
+                if off_diagonal(squares[i]) > 0 {
+                    squares[i..]
+                        .iter_mut()
+                        .for_each(|sq| *sq = flip_diagonal(*sq));
+                }
+            }
+            index = if self.three_leading {
+                three_piece_index(squares)
+            } else {
+                MAP_KK[MAP_A1D1D4[squares[0]]][squares[1]]
+            };
+        }
+        index = index.wrapping_mul(self.factors[0]);
+
+        // the other groups count combinations of the squares the earlier groups left free.
+        let mut start = self.groups[0];
+        let mut pawn_group = self.other_pawns > 0;
+        for (group, &len) in self.groups.iter().enumerate().skip(1) {
+            let (earlier, rest) = squares.split_at_mut(start);
+            let members = &mut rest[..len];
+            members.sort_unstable();
+            let mut combination = 0;
+            for (i, &sq) in members.iter().enumerate() {
+                let taken = earlier.iter().filter(|&&other| other < sq).count();
+                // pawns can't stand on the first rank.
+                let first_rank = if pawn_group { 8 } else { 0 };
+                combination += BINOMIAL[i + 1][sq - taken - first_rank];
+            }
+            pawn_group = false;
+            index = index.wrapping_add(combination * self.factors[group]);
+            start += len;
+        }
+        index
+    }
+}
+
+// three distinct leading pieces, the first in the a1-d1-d4 triangle: first off the diagonal,
+// then on it with the second below, then two on it with the third below, then all three on it.
+fn three_piece_index(squares: &[usize]) -> u64 {
+    let (s0, s1, s2) = (squares[0], squares[1], squares[2]);
+    let adjust1 = (s1 > s0) as usize;
+    let adjust2 = (s2 > s0) as usize + (s2 > s1) as usize;
+    let index = if off_diagonal(s0) != 0 {
+        (MAP_A1D1D4[s0] * 63 + s1 - adjust1) * 62 + s2 - adjust2
+    } else if off_diagonal(s1) != 0 {
+        (6 * 63 + (s0 / 8) * 28 + MAP_B1H1H7[s1]) * 62 + s2 - adjust2
+    } else if off_diagonal(s2) != 0 {
+        6 * 63 * 62 + 4 * 28 * 62 + (s0 / 8) * 7 * 28 + (s1 / 8 - adjust1) * 28 + MAP_B1H1H7[s2]
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + (s0 / 8) * 7 * 6 + (s1 / 8 - adjust1) * 6 + s2 / 8
+            - adjust2
+    };
+    index as u64
+}
+
+/// Where the positions of one material balance are stored: the table's name (white's pieces
+/// first) and the encoding of each of its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableLayout {
+    material: String,
+    symmetric: bool,
+    has_pawns: bool,
+    encodings: Vec<Vec<Encoding>>, // by file of the leading pawn (just one without pawns), then side
+}
+
+impl TableLayout {
+    /// `encodings` holds one entry per file a-d of the leading pawn (a single one without
+    /// pawns), each with an encoding per side to move stored.
+    pub fn new(material: &str, encodings: Vec<Vec<Encoding>>) -> TableLayout {
+        let (white, black) = material.split_once('v').unwrap_or((material, ""));
+        TableLayout {
+            material: material.to_string(),
+            symmetric: white == black,
+            has_pawns: material.contains('P'),
+            encodings,
+        }
+    }
+
+    pub fn material(&self) -> &str {
+        &self.material
+    }
+
+    pub fn files(&self) -> usize {
+        self.encodings.len()
+    }
+
+    pub fn encoding(&self, file: usize, side: usize) -> &Encoding {
+        let sides = &self.encodings[file];
+        &sides[side.min(sides.len() - 1)]
+    }
+
+    /// Where a position with this material is stored: the file of its leading pawn, the side to
+    /// move in the table's colors (0 for white) and the index. None if the position doesn't
+    /// have the table's material.
+    pub fn locate(&self, board: &ChessBoard) -> Option<(usize, usize, u64)> {
+        // the table has white as the side named first; both sides to move are stored with
+        // white to move when the two sides have the same pieces.
+        let to_move = board.side_to_move;
+        let flip = if self.symmetric {
+            to_move.is_black()
+        } else {
+            material_name(board) != self.material
+        };
+        let side = flip as usize ^ to_move.index();
+        let color_flip = if flip { 8 } else { 0 };
+        let square_flip = if flip { 56 } else { 0 };
+
+        let mut squares: ArrayVec<usize, MAX_PIECES> = ArrayVec::new();
+        let mut file = 0;
+        let mut lead_pawns = Bitboard::EMPTY;
+        if self.has_pawns {
+            let lead = self.encodings[0][0].pieces[0] ^ color_flip;
+            lead_pawns = board.pawns & board.pieces_of(Color::from(lead & 8 == 0));
+            squares.extend(
+                lead_pawns
+                    .into_iter()
+                    .map(|sq| sq.index() as usize ^ square_flip),
+            );
+            let leader = (0..squares.len()).max_by_key(|&i| MAP_PAWNS[squares[i]])?;
+            squares.swap(0, leader);
+            let leader_file = squares[0] % 8;
+            file = leader_file.min(7 - leader_file);
+        }
+
+        let encoding = self.encodings.get(file)?.first()?;
+        let encoding = self.encodings[file].get(side).unwrap_or(encoding);
+        let mut rest: ArrayVec<(u8, usize), MAX_PIECES> = (board.white_pieces | board.black_pieces)
+            .without(lead_pawns)
+            .into_iter()
+            .map(|sq| {
+                let code = piece_code(board.piece_at(sq).unwrap()) ^ color_flip;
+                (code, sq.index() as usize ^ square_flip)
+            })
+            .collect();
+        if squares.len() + rest.len() != encoding.pieces.len() {
+            return None;
+        }
+        for &code in &encoding.pieces[squares.len()..] {
+            let i = rest.iter().position(|&(other, _)| other == code)?;
+            squares.push(rest.swap_remove(i).1);
+        }
+        Some((file, side, encoding.index(&mut squares)))
+    }
+}
+
+// reads the little-endian fields of a table header in order.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> io::Result<&[u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| invalid("the header is cut short"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn align(&mut self, to: usize) {
+        self.pos = self.pos.next_multiple_of(to);
+    }
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+// a big-endian number from `len` bytes at `pos`, with zeros past the end of the file.
+fn read_be(bytes: &[u8], pos: usize, len: usize) -> u64 {
+    (0..len).fold(0, |value, i| {
+        (value << 8) | *bytes.get(pos + i).unwrap_or(&0) as u64
+    })
+}
+
+fn read_u16_le(bytes: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(pos..pos + 2)?.try_into().unwrap(),
+    ))
+}
+
+fn read_u32_le(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(pos..pos + 4)?.try_into().unwrap(),
+    ))
+}
+
+/// One compressed part of a table: a value per index of its encoding.
+#[derive(Debug, Clone, Default)]
+struct PairsData {
+    flags: u8,
+    single_value: Option<u16>,
+    block_size: usize, // bytes per block
+    span: u64,         // indices between sparse index entries
+    sparse_entries: usize,
+    blocks: usize,
+    block_lengths: usize, // entries in the block length array, padding included
+    min_len: u32,
+    lowest_symbol: Vec<u16>, // the first symbol of each code length, from min_len up
+    base: Vec<u64>,          // the lowest code of each length, left-aligned in 64 bits
+    symbol_len: Vec<u32>,    // how many values each symbol stands for, minus one
+    pairs: Vec<(u16, u16)>,  // the two halves of each symbol; (value, 0xFFF) for single values
+    sparse_index_pos: usize,
+    block_lengths_pos: usize,
+    data_pos: usize,
+}
+
+impl PairsData {
+    fn parse(reader: &mut Reader, size: u64) -> io::Result<PairsData> {
+        let flags = reader.u8()?;
+        if flags & FLAG_SINGLE_VALUE != 0 {
+            let value = reader.u8()? as u16;
+            return Ok(PairsData {
+                flags,
+                single_value: Some(value),
+                ..PairsData::default()
+            });
+        }
+
+        let block_bits = reader.u8()?;
+        let span_bits = reader.u8()?;
+        if block_bits > 30 || span_bits > 40 {
+            return Err(invalid("block size out of range"));
+        }
+        let padding = reader.u8()? as usize;
+        let blocks = reader.u32()? as usize;
+        let max_len = reader.u8()? as u32;
+        let min_len = reader.u8()? as u32;
+        if min_len == 0 || max_len < min_len || max_len > 32 {
+            return Err(invalid("code lengths out of range"));
+        }
+
+        let lengths = (max_len - min_len + 1) as usize;
+        let lowest_symbol = (0..lengths)
+            .map(|_| reader.u16())
+            .collect::<io::Result<Vec<u16>>>()?;
+        // codes are canonical with longer codes numerically lower: the lowest code of each
+        // length follows from how many codes the next longer length has.
+        let mut base = vec![0u64; lengths];
+        for i in (0..lengths - 1).rev() {
+            let count = lowest_symbol[i]
+                .checked_sub(lowest_symbol[i + 1])
+                .ok_or_else(|| invalid("code lengths out of order"))?;
+            base[i] = (base[i + 1] + count as u64) / 2;
+        }
+        for (i, base) in base.iter_mut().enumerate() {
+            *base <<= 64 - min_len - i as u32;
+        }
+
+        let symbols = reader.u16()? as usize;
+        let pairs: Vec<(u16, u16)> = reader
+            .take(3 * symbols)?
+            .chunks_exact(3)
+            .map(|lr| {
+                let left = ((lr[1] as u16 & 0xF) << 8) | lr[0] as u16;
+                let right = ((lr[2] as u16) << 4) | (lr[1] as u16 >> 4);
+                (left, right)
+            })
+            .collect();
+        if symbols % 2 == 1 {
+            reader.u8()?;
+        }
+        let mut symbol_len = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for symbol in 0..symbols {
+            count_values(symbol, &pairs, &mut symbol_len, &mut visited)?;
+        }
+
+        Ok(PairsData {
+            flags,
+            single_value: None,
+            block_size: 1 << block_bits,
+            span: 1 << span_bits,
+            sparse_entries: size.div_ceil(1 << span_bits) as usize,
+            blocks,
+            block_lengths: blocks + padding,
+            min_len,
+            lowest_symbol,
+            base,
+            symbol_len,
+            pairs,
+            ..PairsData::default()
+        })
+    }
+
+    // the value stored at an index.
+    fn value(&self, bytes: &[u8], index: u64) -> Option<u16> {
+        if let Some(value) = self.single_value {
+            return Some(value);
+        }
+
+        // the sparse index gives the block and offset of every span's middle index; from there
+        // walk to the block holding ours.
+        let entry = self.sparse_index_pos + (index / self.span) as usize * 6;
+        let mut block = read_u32_le(bytes, entry)? as usize;
+        let mut offset = read_u16_le(bytes, entry + 4)? as i64;
+        offset += (index % self.span) as i64 - (self.span / 2) as i64;
+        let block_len = |block: usize| -> Option<i64> {
+            if block >= self.block_lengths {
+                return None;
+            }
+            Some(read_u16_le(bytes, self.block_lengths_pos + 2 * block)? as i64 + 1)
+        };
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_len(block)?;
+        }
+        while offset >= block_len(block)? {
+            offset -= block_len(block)?;
+            block += 1;
+        }
+        if block >= self.blocks {
+            return None;
+        }
+
+        // decode the block's symbols until the one covering the offset.
+        let start = self.data_pos + block * self.block_size;
+        let mut pos = start + 8;
+        let mut buffer = read_be(bytes, start, 8);
+        let mut buffer_bits = 64;
+        let mut symbol = loop {
+            let mut len = 0;
+            while buffer < self.base[len] {
+                len += 1;
+            }
+            let code_len = len as u32 + self.min_len;
+            let symbol = ((buffer - self.base[len]) >> (64 - code_len)) as usize
+                + self.lowest_symbol[len] as usize;
+            let values = *self.symbol_len.get(symbol)? as i64 + 1;
+            if offset < values {
+                break symbol;
+            }
+            offset -= values;
+            buffer <<= code_len;
+            buffer_bits -= code_len;
+            if buffer_bits <= 32 {
+                if pos > start + self.block_size {
+                    return None;
+                }
+                buffer_bits += 32;
+                buffer |= read_be(bytes, pos, 4) << (64 - buffer_bits);
+                pos += 4;
+            }
+        };
+
+        // then down the pair tree to the single value.
+        while self.symbol_len[symbol] != 0 {
+            let (left, right) = self.pairs[symbol];
+            let left_values = *self.symbol_len.get(left as usize)? as i64 + 1;
+            if offset < left_values {
+                symbol = left as usize;
+            } else {
+                offset -= left_values;
+                symbol = right as usize;
+            }
+        }
+        Some(self.pairs[symbol].0)
+    }
+}
+
+// how many values a symbol stands for, minus one, filling in its halves first.
+fn count_values(
+    symbol: usize,
+    pairs: &[(u16, u16)],
+    symbol_len: &mut [u32],
+    visited: &mut [bool],
+) -> io::Result<u32> {
+    if visited[symbol] {
+        return Ok(symbol_len[symbol]);
+    }
+    visited[symbol] = true;
+    let (left, right) = pairs[symbol];
+    if right != 0xFFF {
+        if left as usize >= pairs.len() || right as usize >= pairs.len() {
+            return Err(invalid("symbol out of range"));
+        }
+        let left = count_values(left as usize, pairs, symbol_len, visited)?;
+        let right = count_values(right as usize, pairs, symbol_len, visited)?;
+        symbol_len[symbol] = left + right + 1;
+    }
+    Ok(symbol_len[symbol])
+}
+
+/// A table file read into memory.
+#[derive(Debug)]
+struct Table {
+    layout: TableLayout,
+    parts: Vec<Vec<PairsData>>,        // by file, then side
+    dtz_maps: Vec<Option<[usize; 4]>>, // by file: where each outcome's DTZ map starts
+    bytes: Vec<u8>,
+}
+
+impl Table {
+    fn parse(bytes: Vec<u8>, material: &str, dtz: bool) -> io::Result<Table> {
+        let magic = if dtz { DTZ_MAGIC } else { WDL_MAGIC };
+        if bytes.get(..4) != Some(&magic[..]) {
+            return Err(invalid("not a Syzygy table"));
+        }
+        let (white, black) = material
+            .split_once('v')
+            .ok_or_else(|| invalid("not a material name"))?;
+        let mut expected: Vec<u8> = [(white, 0), (black, 8)]
+            .into_iter()
+            .flat_map(|(side, color)| {
+                side.chars().map(move |letter| {
+                    "PNBRQK"
+                        .find(letter)
+                        .map_or(0, |kind| kind as u8 + 1 + color)
+                })
+            })
+            .collect();
+        expected.sort_unstable();
+        if expected.len() > MAX_PIECES || expected.contains(&0) {
+            return Err(invalid("not a material name"));
+        }
+
+        let mut reader = Reader {
+            bytes: &bytes,
+            pos: 4,
+        };
+        let flags = reader.u8()?;
+        let has_pawns = flags & 2 != 0;
+        let split = flags & 1 != 0;
+        if has_pawns != material.contains('P') || split != (white != black) {
+            return Err(invalid("the header doesn't match the table's material"));
+        }
+        let both_pawns = white.contains('P') && black.contains('P');
+        let sides = if split && !dtz { 2 } else { 1 };
+        let files = if has_pawns { 4 } else { 1 };
+
+        let mut encodings = Vec::new();
+        for file in 0..files {
+            let order = reader.u8()?;
+            let other_order = if both_pawns { reader.u8()? } else { 0xFF };
+            let pieces = reader.take(expected.len())?;
+            let mut per_side = Vec::new();
+            for side in 0..sides {
+                let shift = 4 * side;
+                let codes: ArrayVec<u8, MAX_PIECES> =
+                    pieces.iter().map(|&code| (code >> shift) & 0xF).collect();
+                let mut sorted = codes.clone();
+                sorted.sort_unstable();
+                if *sorted != *expected {
+                    return Err(invalid("the pieces don't match the table's material"));
+                }
+                let order = [(order >> shift) & 0xF, (other_order >> shift) & 0xF];
+                per_side.push(Encoding::new(&codes, order, file));
+            }
+            encodings.push(per_side);
+        }
+        reader.align(2);
+        let layout = TableLayout::new(material, encodings);
+
+        let mut parts = Vec::new();
+        for file in 0..files {
+            let mut per_side = Vec::new();
+            for side in 0..sides {
+                let size = layout.encoding(file, side).size();
+                per_side.push(PairsData::parse(&mut reader, size)?);
+            }
+            parts.push(per_side);
+        }
+
+        // each mapped DTZ part lists the stored values for wins, losses, cursed wins and
+        // blessed losses, each list after its length.
+        let mut dtz_maps = vec![None; files];
+        if dtz {
+            for (file, map) in dtz_maps.iter_mut().enumerate() {
+                let part_flags = parts[file][0].flags;
+                if part_flags & FLAG_MAPPED == 0 {
+                    continue;
+                }
+                let mut starts = [0; 4];
+                for start in &mut starts {
+                    if part_flags & FLAG_WIDE != 0 {
+                        reader.align(2);
+                        let len = reader.u16()? as usize;
+                        *start = reader.pos;
+                        reader.take(2 * len)?;
+                    } else {
+                        let len = reader.u8()? as usize;
+                        *start = reader.pos;
+                        reader.take(len)?;
+                    }
+                }
+                *map = Some(starts);
+            }
+            reader.align(2);
+        }
+
+        for part in parts.iter_mut().flatten() {
+            part.sparse_index_pos = reader.pos;
+            reader.pos += part.sparse_entries * 6;
+        }
+        for part in parts.iter_mut().flatten() {
+            part.block_lengths_pos = reader.pos;
+            reader.pos += part.block_lengths * 2;
+        }
+        for part in parts.iter_mut().flatten() {
+            reader.align(64);
+            part.data_pos = reader.pos;
+            reader.pos += part.blocks * part.block_size;
+        }
+
+        Ok(Table {
+            layout,
+            parts,
+            dtz_maps,
+            bytes,
+        })
+    }
+
+    // the stored value for a position, with the part it came from.
+    fn probe(&self, board: &ChessBoard) -> Option<(u16, &PairsData, usize, usize)> {
+        let (file, side, index) = self.layout.locate(board)?;
+        let parts = &self.parts[file];
+        let part = parts.get(side).unwrap_or(&parts[0]);
+        if index >= self.layout.encoding(file, side).size() {
+            return None;
+        }
+        Some((part.value(&self.bytes, index)?, part, file, side))
+    }
+}
+
+// the DTZ of a position whose best move is a capture or pawn move into an outcome.
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => 1,
+        Wdl::CursedWin => 101,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -101,
+        Wdl::Loss => -1,
+    }
+}
+
+fn sign(wdl: Wdl) -> i32 {
+    (wdl as i32).signum()
+}
+
+// the table files are read when a position first needs them and kept for later probes.
+type TableCache = Mutex<FxHashMap<PathBuf, Option<Arc<Table>>>>;
+
+/// A `TablebaseBackend` reading the Syzygy `.rtbw`/`.rtbz` files found by a `SyzygyFiles`.
+/// A table is read into memory the first time a position needs it.
+#[derive(Debug, Default)]
+pub struct SyzygyTablebase {
+    files: SyzygyFiles,
+    wdl_tables: TableCache,
+    dtz_tables: TableCache,
+}
+
+impl SyzygyTablebase {
+    pub fn new(files: SyzygyFiles) -> Self {
+        SyzygyTablebase {
+            files,
+            ..SyzygyTablebase::default()
+        }
+    }
+
+    /// The tables in a directory; see `SyzygyFiles::add_directory`.
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let mut files = SyzygyFiles::new();
+        files.add_directory(dir)?;
+        Ok(SyzygyTablebase::new(files))
+    }
+
+    pub fn files(&self) -> &SyzygyFiles {
+        &self.files
+    }
+
+    fn table(&self, board: &ChessBoard, dtz: bool) -> Option<Arc<Table>> {
+        let (path, cache) = if dtz {
+            (self.files.dtz_path(board)?, &self.dtz_tables)
+        } else {
+            (self.files.wdl_path(board)?, &self.wdl_tables)
+        };
+        let mut cache = cache.lock().unwrap();
+        cache
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                let material = path.file_stem()?.to_str()?;
+                let table = Table::parse(fs::read(path).ok()?, material, dtz).ok()?;
+                Some(Arc::new(table))
+            })
+            .clone()
+    }
+
+    // the WDL table's value, which may be wrong where a capture does at least as well.
+    fn probe_wdl_table(&self, board: &ChessBoard) -> Option<Wdl> {
+        if (board.white_pieces | board.black_pieces).popcount() == 2 {
+            return Some(Wdl::Draw);
+        }
+        let table = self.table(board, false)?;
+        match table.probe(board)?.0 {
+            0 => Some(Wdl::Loss),
+            1 => Some(Wdl::BlessedLoss),
+            2 => Some(Wdl::Draw),
+            3 => Some(Wdl::CursedWin),
+            4 => Some(Wdl::Win),
+            _ => None,
+        }
+    }
+
+    // the outcome with the captures (and, with `zeroing`, the pawn moves) searched, and whether
+    // one of those is the best move, in which case the DTZ table may not hold the position.
+    fn search(&self, board: &mut ChessBoard, zeroing: bool) -> Option<(Wdl, bool)> {
+        let moves = get_legal_moves(board);
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for &mv in &moves {
+            let pawn_move = board.pawns.contains(mv.from());
+            if !(mv.is_capture() || zeroing && pawn_move) {
+                continue;
+            }
+            searched += 1;
+            let undo_info = board.make_move(mv).ok()?;
+            let value = self.search(board, false);
+            board.unmake_move(mv, &undo_info).ok()?;
+            let value = value?.0.flip();
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Some((value, true));
+                }
+            }
+        }
+
+        // with every move searched the table isn't needed (and may be wrong).
+        let every_move = searched > 0 && searched == moves.len();
+        let value = if every_move {
+            best
+        } else {
+            self.probe_wdl_table(board)?
+        };
+        if best >= value {
+            Some((best, best > Wdl::Draw || every_move))
+        } else {
+            Some((value, false))
+        }
+    }
+
+    // the DTZ table's value in plies, or None inside if it stores the other side to move.
+    fn probe_dtz_table(&self, board: &ChessBoard, wdl: Wdl) -> Option<Option<i32>> {
+        let table = self.table(board, true)?;
+        let (value, part, file, side) = table.probe(board)?;
+        let any_side = table.layout.symmetric && !table.layout.has_pawns;
+        if (part.flags & FLAG_STM) as usize != side && !any_side {
+            return Some(None);
+        }
+
+        let mut value = value as i32;
+        if let Some(starts) = table.dtz_maps[file] {
+            let start = starts[WDL_TO_MAP[(wdl as i8 + 2) as usize]];
+            value = if part.flags & FLAG_WIDE != 0 {
+                read_u16_le(&table.bytes, start + 2 * value as usize)? as i32
+            } else {
+                *table.bytes.get(start + value as usize)? as i32
+            };
+        }
+        // values stored in moves rather than plies may be one ply short.
+        let plies = match wdl {
+            Wdl::Win => part.flags & FLAG_WIN_PLIES != 0,
+            Wdl::Loss => part.flags & FLAG_LOSS_PLIES != 0,
+            _ => false,
+        };
+        if !plies {
+            value *= 2;
+        }
+        let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+        Some(Some((value + 1 + 100 * cursed as i32) * sign(wdl)))
+    }
+
+    fn dtz(&self, board: &mut ChessBoard) -> Option<i32> {
+        let (wdl, zeroing_is_best) = self.search(board, true)?;
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+        if zeroing_is_best {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        if let Some(dtz) = self.probe_dtz_table(board, wdl)? {
+            return Some(dtz);
+        }
+
+        // the table holds the other side to move: take the best reply's DTZ, one ply on.
+        let mut best: Option<i32> = None;
+        for mv in get_legal_moves(board) {
+            let zeroing = mv.is_capture() || board.pawns.contains(mv.from());
+            let undo_info = board.make_move(mv).ok()?;
+            let dtz = if zeroing {
+                self.search(board, false)
+                    .map(|(wdl, _)| -dtz_before_zeroing(wdl))
+            } else {
+                self.dtz(board).map(|dtz| -dtz - dtz.signum())
+            };
+            let mates = dtz == Some(2)
+                && is_check(board, board.side_to_move)
+                && get_legal_moves(board).is_empty();
+            board.unmake_move(mv, &undo_info).ok()?;
+            let dtz = if mates { 1 } else { dtz? };
+            if dtz.signum() == sign(wdl) && best.is_none_or(|best| dtz < best) {
+                best = Some(dtz);
+            }
+        }
+        // no move keeps the outcome only when the side to move is mated.
+        Some(best.unwrap_or(-1))
+    }
+}
+
+// the tables don't know about en passant, which `Tablebase` handles, so it is cleared.
+fn without_en_passant(board: &ChessBoard) -> ChessBoard {
+    let mut board = board.clone();
+    if board.en_passant.is_nonempty() {
+        board.en_passant = Bitboard::EMPTY;
+        board.zobrist_hash = board.generate_zobrist_hash();
+    }
+    board
+}
+
+impl TablebaseBackend for SyzygyTablebase {
+    fn max_pieces(&self) -> u32 {
+        self.files.max_pieces()
+    }
+
+    fn probe_wdl(&self, board: &ChessBoard) -> Option<Wdl> {
+        Some(self.search(&mut without_en_passant(board), false)?.0)
+    }
+
+    fn probe_dtz(&self, board: &ChessBoard) -> Option<i32> {
+        self.dtz(&mut without_en_passant(board))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tablebase::{ProbeResult, Tablebase};
+
+    const TABLES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy");
+
+    fn board(fen: &str) -> ChessBoard {
+        ChessBoard::initialize_from_fen(fen).unwrap()
+    }
+
+    fn tablebase() -> Tablebase<SyzygyTablebase> {
+        Tablebase::new(SyzygyTablebase::open(TABLES).unwrap())
+    }
+
+    fn probe(tablebase: &Tablebase<SyzygyTablebase>, fen: &str) -> (Wdl, i32) {
+        let result = tablebase.probe(&mut board(fen)).unwrap();
+        (result.wdl, result.dtz)
+    }
+
+    #[test]
+    fn test_index_tables() {
+        let king_pairs = MAP_KK.iter().flatten().filter(|&&code| code != u64::MAX);
+        assert_eq!(king_pairs.clone().count() as u64, KING_PAIRS);
+        assert_eq!(king_pairs.max(), Some(&(KING_PAIRS - 1)));
+        assert_eq!(MAP_PAWNS[8], 47); // a2
+        assert_eq!(MAP_PAWNS[15], 46); // h2
+        assert_eq!(MAP_PAWNS[51], 1); // d7
+        assert_eq!(MAP_PAWNS[52], 0); // e7
+        assert_eq!(LEAD_PAWNS_SIZE[1], [6, 6, 6, 6]);
+        assert_eq!(BINOMIAL[2][62], 1891);
+
+        // without pawns: kings, a piece there is only one of, or kings and a pair.
+        assert_eq!(Encoding::new(&[6, 14, 5], [0, 0xF], 0).size(), THREE_PIECES);
+        assert_eq!(
+            Encoding::new(&[6, 14, 2, 2], [1, 0xF], 0).size(),
+            462 * 1891
+        );
+        // the leading pawn's file decides how many placements it has.
+        let pawn = Encoding::new(&[1, 6, 14], [2, 0xF], 0);
+        assert_eq!(pawn.size(), 6 * 63 * 62);
+    }
+
+    #[test]
+    fn test_kqk() {
+        let tablebase = tablebase();
+        let (wdl, dtz) = probe(&tablebase, "8/8/8/4k3/8/8/8/3QK3 w - - 0 1");
+        assert_eq!(wdl, Wdl::Win);
+        assert!(dtz > 1 && dtz < 100);
+        let (wdl, dtz) = probe(&tablebase, "8/8/8/4k3/8/8/8/3QK3 b - - 0 1");
+        assert_eq!(wdl, Wdl::Loss);
+        assert!(dtz < -1);
+        // the same with the colors swapped, where the table has to be read the other way.
+        assert_eq!(
+            probe(&tablebase, "3qk3/8/8/8/4K3/8/8/8 b - - 0 1").0,
+            Wdl::Win
+        );
+
+        // black takes the queen; stalemate; mate in one; mated.
+        assert_eq!(
+            probe(&tablebase, "8/8/8/8/8/3k4/3Q4/7K b - - 0 1"),
+            (Wdl::Draw, 0)
+        );
+        assert_eq!(
+            probe(&tablebase, "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"),
+            (Wdl::Draw, 0)
+        );
+        assert_eq!(
+            probe(&tablebase, "k7/8/1K6/8/8/8/8/6Q1 w - - 0 1"),
+            (Wdl::Win, 1)
+        );
+        assert_eq!(
+            probe(&tablebase, "k6Q/8/1K6/8/8/8/8/8 b - - 0 1"),
+            (Wdl::Loss, -1)
+        );
+
+        // a quick win still fits after 95 plies, a long one doesn't.
+        assert_eq!(
+            tablebase.probe(&mut board("k7/8/1K6/8/8/8/8/6Q1 w - - 95 80")),
+            Some(ProbeResult {
+                wdl: Wdl::Win,
+                dtz: 1
+            })
+        );
+        assert_eq!(
+            probe(&tablebase, "8/8/8/4k3/8/8/8/3QK3 w - - 99 80").0,
+            Wdl::CursedWin
+        );
+    }
+
+    #[test]
+    fn test_kpk() {
+        let tablebase = tablebase();
+        // with the pawn on the sixth, the king in front of it wins; behind it, black blockades.
+        assert_eq!(
+            probe(&tablebase, "4k3/8/4P3/4K3/8/8/8/8 w - - 0 1"),
+            (Wdl::Draw, 0)
+        );
+        assert_eq!(
+            probe(&tablebase, "4k3/8/4P3/4K3/8/8/8/8 b - - 0 1").0,
+            Wdl::Draw
+        );
+        assert_eq!(
+            probe(&tablebase, "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").0,
+            Wdl::Win
+        );
+        assert_eq!(
+            probe(&tablebase, "4k3/8/4K3/4P3/8/8/8/8 b - - 0 1").0,
+            Wdl::Loss
+        );
+        // a rook pawn with the black king in the corner, on either side of the board.
+        assert_eq!(
+            probe(&tablebase, "k7/8/8/8/8/8/P7/K7 w - - 0 1").0,
+            Wdl::Draw
+        );
+        assert_eq!(
+            probe(&tablebase, "7k/8/8/8/8/8/7P/7K w - - 0 1").0,
+            Wdl::Draw
+        );
+        // a pawn about to promote wins with a pawn move, whichever color it is.
+        assert_eq!(
+            probe(&tablebase, "8/4P3/8/8/8/k7/8/4K3 w - - 0 1"),
+            (Wdl::Win, 1)
+        );
+        assert_eq!(
+            probe(&tablebase, "4k3/8/K7/8/8/8/4p3/8 b - - 0 1"),
+            (Wdl::Win, 1)
+        );
+    }
+
+    #[test]
+    fn test_knnk() {
+        let tablebase = tablebase();
+        assert_eq!(
+            probe(&tablebase, "8/8/3k4/8/8/3K4/3N4/3N4 w - - 0 1"),
+            (Wdl::Draw, 0)
+        );
+        assert_eq!(
+            probe(&tablebase, "7k/5K2/5N2/4N3/8/8/8/8 w - - 0 1"),
+            (Wdl::Win, 1)
+        );
+    }
+
+    #[test]
+    fn test_krkr() {
+        let tablebase = tablebase();
+        // trading rooks or nothing to attack is a draw.
+        assert_eq!(
+            probe(&tablebase, "8/8/3k4/3r4/3R4/3K4/8/8 w - - 0 1"),
+            (Wdl::Draw, 0)
+        );
+        assert_eq!(
+            probe(&tablebase, "8/8/2k5/8/8/5K2/r7/7R w - - 0 1"),
+            (Wdl::Draw, 0)
+        );
+
+        // a skewer: Ra8+, the king moves, Rxh8.
+        let fen = "3k3r/8/8/8/8/8/8/R3K3 w - - 0 1";
+        assert_eq!(probe(&tablebase, fen), (Wdl::Win, 3));
+        // with black to move, Rh1+ skewers white's rook instead.
+        assert_eq!(probe(&tablebase, &fen.replace(" w ", " b ")), (Wdl::Win, 3));
+        let mut position = board(fen);
+        let ranked = tablebase.rank_root_moves(&mut position).unwrap();
+        assert_eq!(ranked.len(), get_legal_moves(&mut position).len());
+        assert_eq!(ranked[0].mv.to_string(), "a1a8");
+        assert_eq!((ranked[0].wdl, ranked[0].dtz), (Wdl::Win, 3));
+        assert!(ranked[1..]
+            .iter()
+            .all(|root_move| root_move.wdl <= Wdl::Draw));
+        assert_eq!(tablebase.best_root_moves(&mut position).unwrap().len(), 1);
+    }
+
+    // the official tables, when `SYZYGY_PATH` points to them. the tables in `TABLES` are written
+    // by this crate, so they can't catch a misreading of the format shared by both sides.
+    fn official_tablebase() -> Option<Tablebase<SyzygyTablebase>> {
+        let path = std::env::var("SYZYGY_PATH").ok()?;
+        let tablebase = Tablebase::new(SyzygyTablebase::open(path).unwrap());
+        assert!(tablebase.backend.max_pieces() >= 4);
+        Some(tablebase)
+    }
+
+    #[test]
+    fn test_official_tables() {
+        let Some(tablebase) = official_tablebase() else {
+            return;
+        };
+        let known = [
+            ("8/8/8/4k3/8/8/8/3QK3 w - - 0 1", Wdl::Win, None),
+            ("8/8/8/4k3/8/8/8/3QK3 b - - 0 1", Wdl::Loss, None),
+            ("8/8/8/8/8/3k4/3Q4/7K b - - 0 1", Wdl::Draw, Some(0)),
+            ("k7/8/1K6/8/8/8/8/6Q1 w - - 0 1", Wdl::Win, Some(1)),
+            ("k6Q/8/1K6/8/8/8/8/8 b - - 0 1", Wdl::Loss, Some(-1)),
+            ("8/8/8/4k3/8/8/8/3RK3 w - - 0 1", Wdl::Win, None),
+            ("4k3/8/4P3/4K3/8/8/8/8 w - - 0 1", Wdl::Draw, Some(0)),
+            ("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1", Wdl::Win, None),
+            ("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1", Wdl::Loss, None),
+            ("k7/8/8/8/8/8/P7/K7 w - - 0 1", Wdl::Draw, Some(0)),
+            ("8/4P3/8/8/8/k7/8/4K3 w - - 0 1", Wdl::Win, Some(1)),
+            ("4k3/8/K7/8/8/8/4p3/8 b - - 0 1", Wdl::Win, Some(1)),
+            ("8/8/3k4/8/8/3K4/3N4/3N4 w - - 0 1", Wdl::Draw, Some(0)),
+            ("7k/5K2/5N2/4N3/8/8/8/8 w - - 0 1", Wdl::Win, Some(1)),
+            ("8/8/2k5/8/8/5K2/r7/7R w - - 0 1", Wdl::Draw, Some(0)),
+            ("3k3r/8/8/8/8/8/8/R3K3 w - - 0 1", Wdl::Win, Some(3)),
+            ("3k3r/8/8/8/8/8/8/R3K3 b - - 0 1", Wdl::Win, Some(3)),
+        ];
+        for (fen, wdl, dtz) in known {
+            let (probed_wdl, probed_dtz) = probe(&tablebase, fen);
+            assert_eq!(probed_wdl, wdl, "{fen}");
+            if let Some(dtz) = dtz {
+                assert_eq!(probed_dtz, dtz, "{fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_official_tables_match_generated() {
+        let Some(official) = official_tablebase() else {
+            return;
+        };
+        let generated = tablebase();
+        let mut seed = 0x9E37_79B9_7F4A_7C15_u64;
+        let mut next = |bound: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % bound as u64) as usize
+        };
+        for material in ["KBvK", "KNvK", "KQvK", "KRvK", "KPvK", "KNNvK", "KRvKR"] {
+            let (white, black) = material.split_once('v').unwrap();
+            let pieces: Vec<char> = white
+                .chars()
+                .chain(black.chars().map(|piece| piece.to_ascii_lowercase()))
+                .collect();
+            let mut compared = 0;
+            while compared < 2000 {
+                let mut squares = ['1'; 64];
+                for &piece in &pieces {
+                    let mut square = next(64);
+                    while squares[square] != '1' {
+                        square = next(64);
+                    }
+                    squares[square] = piece;
+                }
+                let ranks: Vec<String> = squares.chunks(8).map(String::from_iter).collect();
+                let side = if next(2) == 0 { "w" } else { "b" };
+                let fen = format!("{} {side} - - 0 1", ranks.join("/"));
+                // skips pawns on the back rank and the side not to move in check.
+                let Ok(position) = ChessBoard::initialize_from_fen(&fen) else {
+                    continue;
+                };
+                let expected = generated.probe(&mut position.clone()).unwrap();
+                let probed = official.probe(&mut position.clone()).unwrap();
+                assert_eq!(probed.wdl, expected.wdl, "{fen}");
+                compared += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_missing_and_bad_tables() {
+        let tablebase = tablebase();
+        assert_eq!(tablebase.backend.max_pieces(), 4);
+        assert!(tablebase.covers(&board("4k3/8/8/8/8/8/8/2BBK3 w - - 0 1")));
+        // covered by piece count, but there is no KBBvK table.
+        assert_eq!(
+            tablebase.probe(&mut board("4k3/8/8/8/8/8/8/2BBK3 w - - 0 1")),
+            None
+        );
+        assert_eq!(
+            probe(&tablebase, "4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            (Wdl::Draw, 0)
+        );
+
+        let bytes = fs::read(format!("{TABLES}/KQvK.rtbw")).unwrap();
+        assert!(Table::parse(bytes.clone(), "KQvK", false).is_ok());
+        assert!(Table::parse(bytes.clone(), "KQvK", true).is_err());
+        assert!(Table::parse(bytes.clone(), "KRvK", false).is_err());
+        assert!(Table::parse(bytes[..20].to_vec(), "KQvK", false).is_err());
+    }
+}
@@ -0,0 +1,514 @@
+// this file probes endgame tablebases: win/draw/loss (WDL) and distance to zeroing (DTZ).
+//
+// the tables themselves come from a `TablebaseBackend`. like the Syzygy tables, a backend only
+// covers positions without castling rights, ignores en passant and assumes a fresh 50-move
+// counter; `Tablebase` adds those on top:
+//   - en passant captures are tried by hand and win over the table value when they are better,
+//   - `halfmove_clock` turns wins and losses that can't be completed within 100 plies into
+//     cursed wins and blessed losses,
+//   - root moves are ranked by DTZ, so an engine keeps making progress toward a won ending.
+//
+// `SyzygyFiles` finds local `.rtbw`/`.rtbz` files and checks their headers; the backend reading
+// them is `syzygy::SyzygyTablebase`.
+
+use crate::board::ChessBoard;
+use crate::moves::get_legal_moves;
+use crate::types::{Color, Move, PieceKind};
+use rustc_hash::FxHashMap;
+use std::cmp::Ordering;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+pub const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+pub const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+const WDL_EXTENSION: &str = "rtbw";
+const DTZ_EXTENSION: &str = "rtbz";
+
+/// The outcome of a position with best play, from the side to move's point of view.
+/// Cursed wins and blessed losses are decided by the 50-move rule: the win takes too long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    /// The same outcome seen from the other side.
+    pub const fn flip(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+
+    /// True for a win or loss the 50-move rule doesn't turn into a draw.
+    pub const fn is_decisive(self) -> bool {
+        matches!(self, Wdl::Win | Wdl::Loss)
+    }
+}
+
+/// Raw table lookups. Positions are never passed with castling rights or more pieces than
+/// `max_pieces()`; the en passant square and `halfmove_clock` are to be ignored.
+///
+/// DTZ is in plies to the next capture or pawn move (or mate) with best play, positive when
+/// the side to move wins and negative when it loses, and 0 for draws.
+pub trait TablebaseBackend {
+    fn max_pieces(&self) -> u32;
+
+    fn probe_wdl(&self, board: &ChessBoard) -> Option<Wdl>;
+
+    fn probe_dtz(&self, board: &ChessBoard) -> Option<i32>;
+}
+
+/// A probed position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeResult {
+    pub wdl: Wdl, // with the 50-move rule applied from the board's halfmove_clock
+    pub dtz: i32,
+}
+
+/// A legal root move with the outcome it leads to, best first in `Tablebase::rank_root_moves`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RootMove {
+    pub mv: Move,
+    pub wdl: Wdl,
+    pub dtz: i32, // plies to zeroing after this move, from the mover's point of view
+}
+
+impl RootMove {
+    // higher is better: the outcome first, then the quickest win or the slowest loss.
+    fn rank(&self) -> (Wdl, i32) {
+        (self.wdl, -self.dtz)
+    }
+}
+
+/// Probes a `TablebaseBackend`, handling what the raw tables leave out.
+#[derive(Debug, Clone)]
+pub struct Tablebase<B> {
+    pub backend: B,
+}
+
+impl<B: TablebaseBackend> Tablebase<B> {
+    pub fn new(backend: B) -> Self {
+        Tablebase { backend }
+    }
+
+    /// True if the position has few enough pieces and no castling rights.
+    pub fn covers(&self, board: &ChessBoard) -> bool {
+        board.castling_rights == 0
+            && (board.white_pieces | board.black_pieces).popcount() <= self.backend.max_pieces()
+    }
+
+    /// The outcome ignoring the 50-move counter, with en passant captures taken into account.
+    pub fn probe_wdl(&self, board: &mut ChessBoard) -> Option<Wdl> {
+        Some(self.probe_raw(board)?.0)
+    }
+
+    /// The outcome and DTZ, with en passant and the 50-move rule taken into account.
+    pub fn probe(&self, board: &mut ChessBoard) -> Option<ProbeResult> {
+        let (wdl, dtz) = self.probe_raw(board)?;
+        Some(ProbeResult {
+            wdl: apply_fifty_move_rule(wdl, dtz, board.halfmove_clock),
+            dtz,
+        })
+    }
+
+    /// Every legal move with its outcome, best first. The outcome of each move accounts for
+    /// the 50-move counter after it, so a win that would run out of moves ranks as cursed.
+    pub fn rank_root_moves(&self, board: &mut ChessBoard) -> Option<Vec<RootMove>> {
+        if !self.covers(board) {
+            return None;
+        }
+        let mut root_moves = Vec::new();
+        for mv in get_legal_moves(board) {
+            let undo_info = board.make_move(mv).ok()?;
+            let probed = self.probe_raw(board);
+            let halfmove_clock = board.halfmove_clock;
+            board.unmake_move(mv, &undo_info).ok()?;
+            let (child_wdl, child_dtz) = probed?;
+
+            let wdl = child_wdl.flip();
+            // a zeroing move reaches the next phase at once; otherwise it is one ply further
+            // from zeroing than the reply is.
+            let dtz = if halfmove_clock == 0 {
+                zeroing_dtz(wdl)
+            } else {
+                -child_dtz - child_dtz.signum()
+            };
+            root_moves.push(RootMove {
+                mv,
+                wdl: apply_fifty_move_rule(wdl, dtz, halfmove_clock),
+                dtz,
+            });
+        }
+        root_moves.sort_by_key(|root_move| std::cmp::Reverse(root_move.rank()));
+        Some(root_moves)
+    }
+
+    /// The moves that keep the best outcome reachable, e.g. to restrict a search at the root.
+    pub fn best_root_moves(&self, board: &mut ChessBoard) -> Option<Vec<Move>> {
+        let root_moves = self.rank_root_moves(board)?;
+        let best = root_moves.first()?.wdl;
+        Some(
+            root_moves
+                .iter()
+                .filter(|root_move| root_move.wdl == best)
+                .map(|root_move| root_move.mv)
+                .collect(),
+        )
+    }
+
+    // (wdl, dtz) ignoring the 50-move counter.
+    fn probe_raw(&self, board: &mut ChessBoard) -> Option<(Wdl, i32)> {
+        if !self.covers(board) {
+            return None;
+        }
+        let wdl = self.backend.probe_wdl(board)?;
+        let dtz = self.backend.probe_dtz(board)?;
+        if board.en_passant.is_empty() {
+            return Some((wdl, dtz));
+        }
+
+        // the tables don't know about en passant: play the captures and keep the best one
+        // if it beats the table, or if no other move is legal.
+        let legal_moves = get_legal_moves(board);
+        let mut best_ep: Option<Wdl> = None;
+        for &mv in legal_moves.iter().filter(|mv| mv.is_en_passant()) {
+            let undo_info = board.make_move(mv).ok()?;
+            let probed = self.probe_raw(board);
+            board.unmake_move(mv, &undo_info).ok()?;
+            let child_wdl = probed?.0.flip();
+            best_ep = best_ep.max(Some(child_wdl));
+        }
+        match best_ep {
+            Some(ep_wdl) if ep_wdl > wdl || legal_moves.iter().all(|mv| mv.is_en_passant()) => {
+                Some((ep_wdl, zeroing_dtz(ep_wdl)))
+            }
+            _ => Some((wdl, dtz)),
+        }
+    }
+}
+
+// the DTZ of a capture or pawn move into a position with outcome wdl (for the mover).
+fn zeroing_dtz(wdl: Wdl) -> i32 {
+    match wdl.cmp(&Wdl::Draw) {
+        Ordering::Greater => 1,
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+    }
+}
+
+// a win or loss that can't reach the next zeroing move before the 100th ply since the last
+// one is a draw under the 50-move rule.
+fn apply_fifty_move_rule(wdl: Wdl, dtz: i32, halfmove_clock: u8) -> Wdl {
+    if wdl.is_decisive() && dtz.abs() + halfmove_clock as i32 > 100 {
+        match wdl {
+            Wdl::Win => Wdl::CursedWin,
+            _ => Wdl::BlessedLoss,
+        }
+    } else {
+        wdl
+    }
+}
+
+/// The Syzygy name of the position's material, white's pieces first, e.g. "KRPvKR".
+pub fn material_name(board: &ChessBoard) -> String {
+    let side = |color: Color| -> String {
+        let own = board.pieces_of(color);
+        [
+            PieceKind::King,
+            PieceKind::Queen,
+            PieceKind::Rook,
+            PieceKind::Bishop,
+            PieceKind::Knight,
+            PieceKind::Pawn,
+        ]
+        .into_iter()
+        .flat_map(|kind| {
+            let count = (board.pieces(kind) & own).popcount() as usize;
+            std::iter::repeat_n(kind.letter().to_ascii_uppercase(), count)
+        })
+        .collect()
+    };
+    format!("{}v{}", side(Color::White), side(Color::Black))
+}
+
+/// The Syzygy files found in a set of directories, by material name.
+#[derive(Debug, Clone, Default)]
+pub struct SyzygyFiles {
+    wdl: FxHashMap<String, PathBuf>,
+    dtz: FxHashMap<String, PathBuf>,
+}
+
+impl SyzygyFiles {
+    pub fn new() -> Self {
+        SyzygyFiles::default()
+    }
+
+    /// Adds the `.rtbw`/`.rtbz` files in a directory. Files with another extension are skipped;
+    /// a tablebase file with the wrong magic number is an error.
+    pub fn add_directory(&mut self, dir: impl AsRef<Path>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let (Some(stem), Some(extension)) = (path.file_stem(), path.extension()) else {
+                continue;
+            };
+            let (Some(name), Some(extension)) = (stem.to_str(), extension.to_str()) else {
+                continue;
+            };
+            let (magic, files) = match extension {
+                WDL_EXTENSION => (WDL_MAGIC, &mut self.wdl),
+                DTZ_EXTENSION => (DTZ_MAGIC, &mut self.dtz),
+                _ => continue,
+            };
+            let mut header = [0u8; 4];
+            fs::File::open(&path)?.read_exact(&mut header)?;
+            if header != magic {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} is not a Syzygy table", path.display()),
+                ));
+            }
+            files.insert(name.to_string(), path);
+        }
+        Ok(())
+    }
+
+    /// The most pieces of any table found.
+    pub fn max_pieces(&self) -> u32 {
+        self.wdl
+            .keys()
+            .chain(self.dtz.keys())
+            .map(|name| name.chars().filter(|c| *c != 'v').count() as u32)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The WDL file for the position's material. Tables are stored with either side first.
+    pub fn wdl_path(&self, board: &ChessBoard) -> Option<&Path> {
+        find_table(&self.wdl, board)
+    }
+
+    /// The DTZ file for the position's material.
+    pub fn dtz_path(&self, board: &ChessBoard) -> Option<&Path> {
+        find_table(&self.dtz, board)
+    }
+}
+
+fn find_table<'a>(files: &'a FxHashMap<String, PathBuf>, board: &ChessBoard) -> Option<&'a Path> {
+    let name = material_name(board);
+    let (white, black) = name.split_once('v')?;
+    files
+        .get(&name)
+        .or_else(|| files.get(&format!("{black}v{white}")))
+        .map(PathBuf::as_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a backend answering from a list of positions (piece placement and side to move), with
+    // every other position a draw.
+    struct FixedTables(FxHashMap<String, (Wdl, i32)>);
+
+    impl FixedTables {
+        fn new(entries: &[(&str, Wdl, i32)]) -> Self {
+            FixedTables(
+                entries
+                    .iter()
+                    .map(|&(fen, wdl, dtz)| (position_key(fen), (wdl, dtz)))
+                    .collect(),
+            )
+        }
+
+        fn lookup(&self, board: &ChessBoard) -> (Wdl, i32) {
+            let key = position_key(&board.to_fen());
+            self.0.get(&key).copied().unwrap_or((Wdl::Draw, 0))
+        }
+    }
+
+    fn position_key(fen: &str) -> String {
+        fen.split(' ').take(2).collect::<Vec<_>>().join(" ")
+    }
+
+    impl TablebaseBackend for FixedTables {
+        fn max_pieces(&self) -> u32 {
+            4
+        }
+
+        fn probe_wdl(&self, board: &ChessBoard) -> Option<Wdl> {
+            Some(self.lookup(board).0)
+        }
+
+        fn probe_dtz(&self, board: &ChessBoard) -> Option<i32> {
+            Some(self.lookup(board).1)
+        }
+    }
+
+    fn board(fen: &str) -> ChessBoard {
+        ChessBoard::initialize_from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn test_coverage_and_fifty_move_rule() {
+        let tablebase = Tablebase::new(FixedTables::new(&[
+            ("8/8/8/4k3/8/8/8/3QK3 w - - 0 1", Wdl::Win, 19),
+            ("8/8/8/4k3/8/8/8/3QK3 b - - 0 1", Wdl::Loss, -18),
+        ]));
+
+        assert_eq!(tablebase.probe(&mut ChessBoard::initialize()), None);
+        assert_eq!(
+            tablebase.probe(&mut board("8/8/8/4k3/8/8/8/R3K3 w Q - 0 1")),
+            None
+        );
+
+        let result = tablebase.probe(&mut board("8/8/8/4k3/8/8/8/3QK3 w - - 0 1"));
+        assert_eq!(
+            result,
+            Some(ProbeResult {
+                wdl: Wdl::Win,
+                dtz: 19
+            })
+        );
+        // 19 more plies don't fit after 90 without a capture or pawn move.
+        let result = tablebase.probe(&mut board("8/8/8/4k3/8/8/8/3QK3 w - - 90 80"));
+        assert_eq!(
+            result,
+            Some(ProbeResult {
+                wdl: Wdl::CursedWin,
+                dtz: 19
+            })
+        );
+        let result = tablebase.probe(&mut board("8/8/8/4k3/8/8/8/3QK3 b - - 90 80"));
+        assert_eq!(
+            result,
+            Some(ProbeResult {
+                wdl: Wdl::BlessedLoss,
+                dtz: -18
+            })
+        );
+        assert_eq!(
+            tablebase.probe_wdl(&mut board("8/8/8/4k3/8/8/8/3QK3 b - - 90 80")),
+            Some(Wdl::Loss)
+        );
+    }
+
+    #[test]
+    fn test_en_passant() {
+        // the table calls it a draw, but taking en passant leaves black with a won ending.
+        let fen = "8/8/8/8/3pP3/8/8/K6k b - e3 0 1";
+        let tablebase = Tablebase::new(FixedTables::new(&[(
+            "8/8/8/8/8/4p3/8/K6k w - - 0 1",
+            Wdl::Loss,
+            -9,
+        )]));
+        assert_eq!(
+            tablebase.probe(&mut board(fen)),
+            Some(ProbeResult {
+                wdl: Wdl::Win,
+                dtz: 1
+            })
+        );
+        // without the en passant square the capture isn't there.
+        assert_eq!(
+            tablebase.probe(&mut board(&fen.replace("e3", "-"))),
+            Some(ProbeResult {
+                wdl: Wdl::Draw,
+                dtz: 0
+            })
+        );
+
+        // a worse capture doesn't change the table value.
+        let tablebase = Tablebase::new(FixedTables::new(&[
+            ("8/8/8/8/3pP3/8/8/K6k b - - 0 1", Wdl::Win, 5),
+            ("8/8/8/8/8/4p3/8/K6k w - - 0 1", Wdl::Draw, 0),
+        ]));
+        assert_eq!(
+            tablebase.probe(&mut board(fen)),
+            Some(ProbeResult {
+                wdl: Wdl::Win,
+                dtz: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_rank_root_moves() {
+        let fen = "8/8/8/4k3/8/8/4P3/3QK3 w - - 40 60";
+        let tablebase = Tablebase::new(FixedTables::new(&[
+            // after Qd4+ black loses with 7 plies to go; after Qd5+ with 3.
+            ("8/8/8/4k3/3Q4/8/4P3/4K3 b - - 0 1", Wdl::Loss, -7),
+            ("8/8/8/3Qk3/8/8/4P3/4K3 b - - 0 1", Wdl::Loss, -3),
+            // after e4 black loses at once (the pawn move zeroes the counter).
+            ("8/8/8/4k3/4P3/8/8/3QK3 b - - 0 1", Wdl::Loss, -50),
+            // after Qd3 black loses, but too slowly for the 50-move rule.
+            ("8/8/8/4k3/8/3Q4/4P3/4K3 b - - 0 1", Wdl::Loss, -61),
+        ]));
+
+        let mut position = board(fen);
+        let ranked = tablebase.rank_root_moves(&mut position).unwrap();
+        assert_eq!(ranked.len(), get_legal_moves(&mut position).len());
+        let summary: Vec<(String, Wdl, i32)> = ranked
+            .iter()
+            .take(4)
+            .map(|root_move| (root_move.mv.to_string(), root_move.wdl, root_move.dtz))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("e2e4".to_string(), Wdl::Win, 1),
+                ("d1d5".to_string(), Wdl::Win, 4),
+                ("d1d4".to_string(), Wdl::Win, 8),
+                ("d1d3".to_string(), Wdl::CursedWin, 62),
+            ]
+        );
+        assert!(ranked[4..]
+            .iter()
+            .all(|root_move| root_move.wdl == Wdl::Draw));
+        assert_eq!(tablebase.best_root_moves(&mut position).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_syzygy_files() {
+        assert_eq!(
+            material_name(&board("8/8/8/4k3/8/8/4P3/3QK3 w - - 0 1")),
+            "KQPvK"
+        );
+        assert_eq!(
+            material_name(&board("8/8/8/2r1k3/8/8/4P3/4K3 w - - 0 1")),
+            "KPvKR"
+        );
+
+        let dir = std::env::temp_dir().join(format!("oxi_syzygy_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("KRvKP.rtbw"), WDL_MAGIC).unwrap();
+        fs::write(dir.join("KRvKP.rtbz"), DTZ_MAGIC).unwrap();
+        fs::write(dir.join("README.txt"), "not a table").unwrap();
+
+        let mut files = SyzygyFiles::new();
+        files.add_directory(&dir).unwrap();
+        assert_eq!(files.max_pieces(), 4);
+        let position = board("8/8/8/2r1k3/8/8/4P3/4K3 w - - 0 1");
+        assert_eq!(
+            files.wdl_path(&position),
+            Some(dir.join("KRvKP.rtbw").as_path())
+        );
+        assert_eq!(
+            files.dtz_path(&position),
+            Some(dir.join("KRvKP.rtbz").as_path())
+        );
+        assert_eq!(files.wdl_path(&ChessBoard::initialize()), None);
+
+        fs::write(dir.join("KQvK.rtbw"), DTZ_MAGIC).unwrap();
+        assert!(SyzygyFiles::new().add_directory(&dir).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,526 @@
+// this file caches search results and perft counts by ChessBoard::zobrist_hash.
+//
+// every entry is a (key, data) pair of u64s. search entries pack the data as
+//   bits 0-7 depth, 8-9 bound, 10-15 age, 16-31 score (i16), 32-47 move (0 = none)
+// and perft entries as
+//   bits 0-7 depth, 10-15 age, 16-63 node count.
+// data 0 marks an empty slot (search entries always have a bound, perft entries a count).
+
+use crate::search::MATE_BOUND;
+use crate::types::Move;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+const BUCKET_SIZE: usize = 4;
+const BYTES_PER_BUCKET: usize = BUCKET_SIZE * 16;
+const AGE_MASK: u8 = 0b11_1111;
+const MAX_PERFT_NODES: u64 = (1 << 48) - 1;
+
+/// How a stored score relates to the true score of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower, // the search failed high: score is at least this
+    Upper, // the search failed low: score is at most this
+}
+
+/// Which entry of a full bucket a new entry evicts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplacementPolicy {
+    /// Always store, over the shallowest entry.
+    Always,
+    /// Keep deeper entries: a shallower result is dropped, even over the same position's entry
+    /// (unless it is exact).
+    DepthPreferred,
+    /// Always store, over entries from earlier searches first, then the shallowest.
+    #[default]
+    AgeThenDepth,
+}
+
+/// A probed search entry. The score is already adjusted to the probing ply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtEntry {
+    pub mv: Option<Move>,
+    pub score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+    pub age: u8,
+}
+
+/// Storage for one entry. `TranspositionTable` uses plain cells, `SharedTranspositionTable`
+/// atomics that several threads can read and write without locking.
+pub trait Slot: Default {
+    /// (key, data); data 0 is an empty slot.
+    fn load(&self) -> (u64, u64);
+    fn store(&self, key: u64, data: u64);
+}
+
+#[derive(Debug, Default)]
+pub struct LocalSlot {
+    key: Cell<u64>,
+    data: Cell<u64>,
+}
+
+impl Slot for LocalSlot {
+    fn load(&self) -> (u64, u64) {
+        (self.key.get(), self.data.get())
+    }
+
+    fn store(&self, key: u64, data: u64) {
+        self.key.set(key);
+        self.data.set(data);
+    }
+}
+
+// the key is stored xor-ed with the data, so an entry half-written by another thread fails
+// the key check instead of returning another position's data.
+#[derive(Debug, Default)]
+pub struct SharedSlot {
+    key_xor_data: AtomicU64,
+    data: AtomicU64,
+}
+
+impl Slot for SharedSlot {
+    fn load(&self) -> (u64, u64) {
+        let key_xor_data = self.key_xor_data.load(Ordering::Relaxed);
+        let data = self.data.load(Ordering::Relaxed);
+        (key_xor_data ^ data, data)
+    }
+
+    fn store(&self, key: u64, data: u64) {
+        self.key_xor_data.store(key ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+}
+
+/// A fixed-size hash table of buckets of four entries, sized to a power of two.
+#[derive(Debug)]
+pub struct Table<S: Slot> {
+    slots: Vec<S>,
+    bucket_mask: usize,
+    generation: AtomicU8, // advanced by new_search, which threads sharing the table may call
+    pub policy: ReplacementPolicy,
+}
+
+/// Transposition table for a single thread.
+pub type TranspositionTable = Table<LocalSlot>;
+
+/// Lock-free transposition table that several search threads can share by reference.
+pub type SharedTranspositionTable = Table<SharedSlot>;
+
+/// Converts a score relative to the root into one relative to the node at `ply`, so a stored
+/// mate stays correct when the position is reached at another ply.
+pub fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score + ply as i32
+    } else if score <= -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+/// The inverse of `score_to_tt`.
+pub fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if score >= MATE_BOUND {
+        score - ply as i32
+    } else if score <= -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+fn pack(mv: Option<Move>, score: i32, depth: u8, bound: Bound, age: u8) -> u64 {
+    let bound_bits: u64 = match bound {
+        Bound::Exact => 1,
+        Bound::Lower => 2,
+        Bound::Upper => 3,
+    };
+    let score = score.clamp(i16::MIN as i32, i16::MAX as i32) as i16 as u16;
+    depth as u64
+        | bound_bits << 8
+        | ((age & AGE_MASK) as u64) << 10
+        | (score as u64) << 16
+        | (mv.map_or(0, Move::raw) as u64) << 32
+}
+
+fn unpack(data: u64) -> Option<TtEntry> {
+    let bound = match (data >> 8) & 0b11 {
+        1 => Bound::Exact,
+        2 => Bound::Lower,
+        3 => Bound::Upper,
+        _ => return None,
+    };
+    let raw_move = (data >> 32) as u16;
+    Some(TtEntry {
+        mv: (raw_move != 0).then(|| Move::from_raw(raw_move)),
+        score: (data >> 16) as u16 as i16 as i32,
+        depth: data as u8,
+        bound,
+        age: (data >> 10) as u8 & AGE_MASK,
+    })
+}
+
+// perft counts for the same position at different depths get different keys.
+fn perft_key(key: u64, depth: u8) -> u64 {
+    key ^ (depth as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+impl<S: Slot> Table<S> {
+    /// A table using at most `mb` megabytes (at least one bucket).
+    pub fn new(mb: usize) -> Self {
+        let mut table = Table {
+            slots: Vec::new(),
+            bucket_mask: 0,
+            generation: AtomicU8::new(0),
+            policy: ReplacementPolicy::default(),
+        };
+        table.resize(mb);
+        table
+    }
+
+    pub fn with_policy(mut self, policy: ReplacementPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Reallocates the table for `mb` megabytes, dropping every entry.
+    pub fn resize(&mut self, mb: usize) {
+        let max_buckets = (mb * 1024 * 1024 / BYTES_PER_BUCKET).max(1);
+        let buckets = if max_buckets.is_power_of_two() {
+            max_buckets
+        } else {
+            max_buckets.next_power_of_two() / 2
+        };
+        self.slots = (0..buckets * BUCKET_SIZE).map(|_| S::default()).collect();
+        self.bucket_mask = buckets - 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter().for_each(|slot| slot.store(0, 0));
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    /// Number of entries the table holds.
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Marks the start of a new search, so the entries of earlier ones age. `search_with` calls
+    /// this itself.
+    pub fn new_search(&self) {
+        let next = (self.generation() + 1) & AGE_MASK;
+        self.generation.store(next, Ordering::Relaxed);
+    }
+
+    fn generation(&self) -> u8 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Permille of the first thousand entries used by the current search, as UCI reports it.
+    pub fn hashfull(&self) -> usize {
+        let sample = self.slots.len().min(1000);
+        let used = self.slots[..sample]
+            .iter()
+            .filter(|slot| {
+                let (_, data) = slot.load();
+                data != 0 && (data >> 10) as u8 & AGE_MASK == self.generation()
+            })
+            .count();
+        used * 1000 / sample
+    }
+
+    fn bucket(&self, key: u64) -> &[S] {
+        let start = (key as usize & self.bucket_mask) * BUCKET_SIZE;
+        &self.slots[start..start + BUCKET_SIZE]
+    }
+
+    fn find(&self, key: u64) -> Option<u64> {
+        self.bucket(key).iter().find_map(|slot| {
+            let (slot_key, data) = slot.load();
+            (slot_key == key && data != 0).then_some(data)
+        })
+    }
+
+    pub fn probe(&self, key: u64, ply: usize) -> Option<TtEntry> {
+        let mut entry = unpack(self.find(key)?)?;
+        entry.score = score_from_tt(entry.score, ply);
+        Some(entry)
+    }
+
+    /// Stores a search result found at `ply`; mate scores are made relative to the position.
+    pub fn store(
+        &self,
+        key: u64,
+        ply: usize,
+        mv: Option<Move>,
+        score: i32,
+        depth: u8,
+        bound: Bound,
+    ) {
+        let data = pack(mv, score_to_tt(score, ply), depth, bound, self.generation());
+        self.store_data(key, data, bound == Bound::Exact);
+    }
+
+    pub fn probe_perft(&self, key: u64, depth: u8) -> Option<u64> {
+        let data = self.find(perft_key(key, depth))?;
+        (data as u8 == depth).then_some(data >> 16)
+    }
+
+    /// Stores the node count of a perft of `depth` from the position. Counts that don't fit in
+    /// 48 bits are not stored.
+    pub fn store_perft(&self, key: u64, depth: u8, nodes: u64) {
+        if nodes <= MAX_PERFT_NODES {
+            let data = depth as u64 | (self.generation() as u64) << 10 | nodes << 16;
+            self.store_data(perft_key(key, depth), data, true);
+        }
+    }
+
+    // picks the slot for a new entry by the replacement policy, or drops the entry.
+    fn store_data(&self, key: u64, data: u64, exact: bool) {
+        let depth = data as u8;
+        let bucket = self.bucket(key);
+        if let Some(slot) = bucket.iter().find(|slot| slot.load().0 == key) {
+            let (_, slot_data) = slot.load();
+            let keep_deeper = self.policy == ReplacementPolicy::DepthPreferred
+                && !exact
+                && depth < slot_data as u8;
+            if !keep_deeper {
+                slot.store(key, data);
+            }
+            return;
+        }
+
+        let mut victim = 0;
+        let mut victim_value = i32::MAX;
+        for (i, slot) in bucket.iter().enumerate() {
+            let (_, slot_data) = slot.load();
+            if slot_data == 0 {
+                slot.store(key, data);
+                return;
+            }
+            let slot_depth = slot_data as u8 as i32;
+            let value = match self.policy {
+                ReplacementPolicy::Always | ReplacementPolicy::DepthPreferred => slot_depth,
+                ReplacementPolicy::AgeThenDepth => {
+                    let slot_age = (slot_data >> 10) as u8 & AGE_MASK;
+                    let age = self.generation().wrapping_sub(slot_age) & AGE_MASK;
+                    slot_depth - 8 * age as i32
+                }
+            };
+            if value < victim_value {
+                victim = i;
+                victim_value = value;
+            }
+        }
+        if self.policy == ReplacementPolicy::DepthPreferred && (depth as i32) < victim_value {
+            return;
+        }
+        bucket[victim].store(key, data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::ChessBoard;
+    use crate::eval::TaperedEvaluator;
+    use crate::search::{search_with, SearchLimits, MATE};
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    // keys that all land in bucket 0 of a table.
+    fn colliding_key<S: Slot>(table: &Table<S>, i: u64) -> u64 {
+        (i + 1) * (table.bucket_mask as u64 + 1)
+    }
+
+    #[test]
+    fn test_store_and_probe() {
+        let table = TranspositionTable::new(1);
+        assert_eq!(table.capacity(), 1024 * 1024 / 16);
+
+        let mv: Move = "e2e4".parse().unwrap();
+        table.store(0x1234, 0, Some(mv), -150, 7, Bound::Lower);
+        assert_eq!(
+            table.probe(0x1234, 0),
+            Some(TtEntry {
+                mv: Some(mv),
+                score: -150,
+                depth: 7,
+                bound: Bound::Lower,
+                age: 0
+            })
+        );
+        assert_eq!(table.probe(0x1235, 0), None);
+
+        table.store(0x99, 3, None, 20, 0, Bound::Exact);
+        let entry = table.probe(0x99, 3).unwrap();
+        assert_eq!(
+            (entry.mv, entry.score, entry.bound),
+            (None, 20, Bound::Exact)
+        );
+
+        // a mate found 3 plies into the search, 2 plies after this node, reached again at ply 5.
+        let mate_in_5_plies = MATE - 5;
+        table.store(0x77, 3, None, mate_in_5_plies, 4, Bound::Exact);
+        assert_eq!(table.probe(0x77, 5).unwrap().score, MATE - 7);
+        table.store(0x78, 3, None, -mate_in_5_plies, 4, Bound::Exact);
+        assert_eq!(table.probe(0x78, 1).unwrap().score, -(MATE - 3));
+        assert_eq!(score_from_tt(score_to_tt(-MATE + 9, 4), 4), -MATE + 9);
+        assert_eq!(score_to_tt(250, 10), 250);
+
+        table.store_perft(0x1234, 5, 4_865_609);
+        assert_eq!(table.probe_perft(0x1234, 5), Some(4_865_609));
+        assert_eq!(table.probe_perft(0x1234, 4), None);
+        // the search entry for the same position is still there.
+        assert_eq!(table.probe(0x1234, 0).unwrap().depth, 7);
+
+        let mut table = table;
+        table.clear();
+        assert_eq!(table.probe(0x1234, 0), None);
+        assert_eq!(table.probe_perft(0x1234, 5), None);
+    }
+
+    #[test]
+    fn test_sizing() {
+        assert_eq!(TranspositionTable::new(0).capacity(), BUCKET_SIZE);
+        assert_eq!(
+            TranspositionTable::new(16).capacity(),
+            16 * 1024 * 1024 / 16
+        );
+        // rounded down to a power of two.
+        assert_eq!(
+            TranspositionTable::new(24).capacity(),
+            16 * 1024 * 1024 / 16
+        );
+
+        let mut table = SharedTranspositionTable::new(1);
+        table.store(5, 0, None, 0, 1, Bound::Exact);
+        table.resize(2);
+        assert_eq!(table.capacity(), 2 * 1024 * 1024 / 16);
+        assert_eq!(table.probe(5, 0), None);
+    }
+
+    #[test]
+    fn test_replacement() {
+        let table = TranspositionTable::new(1);
+        let keys: Vec<u64> = (0..5).map(|i| colliding_key(&table, i)).collect();
+        for (depth, &key) in [4, 2, 6, 3].iter().zip(&keys) {
+            table.store(key, 0, None, 0, *depth, Bound::Exact);
+        }
+        // the bucket is full: the shallowest entry (depth 2) goes.
+        table.store(keys[4], 0, None, 0, 1, Bound::Exact);
+        assert!(table.probe(keys[1], 0).is_none());
+        assert!(table.probe(keys[4], 0).is_some());
+
+        // entries from an earlier search go before shallower current ones.
+        table.new_search();
+        table.store(keys[4], 0, None, 0, 1, Bound::Exact);
+        table.store(keys[1], 0, None, 0, 1, Bound::Exact);
+        assert!(table.probe(keys[4], 0).is_some());
+        assert!(table.probe(keys[3], 0).is_none());
+        assert_eq!(table.probe(keys[1], 0).unwrap().age, 1);
+
+        // depth preferred keeps deeper entries, also of the same position.
+        let table = TranspositionTable::new(1).with_policy(ReplacementPolicy::DepthPreferred);
+        for (depth, &key) in [4, 2, 6, 3].iter().zip(&keys) {
+            table.store(key, 0, None, 0, *depth, Bound::Exact);
+        }
+        table.store(keys[4], 0, None, 0, 1, Bound::Lower);
+        assert!(table.probe(keys[4], 0).is_none());
+        table.store(keys[0], 0, None, 50, 3, Bound::Upper);
+        assert_eq!(table.probe(keys[0], 0).unwrap().bound, Bound::Exact);
+        table.store(keys[0], 0, None, 50, 5, Bound::Upper);
+        assert_eq!(table.probe(keys[0], 0).unwrap().bound, Bound::Upper);
+
+        let table = TranspositionTable::new(1).with_policy(ReplacementPolicy::Always);
+        table.store(keys[0], 0, None, 0, 9, Bound::Exact);
+        table.store(keys[0], 0, None, 0, 1, Bound::Upper);
+        assert_eq!(table.probe(keys[0], 0).unwrap().depth, 1);
+    }
+
+    #[test]
+    fn test_hashfull() {
+        let table = TranspositionTable::new(1);
+        assert_eq!(table.hashfull(), 0);
+        for key in 0..250 {
+            table.store(key, 0, None, 0, 1, Bound::Exact);
+        }
+        assert_eq!(table.hashfull(), 250);
+        table.new_search();
+        assert_eq!(table.hashfull(), 0);
+    }
+
+    #[test]
+    fn test_shared_table() {
+        let table = SharedTranspositionTable::new(1);
+        thread::scope(|scope| {
+            for t in 0..4u64 {
+                let table = &table;
+                scope.spawn(move || {
+                    for key in (t * 1000)..(t * 1000 + 1000) {
+                        table.store_perft(key, 3, key * 7);
+                    }
+                });
+            }
+        });
+        for key in 0..4000 {
+            assert_eq!(table.probe_perft(key, 3), Some(key * 7));
+        }
+    }
+
+    #[test]
+    fn test_search_with_table() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut board = ChessBoard::initialize_from_fen(fen).unwrap();
+        let limits = SearchLimits::depth(4);
+        let evaluator = TaperedEvaluator::default();
+        let stop = AtomicBool::new(false);
+        let plain = search_with(&mut board, &limits, &evaluator, None, &stop, |_| {});
+
+        let table = SharedTranspositionTable::new(4);
+        let hashed = search_with(&mut board, &limits, &evaluator, Some(&table), &stop, |_| {});
+        assert_eq!(hashed.score, plain.score);
+        assert!(hashed.nodes < plain.nodes);
+        assert!(table.probe(board.zobrist_hash, 0).is_some());
+
+        // the mate is still found, and found at the right distance.
+        let mut board = ChessBoard::initialize_from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+        let table = SharedTranspositionTable::new(1);
+        let result = search_with(
+            &mut board,
+            &SearchLimits::depth(5),
+            &evaluator,
+            Some(&table),
+            &stop,
+            |_| {},
+        );
+        assert_eq!(result.score, crate::search::Score::Mate(2));
+    }
+
+    #[test]
+    fn test_search_with_ages_entries() {
+        // a bucket full of deep entries from before the search.
+        let table = SharedTranspositionTable::new(1);
+        let keys: Vec<u64> = (0..5).map(|i| colliding_key(&table, i)).collect();
+        for &key in &keys[..4] {
+            table.store(key, 0, None, 0, 5, Bound::Exact);
+        }
+
+        let mut board = ChessBoard::initialize();
+        let limits = SearchLimits::depth(2);
+        let evaluator = TaperedEvaluator::default();
+        let stop = AtomicBool::new(false);
+        search_with(&mut board, &limits, &evaluator, Some(&table), &stop, |_| {});
+        assert_eq!(table.probe(board.zobrist_hash, 0).unwrap().age, 1);
+
+        // a shallower entry of the current search replaces one of them.
+        table.store(keys[4], 0, None, 0, 1, Bound::Exact);
+        assert_eq!(table.probe(keys[4], 0).unwrap().age, 1);
+        let old = keys[..4]
+            .iter()
+            .filter(|&&key| table.probe(key, 0).is_some());
+        assert_eq!(old.count(), 3);
+    }
+}
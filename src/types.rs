@@ -0,0 +1,467 @@
+// this file defines the typed building blocks of the API: squares, colors, pieces and moves.
+
+use crate::bitboard::Bitboard;
+use crate::error::ChessError;
+use std::fmt;
+use std::ops::Not;
+use std::str::FromStr;
+
+/// Side to move / piece color.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    pub const fn is_white(self) -> bool {
+        matches!(self, Color::White)
+    }
+
+    pub const fn is_black(self) -> bool {
+        matches!(self, Color::Black)
+    }
+
+    // 0 for white, 1 for black. used to index per-color tables.
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Not for Color {
+    type Output = Color;
+
+    fn not(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+impl From<bool> for Color {
+    // true = white, matching the historical bool convention.
+    fn from(white: bool) -> Self {
+        if white {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", if self.is_white() { 'w' } else { 'b' })
+    }
+}
+
+impl FromStr for Color {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "w" | "white" => Ok(Color::White),
+            "b" | "black" => Ok(Color::Black),
+            _ => Err(ChessError::InvalidFen {
+                field: crate::error::FenField::SideToMove,
+                reason: format!("expected 'w' or 'b', found '{s}'"),
+            }),
+        }
+    }
+}
+
+/// A square index, 0 = a1 through 63 = h8 (the same order as the bitboards).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    pub const A1: Square = Square(0);
+    pub const C1: Square = Square(2);
+    pub const E1: Square = Square(4);
+    pub const G1: Square = Square(6);
+    pub const H1: Square = Square(7);
+    pub const A8: Square = Square(56);
+    pub const C8: Square = Square(58);
+    pub const E8: Square = Square(60);
+    pub const G8: Square = Square(62);
+    pub const H8: Square = Square(63);
+
+    /// Panics if `index` is not below 64.
+    pub const fn new(index: u8) -> Self {
+        assert!(index < 64, "square index out of range");
+        Square(index)
+    }
+
+    /// `file` and `rank` are 0-based (0 = a-file / 1st rank).
+    pub const fn from_file_rank(file: u8, rank: u8) -> Self {
+        Square::new(rank * 8 + file)
+    }
+
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+
+    pub const fn file(self) -> u8 {
+        self.0 % 8
+    }
+
+    pub const fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    pub const fn bb(self) -> Bitboard {
+        Bitboard(1 << self.0)
+    }
+
+    /// The square `delta` indices away, if it is still on the board.
+    pub const fn offset(self, delta: i8) -> Option<Square> {
+        let index = self.0 as i8 + delta;
+        if index >= 0 && index < 64 {
+            Some(Square(index as u8))
+        } else {
+            None
+        }
+    }
+}
+
+impl TryFrom<u8> for Square {
+    type Error = ChessError;
+
+    fn try_from(index: u8) -> Result<Self, Self::Error> {
+        if index < 64 {
+            Ok(Square(index))
+        } else {
+            Err(ChessError::InvalidSquare(index.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file()) as char, self.rank() + 1)
+    }
+}
+
+impl FromStr for Square {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.as_bytes() {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Ok(Square::from_file_rank(file - b'a', rank - b'1'))
+            }
+            _ => Err(ChessError::InvalidSquare(s.to_string())),
+        }
+    }
+}
+
+/// Piece type without color. The discriminants match the board's piece codes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum PieceKind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+impl PieceKind {
+    pub const ALL: [PieceKind; 6] = [
+        PieceKind::Pawn,
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+        PieceKind::King,
+    ];
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub const fn from_index(index: u8) -> Option<PieceKind> {
+        if index < 6 {
+            Some(PieceKind::ALL[index as usize])
+        } else {
+            None
+        }
+    }
+
+    // uppercase letter, as used in SAN.
+    pub const fn letter(self) -> char {
+        match self {
+            PieceKind::Pawn => 'P',
+            PieceKind::Knight => 'N',
+            PieceKind::Bishop => 'B',
+            PieceKind::Rook => 'R',
+            PieceKind::Queen => 'Q',
+            PieceKind::King => 'K',
+        }
+    }
+}
+
+impl fmt::Display for PieceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.letter())
+    }
+}
+
+impl FromStr for PieceKind {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => PieceKind::ALL
+                .into_iter()
+                .find(|kind| kind.letter() == c.to_ascii_uppercase())
+                .ok_or(ChessError::InvalidPiece(s.to_string())),
+            _ => Err(ChessError::InvalidPiece(s.to_string())),
+        }
+    }
+}
+
+/// A colored piece.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Piece {
+    pub color: Color,
+    pub kind: PieceKind,
+}
+
+impl Piece {
+    pub const fn new(color: Color, kind: PieceKind) -> Self {
+        Piece { color, kind }
+    }
+
+    // index into the zobrist piece keys (white PNBRQK, then black pnbrqk).
+    pub const fn index(self) -> usize {
+        self.color.index() * 6 + self.kind.index()
+    }
+
+    // FEN character: uppercase for white, lowercase for black.
+    pub const fn fen_char(self) -> char {
+        match self.color {
+            Color::White => self.kind.letter(),
+            Color::Black => self.kind.letter().to_ascii_lowercase(),
+        }
+    }
+}
+
+impl fmt::Display for Piece {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.fen_char())
+    }
+}
+
+impl FromStr for Piece {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let kind: PieceKind = s.parse()?;
+        let color = Color::from(s.chars().all(|c| c.is_ascii_uppercase()));
+        Ok(Piece::new(color, kind))
+    }
+}
+
+/// A move packed into 16 bits: from square << 10 | to square << 4 | flag.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default, PartialOrd, Ord)]
+pub struct Move(u16);
+
+impl Move {
+    // move type flags. promotions add the promotion piece (knight = 0 .. queen = 3).
+    pub const QUIET: u8 = 0;
+    pub const CAPTURE: u8 = 1;
+    pub const CASTLE: u8 = 2;
+    pub const EN_PASSANT: u8 = 3;
+    pub const PROMOTION: u8 = 4;
+    pub const PROMOTION_CAPTURE: u8 = 8;
+
+    /// Packs a move from raw parts. `flag` is one of the flag constants (plus promotion offset).
+    pub const fn new(from: Square, to: Square, flag: u8) -> Self {
+        Move(((from.0 as u16) << 10) | ((to.0 as u16) << 4) | (flag & 0b1111) as u16)
+    }
+
+    pub const fn quiet(from: Square, to: Square) -> Self {
+        Move::new(from, to, Move::QUIET)
+    }
+
+    pub const fn capture(from: Square, to: Square) -> Self {
+        Move::new(from, to, Move::CAPTURE)
+    }
+
+    /// Castling is encoded as the king's move (e1g1), or in Chess960 as king-takes-rook (e1h1).
+    pub const fn castle(from: Square, to: Square) -> Self {
+        Move::new(from, to, Move::CASTLE)
+    }
+
+    pub const fn en_passant(from: Square, to: Square) -> Self {
+        Move::new(from, to, Move::EN_PASSANT)
+    }
+
+    /// Panics if `kind` is not a knight, bishop, rook or queen.
+    pub const fn promote(from: Square, to: Square, kind: PieceKind, capture: bool) -> Self {
+        assert!(
+            matches!(
+                kind,
+                PieceKind::Knight | PieceKind::Bishop | PieceKind::Rook | PieceKind::Queen
+            ),
+            "invalid promotion piece"
+        );
+        let base = if capture {
+            Move::PROMOTION_CAPTURE
+        } else {
+            Move::PROMOTION
+        };
+        Move::new(from, to, base + kind as u8 - 1)
+    }
+
+    pub const fn from_raw(raw: u16) -> Self {
+        Move(raw)
+    }
+
+    pub const fn raw(self) -> u16 {
+        self.0
+    }
+
+    pub const fn from(self) -> Square {
+        Square((self.0 >> 10) as u8)
+    }
+
+    pub const fn to(self) -> Square {
+        Square(((self.0 >> 4) & 0b111111) as u8)
+    }
+
+    pub const fn flag(self) -> u8 {
+        (self.0 & 0b1111) as u8
+    }
+
+    pub const fn promotion(self) -> Option<PieceKind> {
+        match self.flag() {
+            4..=11 => PieceKind::from_index((self.flag() - 4) % 4 + 1),
+            _ => None,
+        }
+    }
+
+    pub const fn is_promotion(self) -> bool {
+        matches!(self.flag(), 4..=11)
+    }
+
+    /// True for every capture, including en passant and promotion captures.
+    pub const fn is_capture(self) -> bool {
+        matches!(self.flag(), 1 | 3 | 8..=11)
+    }
+
+    pub const fn is_castle(self) -> bool {
+        self.flag() == Move::CASTLE
+    }
+
+    /// True for O-O under either castling encoding: the king heads towards the h-file.
+    pub const fn is_kingside_castle(self) -> bool {
+        self.is_castle() && self.to().file() > self.from().file()
+    }
+
+    pub const fn is_en_passant(self) -> bool {
+        self.flag() == Move::EN_PASSANT
+    }
+}
+
+/// Displays the move in UCI notation ("e2e4", "e7e8q").
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.from(), self.to())?;
+        if let Some(kind) = self.promotion() {
+            write!(f, "{}", kind.letter().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses UCI notation. Only promotion flags are set; capture, castle and en passant flags
+/// depend on the position (see `ChessGame::make_move_from_uci()`).
+impl FromStr for Move {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if ![4, 5].contains(&s.len()) || !s.is_ascii() {
+            return Err(ChessError::InvalidUci(s.to_string()));
+        }
+        let from: Square = s[0..2].parse()?;
+        let to: Square = s[2..4].parse()?;
+        match s[4..].chars().next() {
+            None => Ok(Move::quiet(from, to)),
+            Some(c @ ('n' | 'b' | 'r' | 'q')) => {
+                let kind = PieceKind::from_str(&c.to_string())?;
+                Ok(Move::promote(from, to, kind, false))
+            }
+            Some(_) => Err(ChessError::InvalidPromotion(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let e4: Square = "e4".parse().unwrap();
+        assert_eq!(e4, Square::new(28));
+        assert_eq!((e4.file(), e4.rank()), (4, 3));
+        assert_eq!(e4.bb(), Bitboard(0x0000000010000000));
+        assert_eq!(e4.to_string(), "e4");
+        assert_eq!(Square::from_file_rank(7, 7), Square::H8);
+        assert_eq!(Square::A1.offset(-1), None);
+        assert_eq!(Square::E1.offset(8), Some(Square::new(12)));
+        assert!("i1".parse::<Square>().is_err());
+        assert!("a9".parse::<Square>().is_err());
+        assert!(Square::try_from(64).is_err());
+    }
+
+    #[test]
+    fn test_color_and_piece() {
+        assert_eq!(!Color::White, Color::Black);
+        assert_eq!(Color::from(false), Color::Black);
+        assert_eq!("w".parse::<Color>(), Ok(Color::White));
+        assert!("x".parse::<Color>().is_err());
+
+        let black_knight = Piece::new(Color::Black, PieceKind::Knight);
+        assert_eq!(black_knight.to_string(), "n");
+        assert_eq!(black_knight.index(), 7);
+        assert_eq!("n".parse::<Piece>(), Ok(black_knight));
+        assert_eq!(
+            "Q".parse::<Piece>(),
+            Ok(Piece::new(Color::White, PieceKind::Queen))
+        );
+        assert!("x".parse::<Piece>().is_err());
+        assert_eq!(PieceKind::from_index(3), Some(PieceKind::Rook));
+    }
+
+    #[test]
+    fn test_move() {
+        let e2 = Square::new(12);
+        let e4 = Square::new(28);
+        let mv = Move::quiet(e2, e4);
+        assert_eq!(mv.raw(), 0b0011000111000000);
+        assert_eq!((mv.from(), mv.to(), mv.flag()), (e2, e4, Move::QUIET));
+        assert!(!mv.is_capture() && !mv.is_castle() && !mv.is_promotion());
+        assert_eq!(mv.to_string(), "e2e4");
+        assert_eq!("e2e4".parse::<Move>(), Ok(mv));
+
+        let promo = Move::promote(Square::new(53), Square::H8, PieceKind::Queen, true);
+        assert_eq!(promo.flag(), 11);
+        assert_eq!(promo.promotion(), Some(PieceKind::Queen));
+        assert!(promo.is_capture());
+        assert_eq!(promo.to_string(), "f7h8q");
+        assert_eq!(
+            "f7h8q".parse::<Move>().map(|m| m.flag()),
+            Ok(Move::PROMOTION + 3)
+        );
+
+        assert!(Move::castle(Square::E1, Square::G1).is_castle());
+        assert!(Move::en_passant(Square::new(36), Square::new(43)).is_capture());
+        assert!("e2".parse::<Move>().is_err());
+        assert!("e7e8k".parse::<Move>().is_err());
+    }
+}
@@ -0,0 +1,748 @@
+// this file speaks the Universal Chess Interface (UCI) protocol on behalf of a searcher.
+
+use crate::error::ChessError;
+use crate::game::{ChessGame, DrawReason, GameResult, TimeControl};
+use crate::moves;
+pub use crate::search::Score;
+use crate::types::Move;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// Search limits sent with `go`. Times are in milliseconds; unset fields are None.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GoParams {
+    pub searchmoves: Vec<String>, // uci moves to restrict the search to, empty = all
+    pub ponder: bool,
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u32>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub mate: Option<u32>,
+    pub movetime: Option<u64>,
+    pub infinite: bool,
+}
+
+/// A command sent from the GUI to the engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UciCommand {
+    Uci,
+    Debug(bool),
+    IsReady,
+    SetOption {
+        name: String,
+        value: Option<String>,
+    },
+    UciNewGame,
+    Position {
+        fen: Option<String>,
+        moves: Vec<String>,
+    }, // fen None = startpos
+    Go(GoParams),
+    Stop,
+    PonderHit,
+    Quit,
+}
+
+fn invalid_command(line: &str) -> ChessError {
+    ChessError::InvalidUciCommand(line.to_string())
+}
+
+// the value following a `go` keyword.
+fn parse_go_value<T: FromStr>(line: &str, value: Option<&str>) -> Result<T, ChessError> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| invalid_command(line))
+}
+
+fn parse_go(line: &str, tokens: &[&str]) -> Result<GoParams, ChessError> {
+    let mut params = GoParams::default();
+    let mut tokens = tokens.iter().copied().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            "searchmoves" => {
+                while let Some(mv) = tokens.next_if(|t| t.parse::<Move>().is_ok()) {
+                    params.searchmoves.push(mv.to_string());
+                }
+            }
+            "ponder" => params.ponder = true,
+            "infinite" => params.infinite = true,
+            "wtime" => params.wtime = Some(parse_go_value(line, tokens.next())?),
+            "btime" => params.btime = Some(parse_go_value(line, tokens.next())?),
+            "winc" => params.winc = Some(parse_go_value(line, tokens.next())?),
+            "binc" => params.binc = Some(parse_go_value(line, tokens.next())?),
+            "movestogo" => params.movestogo = Some(parse_go_value(line, tokens.next())?),
+            "depth" => params.depth = Some(parse_go_value(line, tokens.next())?),
+            "nodes" => params.nodes = Some(parse_go_value(line, tokens.next())?),
+            "mate" => params.mate = Some(parse_go_value(line, tokens.next())?),
+            "movetime" => params.movetime = Some(parse_go_value(line, tokens.next())?),
+            _ => return Err(invalid_command(line)),
+        }
+    }
+    Ok(params)
+}
+
+fn parse_position(line: &str, tokens: &[&str]) -> Result<UciCommand, ChessError> {
+    let (position, moves) = match tokens.iter().position(|&t| t == "moves") {
+        Some(i) => (&tokens[..i], &tokens[i + 1..]),
+        None => (tokens, &[][..]),
+    };
+    let fen = match position {
+        ["startpos"] => None,
+        ["fen", fen @ ..] if !fen.is_empty() => Some(fen.join(" ")),
+        _ => return Err(invalid_command(line)),
+    };
+    Ok(UciCommand::Position {
+        fen,
+        moves: moves.iter().map(|m| m.to_string()).collect(),
+    })
+}
+
+// `setoption name <id> [value <x>]`, where both the name and the value may contain spaces.
+fn parse_setoption(line: &str, tokens: &[&str]) -> Result<UciCommand, ChessError> {
+    let ["name", rest @ ..] = tokens else {
+        return Err(invalid_command(line));
+    };
+    let (name, value) = match rest.iter().position(|&t| t == "value") {
+        Some(i) => (&rest[..i], Some(rest[i + 1..].join(" "))),
+        None => (rest, None),
+    };
+    if name.is_empty() {
+        return Err(invalid_command(line));
+    }
+    Ok(UciCommand::SetOption {
+        name: name.join(" "),
+        value,
+    })
+}
+
+impl FromStr for UciCommand {
+    type Err = ChessError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return Err(invalid_command(line));
+        };
+        match (command, args) {
+            ("uci", []) => Ok(UciCommand::Uci),
+            ("debug", ["on"]) => Ok(UciCommand::Debug(true)),
+            ("debug", ["off"]) => Ok(UciCommand::Debug(false)),
+            ("isready", []) => Ok(UciCommand::IsReady),
+            ("setoption", args) => parse_setoption(line, args),
+            ("ucinewgame", []) => Ok(UciCommand::UciNewGame),
+            ("position", args) => parse_position(line, args),
+            ("go", args) => Ok(UciCommand::Go(parse_go(line, args)?)),
+            ("stop", []) => Ok(UciCommand::Stop),
+            ("ponderhit", []) => Ok(UciCommand::PonderHit),
+            ("quit", []) => Ok(UciCommand::Quit),
+            _ => Err(invalid_command(line)),
+        }
+    }
+}
+
+/// The fields of an `info` line. Unset fields are left out.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchInfo {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    pub score: Option<Score>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    pub time_ms: Option<u64>,
+    pub pv: Vec<Move>,
+}
+
+impl fmt::Display for SearchInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "info")?;
+        if let Some(depth) = self.depth {
+            write!(f, " depth {depth}")?;
+        }
+        if let Some(seldepth) = self.seldepth {
+            write!(f, " seldepth {seldepth}")?;
+        }
+        if let Some(score) = self.score {
+            write!(f, " score {score}")?;
+        }
+        if let Some(nodes) = self.nodes {
+            write!(f, " nodes {nodes}")?;
+        }
+        if let Some(nps) = self.nps {
+            write!(f, " nps {nps}")?;
+        }
+        if let Some(time_ms) = self.time_ms {
+            write!(f, " time {time_ms}")?;
+        }
+        if !self.pv.is_empty() {
+            write!(f, " pv")?;
+            for mv in &self.pv {
+                write!(f, " {mv}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An engine option advertised in reply to `uci`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UciOption {
+    Check {
+        name: String,
+        default: bool,
+    },
+    Spin {
+        name: String,
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    String {
+        name: String,
+        default: String,
+    },
+    Button {
+        name: String,
+    },
+}
+
+impl fmt::Display for UciOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciOption::Check { name, default } => {
+                write!(f, "option name {name} type check default {default}")
+            }
+            UciOption::Spin {
+                name,
+                default,
+                min,
+                max,
+            } => write!(
+                f,
+                "option name {name} type spin default {default} min {min} max {max}"
+            ),
+            UciOption::String { name, default } => {
+                let default = if default.is_empty() {
+                    "<empty>"
+                } else {
+                    default
+                };
+                write!(f, "option name {name} type string default {default}")
+            }
+            UciOption::Button { name } => write!(f, "option name {name} type button"),
+        }
+    }
+}
+
+/// A message sent from the engine to the GUI. `Display` gives the protocol line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UciResponse {
+    IdName(String),
+    IdAuthor(String),
+    Option(UciOption),
+    UciOk,
+    ReadyOk,
+    BestMove {
+        best: Option<Move>, // None (sent as 0000) when there is no legal move
+        ponder: Option<Move>,
+    },
+    Info(SearchInfo),
+    InfoString(String),
+}
+
+impl fmt::Display for UciResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UciResponse::IdName(name) => write!(f, "id name {name}"),
+            UciResponse::IdAuthor(author) => write!(f, "id author {author}"),
+            UciResponse::Option(option) => write!(f, "{option}"),
+            UciResponse::UciOk => write!(f, "uciok"),
+            UciResponse::ReadyOk => write!(f, "readyok"),
+            UciResponse::BestMove { best, ponder } => {
+                match best {
+                    Some(best) => write!(f, "bestmove {best}")?,
+                    None => write!(f, "bestmove 0000")?,
+                }
+                if let Some(ponder) = ponder {
+                    write!(f, " ponder {ponder}")?;
+                }
+                Ok(())
+            }
+            UciResponse::Info(info) => write!(f, "{info}"),
+            UciResponse::InfoString(text) => write!(f, "info string {text}"),
+        }
+    }
+}
+
+/// The engine behind a `UciSession`. The session runs `search` on its own thread, so the
+/// searcher must poll `stop` and return soon after it is set.
+pub trait UciSearcher: Send + 'static {
+    fn name(&self) -> String;
+
+    fn author(&self) -> String;
+
+    /// Options to advertise besides `UCI_Chess960`, which the session handles itself.
+    fn options(&self) -> Vec<UciOption> {
+        Vec::new()
+    }
+
+    fn set_option(&mut self, _name: &str, _value: Option<&str>) {}
+
+    /// Called on `ucinewgame`, e.g. to clear hash tables.
+    fn new_game(&mut self) {}
+
+    /// Picks a move for the side to move in `game`, or None if it has no legal move.
+    /// Progress can be reported through `info`; the last pv sent supplies the ponder move.
+    fn search(
+        &mut self,
+        game: &mut ChessGame,
+        params: &GoParams,
+        stop: &AtomicBool,
+        info: &mut dyn FnMut(SearchInfo),
+    ) -> Option<Move>;
+}
+
+/// Tracks the position set up by the GUI and runs searches on a background thread.
+/// Every response, including the `bestmove` of a running search, is sent through `out`.
+pub struct UciSession<S: UciSearcher> {
+    pub game: ChessGame,
+    pub debug: bool,
+    pub chess960: bool,
+    searcher: Option<S>, // None while a search thread owns it
+    search: Option<JoinHandle<S>>,
+    stop: Arc<AtomicBool>,
+    pondering: Arc<AtomicBool>,
+    out: Sender<UciResponse>,
+}
+
+impl<S: UciSearcher> UciSession<S> {
+    pub fn new(searcher: S, out: Sender<UciResponse>) -> Self {
+        UciSession {
+            game: ChessGame::new(TimeControl::new(0, 0)),
+            debug: false,
+            chess960: false,
+            searcher: Some(searcher),
+            search: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            pondering: Arc::new(AtomicBool::new(false)),
+            out,
+        }
+    }
+
+    /// Reads commands until `quit` or the end of input. Bad commands and positions are reported
+    /// with `info string` and otherwise ignored, as the protocol asks.
+    pub fn run(&mut self, input: impl BufRead) {
+        for line in input.lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let result = line.parse().and_then(|command| {
+                let quit = command == UciCommand::Quit;
+                self.handle(command).map(|_| quit)
+            });
+            match result {
+                Ok(true) => return,
+                Ok(false) => {}
+                Err(err) => self.send(UciResponse::InfoString(err.to_string())),
+            }
+        }
+        self.stop_search();
+    }
+
+    pub fn handle(&mut self, command: UciCommand) -> Result<(), ChessError> {
+        match command {
+            UciCommand::Uci => {
+                let searcher = self.searcher();
+                let mut responses = vec![
+                    UciResponse::IdName(searcher.name()),
+                    UciResponse::IdAuthor(searcher.author()),
+                    UciResponse::Option(UciOption::Check {
+                        name: "UCI_Chess960".to_string(),
+                        default: false,
+                    }),
+                ];
+                responses.extend(searcher.options().into_iter().map(UciResponse::Option));
+                responses.push(UciResponse::UciOk);
+                for response in responses {
+                    self.send(response);
+                }
+            }
+            UciCommand::Debug(on) => self.debug = on,
+            UciCommand::IsReady => self.send(UciResponse::ReadyOk),
+            UciCommand::SetOption { name, value } => {
+                if name.eq_ignore_ascii_case("UCI_Chess960") {
+                    self.chess960 = value.as_deref() == Some("true");
+                } else {
+                    self.searcher().set_option(&name, value.as_deref());
+                }
+            }
+            UciCommand::UciNewGame => {
+                self.searcher().new_game();
+                self.game = ChessGame::new(TimeControl::new(0, 0));
+            }
+            UciCommand::Position { fen, moves } => {
+                self.stop_search();
+                self.game = self.setup_position(fen.as_deref(), &moves)?;
+            }
+            UciCommand::Go(params) => self.go(params),
+            UciCommand::Stop | UciCommand::Quit => self.stop_search(),
+            UciCommand::PonderHit => self.pondering.store(false, Ordering::SeqCst),
+        }
+        Ok(())
+    }
+
+    fn send(&self, response: UciResponse) {
+        // a closed channel means nobody is listening any more, so there's no one to tell.
+        let _ = self.out.send(response);
+    }
+
+    // the searcher, after stopping any search that has it.
+    fn searcher(&mut self) -> &mut S {
+        self.stop_search();
+        self.searcher
+            .as_mut()
+            .expect("searcher is returned when its search ends")
+    }
+
+    fn setup_position(&self, fen: Option<&str>, moves: &[String]) -> Result<ChessGame, ChessError> {
+        let mut game =
+            ChessGame::initialize_from_fen(TimeControl::new(0, 0), fen.unwrap_or(STARTING_FEN))?;
+        if fen.is_none() {
+            game.start_fen = None;
+        }
+        if self.chess960 && !game.board.chess960 {
+            // castling moves are king-takes-rook even from the standard position.
+            game.board.chess960 = true;
+            game.legal_moves = moves::get_legal_moves(&mut game.board);
+        }
+        // repetition and fifty move draws are for the GUI to claim; the engine plays on.
+        let play_on = |game: &mut ChessGame| {
+            if matches!(
+                game.result,
+                GameResult::Draw(DrawReason::ThreefoldRepitition)
+                    | GameResult::Draw(DrawReason::FiftyMoveRule)
+            ) {
+                game.result = GameResult::InProgress;
+            }
+        };
+        play_on(&mut game);
+        for mv in moves {
+            game.make_move_from_uci(mv, true, false)?;
+            play_on(&mut game);
+        }
+        Ok(game)
+    }
+
+    fn go(&mut self, params: GoParams) {
+        self.stop_search();
+        let mut searcher = self
+            .searcher
+            .take()
+            .expect("searcher is returned when its search ends");
+        let mut game = self.game.clone();
+        let stop = Arc::clone(&self.stop);
+        let pondering = Arc::clone(&self.pondering);
+        let out = self.out.clone();
+        stop.store(false, Ordering::SeqCst);
+        pondering.store(params.ponder, Ordering::SeqCst);
+
+        self.search = Some(thread::spawn(move || {
+            let mut pv = Vec::new();
+            let best = searcher.search(&mut game, &params, &stop, &mut |info| {
+                if !info.pv.is_empty() {
+                    pv.clone_from(&info.pv);
+                }
+                let _ = out.send(UciResponse::Info(info));
+            });
+            // bestmove may not be sent before `stop` (or `ponderhit`) ends an infinite or
+            // ponder search, even if the searcher finished early.
+            while (params.infinite || pondering.load(Ordering::SeqCst))
+                && !stop.load(Ordering::SeqCst)
+            {
+                thread::sleep(Duration::from_millis(1));
+            }
+            let ponder = match pv.as_slice() {
+                [first, second, ..] if Some(*first) == best => Some(*second),
+                _ => None,
+            };
+            let _ = out.send(UciResponse::BestMove { best, ponder });
+            searcher
+        }));
+    }
+
+    /// Stops the running search, if any, and waits for its `bestmove`.
+    pub fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.stop.store(true, Ordering::SeqCst);
+            let searcher = search.join().expect("search thread panicked");
+            self.searcher = Some(searcher);
+        }
+    }
+
+    /// True from `go` until the search thread is joined, which happens on `stop` or the next
+    /// command that needs the searcher. The search itself may already be over.
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    // plays the first legal move, reporting it as a one-move pv.
+    struct FirstMove;
+
+    impl UciSearcher for FirstMove {
+        fn name(&self) -> String {
+            "first move".to_string()
+        }
+
+        fn author(&self) -> String {
+            "tests".to_string()
+        }
+
+        fn options(&self) -> Vec<UciOption> {
+            vec![UciOption::Spin {
+                name: "Hash".to_string(),
+                default: 16,
+                min: 1,
+                max: 1024,
+            }]
+        }
+
+        fn search(
+            &mut self,
+            game: &mut ChessGame,
+            _params: &GoParams,
+            _stop: &AtomicBool,
+            info: &mut dyn FnMut(SearchInfo),
+        ) -> Option<Move> {
+            let best = game.legal_moves.first().copied()?;
+            info(SearchInfo {
+                depth: Some(1),
+                score: Some(Score::Centipawns(0)),
+                pv: vec![best],
+                ..SearchInfo::default()
+            });
+            Some(best)
+        }
+    }
+
+    fn lines(responses: &mpsc::Receiver<UciResponse>) -> Vec<String> {
+        responses.try_iter().map(|r| r.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!("uci".parse(), Ok(UciCommand::Uci));
+        assert_eq!(" isready ".parse(), Ok(UciCommand::IsReady));
+        assert_eq!("debug on".parse(), Ok(UciCommand::Debug(true)));
+        assert_eq!(
+            "setoption name Clear Hash".parse(),
+            Ok(UciCommand::SetOption {
+                name: "Clear Hash".to_string(),
+                value: None
+            })
+        );
+        assert_eq!(
+            "setoption name Book File value my book.bin".parse(),
+            Ok(UciCommand::SetOption {
+                name: "Book File".to_string(),
+                value: Some("my book.bin".to_string())
+            })
+        );
+        assert_eq!(
+            "position startpos moves e2e4 e7e5".parse(),
+            Ok(UciCommand::Position {
+                fen: None,
+                moves: vec!["e2e4".to_string(), "e7e5".to_string()]
+            })
+        );
+        assert_eq!(
+            "position fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1".parse(),
+            Ok(UciCommand::Position {
+                fen: Some("4k3/8/8/8/8/8/8/4K2R w K - 0 1".to_string()),
+                moves: Vec::new()
+            })
+        );
+        assert_eq!(
+            "go wtime 1000 btime 900 winc 10 binc 10 movestogo 20".parse(),
+            Ok(UciCommand::Go(GoParams {
+                wtime: Some(1000),
+                btime: Some(900),
+                winc: Some(10),
+                binc: Some(10),
+                movestogo: Some(20),
+                ..GoParams::default()
+            }))
+        );
+        assert_eq!(
+            "go searchmoves e2e4 d2d4 depth 5".parse(),
+            Ok(UciCommand::Go(GoParams {
+                searchmoves: vec!["e2e4".to_string(), "d2d4".to_string()],
+                depth: Some(5),
+                ..GoParams::default()
+            }))
+        );
+
+        for line in [
+            "",
+            "go depth",
+            "go depth x",
+            "position",
+            "position fen",
+            "setoption",
+        ] {
+            assert_eq!(
+                line.parse::<UciCommand>(),
+                Err(ChessError::InvalidUciCommand(line.to_string()))
+            );
+        }
+        assert!("xboard".parse::<UciCommand>().is_err());
+    }
+
+    #[test]
+    fn test_response_display() {
+        let e2e4: Move = "e2e4".parse().unwrap();
+        let e7e5: Move = "e7e5".parse().unwrap();
+        assert_eq!(
+            UciResponse::BestMove {
+                best: Some(e2e4),
+                ponder: Some(e7e5)
+            }
+            .to_string(),
+            "bestmove e2e4 ponder e7e5"
+        );
+        assert_eq!(
+            UciResponse::BestMove {
+                best: None,
+                ponder: None
+            }
+            .to_string(),
+            "bestmove 0000"
+        );
+        let info = SearchInfo {
+            depth: Some(2),
+            score: Some(Score::Mate(-3)),
+            nodes: Some(400),
+            time_ms: Some(5),
+            pv: vec![e2e4, e7e5],
+            ..SearchInfo::default()
+        };
+        assert_eq!(
+            UciResponse::Info(info).to_string(),
+            "info depth 2 score mate -3 nodes 400 time 5 pv e2e4 e7e5"
+        );
+        assert_eq!(
+            UciOption::String {
+                name: "Book".to_string(),
+                default: String::new()
+            }
+            .to_string(),
+            "option name Book type string default <empty>"
+        );
+    }
+
+    #[test]
+    fn test_session() {
+        let (out, responses) = mpsc::channel();
+        let mut session = UciSession::new(FirstMove, out);
+        session
+            .run("uci\nisready\nposition startpos moves e2e4 e7e5\ngo depth 1\nquit\n".as_bytes());
+        assert_eq!(
+            lines(&responses),
+            [
+                "id name first move",
+                "id author tests",
+                "option name UCI_Chess960 type check default false",
+                "option name Hash type spin default 16 min 1 max 1024",
+                "uciok",
+                "readyok",
+                "info depth 1 score cp 0 pv a2a3",
+                "bestmove a2a3",
+            ]
+        );
+        assert_eq!(session.game.moves.len(), 2);
+
+        // a bad position is reported and leaves the previous one in place.
+        session.run("position startpos moves e2e5\nbogus\nquit\n".as_bytes());
+        assert_eq!(
+            lines(&responses),
+            [
+                "info string Illegal move: e2e5",
+                "info string Invalid UCI command: bogus"
+            ]
+        );
+        assert_eq!(session.game.moves.len(), 2);
+
+        // no legal moves: checkmated by the fool's mate.
+        let mate = "position startpos moves f2f3 e7e5 g2g4 d8h4\ngo\nstop\n";
+        session.run(mate.as_bytes());
+        assert_eq!(lines(&responses), ["bestmove 0000"]);
+    }
+
+    #[test]
+    fn test_session_plays_on_after_claimable_draws() {
+        let (out, responses) = mpsc::channel();
+        let mut session = UciSession::new(FirstMove, out);
+
+        // the knights shuffle back twice, repeating the start position a third time.
+        let shuffle = "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1 f6g8";
+        session.run(format!("position startpos moves {shuffle} e2e4\n").as_bytes());
+        assert_eq!(lines(&responses), Vec::<String>::new());
+        assert_eq!(session.game.moves.len(), 9);
+        assert_eq!(session.game.result, GameResult::InProgress);
+
+        // the fifty move counter runs out on the first move.
+        let fen = "8/3k4/8/8/8/8/6R1/K7 w - - 99 80";
+        session.run(format!("position fen {fen} moves g2g3 d7d6\ngo\nstop\n").as_bytes());
+        assert_eq!(
+            session.game.board.to_fen(),
+            "8/8/3k4/8/8/6R1/8/K7 w - - 101 81"
+        );
+        assert_eq!(session.game.result, GameResult::InProgress);
+        assert_eq!(
+            lines(&responses),
+            ["info depth 1 score cp 0 pv g3g1", "bestmove g3g1"]
+        );
+    }
+
+    #[test]
+    fn test_session_infinite_and_chess960() {
+        let (out, responses) = mpsc::channel();
+        let mut session = UciSession::new(FirstMove, out);
+
+        // an infinite search holds its bestmove until stop.
+        session.handle("go infinite".parse().unwrap()).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(lines(&responses), ["info depth 1 score cp 0 pv a2a3"]);
+        assert!(session.is_searching());
+        session.handle(UciCommand::Stop).unwrap();
+        assert_eq!(lines(&responses), ["bestmove a2a3"]);
+        assert!(!session.is_searching());
+
+        // with UCI_Chess960 on, castling is sent as king-takes-rook.
+        session.run("setoption name UCI_Chess960 value true\n".as_bytes());
+        let position = "position fen 4k3/8/8/8/8/8/8/4K2R w K - 0 1 moves e1h1";
+        session.handle(position.parse().unwrap()).unwrap();
+        assert_eq!(
+            session.game.board.to_fen(),
+            "4k3/8/8/8/8/8/8/5RK1 b - - 1 1"
+        );
+    }
+}
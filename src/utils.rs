@@ -2,176 +2,29 @@
 
 use std::u64;
 
+use crate::bitboard::Bitboard;
 use crate::board::ChessBoard;
-
-pub const fn on_a_file(piece_location: u64) -> bool {
-    // if more than one bit = 1: raise error.
-
-    if piece_location.trailing_zeros() % 8 == 0 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_b_file(piece_location: u64) -> bool {
-    // if more than one bit = 1: raise error.
-
-    if piece_location.trailing_zeros() % 8 == 1 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_c_file(piece_location: u64) -> bool {
-    // if more than one bit = 1: raise error.
-
-    if piece_location.trailing_zeros() % 8 == 2 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_d_file(piece_location: u64) -> bool {
-    // if more than one bit = 1: raise error.
-
-    if piece_location.trailing_zeros() % 8 == 3 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_e_file(piece_location: u64) -> bool {
-    // if more than one bit = 1: raise error.
-
-    if piece_location.trailing_zeros() % 8 == 4 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_f_file(piece_location: u64) -> bool {
-    // if more than one bit = 1: raise error.
-
-    if piece_location.trailing_zeros() % 8 == 5 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_g_file(piece_location: u64) -> bool {
-    // if more than one bit = 1: raise error.
-
-    if piece_location.trailing_zeros() % 8 == 6 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_h_file(piece_location: u64) -> bool {
-    // if more than one bit = 1: raise error.
-
-    if piece_location.trailing_zeros() % 8 == 7 {
-        return true;
-    } else {
-        return false;
-    }
-}
+use crate::error::ChessError;
+use crate::types::{Move, Square};
 
 // 1 -> a file, 8 -> h file
-pub const fn file_value(piece_location: u64) -> u16 {
-    return (piece_location.trailing_zeros() % 8) as u16 + 1;
+pub const fn file_value(piece_location: Bitboard) -> u16 {
+    (piece_location.0.trailing_zeros() % 8) as u16 + 1
 }
 
-pub const fn on_rank_1(piece_location: u64) -> bool {
-    if piece_location < 0x0000000000000100 {
-        return true;
-    } else {
-        return false;
-    }
+// 1 -> rank 1, 8 -> rank 8
+pub const fn rank_value(piece_location: Bitboard) -> u16 {
+    (piece_location.0.trailing_zeros() / 8) as u16 + 1
 }
 
-pub const fn on_rank_2(piece_location: u64) -> bool {
-    if piece_location < 0x0000000000010000 && piece_location > 0x0000000000000080 {
-        return true;
+pub fn bb_to_square(bitboard: &Bitboard) -> Result<String, ChessError> {
+    if bitboard.popcount() != 1 {
+        Err(ChessError::InvalidSquare(format!(
+            "bitboard {:#018x} does not hold exactly one square",
+            bitboard.0
+        )))
     } else {
-        return false;
-    }
-}
-
-pub const fn on_rank_3(piece_location: u64) -> bool {
-    if piece_location < 0x0000000001000000 && piece_location > 0x0000000000008000 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_rank_4(piece_location: u64) -> bool {
-    if piece_location < 0x0000000100000000 && piece_location > 0x0000000000800000 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_rank_5(piece_location: u64) -> bool {
-    if piece_location < 0x0000010000000000 && piece_location > 0x0000000080000000 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_rank_6(piece_location: u64) -> bool {
-    if piece_location < 0x0001000000000000 && piece_location > 0x0000008000000000 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_rank_7(piece_location: u64) -> bool {
-    if piece_location < 0x0100000000000000 && piece_location > 0x0000800000000000 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn on_rank_8(piece_location: u64) -> bool {
-    if piece_location > 0x0080000000000000 {
-        return true;
-    } else {
-        return false;
-    }
-}
-
-pub const fn rank_value(piece_location: u64) -> u16 {
-    match piece_location {
-        0..0x0000000000000100 => 1,
-        0x0000000000000081..0x0000000000010000 => 2,
-        0x0000000000008001..0x0000000001000000 => 3,
-        0x0000000000800001..0x0000000100000000 => 4,
-        0x0000000080000001..0x0000010000000000 => 5,
-        0x0000008000000001..0x0001000000000000 => 6,
-        0x0000800000000001..0x0100000000000000 => 7,
-        0x0080000000000000.. => 8,
-    }
-}
-
-pub fn bb_to_square(bitboard: &u64) -> Result<String, String> {
-    let bb_null = bitboard & (bitboard - 1);
-    if bb_null != 0 {
-        Err("Invalid bitboard: more than one piece on the board.".to_string())
-    } else {
-        let bit_position = bitboard.trailing_zeros();
+        let bit_position = bitboard.0.trailing_zeros();
         let rank = (bit_position / 8) + 1;
         let file_num = (bit_position % 8) as u8; // ASCII is in u8 format.
         let file = (b'a' + file_num) as char;
@@ -184,14 +37,14 @@ pub fn bb_to_square(bitboard: &u64) -> Result<String, String> {
 pub const FILES: [char; 8] = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
 pub const RANKS: [char; 8] = ['1', '2', '3', '4', '5', '6', '7', '8'];
 
-pub fn square_to_bb(square: &str) -> Result<u64, String> {
+pub fn square_to_bb(square: &str) -> Result<Bitboard, ChessError> {
     if square.chars().count() != 2 {
-        return Err("Invalid square: too many characters.".to_string());
+        return Err(ChessError::InvalidSquare(square.to_string()));
     }
     let file = square.chars().nth(0).unwrap();
     let rank = square.chars().nth(1).unwrap();
     if !(FILES.contains(&file) && RANKS.contains(&rank)) {
-        return Err("Invalid square: invalid file or rank.".to_string());
+        return Err(ChessError::InvalidSquare(square.to_string()));
     }
 
     let mut modifier: u64 = 0;
@@ -200,13 +53,12 @@ pub fn square_to_bb(square: &str) -> Result<u64, String> {
     let file_modifier = (file as u8 - b'a') as u64;
     modifier += file_modifier;
 
-    let square: u64 = 1 << modifier;
-    Ok(square)
+    Ok(Bitboard(1 << modifier))
 }
 
-pub fn print_board_binary(bitboard: &u64) {
+pub fn print_board_binary(bitboard: &Bitboard) {
     // prints a binary mapping of a bitboard (no piece type information) for debugging and development purposes.
-    let binary_bb = format!("{:064b}", bitboard);
+    let binary_bb = format!("{:064b}", bitboard.0);
     for i in 0..8 {
         let line_mirrored = &binary_bb[(i * 8)..((i + 1) * 8)];
         let line: String = line_mirrored.chars().rev().collect();
@@ -214,67 +66,41 @@ pub fn print_board_binary(bitboard: &u64) {
     }
 }
 
-pub fn squares_above(square: &u64) -> u64 {
+pub fn squares_above(square: &Bitboard) -> Bitboard {
     // prints mask of all squares above specified square
-    let cutoff = ((square.trailing_zeros() % 8) + 1) * 8;
-    let mask: u64 = (u64::MAX).checked_shl(cutoff).unwrap_or(0);
-    mask
+    let cutoff = ((square.0.trailing_zeros() % 8) + 1) * 8;
+    Bitboard((u64::MAX).checked_shl(cutoff).unwrap_or(0))
 }
 
-pub fn squares_below(square: &u64) -> u64 {
+pub fn squares_below(square: &Bitboard) -> Bitboard {
     // prints mask of all squares below specified square
-    let cutoff = ((square.leading_zeros() % 8) + 1) * 8;
-    let mask: u64 = (u64::MAX).checked_shr(cutoff).unwrap_or(0);
-    mask
+    let cutoff = ((square.0.leading_zeros() % 8) + 1) * 8;
+    Bitboard((u64::MAX).checked_shr(cutoff).unwrap_or(0))
 }
 
-pub fn squares_left(square: &u64) -> u64 {
-    if square.trailing_zeros() % 8 == 0 {
-        0
-    } else {
-        let mut mask: u64 = 0;
-        let file_template: u64 = 0x0101010101010101;
-
-        let mut file_mod: u64 = ((square.trailing_zeros() as u64) % 8) - 1;
-
-        while file_mod < u64::MAX {
-            mask = mask | (file_template << file_mod);
-            file_mod = file_mod.wrapping_sub(1);
-        }
-
-        mask
-    }
+pub fn squares_left(square: &Bitboard) -> Bitboard {
+    // mask of all files left of the specified square
+    let file = (square.0.trailing_zeros() % 8) as usize;
+    Bitboard::FILES[..file]
+        .iter()
+        .fold(Bitboard::EMPTY, |mask, &f| mask | f)
 }
 
-pub fn squares_right(square: &u64) -> u64 {
-    if square.trailing_zeros() % 8 == 8 {
-        0
-    } else {
-        let mut mask: u64 = 0;
-        let file_template: u64 = 0x0101010101010101;
-
-        let mut file_mod: u64 = ((square.trailing_zeros() as u64) % 8) + 1;
-
-        while file_mod < 8 {
-            mask = mask | (file_template << file_mod);
-            file_mod += 1;
-        }
-
-        mask
-    }
+pub fn squares_right(square: &Bitboard) -> Bitboard {
+    // mask of all files right of the specified square
+    let file = (square.0.trailing_zeros() % 8) as usize;
+    Bitboard::FILES[file + 1..]
+        .iter()
+        .fold(Bitboard::EMPTY, |mask, &f| mask | f)
 }
 
-pub fn encode_move(from_square: u8, to_square: u8, flag: u8) -> u16 {
-    let encoded_move: u16 =
-        (((from_square as u16) << 10) | (to_square as u16) << 4) | (flag as u16);
-    return encoded_move;
+// packs raw square indices and a flag into a Move (see the flag constants on Move).
+pub fn encode_move(from_square: u8, to_square: u8, flag: u8) -> Move {
+    Move::new(Square::new(from_square), Square::new(to_square), flag)
 }
 
-pub fn decode_move(move_i: u16) -> [u8; 3] {
-    let from_sqi = (move_i >> 10) as u8;
-    let to_sqi = ((move_i >> 4) & 0b111111) as u8;
-    let flag = (move_i & 0b1111) as u8;
-    return [from_sqi, to_sqi, flag];
+pub fn decode_move(mv: Move) -> [u8; 3] {
+    [mv.from().index(), mv.to().index(), mv.flag()]
 }
 
 pub fn square_color(square_i: u8) -> bool {
@@ -282,15 +108,15 @@ pub fn square_color(square_i: u8) -> bool {
     ((square_i / 8) + (square_i % 8)) % 2 != 0
 }
 
-pub fn sq_to_u8(square: &str) -> Result<u8, String> {
+pub fn sq_to_u8(square: &str) -> Result<u8, ChessError> {
     if square.chars().count() != 2 {
-        return Err("Invalid square: too many characters.".to_string());
+        return Err(ChessError::InvalidSquare(square.to_string()));
     }
     let mut sqi = 0;
     let file = square.chars().nth(0).unwrap();
     let rank = square.chars().nth(1).unwrap();
     if !(FILES.contains(&file) && RANKS.contains(&rank)) {
-        return Err("Invalid square: invalid file or rank.".to_string());
+        return Err(ChessError::InvalidSquare(square.to_string()));
     }
 
     sqi += (rank.to_digit(10).unwrap() as u8 - 1) * 8;
@@ -302,69 +128,20 @@ pub fn sq_to_u8(square: &str) -> Result<u8, String> {
 
 // returns empty flag unless promotion piece is specified. If promotion, non-capture is assumed.
 // In game::make_uci_move(), it will infer capture and alter the flag if necessary.
-pub fn encode_from_uci(uci_move: &str) -> Result<u16, String> {
-    if ![4, 5].contains(&uci_move.len()) {
-        return Err("Invalid move.".to_string());
-    }
-
-    let sqi_1: u8 = sq_to_u8(&uci_move[0..=1])?;
-    let sqi_2: u8 = sq_to_u8(&uci_move[2..=3])?;
-
-    let flag: u8;
-    if uci_move.chars().count() == 5 {
-        match uci_move.chars().nth(4).unwrap() {
-            'n' => flag = 4,
-            'b' => flag = 5,
-            'r' => flag = 6,
-            'q' => flag = 7,
-            _ => return Err("Invalid promotion type.".to_string()),
-        };
-    } else {
-        flag = 0;
-    }
-
-    let movei = encode_move(sqi_1, sqi_2, flag);
-    return Ok(movei);
+pub fn encode_from_uci(uci_move: &str) -> Result<Move, ChessError> {
+    uci_move.parse()
 }
 
-pub fn decode_to_uci(move_i: u16) -> Result<String, String> {
-    let flag = (move_i & 0xF) as usize;
-    let to_sq = ((move_i >> 4) & 0x3F) as usize;
-    let from_sq = ((move_i >> 10) & 0x3F) as usize;
-
-    let from_file = FILES[from_sq % 8];
-    let from_rank = (from_sq / 8) + 1;
-    let to_file = FILES[to_sq % 8];
-    let to_rank = (to_sq / 8) + 1;
-
-    let promo = match flag {
-        4 | 8 => "n",
-        5 | 9 => "b",
-        6 | 10 => "r",
-        7 | 11 => "q",
-        _ => "",
-    };
-
-    Ok(format!("{from_file}{from_rank}{to_file}{to_rank}{promo}"))
+pub fn decode_to_uci(mv: Move) -> Result<String, ChessError> {
+    Ok(mv.to_string())
 }
 
 pub fn render_board(board: &ChessBoard) {
     let mut output_array = [' '; 64]; // a1 to h8
     for i in 0..=63 {
         let iu = i as usize;
-        match board.piece_type_at(i) {
-            None => {}
-            Some(0) => output_array[iu] = 'p',
-            Some(1) => output_array[iu] = 'n',
-            Some(2) => output_array[iu] = 'b',
-            Some(3) => output_array[iu] = 'r',
-            Some(4) => output_array[iu] = 'q',
-            Some(5) => output_array[iu] = 'k',
-            _ => {}
-        }
-        let sq_bb: u64 = 1 << i;
-        if sq_bb & board.white_pieces != 0 {
-            output_array[iu] = output_array[iu].to_ascii_uppercase();
+        if let Some(piece) = board.piece_at(Square::new(i)) {
+            output_array[iu] = piece.fen_char();
         }
     }
 
@@ -386,22 +163,19 @@ pub fn check_integrity(board: &ChessBoard) -> bool {
         board.pawns | board.knights | board.bishops | board.rooks | board.queens | board.kings;
     let all_colors = board.white_pieces | board.black_pieces;
 
-    board.pawns & board.knights == 0
-        && board.pawns & board.bishops == 0
-        && board.pawns & board.rooks == 0
-        && board.pawns & board.queens == 0
-        && board.pawns & board.kings == 0
-        && board.knights & board.bishops == 0
-        && board.knights & board.rooks == 0
-        && board.knights & board.queens == 0
-        && board.knights & board.kings == 0
-        && board.bishops & board.rooks == 0
-        && board.bishops & board.queens == 0
-        && board.bishops & board.kings == 0
-        && board.rooks & board.queens == 0
-        && board.rooks & board.kings == 0
-        && board.queens & board.kings == 0
-        && board.white_pieces & board.black_pieces == 0
+    let piece_types = [
+        board.pawns,
+        board.knights,
+        board.bishops,
+        board.rooks,
+        board.queens,
+        board.kings,
+    ];
+    // no square holds two piece types: the counts add up only if the sets are disjoint.
+    let piece_count: u32 = piece_types.iter().map(|bb| bb.popcount()).sum();
+
+    piece_count == all_piece_types.popcount()
+        && !board.white_pieces.intersects(board.black_pieces)
         && all_piece_types == all_colors
 }
 
@@ -429,275 +203,63 @@ pub fn print_all_board_info(board: &ChessBoard) {
 mod tests {
     use super::*;
 
-    const A_SQUARES: [u64; 8] = [
-        0x0000000000000001,
-        0x0000000000000100,
-        0x0000000000010000,
-        0x0000000001000000,
-        0x0000000100000000,
-        0x0000010000000000,
-        0x0001000000000000,
-        0x0100000000000000,
-    ];
-    #[test]
-    fn test_on_a_file() {
-        for i in 0..64 {
-            let square: u64 = 1 << i;
-            if A_SQUARES.contains(&square) {
-                assert_eq!(on_a_file(square), true);
-            } else {
-                assert_eq!(on_a_file(square), false);
-            }
-        }
-    }
-
-    #[test]
-    fn test_on_b_file() {
-        let b_squares: [u64; 8] = A_SQUARES.map(|x| x << 1);
-
-        for i in 0..64 {
-            let square: u64 = 1 << i;
-            if b_squares.contains(&square) {
-                assert_eq!(on_b_file(square), true);
-            } else {
-                assert_eq!(on_b_file(square), false);
-            }
-        }
-    }
-
-    #[test]
-    fn test_on_c_file() {
-        let c_squares: [u64; 8] = A_SQUARES.map(|x| x << 2);
-
-        for i in 0..64 {
-            let square: u64 = 1 << i;
-            if c_squares.contains(&square) {
-                assert_eq!(on_c_file(square), true);
-            } else {
-                assert_eq!(on_c_file(square), false);
-            }
-        }
-    }
-
-    #[test]
-    fn test_on_d_file() {
-        let d_squares: [u64; 8] = A_SQUARES.map(|x| x << 3);
-
-        for i in 0..64 {
-            let square: u64 = 1 << i;
-            if d_squares.contains(&square) {
-                assert_eq!(on_d_file(square), true);
-            } else {
-                assert_eq!(on_d_file(square), false);
-            }
-        }
-    }
-
-    #[test]
-    fn test_on_e_file() {
-        let e_squares: [u64; 8] = A_SQUARES.map(|x| x << 4);
-
-        for i in 0..64 {
-            let square: u64 = 1 << i;
-            if e_squares.contains(&square) {
-                assert_eq!(on_e_file(square), true);
-            } else {
-                assert_eq!(on_e_file(square), false);
-            }
-        }
-    }
-
-    #[test]
-    fn test_on_f_file() {
-        let f_squares: [u64; 8] = A_SQUARES.map(|x| x << 5);
-
-        for i in 0..64 {
-            let square: u64 = 1 << i;
-            if f_squares.contains(&square) {
-                assert_eq!(on_f_file(square), true);
-            } else {
-                assert_eq!(on_f_file(square), false);
-            }
-        }
-    }
-
-    #[test]
-    fn test_on_g_file() {
-        let g_squares: [u64; 8] = A_SQUARES.map(|x| x << 6);
-
-        for i in 0..64 {
-            let square: u64 = 1 << i;
-            if g_squares.contains(&square) {
-                assert_eq!(on_g_file(square), true);
-            } else {
-                assert_eq!(on_g_file(square), false);
-            }
-        }
-    }
-
-    #[test]
-    fn test_on_h_file() {
-        let h_squares: [u64; 8] = A_SQUARES.map(|x| x << 7);
-
-        for i in 0..64 {
-            let square: u64 = 1 << i;
-            if h_squares.contains(&square) {
-                assert_eq!(on_h_file(square), true);
-            } else {
-                assert_eq!(on_h_file(square), false);
-            }
-        }
-    }
-
-    #[test]
-    fn test_on_rank_1() {
-        for i in 0..8 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_1(square), true);
-        }
-        for i in 8..64 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_1(square), false);
-        }
-    }
-
-    #[test]
-    fn test_on_rank_2() {
-        for i in (0..8).chain(16..64) {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_2(square), false);
-        }
-        for i in 8..16 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_2(square), true);
-        }
-    }
-
-    #[test]
-    fn test_on_rank_3() {
-        for i in (0..16).chain(24..64) {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_3(square), false);
-        }
-        for i in 16..24 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_3(square), true);
-        }
-    }
-
-    #[test]
-    fn test_on_rank_4() {
-        for i in (0..24).chain(32..64) {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_4(square), false);
-        }
-        for i in 24..32 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_4(square), true);
-        }
-    }
-
-    #[test]
-    fn test_on_rank_5() {
-        for i in (0..32).chain(40..64) {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_5(square), false);
-        }
-        for i in 32..40 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_5(square), true);
-        }
-    }
-
-    #[test]
-    fn test_on_rank_6() {
-        for i in (0..40).chain(48..64) {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_6(square), false);
-        }
-        for i in 40..48 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_6(square), true);
-        }
-    }
-
-    #[test]
-    fn test_on_rank_7() {
-        for i in (0..48).chain(56..64) {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_7(square), false);
-        }
-        for i in 48..56 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_7(square), true);
-        }
-    }
-
-    #[test]
-    fn test_on_rank_8() {
-        for i in 0..56 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_8(square), false);
-        }
-        for i in 56..64 {
-            let square: u64 = 1 << i;
-            assert_eq!(on_rank_8(square), true);
-        }
-    }
-
     #[test]
     fn test_bb_to_square() {
         // a1
-        let board1: u64 = 1;
+        let board1 = Bitboard(1);
         assert_eq!(bb_to_square(&board1).unwrap(), "a1");
         // e4
-        let board2: u64 = 0x0000000010000000;
+        let board2 = Bitboard(0x0000000010000000);
         assert_eq!(bb_to_square(&board2).unwrap(), "e4");
         // h8
-        let board3: u64 = 0x8000000000000000;
+        let board3 = Bitboard(0x8000000000000000);
         assert_eq!(bb_to_square(&board3).unwrap(), "h8");
         // c7
-        let board4: u64 = 0x0004000000000000;
+        let board4 = Bitboard(0x0004000000000000);
         assert_eq!(bb_to_square(&board4).unwrap(), "c7");
         // invalid: too many squares
-        let board5: u64 = 3;
+        let board5 = Bitboard(3);
         assert_eq!(
             bb_to_square(&board5),
-            Err("Invalid bitboard: more than one piece on the board.".to_string())
+            Err(ChessError::InvalidSquare(
+                "bitboard 0x0000000000000003 does not hold exactly one square".to_string()
+            ))
         );
+        // invalid: empty bitboard
+        assert!(bb_to_square(&Bitboard::EMPTY).is_err());
     }
 
     #[test]
     fn test_square_to_bb() {
         // a1
         let square1: &str = "a1";
-        assert_eq!(square_to_bb(square1).unwrap(), 1);
+        assert_eq!(square_to_bb(square1).unwrap(), Bitboard(1));
         // e4
         let square2: &str = "e4";
-        assert_eq!(square_to_bb(square2).unwrap(), 0x0000000010000000);
+        assert_eq!(square_to_bb(square2).unwrap(), Bitboard(0x0000000010000000));
         // h8
         let square3: &str = "h8";
-        assert_eq!(square_to_bb(square3).unwrap(), 0x8000000000000000);
+        assert_eq!(square_to_bb(square3).unwrap(), Bitboard(0x8000000000000000));
         // c7
         let square4: &str = "c7";
-        assert_eq!(square_to_bb(square4).unwrap(), 0x0004000000000000);
+        assert_eq!(square_to_bb(square4).unwrap(), Bitboard(0x0004000000000000));
         // invalid: file out of range
         let square5: &str = "i1";
         assert_eq!(
             square_to_bb(square5),
-            Err("Invalid square: invalid file or rank.".to_string())
+            Err(ChessError::InvalidSquare("i1".to_string()))
         );
         // invalid: rank out of range
         let square6: &str = "a9";
         assert_eq!(
             square_to_bb(square6),
-            Err("Invalid square: invalid file or rank.".to_string())
+            Err(ChessError::InvalidSquare("a9".to_string()))
         );
         // invalid: too many chars
         let square7: &str = "a11";
         assert_eq!(
             square_to_bb(square7),
-            Err("Invalid square: too many characters.".to_string())
+            Err(ChessError::InvalidSquare("a11".to_string()))
         );
     }
 
@@ -705,48 +267,48 @@ mod tests {
     fn test_squares_above() {
         // a1
         let square1: &str = "a1";
-        let sq1: u64 = square_to_bb(square1).unwrap();
-        assert_eq!(squares_above(&sq1), 0xFFFFFFFFFFFFFF00);
+        let sq1 = square_to_bb(square1).unwrap();
+        assert_eq!(squares_above(&sq1), Bitboard(0xFFFFFFFFFFFFFF00));
         // g7
         let square2: &str = "g7";
-        let sq2: u64 = square_to_bb(square2).unwrap();
-        assert_eq!(squares_above(&sq2), 0xFF00000000000000);
+        let sq2 = square_to_bb(square2).unwrap();
+        assert_eq!(squares_above(&sq2), Bitboard(0xFF00000000000000));
     }
 
     #[test]
     fn test_squares_below() {
         // a1
         let square1: &str = "a1";
-        let sq1: u64 = square_to_bb(square1).unwrap();
-        assert_eq!(squares_below(&sq1), 0);
+        let sq1 = square_to_bb(square1).unwrap();
+        assert_eq!(squares_below(&sq1), Bitboard(0));
         // g7
         let square2: &str = "g7";
-        let sq2: u64 = square_to_bb(square2).unwrap();
-        assert_eq!(squares_below(&sq2), 0x0000FFFFFFFFFFFF);
+        let sq2 = square_to_bb(square2).unwrap();
+        assert_eq!(squares_below(&sq2), Bitboard(0x0000FFFFFFFFFFFF));
     }
 
     #[test]
     fn test_squares_left() {
         // a1
         let square1: &str = "a1";
-        let sq1: u64 = square_to_bb(square1).unwrap();
-        assert_eq!(squares_left(&sq1), 0);
+        let sq1 = square_to_bb(square1).unwrap();
+        assert_eq!(squares_left(&sq1), Bitboard(0));
         // g7
         let square2: &str = "g7";
-        let sq2: u64 = square_to_bb(square2).unwrap();
-        assert_eq!(squares_left(&sq2), 0x3F3F3F3F3F3F3F3F);
+        let sq2 = square_to_bb(square2).unwrap();
+        assert_eq!(squares_left(&sq2), Bitboard(0x3F3F3F3F3F3F3F3F));
     }
 
     #[test]
     fn test_squares_right() {
         // a1
         let square1: &str = "a1";
-        let sq1: u64 = square_to_bb(square1).unwrap();
-        assert_eq!(squares_right(&sq1), 0xFEFEFEFEFEFEFEFE);
+        let sq1 = square_to_bb(square1).unwrap();
+        assert_eq!(squares_right(&sq1), Bitboard(0xFEFEFEFEFEFEFEFE));
         // g7
         let square2: &str = "g7";
-        let sq2: u64 = square_to_bb(square2).unwrap();
-        assert_eq!(squares_right(&sq2), 0x8080808080808080);
+        let sq2 = square_to_bb(square2).unwrap();
+        assert_eq!(squares_right(&sq2), Bitboard(0x8080808080808080));
     }
 
     #[test]
@@ -756,12 +318,12 @@ mod tests {
         let flag: u8 = 8;
 
         let encoded_move = encode_move(from, to, flag);
-        assert_eq!(encoded_move, 0b0111101100101000);
+        assert_eq!(encoded_move.raw(), 0b0111101100101000);
     }
 
     #[test]
     fn test_decode_move() {
-        let move_i = 0b1100110101011011;
+        let move_i = Move::from_raw(0b1100110101011011);
         let move_i_decoded: [u8; 3] = [0b110011, 0b010101, 0b1011];
 
         assert_eq!(decode_move(move_i), move_i_decoded);
@@ -783,6 +345,19 @@ mod tests {
 
         let uci_move = "h2h1q";
         assert_eq!(encode_from_uci(uci_move).unwrap(), encode_move(15, 7, 7));
+
+        assert_eq!(
+            encode_from_uci("e2"),
+            Err(ChessError::InvalidUci("e2".to_string()))
+        );
+        assert_eq!(
+            encode_from_uci("h2h1k"),
+            Err(ChessError::InvalidPromotion("h2h1k".to_string()))
+        );
+        assert_eq!(
+            encode_from_uci("e2e9"),
+            Err(ChessError::InvalidSquare("e9".to_string()))
+        );
     }
 
     #[test]
@@ -806,7 +381,7 @@ mod tests {
     #[test]
     fn test_rank_value() {
         for i in 0..64u32 {
-            let square: u64 = 1 << i;
+            let square = Bitboard(1 << i);
             let expected = (i / 8 + 1) as u16;
             assert_eq!(rank_value(square), expected, "failed for square index {i}");
         }
@@ -815,7 +390,7 @@ mod tests {
     #[test]
     fn test_file_value() {
         for i in 0..64u32 {
-            let square: u64 = 1 << i;
+            let square = Bitboard(1 << i);
             let expected = (i % 8 + 1) as u16;
             assert_eq!(file_value(square), expected, "failed for square index {i}");
         }
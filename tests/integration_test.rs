@@ -1,20 +1,11 @@
 use oxi_chess_lib::board::ChessBoard;
+use oxi_chess_lib::error::ChessError;
 use oxi_chess_lib::game::ChessGame;
 use oxi_chess_lib::moves;
-
-fn perft(board: &mut ChessBoard, depth: u32) -> u64 {
-    if depth == 0 {
-        return 1;
-    }
-    let moves = moves::get_legal_moves(board);
-    let mut nodes = 0;
-    for move_i in moves {
-        let undo_info = board.make_move(move_i).unwrap();
-        nodes += perft(board, depth - 1);
-        board.unmake_move(move_i, &undo_info).unwrap();
-    }
-    nodes
-}
+use oxi_chess_lib::perft::{
+    find_divergence, parse_divide, parse_perft_suite, perft, perft_detailed, perft_divide,
+    perft_hashed, perft_hashed_verified, perft_parallel, PerftStats,
+};
 
 #[test]
 fn test_perft() {
@@ -36,3 +27,263 @@ fn test_perft() {
     assert_eq!(perft(&mut board, 4), 4085603);
     //assert_eq!(perft(&mut board, 5), 193690690);
 }
+
+#[test]
+fn test_perft_chess960() {
+    let mut board = ChessBoard::initialize_chess960(518);
+    assert_eq!(perft(&mut board, 3), 8902);
+
+    let mut board = ChessBoard::initialize_from_fen(
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+    )
+    .unwrap();
+    assert_eq!(perft(&mut board, 1), 21);
+    assert_eq!(perft(&mut board, 2), 528);
+    assert_eq!(perft(&mut board, 3), 12189);
+    assert_eq!(perft(&mut board, 4), 326672);
+}
+
+#[test]
+fn test_perft_suite() {
+    let suite = parse_perft_suite(include_str!("perftsuite.epd")).unwrap();
+    assert!(suite.len() >= 100);
+    // the small counts here; `cargo run --release --bin perft` runs the rest.
+    for case in suite {
+        for (depth, expected) in case.expected {
+            if expected <= 20_000 {
+                let mut board = case.board.clone();
+                assert_eq!(
+                    perft(&mut board, depth),
+                    expected,
+                    "{} depth {depth}",
+                    case.fen
+                );
+            }
+        }
+    }
+
+    let suite = parse_perft_suite(
+        "# comment\n\n4k3/8/8/8/8/8/8/4K2R w K - 0 1 ;D1 15 ;D2 66\n8/8/8/8/8/8/8/K6k w - - ;D3 89",
+    )
+    .unwrap();
+    assert_eq!(suite.len(), 2);
+    assert_eq!(suite[0].expected, [(1, 15), (2, 66)]);
+    assert_eq!(suite[1].fen, "8/8/8/8/8/8/8/K6k w - -");
+    for (text, line) in [
+        ("8/8/8/8/8/8/8/K6k w - - ;D1", 1),
+        ("# a\n8/8/8/8/8/8/8/K6k w - - ;1 3", 2),
+        ("8/8/8/8/8/8/8/K6k w - -", 1),
+        ("8/8/8/8/8/8/8/K6k x - - ;D1 3", 1),
+    ] {
+        match parse_perft_suite(text) {
+            Err(ChessError::InvalidEpd(reason)) => {
+                assert!(reason.starts_with(&format!("line {line}:")), "{reason}")
+            }
+            other => panic!("{text}: {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_perft_parallel() {
+    for (fen, depth, nodes) in [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            4,
+            197281,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            3,
+            97862,
+        ),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674624),
+    ] {
+        let board = ChessBoard::initialize_from_fen(fen).unwrap();
+        for threads in [0, 1, 3, 8] {
+            assert_eq!(perft_parallel(&board, depth, threads), nodes, "{fen}");
+        }
+    }
+    let board = ChessBoard::initialize();
+    assert_eq!(perft_parallel(&board, 0, 4), 1);
+    assert_eq!(perft_parallel(&board, 1, 4), 20);
+}
+
+#[test]
+fn test_perft_hashed() {
+    for (fen, depth, nodes) in [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            5,
+            4865609,
+        ),
+        (
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            4,
+            4085603,
+        ),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 5, 674624),
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            4,
+            326672,
+        ),
+    ] {
+        let mut board = ChessBoard::initialize_from_fen(fen).unwrap();
+        // a tiny table that keeps overwriting itself must count the same as a roomy one.
+        assert_eq!(perft_hashed(&mut board, depth, 0), nodes, "{fen}");
+        assert_eq!(perft_hashed(&mut board, depth, 16), nodes, "{fen}");
+    }
+
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "1r2k1rr/8/8/8/8/8/8/RR2K2R w BHbg - 0 1",
+    ] {
+        let mut board = ChessBoard::initialize_from_fen(fen).unwrap();
+        let nodes = perft(&mut board, 3);
+        assert_eq!(perft_hashed_verified(&mut board, 3, 1), Ok(nodes), "{fen}");
+    }
+
+    // a hash that is off from the start is caught at the root.
+    let mut board = ChessBoard::initialize();
+    board.zobrist_hash ^= 1;
+    let mismatch = perft_hashed_verified(&mut board, 2, 1).unwrap_err();
+    assert!(mismatch.moves.is_empty());
+    assert_eq!(mismatch.incremental ^ mismatch.from_scratch, 1);
+    assert!(mismatch
+        .to_string()
+        .starts_with("zobrist hash diverges after []"));
+}
+
+// rows of the Chess Programming Wiki perft tables: nodes, captures, en passant, castles,
+// promotions, checks, discovered checks, double checks and checkmates.
+fn stats(row: [u64; 9]) -> PerftStats {
+    let [nodes, captures, en_passant, castles, promotions, checks, discovered_checks, double_checks, checkmates] =
+        row;
+    PerftStats {
+        nodes,
+        captures,
+        en_passant,
+        castles,
+        promotions,
+        checks,
+        discovered_checks,
+        double_checks,
+        checkmates,
+    }
+}
+
+#[test]
+fn test_perft_detailed() {
+    let mut board = ChessBoard::initialize();
+    let expected = [
+        [20, 0, 0, 0, 0, 0, 0, 0, 0],
+        [400, 0, 0, 0, 0, 0, 0, 0, 0],
+        [8902, 34, 0, 0, 0, 12, 0, 0, 0],
+        [197281, 1576, 0, 0, 0, 469, 0, 0, 8],
+        [4865609, 82719, 258, 0, 0, 27351, 6, 0, 347],
+    ];
+    assert_eq!(perft_detailed(&mut board, 5), expected.map(stats));
+
+    let mut board = ChessBoard::initialize_from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    )
+    .unwrap();
+    let expected = [
+        [48, 8, 0, 2, 0, 0, 0, 0, 0],
+        [2039, 351, 1, 91, 0, 3, 0, 0, 0],
+        [97862, 17102, 45, 3162, 0, 993, 0, 0, 1],
+        [4085603, 757163, 1929, 128013, 15172, 25523, 42, 6, 43],
+    ];
+    assert_eq!(perft_detailed(&mut board, 4), expected.map(stats));
+}
+
+// a divide from a move generator that forgets en passant, standing in for a buggy engine.
+fn divide_without_en_passant(board: &ChessBoard, depth: u32) -> Vec<(String, u64)> {
+    fn count(board: &mut ChessBoard, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut nodes = 0;
+        for move_i in moves::get_legal_moves(board) {
+            if !move_i.is_en_passant() {
+                let undo_info = board.make_move(move_i).unwrap();
+                nodes += count(board, depth - 1);
+                board.unmake_move(move_i, &undo_info).unwrap();
+            }
+        }
+        nodes
+    }
+    let mut board = board.clone();
+    perft_divide(&mut board, depth)
+        .into_iter()
+        .filter(|(move_i, _)| !move_i.is_en_passant())
+        .map(|(move_i, _)| {
+            let undo_info = board.make_move(move_i).unwrap();
+            let nodes = count(&mut board, depth - 1);
+            board.unmake_move(move_i, &undo_info).unwrap();
+            (move_i.to_string(), nodes)
+        })
+        .collect()
+}
+
+#[test]
+fn test_perft_divide() {
+    let mut board = ChessBoard::initialize();
+    let divide = perft_divide(&mut board, 3);
+    assert_eq!(divide.len(), 20);
+    assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 8902);
+    assert!(perft_divide(&mut board, 0).is_empty());
+
+    // output as another engine prints it for `go perft 2`.
+    let pasted = "a2a3: 20\nb2b3: 20\n\nNodes searched: 40\n";
+    assert_eq!(
+        parse_divide(pasted).unwrap(),
+        [("a2a3".to_string(), 20), ("b2b3".to_string(), 20)]
+    );
+    assert!(parse_divide("e2e4: lots").is_err());
+
+    let kiwipete = ChessBoard::initialize_from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    )
+    .unwrap();
+    let reference = |board: &ChessBoard, depth| Ok(divide_without_en_passant(board, depth));
+
+    // agreeing with the reference at the root needs no search.
+    let ours: Vec<(String, u64)> = perft_divide(&mut kiwipete.clone(), 3)
+        .into_iter()
+        .map(|(move_i, nodes)| (move_i.to_string(), nodes))
+        .collect();
+    assert_eq!(find_divergence(&kiwipete, 3, &ours, reference), Ok(None));
+
+    // a move only the reference has is reported at the root.
+    let mut with_bogus = ours.clone();
+    with_bogus.push(("a1a8".to_string(), 5));
+    let divergence = find_divergence(&kiwipete, 3, &with_bogus, reference)
+        .unwrap()
+        .unwrap();
+    assert!(divergence.moves.is_empty());
+    assert_eq!(divergence.missing, ["a1a8"]);
+
+    // the reference misses en passant: the search walks down to a position where it is legal.
+    let root = divide_without_en_passant(&kiwipete, 3);
+    let divergence = find_divergence(&kiwipete, 3, &root, reference)
+        .unwrap()
+        .unwrap();
+    assert_eq!(divergence.depth, 1);
+    assert_eq!(divergence.moves.len(), 2);
+    assert!(divergence.missing.is_empty());
+    let mut board = kiwipete.clone();
+    for move_i in &divergence.moves {
+        board.make_move(*move_i).unwrap();
+    }
+    assert_eq!(board.to_fen(), divergence.fen);
+    let en_passant: Vec<String> = moves::get_legal_moves(&mut board)
+        .into_iter()
+        .filter(|move_i| move_i.is_en_passant())
+        .map(|move_i| move_i.to_string())
+        .collect();
+    assert_eq!(divergence.extra, en_passant);
+    assert!(divergence.to_string().contains("extra"));
+}